@@ -0,0 +1,244 @@
+//! The [`Authorizer`] interface implemented by authentication/authorization providers, and the
+//! [`AuthFailureAuditLog`] interface for recording failed authentication attempts.
+
+mod noop_authorizer;
+
+pub use noop_authorizer::NoopAuthorizer;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+
+use crate::error::VssError;
+
+/// The [`AuthResponse`] attribute carrying an IP-binding constraint: a comma-separated list of
+/// CIDR ranges (e.g. `10.0.0.0/8,192.0.2.1/32`) the credential is valid from. Authorizers set it
+/// from the corresponding claim or caveat of the presented credential; the server rejects
+/// requests whose effective client IP falls outside all listed ranges, limiting the blast radius
+/// of a leaked token for server-to-server integrations.
+pub const ALLOWED_CIDRS_ATTRIBUTE: &str = "allowed_cidrs";
+
+/// The [`AuthResponse`] attribute carrying the credential's granted scopes, as a comma- or
+/// whitespace-separated list (e.g. `read write`). Authorizers set it from the corresponding
+/// claim of the presented credential; the server rejects mutating RPCs unless [`WRITE_SCOPE`] is
+/// granted, so issuers can hand out read-only tokens for watch-only wallet features. Credentials
+/// without the attribute are unrestricted.
+pub const SCOPES_ATTRIBUTE: &str = "scopes";
+
+/// The scope permitting mutating RPCs, see [`SCOPES_ATTRIBUTE`].
+pub const WRITE_SCOPE: &str = "write";
+
+/// The [`AuthResponse`] attribute restricting the credential to specific stores: a
+/// comma-separated list of `store_id`s the credential may touch. Authorizers set it from the
+/// corresponding claim of the presented credential; the server rejects requests naming any other
+/// `store_id`, so a token issued for one wallet's store cannot read or write its user's other
+/// stores. Entries are matched verbatim (store ids containing commas cannot be granted
+/// individually); credentials without the attribute may access all of the user's stores.
+pub const ALLOWED_STORE_IDS_ATTRIBUTE: &str = "allowed_store_ids";
+
+/// Returns whether the given `store_id` is among the comma-separated granted entries.
+pub fn store_id_allowed(store_id: &str, allowed_store_ids: &str) -> bool {
+	allowed_store_ids.split(',').any(|entry| entry == store_id)
+}
+
+/// Returns whether the given scope is present in the comma- or whitespace-separated scope list.
+pub fn scopes_contain(scopes: &str, scope: &str) -> bool {
+	scopes.split([',', ' ', '\t']).any(|entry| entry.trim() == scope)
+}
+
+/// Returns whether the given IP address falls within any of the comma-separated CIDR ranges.
+/// Entries without a prefix length are treated as single-address ranges; malformed entries never
+/// match, so a garbled constraint fails closed.
+pub fn ip_within_cidrs(ip: IpAddr, cidrs: &str) -> bool {
+	cidrs.split(',').map(str::trim).any(|cidr| ip_within_cidr(ip, cidr))
+}
+
+fn ip_within_cidr(ip: IpAddr, cidr: &str) -> bool {
+	let (network, prefix_len) = match cidr.split_once('/') {
+		Some((network, prefix_len)) => {
+			match (network.parse::<IpAddr>(), prefix_len.parse::<u32>()) {
+				(Ok(network), Ok(prefix_len)) => (network, prefix_len),
+				_ => return false,
+			}
+		},
+		None => match cidr.parse::<IpAddr>() {
+			Ok(network) => return ip == network,
+			Err(_) => return false,
+		},
+	};
+	match (ip, network) {
+		(IpAddr::V4(ip), IpAddr::V4(network)) => {
+			if prefix_len > 32 {
+				return false;
+			}
+			let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+			u32::from(ip) & mask == u32::from(network) & mask
+		},
+		(IpAddr::V6(ip), IpAddr::V6(network)) => {
+			if prefix_len > 128 {
+				return false;
+			}
+			let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+			u128::from(ip) & mask == u128::from(network) & mask
+		},
+		_ => false,
+	}
+}
+
+/// The result of successfully authenticating/authorizing a request.
+#[derive(Debug, Clone)]
+pub struct AuthResponse {
+	/// An identifier of the authenticated user, used to namespace all storage operations
+	/// performed on behalf of the request.
+	pub user_token: String,
+	/// Additional attributes of the authenticated user (e.g. a tier, an org id or scopes), as
+	/// far as the authentication scheme provides them. They are forwarded to the storage layer
+	/// via [`RequestContext`], so quota wrappers and tenant-aware backends can base decisions on
+	/// who is calling.
+	///
+	/// [`RequestContext`]: crate::kv_store::RequestContext
+	pub attributes: HashMap<String, String>,
+}
+
+impl AuthResponse {
+	/// Constructs an [`AuthResponse`] for the given user without any attributes.
+	pub fn new(user_token: String) -> Self {
+		AuthResponse { user_token, attributes: HashMap::new() }
+	}
+}
+
+/// A lightweight, read-only view over a request's headers.
+///
+/// HTTP servers typically provide a borrowing view over their native header map, so authorizing
+/// a request does not require copying every header into an owned map. An implementation for
+/// `HashMap<String, String>` is provided, mainly for tests.
+pub trait RequestHeaders: Send + Sync {
+	/// Returns the value of the given (lowercase) header name, if present and valid UTF-8.
+	fn get_header(&self, name: &str) -> Option<&str>;
+
+	/// Returns whether the given header is present, regardless of whether its value is valid
+	/// UTF-8.
+	fn contains_header(&self, name: &str) -> bool {
+		self.get_header(name).is_some()
+	}
+
+	/// Returns the path of the request the headers belong to, if the transport exposes it,
+	/// letting authorizers bind credentials to the operation being invoked.
+	fn path(&self) -> Option<&str> {
+		None
+	}
+
+	/// Returns the SHA-256 digest of the request body, if the transport exposes it.
+	fn body_sha256(&self) -> Option<[u8; 32]> {
+		None
+	}
+
+	/// Returns the DER-encoded client certificate presented on the underlying TLS connection,
+	/// if the transport terminates TLS and the client presented one.
+	fn peer_certificate(&self) -> Option<&[u8]> {
+		None
+	}
+
+	/// Returns the transport-derived client IP address of the request, if the transport exposes
+	/// it: the socket peer address or, when the server is configured with trusted reverse
+	/// proxies, the nearest `X-Forwarded-For` hop not belonging to one of them. Unlike reading
+	/// forwarded headers directly, the returned address cannot be chosen by the client.
+	fn client_ip(&self) -> Option<&str> {
+		None
+	}
+}
+
+impl RequestHeaders for HashMap<String, String> {
+	fn get_header(&self, name: &str) -> Option<&str> {
+		self.get(name).map(String::as_str)
+	}
+}
+
+/// An interface for authenticating/authorizing requests before they reach the storage layer.
+///
+/// Implementations derive the effective `user_token` from the request headers, failing with
+/// [`VssError::AuthError`] if the request cannot be authenticated.
+#[async_trait]
+pub trait Authorizer: Send + Sync {
+	/// Verifies the given request headers and returns the authenticated user on success.
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError>;
+}
+
+/// A structured record of a failed authentication attempt, see [`AuthFailureAuditLog`].
+#[derive(Debug, Clone)]
+pub struct AuthFailureEvent {
+	/// The authentication scheme the request attempted, e.g. `bearer` or `signature`.
+	pub scheme: String,
+	/// A coarse categorization of why authentication failed, e.g. `invalid_credentials`.
+	pub reason: String,
+	/// The source IP address of the request, if known.
+	pub source_ip: Option<String>,
+}
+
+/// A sink recording [`AuthFailureEvent`]s, enabling detection of credential stuffing and
+/// misbehaving clients.
+#[async_trait]
+pub trait AuthFailureAuditLog: Send + Sync {
+	/// Records the given authentication failure. The event timestamp is assigned by the sink.
+	async fn record_auth_failure(&self, event: AuthFailureEvent) -> Result<(), VssError>;
+
+	/// Deletes recorded events older than the given number of days, returning the number of
+	/// deleted events.
+	async fn prune_auth_failures(&self, retention_days: u32) -> Result<u64, VssError>;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn cidr_ranges_match_contained_addresses() {
+		assert!(ip_within_cidrs(ip("10.1.2.3"), "10.0.0.0/8"));
+		assert!(!ip_within_cidrs(ip("11.1.2.3"), "10.0.0.0/8"));
+		assert!(ip_within_cidrs(ip("192.0.2.1"), "10.0.0.0/8, 192.0.2.1/32"));
+		assert!(!ip_within_cidrs(ip("192.0.2.2"), "10.0.0.0/8, 192.0.2.1/32"));
+		// A bare address is a single-address range, /0 matches everything.
+		assert!(ip_within_cidrs(ip("192.0.2.1"), "192.0.2.1"));
+		assert!(ip_within_cidrs(ip("203.0.113.7"), "0.0.0.0/0"));
+		assert!(ip_within_cidrs(ip("2001:db8::1"), "2001:db8::/32"));
+		assert!(!ip_within_cidrs(ip("2001:db9::1"), "2001:db8::/32"));
+		// Address families never cross-match.
+		assert!(!ip_within_cidrs(ip("10.1.2.3"), "2001:db8::/32"));
+	}
+
+	#[test]
+	fn scope_lists_split_on_commas_and_whitespace() {
+		assert!(scopes_contain("read write", WRITE_SCOPE));
+		assert!(scopes_contain("read, write", WRITE_SCOPE));
+		assert!(!scopes_contain("read", WRITE_SCOPE));
+		// Scopes match whole entries, never substrings.
+		assert!(!scopes_contain("readwrite", WRITE_SCOPE));
+		assert!(!scopes_contain("", WRITE_SCOPE));
+	}
+
+	#[test]
+	fn store_id_grants_match_verbatim() {
+		assert!(store_id_allowed("wallet-a", "wallet-a,wallet-b"));
+		assert!(store_id_allowed("wallet-b", "wallet-a,wallet-b"));
+		assert!(!store_id_allowed("wallet-c", "wallet-a,wallet-b"));
+		// Entries are taken verbatim, never as prefixes or trimmed.
+		assert!(!store_id_allowed("wallet", "wallet-a,wallet-b"));
+		assert!(!store_id_allowed("wallet-a", "wallet-a , wallet-b"));
+		assert!(!store_id_allowed("anything", ""));
+	}
+
+	#[test]
+	fn malformed_cidrs_fail_closed() {
+		assert!(!ip_within_cidrs(ip("10.1.2.3"), ""));
+		assert!(!ip_within_cidrs(ip("10.1.2.3"), "not-a-cidr"));
+		assert!(!ip_within_cidrs(ip("10.1.2.3"), "10.0.0.0/33"));
+		assert!(!ip_within_cidrs(ip("10.1.2.3"), "10.0.0.0/"));
+		// One malformed entry does not disable the rest.
+		assert!(ip_within_cidrs(ip("10.1.2.3"), "not-a-cidr, 10.0.0.0/8"));
+	}
+}
@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+
+use crate::auth::{AuthResponse, Authorizer, RequestHeaders};
+use crate::error::VssError;
+
+/// An [`Authorizer`] which performs no authentication at all and maps every request to a fixed
+/// `user_token`, or to the value of a trusted header set by an already-authenticating reverse
+/// proxy.
+///
+/// This is primarily useful for local testing and for deployments which are protected by other
+/// means. It must NOT be used for multi-user production deployments unless the trusted header is
+/// guaranteed to be set by the proxy and unspoofable by clients.
+pub struct NoopAuthorizer {
+	fixed_user_token: String,
+	trusted_header: Option<String>,
+}
+
+const UNAUTHENTICATED_USER: &str = "unauth-user";
+
+impl NoopAuthorizer {
+	/// Constructs a [`NoopAuthorizer`] mapping every request to a default fixed user.
+	pub fn new() -> Self {
+		Self::with_fixed_user_token(UNAUTHENTICATED_USER.to_string())
+	}
+
+	/// Constructs a [`NoopAuthorizer`] mapping every request to the given fixed `user_token`.
+	pub fn with_fixed_user_token(user_token: String) -> Self {
+		NoopAuthorizer { fixed_user_token: user_token, trusted_header: None }
+	}
+
+	/// Constructs a [`NoopAuthorizer`] taking the `user_token` from the given header, which must
+	/// only ever be settable by a trusted reverse proxy. Requests missing the header are
+	/// rejected.
+	pub fn with_trusted_header(header: String) -> Self {
+		NoopAuthorizer {
+			fixed_user_token: UNAUTHENTICATED_USER.to_string(),
+			trusted_header: Some(header),
+		}
+	}
+}
+
+impl Default for NoopAuthorizer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[async_trait]
+impl Authorizer for NoopAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		if let Some(trusted_header) = &self.trusted_header {
+			return match headers.get_header(trusted_header) {
+				Some(user_token) if !user_token.is_empty() => {
+					Ok(AuthResponse::new(user_token.to_string()))
+				},
+				_ => Err(VssError::AuthError(format!(
+					"Missing trusted header: {}",
+					trusted_header
+				))),
+			};
+		}
+		Ok(AuthResponse::new(self.fixed_user_token.clone()))
+	}
+}
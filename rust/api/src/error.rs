@@ -0,0 +1,229 @@
+//! Error types returned by [`KvStore`] and [`Authorizer`] implementations.
+//!
+//! [`KvStore`]: crate::kv_store::KvStore
+//! [`Authorizer`]: crate::auth::Authorizer
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+/// When there is an error while writing to VSS storage or while authenticating/authorizing a
+/// request, the response can contain an appropriate [`VssError`].
+#[derive(Debug)]
+pub enum VssError {
+	/// The requested key does not exist.
+	NoSuchKeyError(String),
+	/// The request was missing a required argument or an argument was invalid, incomplete or in
+	/// the wrong format.
+	InvalidRequestError(String),
+	/// The request contained a mismatched version (either key or global), refer to
+	/// [`PutObjectRequest`] for more info.
+	///
+	/// [`PutObjectRequest`]: crate::types::PutObjectRequest
+	ConflictError(String),
+	/// The request could not be authenticated or the authenticated client is not authorized to
+	/// perform the requested operation.
+	AuthError(String),
+	/// An internal server error occurred, the client is probably at no fault and can safely retry
+	/// with exponential backoff.
+	InternalServerError(String),
+}
+
+/// The stable, machine-readable sub-codes carried in [`ErrorResponse::sub_code`], refining the
+/// coarse [`ErrorCode`] without requiring clients to parse human-readable messages.
+///
+/// New sub-codes may be added over time; clients should treat unknown values like an absent
+/// sub-code and fall back to the [`ErrorCode`].
+///
+/// [`ErrorResponse::sub_code`]: crate::types::ErrorResponse
+/// [`ErrorCode`]: crate::types::ErrorCode
+pub mod sub_codes {
+	/// A put or delete item's version did not match the stored key version.
+	pub const CONFLICT_KEY_VERSION: &str = "CONFLICT_KEY_VERSION";
+	/// The request's global version did not match the store's global version.
+	pub const CONFLICT_GLOBAL_VERSION: &str = "CONFLICT_GLOBAL_VERSION";
+	/// The write lost a race with a concurrent transaction and can safely be retried.
+	pub const CONFLICT_CONCURRENT_WRITE: &str = "CONFLICT_CONCURRENT_WRITE";
+	/// An argument was missing, malformed or otherwise invalid.
+	pub const INVALID_ARGUMENT: &str = "INVALID_ARGUMENT";
+	/// A key or store_id exceeded the configured maximum length.
+	pub const LIMIT_IDENTIFIER_LENGTH: &str = "LIMIT_IDENTIFIER_LENGTH";
+	/// The user reached the configured maximum number of stores.
+	pub const LIMIT_STORE_COUNT: &str = "LIMIT_STORE_COUNT";
+	/// The store reached the configured maximum number of keys.
+	pub const LIMIT_KEY_COUNT: &str = "LIMIT_KEY_COUNT";
+	/// The user reached the configured per-user storage quota (total value bytes or key count).
+	pub const LIMIT_USER_QUOTA: &str = "LIMIT_USER_QUOTA";
+	/// The store is delete-protected, deletes are refused until protection is lifted.
+	pub const DELETE_PROTECTED: &str = "DELETE_PROTECTED";
+	/// The store is archived (frozen read-only), writes are refused until it is unarchived.
+	pub const STORE_ARCHIVED: &str = "STORE_ARCHIVED";
+	/// The request carried no credentials.
+	pub const AUTH_MISSING_CREDENTIALS: &str = "AUTH_MISSING_CREDENTIALS";
+	/// The credentials are expired or timestamped outside the allowed clock skew.
+	pub const AUTH_TOKEN_EXPIRED: &str = "AUTH_TOKEN_EXPIRED";
+	/// The request signature did not verify.
+	pub const AUTH_INVALID_SIGNATURE: &str = "AUTH_INVALID_SIGNATURE";
+	/// The bearer token was malformed or did not verify.
+	pub const AUTH_INVALID_TOKEN: &str = "AUTH_INVALID_TOKEN";
+	/// The user has been administratively suspended.
+	pub const AUTH_USER_SUSPENDED: &str = "AUTH_USER_SUSPENDED";
+	/// The credentials were otherwise rejected.
+	pub const AUTH_INVALID_CREDENTIALS: &str = "AUTH_INVALID_CREDENTIALS";
+	/// The requested key does not exist.
+	pub const NO_SUCH_KEY: &str = "NO_SUCH_KEY";
+	/// The request body exceeded the configured maximum size.
+	pub const LIMIT_REQUEST_SIZE: &str = "LIMIT_REQUEST_SIZE";
+	/// The user exceeded a configured rate limit, the client can retry later.
+	pub const LIMIT_RATE: &str = "LIMIT_RATE";
+	/// The trial identity exhausted its write quota, upgrade to a real credential to continue.
+	pub const LIMIT_TRIAL_QUOTA: &str = "LIMIT_TRIAL_QUOTA";
+	/// The server is in maintenance mode and temporarily rejects writes.
+	pub const UNAVAILABLE_MAINTENANCE: &str = "UNAVAILABLE_MAINTENANCE";
+	/// An internal error occurred, the client can safely retry with backoff.
+	pub const INTERNAL: &str = "INTERNAL";
+}
+
+impl VssError {
+	/// Maps the error onto the stable sub-code catalog, see [`sub_codes`].
+	///
+	/// Backends signal the specific condition through their (fixed) message phrasing; the
+	/// classification here is the single place turning it into the stable contract, so the
+	/// messages themselves remain free to change.
+	pub fn sub_code(&self) -> &'static str {
+		match self {
+			VssError::NoSuchKeyError(..) => sub_codes::NO_SUCH_KEY,
+			VssError::InvalidRequestError(message) => {
+				if message.contains("exceeds the maximum length") {
+					sub_codes::LIMIT_IDENTIFIER_LENGTH
+				} else if message.contains("maximum number of stores") {
+					sub_codes::LIMIT_STORE_COUNT
+				} else if message.contains("maximum number of keys") {
+					sub_codes::LIMIT_KEY_COUNT
+				} else if message.contains("per-user storage quota") {
+					sub_codes::LIMIT_USER_QUOTA
+				} else if message.contains("delete-protected") {
+					sub_codes::DELETE_PROTECTED
+				} else if message.contains("archived") {
+					sub_codes::STORE_ARCHIVED
+				} else {
+					sub_codes::INVALID_ARGUMENT
+				}
+			},
+			VssError::ConflictError(message) => {
+				if message.starts_with("Global version mismatch") {
+					sub_codes::CONFLICT_GLOBAL_VERSION
+				} else if message.starts_with("Lost a race") {
+					sub_codes::CONFLICT_CONCURRENT_WRITE
+				} else {
+					sub_codes::CONFLICT_KEY_VERSION
+				}
+			},
+			VssError::AuthError(message) => {
+				let message = message.to_lowercase();
+				if message.contains("missing") {
+					sub_codes::AUTH_MISSING_CREDENTIALS
+				} else if message.contains("expired") || message.contains("clock skew") {
+					sub_codes::AUTH_TOKEN_EXPIRED
+				} else if message.contains("suspended") {
+					sub_codes::AUTH_USER_SUSPENDED
+				} else if message.contains("signature") {
+					sub_codes::AUTH_INVALID_SIGNATURE
+				} else if message.contains("token") {
+					sub_codes::AUTH_INVALID_TOKEN
+				} else {
+					sub_codes::AUTH_INVALID_CREDENTIALS
+				}
+			},
+			VssError::InternalServerError(..) => sub_codes::INTERNAL,
+		}
+	}
+}
+
+impl Display for VssError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			VssError::NoSuchKeyError(message) => {
+				write!(f, "Requested key does not exist: {}", message)
+			},
+			VssError::InvalidRequestError(message) => {
+				write!(f, "Request sent to VSS Storage was invalid: {}", message)
+			},
+			VssError::ConflictError(message) => {
+				write!(f, "Potential version conflict in write operation: {}", message)
+			},
+			VssError::AuthError(message) => {
+				write!(f, "Request failed authentication/authorization: {}", message)
+			},
+			VssError::InternalServerError(message) => {
+				write!(f, "InternalServerError: {}", message)
+			},
+		}
+	}
+}
+
+impl Error for VssError {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn errors_map_to_stable_sub_codes() {
+		let conflict = |message: &str| VssError::ConflictError(message.to_string());
+		assert_eq!(
+			conflict("Global version mismatch for store_id: s").sub_code(),
+			sub_codes::CONFLICT_GLOBAL_VERSION
+		);
+		assert_eq!(conflict("Version mismatch for key: k").sub_code(), sub_codes::CONFLICT_KEY_VERSION);
+		assert_eq!(
+			conflict("Lost a race with a concurrent write, please retry.").sub_code(),
+			sub_codes::CONFLICT_CONCURRENT_WRITE
+		);
+
+		let invalid = |message: &str| VssError::InvalidRequestError(message.to_string());
+		assert_eq!(
+			invalid("key exceeds the maximum length of 600 characters.").sub_code(),
+			sub_codes::LIMIT_IDENTIFIER_LENGTH
+		);
+		assert_eq!(
+			invalid("Reached the maximum number of stores per user (10).").sub_code(),
+			sub_codes::LIMIT_STORE_COUNT
+		);
+		assert_eq!(
+			invalid("Reached the maximum number of keys per store (100).").sub_code(),
+			sub_codes::LIMIT_KEY_COUNT
+		);
+		assert_eq!(
+			invalid("Exceeded the per-user storage quota of 1048576 bytes.").sub_code(),
+			sub_codes::LIMIT_USER_QUOTA
+		);
+		assert_eq!(
+			invalid("Store is delete-protected, deletes are refused until protection is lifted.")
+				.sub_code(),
+			sub_codes::DELETE_PROTECTED
+		);
+		assert_eq!(
+			invalid("Store is archived and read-only, writes are refused until it is unarchived.")
+				.sub_code(),
+			sub_codes::STORE_ARCHIVED
+		);
+		assert_eq!(invalid("store_id must not be empty.").sub_code(), sub_codes::INVALID_ARGUMENT);
+
+		let auth = |message: &str| VssError::AuthError(message.to_string());
+		assert_eq!(
+			auth("Missing Authorization header.").sub_code(),
+			sub_codes::AUTH_MISSING_CREDENTIALS
+		);
+		assert_eq!(
+			auth("Timestamp outside of allowed clock skew.").sub_code(),
+			sub_codes::AUTH_TOKEN_EXPIRED
+		);
+		assert_eq!(auth("User is suspended.").sub_code(), sub_codes::AUTH_USER_SUSPENDED);
+		assert_eq!(
+			auth("Signature verification failed.").sub_code(),
+			sub_codes::AUTH_INVALID_SIGNATURE
+		);
+		assert_eq!(auth("Invalid JWT token: oops").sub_code(), sub_codes::AUTH_INVALID_TOKEN);
+	}
+}
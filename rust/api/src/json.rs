@@ -0,0 +1,220 @@
+//! Canonical proto3 JSON mapping helpers for the types in [`types`].
+//!
+//! The serde derives on the request/response types follow the standard proto3 JSON mapping:
+//! field names are lowerCamelCase, `bytes` fields are standard base64 strings, 64-bit integers
+//! are serialized as decimal strings (but accepted as JSON numbers too, sparing hand-written
+//! clients the quoting) and enums are serialized by their proto value name. The binary protobuf
+//! encoding remains the primary wire format; JSON exists for debugging with curl and for
+//! integrating from environments without protobuf support.
+//!
+//! [`types`]: crate::types
+
+use std::fmt;
+
+use serde::de::{Deserializer, Error as DeError, Visitor};
+use serde::ser::Serializer;
+
+/// Maps `bytes` fields to standard base64 strings (with padding), per the proto3 JSON mapping.
+pub mod base64_bytes {
+	use super::*;
+	use base64::prelude::{Engine, BASE64_STANDARD};
+
+	/// Serializes the bytes as a standard base64 string.
+	pub fn serialize<S: Serializer>(
+		value: &prost::bytes::Bytes, serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&BASE64_STANDARD.encode(value))
+	}
+
+	/// Deserializes the bytes from a standard base64 string.
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<prost::bytes::Bytes, D::Error> {
+		struct Base64Visitor;
+		impl Visitor<'_> for Base64Visitor {
+			type Value = prost::bytes::Bytes;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a base64 string")
+			}
+			fn visit_str<E: DeError>(self, value: &str) -> Result<Self::Value, E> {
+				BASE64_STANDARD
+					.decode(value)
+					.map(prost::bytes::Bytes::from)
+					.map_err(|e| E::custom(format!("invalid base64: {}", e)))
+			}
+		}
+		deserializer.deserialize_str(Base64Visitor)
+	}
+}
+
+struct Int64Visitor;
+
+impl Visitor<'_> for Int64Visitor {
+	type Value = i64;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a 64-bit integer or its decimal string representation")
+	}
+
+	fn visit_i64<E: DeError>(self, value: i64) -> Result<Self::Value, E> {
+		Ok(value)
+	}
+
+	fn visit_u64<E: DeError>(self, value: u64) -> Result<Self::Value, E> {
+		i64::try_from(value).map_err(|_| E::custom("integer out of range for int64"))
+	}
+
+	fn visit_str<E: DeError>(self, value: &str) -> Result<Self::Value, E> {
+		value.parse().map_err(|_| E::custom("invalid int64 string"))
+	}
+}
+
+/// Maps `int64` fields to decimal strings (per the proto3 JSON mapping, keeping them exact in
+/// JavaScript environments), accepting plain JSON numbers on input.
+pub mod int64 {
+	use super::*;
+
+	/// Serializes the integer as a decimal string.
+	pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(value)
+	}
+
+	/// Deserializes the integer from a decimal string or a JSON number.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+		deserializer.deserialize_any(Int64Visitor)
+	}
+}
+
+/// Like [`int64`], for `optional int64` fields.
+pub mod optional_int64 {
+	use super::*;
+
+	/// Serializes the integer as a decimal string, or as `null` when absent.
+	pub fn serialize<S: Serializer>(
+		value: &Option<i64>, serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		match value {
+			Some(value) => serializer.collect_str(value),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	/// Deserializes the integer from a decimal string, a JSON number or `null`.
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Option<i64>, D::Error> {
+		struct OptionalInt64Visitor;
+		impl<'de> Visitor<'de> for OptionalInt64Visitor {
+			type Value = Option<i64>;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a 64-bit integer, its decimal string representation or null")
+			}
+			fn visit_none<E: DeError>(self) -> Result<Self::Value, E> {
+				Ok(None)
+			}
+			fn visit_unit<E: DeError>(self) -> Result<Self::Value, E> {
+				Ok(None)
+			}
+			fn visit_some<D2: Deserializer<'de>>(
+				self, deserializer: D2,
+			) -> Result<Self::Value, D2::Error> {
+				deserializer.deserialize_any(Int64Visitor).map(Some)
+			}
+		}
+		deserializer.deserialize_option(OptionalInt64Visitor)
+	}
+}
+
+/// Maps the [`ErrorCode`] enumeration field to its proto value name (e.g.
+/// `CONFLICT_EXCEPTION`), accepting the numeric value on input as well.
+///
+/// [`ErrorCode`]: crate::types::ErrorCode
+pub mod error_code {
+	use super::*;
+	use crate::types::ErrorCode;
+
+	/// Serializes the error code by its proto value name, falling back to the raw number for
+	/// values unknown to this build.
+	pub fn serialize<S: Serializer>(value: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+		match ErrorCode::try_from(*value) {
+			Ok(error_code) => serializer.serialize_str(error_code.as_str_name()),
+			Err(_) => serializer.serialize_i32(*value),
+		}
+	}
+
+	/// Deserializes the error code from its proto value name or its numeric value.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+		struct ErrorCodeVisitor;
+		impl Visitor<'_> for ErrorCodeVisitor {
+			type Value = i32;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("an error code name or number")
+			}
+			fn visit_i64<E: DeError>(self, value: i64) -> Result<Self::Value, E> {
+				i32::try_from(value).map_err(|_| E::custom("error code out of range"))
+			}
+			fn visit_u64<E: DeError>(self, value: u64) -> Result<Self::Value, E> {
+				i32::try_from(value).map_err(|_| E::custom("error code out of range"))
+			}
+			fn visit_str<E: DeError>(self, value: &str) -> Result<Self::Value, E> {
+				ErrorCode::from_str_name(value)
+					.map(|error_code| error_code as i32)
+					.ok_or_else(|| E::custom(format!("unknown error code: {}", value)))
+			}
+		}
+		deserializer.deserialize_any(ErrorCodeVisitor)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::types::{ErrorCode, ErrorResponse, KeyValue, PutObjectRequest};
+
+	#[test]
+	fn key_values_follow_the_proto3_json_mapping() {
+		let key_value = KeyValue {
+			key: "k1".to_string(),
+			version: 42,
+			value: b"hello".to_vec().into(),
+		};
+		let json = serde_json::to_string(&key_value).unwrap();
+		assert_eq!(json, r#"{"key":"k1","version":"42","value":"aGVsbG8="}"#);
+
+		let parsed: KeyValue = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, key_value);
+	}
+
+	#[test]
+	fn requests_accept_camel_case_and_plain_numbers() {
+		// Hand-written clients may send int64 fields as plain numbers; absent fields take their
+		// proto3 defaults.
+		let request: PutObjectRequest = serde_json::from_str(
+			r#"{"storeId": "s1", "globalVersion": 7,
+				"transactionItems": [{"key": "k1", "version": "0", "value": "aGVsbG8="}]}"#,
+		)
+		.unwrap();
+		assert_eq!(request.store_id, "s1");
+		assert_eq!(request.global_version, Some(7));
+		assert_eq!(request.transaction_items.len(), 1);
+		assert_eq!(request.transaction_items[0].value.as_ref(), b"hello");
+		assert!(request.delete_items.is_empty());
+		assert!(!request.dry_run);
+	}
+
+	#[test]
+	fn error_codes_serialize_by_name() {
+		let error_response = ErrorResponse {
+			error_code: ErrorCode::ConflictException.into(),
+			message: "Version mismatch for key: k1".to_string(),
+			sub_code: "CONFLICT_KEY_VERSION".to_string(),
+		};
+		let json = serde_json::to_value(&error_response).unwrap();
+		assert_eq!(json["errorCode"], "CONFLICT_EXCEPTION");
+
+		let parsed: ErrorResponse = serde_json::from_value(json).unwrap();
+		assert_eq!(parsed, error_response);
+		let numeric: ErrorResponse =
+			serde_json::from_str(r#"{"errorCode": 1, "message": "m", "subCode": ""}"#).unwrap();
+		assert_eq!(numeric.error_code, ErrorCode::ConflictException as i32);
+	}
+}
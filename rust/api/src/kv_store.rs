@@ -0,0 +1,1384 @@
+//! The [`KvStore`] interface implemented by storage backends.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::auth::AuthResponse;
+use crate::error::VssError;
+use crate::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse, RenameObjectRequest,
+	RenameObjectResponse, SwapObjectRequest, SwapObjectResponse,
+};
+
+/// The key used to store the `global_version` of a store.
+///
+/// The global version is maintained as a regular (but reserved) key-version in the store, hence
+/// backends must exclude it from [`KvStore::list_key_versions`] results and clients can read it
+/// through [`KvStore::get`].
+pub const GLOBAL_VERSION_KEY: &str = "vss_global_version";
+
+/// The maximum version number a key (and the store-wide global version) can reach.
+///
+/// Client-supplied versions above this bound (or below `-1`, the non-conditional marker) are
+/// rejected with [`VssError::InvalidRequestError`], so the server-side version increment can
+/// never overflow an `i64` and a key can never be wedged at an unmatchable version.
+pub const MAX_VERSION: i64 = i64::MAX - 1;
+
+/// The maximum number of entries returned in [`GetStoreStatsResponse::largest_keys`].
+pub const STORE_STATS_LARGEST_KEYS: usize = 10;
+
+/// The number of attempts an unconditional [`KvStore::swap`] makes before reporting the conflict
+/// with concurrent writers, see there.
+pub const SWAP_MAX_ATTEMPTS: usize = 4;
+
+/// The per-request context a [`KvStore`] operation is performed under.
+///
+/// Besides the authenticated `user_token` all operations are scoped to, it carries any
+/// [`AuthResponse::attributes`] the authorizer attached to the user, so quota wrappers and
+/// tenant-aware backends can base decisions on who is calling.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestContext {
+	/// The authenticated user all storage operations are scoped to.
+	pub user_token: String,
+	/// Attributes of the authenticated user, see [`AuthResponse::attributes`].
+	pub attributes: HashMap<String, String>,
+}
+
+impl RequestContext {
+	/// Constructs a [`RequestContext`] for the given user without any attributes.
+	pub fn new(user_token: String) -> Self {
+		RequestContext { user_token, attributes: HashMap::new() }
+	}
+}
+
+impl From<AuthResponse> for RequestContext {
+	fn from(auth_response: AuthResponse) -> Self {
+		RequestContext {
+			user_token: auth_response.user_token,
+			attributes: auth_response.attributes,
+		}
+	}
+}
+
+/// An interface to a versioned key-value store, keyed by `user_token`, `store_id` and `key`.
+///
+/// Implementations must provide the conditional-write and versioning semantics documented on the
+/// request types in [`crate::types`], and must be safe for concurrent use.
+#[async_trait]
+pub trait KvStore: Send + Sync {
+	/// Fetches the value and version stored against the requested key.
+	///
+	/// Returns [`VssError::NoSuchKeyError`] if the key does not exist.
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError>;
+
+	/// Writes the provided `transaction_items` and deletes the provided `delete_items` in a
+	/// single all-or-nothing transaction.
+	///
+	/// Returns [`VssError::ConflictError`] if any item's version (or the request's
+	/// `global_version`, if provided) does not match the currently stored version. Non-existent
+	/// keys are treated as version 0: a conditional write of a missing key only succeeds as a
+	/// first write (version 0), while a conditional delete of a missing key always conflicts
+	/// (there is no version 0 row to delete).
+	///
+	/// Returns [`VssError::InvalidRequestError`] for versions outside of `-1..=MAX_VERSION`, see
+	/// [`MAX_VERSION`].
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError>;
+
+	/// Deletes the requested key if the provided version matches the currently stored version.
+	///
+	/// This operation is idempotent: deleting a non-existent key or providing a mismatched
+	/// version is not an error.
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError>;
+
+	/// Lists keys and their versions, optionally filtered by `key_prefix` and paginated
+	/// according to `page_size`/`page_token`.
+	///
+	/// The store's `global_version` is only returned on the first page.
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError>;
+
+	/// Returns keyspace statistics of the requested store: key count, total value bytes, the
+	/// largest keys and the oldest/newest update timestamps, excluding internal bookkeeping
+	/// keys.
+	///
+	/// The default implementation reports the operation as unsupported, for backends without a
+	/// cheap way to aggregate the statistics.
+	async fn get_store_stats(
+		&self, _context: RequestContext, _request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		Err(VssError::InvalidRequestError(
+			"Store statistics are not supported by this backend.".to_string(),
+		))
+	}
+
+	/// Replaces the value stored against a single key and returns the previously stored item in
+	/// the same round trip, sparing clients the get-then-put race window for small coordination
+	/// records (e.g. device-leadership markers).
+	///
+	/// The item's version follows [`KvStore::put`] semantics. An unconditional swap (version -1)
+	/// retries internally up to [`SWAP_MAX_ATTEMPTS`] times when racing concurrent writers, so
+	/// the returned item is always the one the write actually superseded; if all attempts lose
+	/// the race, the last [`VssError::ConflictError`] is returned and the swap has not been
+	/// applied.
+	///
+	/// The default implementation composes [`KvStore::get`] with a conditional [`KvStore::put`]
+	/// (pinned to the fetched version), so every backend supports swaps with full version, quota
+	/// and limit enforcement without dedicated plumbing.
+	async fn swap(
+		&self, context: RequestContext, request: SwapObjectRequest,
+	) -> Result<SwapObjectResponse, VssError> {
+		let item = request
+			.item
+			.ok_or_else(|| VssError::InvalidRequestError("Swap requires an item.".to_string()))?;
+		if item.version < -1 || item.version > MAX_VERSION {
+			return Err(VssError::InvalidRequestError(format!(
+				"Invalid version {} for key: {}",
+				item.version, item.key
+			)));
+		}
+		let unconditional = item.version == -1;
+		let mut attempts = if unconditional { SWAP_MAX_ATTEMPTS } else { 1 };
+		loop {
+			let get_request = GetObjectRequest {
+				store_id: request.store_id.clone(),
+				key: item.key.clone(),
+			};
+			let previous = match self.get(context.clone(), get_request).await {
+				Ok(response) => response.value,
+				Err(VssError::NoSuchKeyError(..)) => None,
+				Err(e) => return Err(e),
+			};
+			let current_version = previous.as_ref().map(|kv| kv.version).unwrap_or(0);
+			if !unconditional && item.version != current_version {
+				return Err(VssError::ConflictError(format!(
+					"Version mismatch for key: {}",
+					item.key
+				)));
+			}
+			let put_request = PutObjectRequest {
+				store_id: request.store_id.clone(),
+				global_version: None,
+				transaction_items: vec![KeyValue {
+					key: item.key.clone(),
+					version: current_version,
+					value: item.value.clone(),
+				}],
+				delete_items: vec![],
+				dry_run: false,
+			};
+			attempts -= 1;
+			match self.put(context.clone(), put_request).await {
+				Ok(..) => return Ok(SwapObjectResponse { previous }),
+				Err(VssError::ConflictError(..)) if unconditional && attempts > 0 => continue,
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	/// Atomically moves the value stored against `from_key` to `to_key`, sparing clients
+	/// migrating their own key schemas the fragile get+put+delete sequence.
+	///
+	/// Both version preconditions follow [`KvStore::put`] semantics: `from_version` must match
+	/// the stored version of the source (-1 moves whatever is currently stored), `to_version`
+	/// that of the destination (0 requires it not to exist yet, -1 overwrites unconditionally).
+	/// The source must exist, otherwise [`VssError::NoSuchKeyError`] is returned.
+	///
+	/// The default implementation composes [`KvStore::get`] with a single [`KvStore::put`]
+	/// carrying the destination as transaction item and the source as delete item, so the move
+	/// executes in one backend transaction with full version, quota and limit enforcement. An
+	/// unconditional source version retries internally up to [`SWAP_MAX_ATTEMPTS`] times when
+	/// racing concurrent writers of the source key.
+	async fn rename(
+		&self, context: RequestContext, request: RenameObjectRequest,
+	) -> Result<RenameObjectResponse, VssError> {
+		if request.from_key == request.to_key {
+			return Err(VssError::InvalidRequestError(
+				"Rename requires distinct keys.".to_string(),
+			));
+		}
+		if request.from_version < -1 || request.from_version > MAX_VERSION {
+			return Err(VssError::InvalidRequestError(format!(
+				"Invalid version {} for key: {}",
+				request.from_version, request.from_key
+			)));
+		}
+		if request.to_version < -1 || request.to_version > MAX_VERSION {
+			return Err(VssError::InvalidRequestError(format!(
+				"Invalid version {} for key: {}",
+				request.to_version, request.to_key
+			)));
+		}
+		let unconditional = request.from_version == -1;
+		let mut attempts = if unconditional { SWAP_MAX_ATTEMPTS } else { 1 };
+		loop {
+			let get_request = GetObjectRequest {
+				store_id: request.store_id.clone(),
+				key: request.from_key.clone(),
+			};
+			let source = match self.get(context.clone(), get_request).await {
+				Ok(response) => response.value,
+				Err(e) => return Err(e),
+			};
+			let source = match source {
+				Some(kv) if kv.version > 0 => kv,
+				// Backends report missing keys either as an error or as a version-0 item.
+				_ => return Err(VssError::NoSuchKeyError(request.from_key.clone())),
+			};
+			if !unconditional && request.from_version != source.version {
+				return Err(VssError::ConflictError(format!(
+					"Version mismatch for key: {}",
+					request.from_key
+				)));
+			}
+			let put_request = PutObjectRequest {
+				store_id: request.store_id.clone(),
+				global_version: None,
+				transaction_items: vec![KeyValue {
+					key: request.to_key.clone(),
+					version: request.to_version,
+					value: source.value,
+				}],
+				delete_items: vec![KeyValue {
+					key: request.from_key.clone(),
+					version: source.version,
+					value: Vec::new().into(),
+				}],
+				dry_run: false,
+			};
+			attempts -= 1;
+			match self.put(context.clone(), put_request).await {
+				Ok(..) => return Ok(RenameObjectResponse {}),
+				// Only retry on an unconditional source version: a conflict may equally stem
+				// from the destination precondition, but re-reading settles that within the
+				// remaining attempts and the final conflict is reported either way.
+				Err(VssError::ConflictError(..)) if unconditional && attempts > 0 => continue,
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	/// Verifies the backend is able to serve requests, e.g. by issuing a trivial query through
+	/// its connection pool. Backs the server's readiness probe, so load balancers stop routing
+	/// traffic to an instance whose database is unreachable.
+	///
+	/// The default implementation reports healthy, fitting backends with no external dependency
+	/// to probe.
+	async fn health_check(&self) -> Result<(), VssError> {
+		Ok(())
+	}
+}
+
+/// Usage statistics of a single store, see [`KvStoreAdmin::get_store_usage`].
+#[derive(Debug, Clone)]
+pub struct StoreUsage {
+	/// The number of keys currently stored in the store, excluding internal bookkeeping keys.
+	pub key_count: i64,
+	/// The total size of all stored values in bytes.
+	pub total_value_bytes: i64,
+}
+
+/// A snapshot of connection-pool statistics, see [`KvStoreAdmin::get_pool_status`].
+#[derive(Debug, Clone)]
+pub struct PoolStatus {
+	/// The number of connections currently managed by the pool.
+	pub connections: u32,
+	/// The number of currently idle connections.
+	pub idle_connections: u32,
+	/// The number of checkouts currently waiting for a connection.
+	pub pending_checkouts: u64,
+	/// The total number of checkouts served without waiting.
+	pub checkouts_direct: u64,
+	/// The total number of checkouts which had to wait for a connection.
+	pub checkouts_waited: u64,
+	/// The total number of checkouts which timed out waiting for a connection.
+	pub checkouts_timed_out: u64,
+	/// The total time spent waiting for connections, in microseconds.
+	pub checkout_wait_time_micros: u64,
+	/// The total number of connections created over the lifetime of the pool.
+	pub connections_created: u64,
+}
+
+/// Administrative operations on a [`KvStore`], not exposed through the client-facing VSS API.
+///
+/// Backends implement this alongside [`KvStore`] to support the server's admin API.
+#[async_trait]
+pub trait KvStoreAdmin: Send + Sync {
+	/// Lists the ids of all stores written by the given user.
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError>;
+
+	/// Returns usage statistics of the given store.
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError>;
+
+	/// Returns a snapshot of the backend's connection-pool statistics, or `None` for backends
+	/// without a connection pool.
+	async fn get_pool_status(&self) -> Result<Option<PoolStatus>, VssError> {
+		Ok(None)
+	}
+}
+
+/// Defines a conformance test-suite which every [`KvStore`] implementation is expected to pass.
+///
+/// Callers provide a name for the generated test module, the concrete store type and an
+/// expression constructing a fresh store instance (evaluated in an async context, so `.await` is
+/// allowed). The generated tests require `tokio` as a dev-dependency at the call site.
+///
+/// Tests use unique `user_token`s so they can safely run concurrently and against shared,
+/// persistent backends.
+#[macro_export]
+macro_rules! define_kv_store_tests {
+	($test_suite_name:ident, $store_type:ty, $create_store:expr) => {
+		mod $test_suite_name {
+			use super::*;
+			use $crate::error::VssError;
+			use $crate::types::{
+				DeleteObjectRequest, GetObjectRequest, KeyValue, ListKeyVersionsRequest,
+				PutObjectRequest,
+			};
+
+			fn unique_context(test_name: &str) -> $crate::kv_store::RequestContext {
+				let nanos = std::time::SystemTime::now()
+					.duration_since(std::time::UNIX_EPOCH)
+					.unwrap()
+					.as_nanos();
+				$crate::kv_store::RequestContext::new(format!(
+					"{}-{}-{}",
+					stringify!($test_suite_name),
+					test_name,
+					nanos
+				))
+			}
+
+			fn put_request(store_id: &str, key: &str, version: i64, value: &[u8]) -> PutObjectRequest {
+				PutObjectRequest {
+					store_id: store_id.to_string(),
+					global_version: None,
+					transaction_items: vec![KeyValue {
+						key: key.to_string(),
+						version,
+						value: value.to_vec().into(),
+					}],
+					delete_items: vec![],
+					dry_run: false,
+				}
+			}
+
+			fn get_request(store_id: &str, key: &str) -> GetObjectRequest {
+				GetObjectRequest { store_id: store_id.to_string(), key: key.to_string() }
+			}
+
+			#[tokio::test]
+			async fn put_get_roundtrip() {
+				let store: $store_type = $create_store;
+				let context = unique_context("put_get_roundtrip");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				let key_value = response.value.unwrap();
+				assert_eq!(key_value.key, "k1");
+				assert_eq!(key_value.version, 1);
+				assert_eq!(key_value.value, b"v1"[..]);
+			}
+
+			#[tokio::test]
+			async fn get_non_existent_key_fails() {
+				let store: $store_type = $create_store;
+				let context = unique_context("get_non_existent_key_fails");
+
+				let result = store.get(context, get_request("store", "missing")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+			}
+
+			#[tokio::test]
+			async fn conditional_put_with_mismatched_version_fails() {
+				let store: $store_type = $create_store;
+				let context = unique_context("conditional_put_with_mismatched_version_fails");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				// Re-using the same version must conflict, the incremented version must succeed.
+				let result =
+					store.put(context.clone(), put_request("store", "k1", 0, b"v2")).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				store
+					.put(context.clone(), put_request("store", "k1", 1, b"v2"))
+					.await
+					.unwrap();
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().value, b"v2"[..]);
+			}
+
+			#[tokio::test]
+			async fn unconditional_put_ignores_version() {
+				let store: $store_type = $create_store;
+				let context = unique_context("unconditional_put_ignores_version");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+				store
+					.put(context.clone(), put_request("store", "k1", -1, b"v2"))
+					.await
+					.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				let key_value = response.value.unwrap();
+				assert_eq!(key_value.version, 2);
+				assert_eq!(key_value.value, b"v2"[..]);
+			}
+
+			#[tokio::test]
+			async fn global_version_conflict_detection() {
+				let store: $store_type = $create_store;
+				let context = unique_context("global_version_conflict_detection");
+
+				let mut request = put_request("store", "k1", 0, b"v1");
+				request.global_version = Some(0);
+				store.put(context.clone(), request).await.unwrap();
+
+				let mut request = put_request("store", "k2", 0, b"v2");
+				request.global_version = Some(0);
+				let result = store.put(context.clone(), request).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				let mut request = put_request("store", "k2", 0, b"v2");
+				request.global_version = Some(1);
+				store.put(context.clone(), request).await.unwrap();
+			}
+
+			#[tokio::test]
+			async fn transaction_is_all_or_nothing() {
+				let store: $store_type = $create_store;
+				let context = unique_context("transaction_is_all_or_nothing");
+
+				store
+					.put(context.clone(), put_request("store", "k2", 0, b"v2"))
+					.await
+					.unwrap();
+
+				// "k1" is a valid first write, but "k2" conflicts, hence neither must be applied.
+				let request = PutObjectRequest {
+					store_id: "store".to_string(),
+					global_version: None,
+					transaction_items: vec![
+						KeyValue { key: "k1".to_string(), version: 0, value: b"v1".to_vec().into() },
+						KeyValue { key: "k2".to_string(), version: 0, value: b"v2'".to_vec().into() },
+					],
+					delete_items: vec![],
+					dry_run: false,
+				};
+				let result = store.put(context.clone(), request).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+				let response =
+					store.get(context.clone(), get_request("store", "k2")).await.unwrap();
+				assert_eq!(response.value.unwrap().value, b"v2"[..]);
+			}
+
+			#[tokio::test]
+			async fn dry_run_put_commits_nothing() {
+				let store: $store_type = $create_store;
+				let context = unique_context("dry_run_put_commits_nothing");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				// A dry run reports conflicts exactly like a real write...
+				let mut request = put_request("store", "k1", 3, b"v1'");
+				request.dry_run = true;
+				let result = store.put(context.clone(), request).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				// ...and a passing dry run must leave both touched and new keys unchanged.
+				let mut request = put_request("store", "k1", 1, b"v1'");
+				request.transaction_items.push(KeyValue {
+					key: "k2".to_string(),
+					version: 0,
+					value: b"v2".to_vec().into(),
+				});
+				request.dry_run = true;
+				store.put(context.clone(), request).await.unwrap();
+
+				let response = store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				let kv = response.value.unwrap();
+				assert_eq!(kv.version, 1);
+				assert_eq!(kv.value, b"v1"[..]);
+				let result = store.get(context.clone(), get_request("store", "k2")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+			}
+
+			#[tokio::test]
+			async fn swap_returns_previous_value() {
+				let store: $store_type = $create_store;
+				let context = unique_context("swap_returns_previous_value");
+
+				let swap_request = |key: &str, version: i64, value: &[u8]| {
+					$crate::types::SwapObjectRequest {
+						store_id: "store".to_string(),
+						item: Some(KeyValue {
+							key: key.to_string(),
+							version,
+							value: value.to_vec().into(),
+						}),
+					}
+				};
+
+				// A swap of a missing key is a first write and reports no previous item.
+				let response =
+					store.swap(context.clone(), swap_request("k1", 0, b"v1")).await.unwrap();
+				assert!(response.previous.is_none());
+
+				// A conditional swap enforces the stored version...
+				let result = store.swap(context.clone(), swap_request("k1", 3, b"v2")).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				// ...and returns the superseded item on a match, as does an unconditional swap.
+				let response =
+					store.swap(context.clone(), swap_request("k1", 1, b"v2")).await.unwrap();
+				let previous = response.previous.unwrap();
+				assert_eq!(previous.version, 1);
+				assert_eq!(previous.value, b"v1"[..]);
+
+				let response =
+					store.swap(context.clone(), swap_request("k1", -1, b"v3")).await.unwrap();
+				let previous = response.previous.unwrap();
+				assert_eq!(previous.version, 2);
+				assert_eq!(previous.value, b"v2"[..]);
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				let kv = response.value.unwrap();
+				assert_eq!(kv.version, 3);
+				assert_eq!(kv.value, b"v3"[..]);
+			}
+
+			#[tokio::test]
+			async fn rename_moves_value_atomically() {
+				let store: $store_type = $create_store;
+				let context = unique_context("rename_moves_value_atomically");
+
+				let rename_request = |from_key: &str, from_version: i64, to_key: &str,
+				                      to_version: i64| {
+					$crate::types::RenameObjectRequest {
+						store_id: "store".to_string(),
+						from_key: from_key.to_string(),
+						from_version,
+						to_key: to_key.to_string(),
+						to_version,
+					}
+				};
+
+				// A missing source cannot be renamed.
+				let result =
+					store.rename(context.clone(), rename_request("k1", -1, "k2", 0)).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				// Both preconditions are enforced: a stale source version conflicts...
+				let result =
+					store.rename(context.clone(), rename_request("k1", 3, "k2", 0)).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				// ...as does an already-existing destination under a create-only precondition.
+				store
+					.put(context.clone(), put_request("store", "k2", 0, b"v2"))
+					.await
+					.unwrap();
+				let result =
+					store.rename(context.clone(), rename_request("k1", 1, "k2", 0)).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+
+				// A matching rename moves the value and removes the source.
+				store.rename(context.clone(), rename_request("k1", 1, "k2", 1)).await.unwrap();
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+				let response =
+					store.get(context.clone(), get_request("store", "k2")).await.unwrap();
+				let kv = response.value.unwrap();
+				assert_eq!(kv.version, 2);
+				assert_eq!(kv.value, b"v1"[..]);
+
+				// Renaming a key onto itself is rejected outright.
+				let result =
+					store.rename(context.clone(), rename_request("k2", -1, "k2", -1)).await;
+				assert!(matches!(result, Err(VssError::InvalidRequestError(..))));
+			}
+
+			#[tokio::test]
+			async fn conditional_put_of_missing_key_requires_version_zero() {
+				let store: $store_type = $create_store;
+				let context =
+					unique_context("conditional_put_of_missing_key_requires_version_zero");
+
+				// Missing keys are treated as version 0: a conditional write with any other
+				// version must conflict instead of creating the key.
+				let result =
+					store.put(context.clone(), put_request("store", "k1", 3, b"v1")).await;
+				assert!(matches!(result, Err(VssError::ConflictError(..))));
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().version, 1);
+			}
+
+			#[tokio::test]
+			async fn conditional_delete_of_missing_key_conflicts() {
+				let store: $store_type = $create_store;
+				let context = unique_context("conditional_delete_of_missing_key_conflicts");
+
+				// Within a put transaction, a conditional delete of a missing key must conflict
+				// for any version (including 0: there is no version 0 row to delete)...
+				for version in [0, 1] {
+					let request = PutObjectRequest {
+						store_id: "store".to_string(),
+						global_version: None,
+						transaction_items: vec![],
+						delete_items: vec![KeyValue {
+							key: "missing".to_string(),
+							version,
+							value: Default::default(),
+						}],
+						dry_run: false,
+					};
+					let result = store.put(context.clone(), request).await;
+					assert!(matches!(result, Err(VssError::ConflictError(..))));
+				}
+
+				// ...while the standalone delete operation stays idempotent.
+				let delete_request = DeleteObjectRequest {
+					store_id: "store".to_string(),
+					key_value: Some(KeyValue {
+						key: "missing".to_string(),
+						version: 0,
+						value: Default::default(),
+					}),
+				};
+				store.delete(context.clone(), delete_request).await.unwrap();
+			}
+
+			#[tokio::test]
+			async fn put_with_duplicate_keys_is_rejected() {
+				let store: $store_type = $create_store;
+				let context = unique_context("put_with_duplicate_keys_is_rejected");
+
+				// The proto requires distinct keys per request, both within `transaction_items`
+				// and across `transaction_items` and `delete_items`.
+				let request = PutObjectRequest {
+					store_id: "store".to_string(),
+					global_version: None,
+					transaction_items: vec![
+						KeyValue { key: "k1".to_string(), version: 0, value: b"v1".to_vec().into() },
+						KeyValue { key: "k1".to_string(), version: 0, value: b"v2".to_vec().into() },
+					],
+					delete_items: vec![],
+					dry_run: false,
+				};
+				let result = store.put(context.clone(), request).await;
+				assert!(matches!(result, Err(VssError::InvalidRequestError(..))));
+
+				let request = PutObjectRequest {
+					store_id: "store".to_string(),
+					global_version: None,
+					transaction_items: vec![KeyValue {
+						key: "k1".to_string(),
+						version: 0,
+						value: b"v1".to_vec().into(),
+					}],
+					delete_items: vec![KeyValue {
+						key: "k1".to_string(),
+						version: -1,
+						value: Default::default(),
+					}],
+					dry_run: false,
+				};
+				let result = store.put(context.clone(), request).await;
+				assert!(matches!(result, Err(VssError::InvalidRequestError(..))));
+
+				// Neither rejected request must have written anything.
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+			}
+
+			#[tokio::test]
+			async fn absurd_versions_are_rejected() {
+				let store: $store_type = $create_store;
+				let context = unique_context("absurd_versions_are_rejected");
+
+				// Versions above `MAX_VERSION` could never have been handed out; accepting them
+				// would let the server-side increment overflow and wedge the key.
+				for version in [i64::MAX, $crate::kv_store::MAX_VERSION + 1, -2, i64::MIN] {
+					let result = store
+						.put(context.clone(), put_request("store", "k1", version, b"v1"))
+						.await;
+					assert!(matches!(result, Err(VssError::InvalidRequestError(..))));
+				}
+
+				// The global version counter is bounded the same way (and must not be negative,
+				// as it has no non-conditional marker).
+				for global_version in [i64::MAX, $crate::kv_store::MAX_VERSION + 1, -1] {
+					let mut request = put_request("store", "k1", 0, b"v1");
+					request.global_version = Some(global_version);
+					let result = store.put(context.clone(), request).await;
+					assert!(matches!(result, Err(VssError::InvalidRequestError(..))));
+				}
+
+				// The rejected requests must not have wedged the key: it is still a fresh
+				// version 0 write.
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+				let mut request = put_request("store", "k1", 0, b"v1");
+				request.global_version = Some(0);
+				store.put(context.clone(), request).await.unwrap();
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().version, 1);
+			}
+
+			#[tokio::test]
+			async fn delete_is_idempotent() {
+				let store: $store_type = $create_store;
+				let context = unique_context("delete_is_idempotent");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				let delete_request = DeleteObjectRequest {
+					store_id: "store".to_string(),
+					key_value: Some(KeyValue {
+						key: "k1".to_string(),
+						version: 1,
+						value: Default::default(),
+					}),
+				};
+				store.delete(context.clone(), delete_request.clone()).await.unwrap();
+
+				let result = store.get(context.clone(), get_request("store", "k1")).await;
+				assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+
+				// Deleting a non-existent key must still succeed.
+				store.delete(context.clone(), delete_request).await.unwrap();
+			}
+
+			#[tokio::test]
+			async fn delete_with_mismatched_version_is_noop() {
+				let store: $store_type = $create_store;
+				let context = unique_context("delete_with_mismatched_version_is_noop");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				let delete_request = DeleteObjectRequest {
+					store_id: "store".to_string(),
+					key_value: Some(KeyValue {
+						key: "k1".to_string(),
+						version: 5,
+						value: Default::default(),
+					}),
+				};
+				store.delete(context.clone(), delete_request).await.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().value, b"v1"[..]);
+			}
+
+			#[tokio::test]
+			async fn list_key_versions_paginates() {
+				let store: $store_type = $create_store;
+				let context = unique_context("list_key_versions_paginates");
+
+				for key in ["k1", "k2", "k3", "k4", "k5"] {
+					store
+						.put(context.clone(), put_request("store", key, 0, b"v"))
+						.await
+						.unwrap();
+				}
+
+				let mut listed_keys = Vec::new();
+				let mut page_token: Option<String> = None;
+				let mut first_page = true;
+				loop {
+					let request = ListKeyVersionsRequest {
+						store_id: "store".to_string(),
+						key_prefix: None,
+						page_size: Some(2),
+						page_token: page_token.clone(),
+					};
+					let response =
+						store.list_key_versions(context.clone(), request).await.unwrap();
+
+					// The global version must only be returned on the first page.
+					assert_eq!(response.global_version.is_some(), first_page);
+					first_page = false;
+
+					for key_version in &response.key_versions {
+						assert_eq!(key_version.version, 1);
+						assert!(key_version.value.is_empty());
+						listed_keys.push(key_version.key.clone());
+					}
+					match response.next_page_token {
+						Some(token) if !token.is_empty() => page_token = Some(token),
+						_ => break,
+					}
+				}
+				listed_keys.sort();
+				assert_eq!(listed_keys, vec!["k1", "k2", "k3", "k4", "k5"]);
+			}
+
+			#[tokio::test]
+			async fn multi_megabyte_value_roundtrip() {
+				let store: $store_type = $create_store;
+				let context = unique_context("multi_megabyte_value_roundtrip");
+
+				// Channel monitors of busy nodes can grow to multiple megabytes.
+				let value: Vec<u8> = (0..3 * 1024 * 1024).map(|idx| idx as u8).collect();
+				store
+					.put(context.clone(), put_request("store", "k1", 0, &value))
+					.await
+					.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().value, value);
+			}
+
+			#[tokio::test]
+			async fn empty_value_roundtrip() {
+				let store: $store_type = $create_store;
+				let context = unique_context("empty_value_roundtrip");
+
+				store.put(context.clone(), put_request("store", "k1", 0, b"")).await.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				let key_value = response.value.unwrap();
+				assert_eq!(key_value.version, 1);
+				assert!(key_value.value.is_empty());
+
+				// An empty value must still be distinguishable from a missing key when listing.
+				let request = ListKeyVersionsRequest {
+					store_id: "store".to_string(),
+					key_prefix: None,
+					page_size: None,
+					page_token: None,
+				};
+				let response =
+					store.list_key_versions(context.clone(), request).await.unwrap();
+				assert_eq!(response.key_versions.len(), 1);
+			}
+
+			#[tokio::test]
+			async fn maximum_length_key_roundtrip() {
+				let store: $store_type = $create_store;
+				let context = unique_context("maximum_length_key_roundtrip");
+
+				// The PostgreSQL schema bounds keys at 600 characters; all backends must accept
+				// keys up to that length.
+				let key = "k".repeat(600);
+				store
+					.put(context.clone(), put_request("store", &key, 0, b"v1"))
+					.await
+					.unwrap();
+
+				let response =
+					store.get(context.clone(), get_request("store", &key)).await.unwrap();
+				assert_eq!(response.value.unwrap().key, key);
+			}
+
+			#[tokio::test]
+			async fn unicode_keys_roundtrip() {
+				let store: $store_type = $create_store;
+				let context = unique_context("unicode_keys_roundtrip");
+
+				for key in ["ключ-1", "鍵-2", "clé-🔑"] {
+					store
+						.put(context.clone(), put_request("store", key, 0, b"v"))
+						.await
+						.unwrap();
+					let response =
+						store.get(context.clone(), get_request("store", key)).await.unwrap();
+					assert_eq!(response.value.unwrap().key, key);
+				}
+
+				let request = ListKeyVersionsRequest {
+					store_id: "store".to_string(),
+					key_prefix: Some("ключ-".to_string()),
+					page_size: None,
+					page_token: None,
+				};
+				let response =
+					store.list_key_versions(context.clone(), request).await.unwrap();
+				assert_eq!(response.key_versions.len(), 1);
+				assert_eq!(response.key_versions[0].key, "ключ-1");
+			}
+
+			#[tokio::test]
+			async fn put_transaction_at_item_limit() {
+				let store: $store_type = $create_store;
+				let context = unique_context("put_transaction_at_item_limit");
+
+				// A put carrying the protocol's full 1000-item envelope must be applied
+				// atomically.
+				let transaction_items = (0..1000)
+					.map(|idx| KeyValue {
+						key: format!("k{:04}", idx),
+						version: 0,
+						value: b"v".to_vec().into(),
+					})
+					.collect();
+				let request = PutObjectRequest {
+					store_id: "store".to_string(),
+					global_version: None,
+					transaction_items,
+					delete_items: vec![],
+					dry_run: false,
+				};
+				store.put(context.clone(), request).await.unwrap();
+
+				let mut listed = 0;
+				let mut page_token: Option<String> = None;
+				loop {
+					let request = ListKeyVersionsRequest {
+						store_id: "store".to_string(),
+						key_prefix: None,
+						page_size: Some(400),
+						page_token: page_token.clone(),
+					};
+					let response =
+						store.list_key_versions(context.clone(), request).await.unwrap();
+					listed += response.key_versions.len();
+					match response.next_page_token {
+						Some(token) if !token.is_empty() => page_token = Some(token),
+						_ => break,
+					}
+				}
+				assert_eq!(listed, 1000);
+			}
+
+			#[tokio::test]
+			async fn concurrent_conditional_puts_have_one_winner() {
+				let store = std::sync::Arc::new($create_store);
+				let context = unique_context("concurrent_conditional_puts_have_one_winner");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v1"))
+					.await
+					.unwrap();
+
+				// All writers race the same conditional put; exactly one must win and the rest
+				// must observe a conflict.
+				let mut tasks = Vec::new();
+				for writer in 0..8u8 {
+					let store = std::sync::Arc::clone(&store);
+					let context = context.clone();
+					tasks.push(tokio::spawn(async move {
+						store
+							.put(context, put_request("store", "k1", 1, &[writer]))
+							.await
+					}));
+				}
+				let mut successes = 0;
+				for task in tasks {
+					match task.await.unwrap() {
+						Ok(_) => successes += 1,
+						Err(VssError::ConflictError(..)) => {},
+						Err(e) => panic!("Unexpected error: {}", e),
+					}
+				}
+				assert_eq!(successes, 1);
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().version, 2);
+			}
+
+			#[tokio::test]
+			async fn concurrent_updates_are_not_lost() {
+				let store = std::sync::Arc::new($create_store);
+				let context = unique_context("concurrent_updates_are_not_lost");
+
+				store
+					.put(context.clone(), put_request("store", "k1", 0, b"v"))
+					.await
+					.unwrap();
+
+				// Writers retry conditional puts until they land a fixed number of updates, while
+				// stale conditional deletes race them and must never take effect. If any update
+				// were lost, the final version would fall short of the success count.
+				const WRITERS: u64 = 4;
+				const UPDATES_PER_WRITER: u64 = 10;
+				let mut tasks = Vec::new();
+				for _ in 0..WRITERS {
+					let store = std::sync::Arc::clone(&store);
+					let context = context.clone();
+					tasks.push(tokio::spawn(async move {
+						let mut updates = 0;
+						while updates < UPDATES_PER_WRITER {
+							let response = store
+								.get(context.clone(), get_request("store", "k1"))
+								.await
+								.unwrap();
+							let version = response.value.unwrap().version;
+
+							let delete_request = DeleteObjectRequest {
+								store_id: "store".to_string(),
+								key_value: Some(KeyValue {
+									key: "k1".to_string(),
+									version: version + 100,
+									value: Default::default(),
+								}),
+							};
+							store.delete(context.clone(), delete_request).await.unwrap();
+
+							let result = store
+								.put(context.clone(), put_request("store", "k1", version, b"v"))
+								.await;
+							match result {
+								Ok(_) => updates += 1,
+								Err(VssError::ConflictError(..)) => {},
+								Err(e) => panic!("Unexpected error: {}", e),
+							}
+						}
+					}));
+				}
+				for task in tasks {
+					task.await.unwrap();
+				}
+
+				let response =
+					store.get(context.clone(), get_request("store", "k1")).await.unwrap();
+				assert_eq!(response.value.unwrap().version as u64, 1 + WRITERS * UPDATES_PER_WRITER);
+			}
+
+			#[tokio::test]
+			async fn list_key_versions_filters_by_prefix() {
+				let store: $store_type = $create_store;
+				let context = unique_context("list_key_versions_filters_by_prefix");
+
+				for key in ["prefix-k1", "prefix-k2", "other-k1"] {
+					store
+						.put(context.clone(), put_request("store", key, 0, b"v"))
+						.await
+						.unwrap();
+				}
+
+				let request = ListKeyVersionsRequest {
+					store_id: "store".to_string(),
+					key_prefix: Some("prefix-".to_string()),
+					page_size: None,
+					page_token: None,
+				};
+				let response = store.list_key_versions(context, request).await.unwrap();
+				let mut listed_keys: Vec<String> =
+					response.key_versions.into_iter().map(|kv| kv.key).collect();
+				listed_keys.sort();
+				assert_eq!(listed_keys, vec!["prefix-k1", "prefix-k2"]);
+			}
+
+			#[tokio::test]
+			async fn store_stats_reflect_keyspace() {
+				let store: $store_type = $create_store;
+				let context = unique_context("store_stats_reflect_keyspace");
+
+				store
+					.put(context.clone(), put_request("store", "small", 0, b"v"))
+					.await
+					.unwrap();
+				store
+					.put(context.clone(), put_request("store", "large", 0, &[0u8; 1024]))
+					.await
+					.unwrap();
+
+				let request =
+					$crate::types::GetStoreStatsRequest { store_id: "store".to_string() };
+				let stats = store.get_store_stats(context, request).await.unwrap();
+				assert_eq!(stats.key_count, 2);
+				assert_eq!(stats.total_value_bytes, 1025);
+				assert_eq!(stats.largest_keys[0].key, "large");
+				assert_eq!(stats.largest_keys[0].value_bytes, 1024);
+				assert!(stats.oldest_updated_at_millis > 0);
+				assert!(stats.newest_updated_at_millis >= stats.oldest_updated_at_millis);
+			}
+		}
+	};
+}
+
+/// Defines a property-based test checking a [`KvStore`] implementation against a model
+/// implementation (typically the in-memory store): random sequences of put/delete/get/list
+/// operations are applied to both and all observable behavior must match.
+///
+/// Callers provide a name for the generated test module, the store type and a constructor
+/// expression, and the model type and a constructor expression (both evaluated in an async
+/// context). The generated test requires `tokio` and `proptest` as dev-dependencies at the call
+/// site.
+#[macro_export]
+macro_rules! define_kv_store_model_tests {
+	($test_suite_name:ident, $store_type:ty, $create_store:expr, $model_type:ty, $create_model:expr) => {
+		mod $test_suite_name {
+			use super::*;
+			use proptest::prelude::*;
+			use $crate::error::VssError;
+			use $crate::kv_store::KvStore;
+			use $crate::types::{
+				DeleteObjectRequest, GetObjectRequest, KeyValue, ListKeyVersionsRequest,
+				PutObjectRequest,
+			};
+
+			/// A single randomly generated store operation. Versions are resolved against the
+			/// model at execution time: `stale` requests deliberately mismatch the stored version
+			/// of an existing key, so conflicts and no-op deletes are exercised deterministically.
+			#[derive(Clone, Debug)]
+			enum Op {
+				Put { key_idx: usize, conditional: bool, stale: bool, value: Vec<u8> },
+				Delete { key_idx: usize, stale: bool },
+				Get { key_idx: usize },
+				List { key_prefix: Option<String>, page_size: i32 },
+			}
+
+			fn op_strategy() -> impl Strategy<Value = Op> {
+				prop_oneof![
+					(
+						0..5usize,
+						any::<bool>(),
+						any::<bool>(),
+						proptest::collection::vec(any::<u8>(), 0..8)
+					)
+						.prop_map(|(key_idx, conditional, stale, value)| Op::Put {
+							key_idx,
+							conditional,
+							stale,
+							value
+						}),
+					(0..5usize, any::<bool>())
+						.prop_map(|(key_idx, stale)| Op::Delete { key_idx, stale }),
+					(0..5usize).prop_map(|key_idx| Op::Get { key_idx }),
+					(
+						prop_oneof![
+							Just(None),
+							Just(Some("k".to_string())),
+							Just(Some("k1".to_string()))
+						],
+						1..4i32
+					)
+						.prop_map(|(key_prefix, page_size)| Op::List { key_prefix, page_size }),
+				]
+			}
+
+			fn unique_user_token() -> String {
+				let nanos = std::time::SystemTime::now()
+					.duration_since(std::time::UNIX_EPOCH)
+					.unwrap()
+					.as_nanos();
+				format!("{}-{}", stringify!($test_suite_name), nanos)
+			}
+
+			fn ctx(user_token: &str) -> $crate::kv_store::RequestContext {
+				$crate::kv_store::RequestContext::new(user_token.to_string())
+			}
+
+			async fn stored_version<S: KvStore>(store: &S, user_token: &str, key: &str) -> i64 {
+				let request =
+					GetObjectRequest { store_id: "store".to_string(), key: key.to_string() };
+				match store.get(ctx(user_token), request).await {
+					Ok(response) => response.value.unwrap().version,
+					Err(_) => 0,
+				}
+			}
+
+			async fn list_all<S: KvStore>(
+				store: &S, user_token: &str, key_prefix: &Option<String>, page_size: i32,
+			) -> (Vec<(String, i64)>, Option<i64>) {
+				let mut listed = Vec::new();
+				let mut first_page_global_version = None;
+				let mut page_token: Option<String> = None;
+				let mut first_page = true;
+				loop {
+					let request = ListKeyVersionsRequest {
+						store_id: "store".to_string(),
+						key_prefix: key_prefix.clone(),
+						page_size: Some(page_size),
+						page_token: page_token.clone(),
+					};
+					let response = store
+						.list_key_versions(ctx(user_token), request)
+						.await
+						.unwrap();
+					if first_page {
+						first_page_global_version = response.global_version;
+						first_page = false;
+					}
+					listed.extend(
+						response.key_versions.into_iter().map(|kv| (kv.key, kv.version)),
+					);
+					match response.next_page_token {
+						Some(token) if !token.is_empty() => page_token = Some(token),
+						_ => break,
+					}
+				}
+				listed.sort();
+				(listed, first_page_global_version)
+			}
+
+			async fn run_ops(
+				store: &$store_type, model: &$model_type, user_token: &str, ops: Vec<Op>,
+			) {
+				for op in ops {
+					match op {
+						Op::Put { key_idx, conditional, stale, value } => {
+							let key = format!("k{}", key_idx);
+							let version = if conditional {
+								let current = stored_version(model, user_token, &key).await;
+								// For missing keys (current version 0), a stale put is any
+								// version but 0 and must conflict on every backend.
+								if stale {
+									current + 1
+								} else {
+									current
+								}
+							} else {
+								-1
+							};
+							let request = PutObjectRequest {
+								store_id: "store".to_string(),
+								global_version: None,
+								transaction_items: vec![KeyValue {
+									key,
+									version,
+									value: value.clone().into(),
+								}],
+								delete_items: vec![],
+								dry_run: false,
+							};
+							let store_result =
+								store.put(ctx(user_token), request.clone()).await;
+							let model_result = model.put(ctx(user_token), request).await;
+							match (&store_result, &model_result) {
+								(Ok(_), Ok(_)) => {},
+								(
+									Err(VssError::ConflictError(..)),
+									Err(VssError::ConflictError(..)),
+								) => {},
+								_ => panic!(
+									"Put outcomes diverged: store {:?}, model {:?}",
+									store_result.is_ok(),
+									model_result.is_ok()
+								),
+							}
+						},
+						Op::Delete { key_idx, stale } => {
+							let key = format!("k{}", key_idx);
+							let current = stored_version(model, user_token, &key).await;
+							let version = if stale { current + 1 } else { current };
+							let request = DeleteObjectRequest {
+								store_id: "store".to_string(),
+								key_value: Some(KeyValue {
+									key,
+									version,
+									value: Default::default(),
+								}),
+							};
+							store.delete(ctx(user_token), request.clone()).await.unwrap();
+							model.delete(ctx(user_token), request).await.unwrap();
+						},
+						Op::Get { key_idx } => {
+							let key = format!("k{}", key_idx);
+							let request = GetObjectRequest {
+								store_id: "store".to_string(),
+								key: key.clone(),
+							};
+							let store_result =
+								store.get(ctx(user_token), request.clone()).await;
+							let model_result = model.get(ctx(user_token), request).await;
+							match (store_result, model_result) {
+								(Ok(store_response), Ok(model_response)) => {
+									let stored = store_response.value.unwrap();
+									let modeled = model_response.value.unwrap();
+									assert_eq!(stored.key, modeled.key);
+									assert_eq!(stored.version, modeled.version);
+									assert_eq!(stored.value, modeled.value);
+								},
+								(
+									Err(VssError::NoSuchKeyError(..)),
+									Err(VssError::NoSuchKeyError(..)),
+								) => {},
+								(store_result, model_result) => panic!(
+									"Get outcomes diverged for key {}: store {:?}, model {:?}",
+									key,
+									store_result.is_ok(),
+									model_result.is_ok()
+								),
+							}
+						},
+						Op::List { key_prefix, page_size } => {
+							let store_listing =
+								list_all(store, user_token, &key_prefix, page_size).await;
+							let model_listing =
+								list_all(model, user_token, &key_prefix, page_size).await;
+							assert_eq!(store_listing, model_listing);
+						},
+					}
+				}
+
+				// Beyond per-operation equivalence, the final states must match in full.
+				let store_listing = list_all(store, user_token, &None, 1).await;
+				let model_listing = list_all(model, user_token, &None, 1).await;
+				assert_eq!(store_listing, model_listing);
+			}
+
+			proptest! {
+				#![proptest_config(ProptestConfig::with_cases(32))]
+				#[test]
+				fn store_matches_model(ops in proptest::collection::vec(op_strategy(), 1..40)) {
+					let runtime = tokio::runtime::Runtime::new().unwrap();
+					runtime.block_on(async {
+						let store: $store_type = $create_store;
+						let model: $model_type = $create_model;
+						let user_token = unique_user_token();
+						run_ops(&store, &model, &user_token, ops).await;
+					});
+				}
+			}
+		}
+	};
+}
@@ -0,0 +1,19 @@
+//! Interface definitions for the Versioned Storage Service (VSS).
+//!
+//! This crate defines the request/response types of the VSS API (generated from
+//! `app/src/main/proto/vss.proto`), the [`KvStore`] interface implemented by storage backends,
+//! and the [`Authorizer`] interface implemented by authentication/authorization providers.
+//!
+//! [`KvStore`]: crate::kv_store::KvStore
+//! [`Authorizer`]: crate::auth::Authorizer
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(missing_docs)]
+
+pub mod auth;
+pub mod error;
+pub mod json;
+pub mod kv_store;
+#[cfg(feature = "test-utils")]
+pub mod mock;
+pub mod types;
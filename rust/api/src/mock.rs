@@ -0,0 +1,310 @@
+//! A programmable mock [`KvStore`] and latency-injection wrapper for testing code built on top
+//! of the VSS interfaces.
+//!
+//! Only available with the `test-utils` feature enabled.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+
+use crate::error::VssError;
+use crate::kv_store::{KvStore, RequestContext};
+use crate::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	ListKeyVersionsRequest, ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+struct Scripted<T> {
+	result: Result<T, VssError>,
+	delay: Option<Duration>,
+}
+
+/// A call received by a [`MockKvStore`], see [`MockKvStore::take_calls`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockCall {
+	/// A [`KvStore::get`] call with the given context and request.
+	Get(RequestContext, GetObjectRequest),
+	/// A [`KvStore::put`] call with the given context and request.
+	Put(RequestContext, PutObjectRequest),
+	/// A [`KvStore::delete`] call with the given context and request.
+	Delete(RequestContext, DeleteObjectRequest),
+	/// A [`KvStore::list_key_versions`] call with the given context and request.
+	ListKeyVersions(RequestContext, ListKeyVersionsRequest),
+}
+
+#[derive(Default)]
+struct Inner {
+	get_responses: VecDeque<Scripted<GetObjectResponse>>,
+	put_responses: VecDeque<Scripted<PutObjectResponse>>,
+	delete_responses: VecDeque<Scripted<DeleteObjectResponse>>,
+	list_responses: VecDeque<Scripted<ListKeyVersionsResponse>>,
+	calls: Vec<MockCall>,
+}
+
+/// A [`KvStore`] whose responses, errors and latencies are scripted per call, allowing
+/// deterministic tests of middleware and embedders without a database.
+///
+/// Responses are scripted per operation in FIFO order via [`MockKvStore::script_get`] and
+/// friends; a call with no remaining scripted response fails with
+/// [`VssError::InternalServerError`]. All received calls are recorded and can be asserted on via
+/// [`MockKvStore::take_calls`].
+#[derive(Default)]
+pub struct MockKvStore {
+	inner: Mutex<Inner>,
+}
+
+impl MockKvStore {
+	/// Constructs a [`MockKvStore`] with no scripted responses.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::get`] call.
+	pub fn script_get(&self, result: Result<GetObjectResponse, VssError>) {
+		self.inner.lock().unwrap().get_responses.push_back(Scripted { result, delay: None });
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::get`] call, delivered after
+	/// the given delay.
+	pub fn script_get_with_delay(
+		&self, result: Result<GetObjectResponse, VssError>, delay: Duration,
+	) {
+		let scripted = Scripted { result, delay: Some(delay) };
+		self.inner.lock().unwrap().get_responses.push_back(scripted);
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::put`] call.
+	pub fn script_put(&self, result: Result<PutObjectResponse, VssError>) {
+		self.inner.lock().unwrap().put_responses.push_back(Scripted { result, delay: None });
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::put`] call, delivered after
+	/// the given delay.
+	pub fn script_put_with_delay(
+		&self, result: Result<PutObjectResponse, VssError>, delay: Duration,
+	) {
+		let scripted = Scripted { result, delay: Some(delay) };
+		self.inner.lock().unwrap().put_responses.push_back(scripted);
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::delete`] call.
+	pub fn script_delete(&self, result: Result<DeleteObjectResponse, VssError>) {
+		self.inner.lock().unwrap().delete_responses.push_back(Scripted { result, delay: None });
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::delete`] call, delivered
+	/// after the given delay.
+	pub fn script_delete_with_delay(
+		&self, result: Result<DeleteObjectResponse, VssError>, delay: Duration,
+	) {
+		let scripted = Scripted { result, delay: Some(delay) };
+		self.inner.lock().unwrap().delete_responses.push_back(scripted);
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::list_key_versions`] call.
+	pub fn script_list_key_versions(&self, result: Result<ListKeyVersionsResponse, VssError>) {
+		self.inner.lock().unwrap().list_responses.push_back(Scripted { result, delay: None });
+	}
+
+	/// Scripts the result returned by the next unscripted [`KvStore::list_key_versions`] call,
+	/// delivered after the given delay.
+	pub fn script_list_key_versions_with_delay(
+		&self, result: Result<ListKeyVersionsResponse, VssError>, delay: Duration,
+	) {
+		let scripted = Scripted { result, delay: Some(delay) };
+		self.inner.lock().unwrap().list_responses.push_back(scripted);
+	}
+
+	/// Returns all calls received so far, clearing the recorded calls.
+	pub fn take_calls(&self) -> Vec<MockCall> {
+		std::mem::take(&mut self.inner.lock().unwrap().calls)
+	}
+
+	async fn respond<T>(
+		&self, scripted: Option<Scripted<T>>, operation: &str,
+	) -> Result<T, VssError> {
+		let scripted = scripted.ok_or_else(|| {
+			VssError::InternalServerError(format!(
+				"MockKvStore: no scripted response remaining for {}",
+				operation
+			))
+		})?;
+		if let Some(delay) = scripted.delay {
+			tokio::time::sleep(delay).await;
+		}
+		scripted.result
+	}
+}
+
+#[async_trait]
+impl KvStore for MockKvStore {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let scripted = {
+			let mut inner = self.inner.lock().unwrap();
+			inner.calls.push(MockCall::Get(context, request));
+			inner.get_responses.pop_front()
+		};
+		self.respond(scripted, "get").await
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		let scripted = {
+			let mut inner = self.inner.lock().unwrap();
+			inner.calls.push(MockCall::Put(context, request));
+			inner.put_responses.pop_front()
+		};
+		self.respond(scripted, "put").await
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let scripted = {
+			let mut inner = self.inner.lock().unwrap();
+			inner.calls.push(MockCall::Delete(context, request));
+			inner.delete_responses.pop_front()
+		};
+		self.respond(scripted, "delete").await
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let scripted = {
+			let mut inner = self.inner.lock().unwrap();
+			inner.calls.push(MockCall::ListKeyVersions(context, request));
+			inner.list_responses.pop_front()
+		};
+		self.respond(scripted, "list_key_versions").await
+	}
+}
+
+/// A [`KvStore`] wrapper delaying every operation by a fixed latency plus an optional uniformly
+/// distributed jitter before forwarding to the wrapped store.
+///
+/// Useful for validating client timeout and retry settings against a slow server locally,
+/// without touching the backing store's behavior otherwise.
+pub struct DelayingKvStore {
+	inner: Arc<dyn KvStore>,
+	fixed_delay: Duration,
+	jitter: Duration,
+}
+
+impl DelayingKvStore {
+	/// Constructs a [`DelayingKvStore`] delaying every operation by `fixed_delay`.
+	pub fn new(inner: Arc<dyn KvStore>, fixed_delay: Duration) -> Self {
+		Self { inner, fixed_delay, jitter: Duration::ZERO }
+	}
+
+	/// Additionally delays every operation by a uniformly distributed duration up to `jitter`.
+	pub fn with_jitter(mut self, jitter: Duration) -> Self {
+		self.jitter = jitter;
+		self
+	}
+
+	async fn delay(&self) {
+		let mut delay = self.fixed_delay;
+		let jitter_nanos = self.jitter.as_nanos() as u64;
+		if jitter_nanos > 0 {
+			// Uniform enough for latency injection, avoiding a dependency on an RNG crate.
+			let nanos =
+				SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos() as u64;
+			delay += Duration::from_nanos(nanos.wrapping_mul(0x9e37_79b9_7f4a_7c15) % jitter_nanos);
+		}
+		tokio::time::sleep(delay).await;
+	}
+}
+
+#[async_trait]
+impl KvStore for DelayingKvStore {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		self.delay().await;
+		self.inner.get(context, request).await
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		self.delay().await;
+		self.inner.put(context, request).await
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		self.delay().await;
+		self.inner.delete(context, request).await
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		self.delay().await;
+		self.inner.list_key_versions(context, request).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn scripted_responses_are_consumed_in_order() {
+		let store = MockKvStore::new();
+		store.script_get(Ok(GetObjectResponse { value: None }));
+		store.script_get(Err(VssError::NoSuchKeyError("k1".to_string())));
+
+		let request = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+		store.get(RequestContext::new("user".to_string()), request.clone()).await.unwrap();
+		let result = store.get(RequestContext::new("user".to_string()), request.clone()).await;
+		assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+
+		// A third, unscripted call must fail, and all calls must have been recorded.
+		let result = store.get(RequestContext::new("user".to_string()), request.clone()).await;
+		assert!(matches!(result, Err(VssError::InternalServerError(..))));
+		let expected_call = MockCall::Get(RequestContext::new("user".to_string()), request);
+		assert_eq!(store.take_calls(), vec![expected_call.clone(); 3]);
+		assert!(store.take_calls().is_empty());
+	}
+
+	#[tokio::test]
+	async fn delaying_store_adds_latency_and_forwards() {
+		let mock = Arc::new(MockKvStore::new());
+		mock.script_get(Ok(GetObjectResponse { value: None }));
+		let fixed_delay = Duration::from_millis(50);
+		let store = DelayingKvStore::new(Arc::clone(&mock) as Arc<dyn KvStore>, fixed_delay)
+			.with_jitter(Duration::from_millis(10));
+
+		let request = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+		let started_at = std::time::Instant::now();
+		store.get(RequestContext::new("user".to_string()), request).await.unwrap();
+		assert!(started_at.elapsed() >= fixed_delay);
+		assert_eq!(mock.take_calls().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn scripted_delay_is_applied() {
+		let store = MockKvStore::new();
+		let delay = Duration::from_millis(50);
+		store.script_put_with_delay(Ok(PutObjectResponse {}), delay);
+
+		let request = PutObjectRequest {
+			store_id: "store".to_string(),
+			global_version: None,
+			transaction_items: vec![],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		let started_at = std::time::Instant::now();
+		store.put(RequestContext::new("user".to_string()), request).await.unwrap();
+		assert!(started_at.elapsed() >= delay);
+	}
+}
@@ -0,0 +1,625 @@
+//! Request/response types of the VSS API.
+//!
+//! This module is kept in sync with the protobuf definitions in `app/src/main/proto/vss.proto`,
+//! which remain the source of truth for the wire format. The types are checked in (rather than
+//! generated at build time) to avoid a build-time dependency on `protoc`.
+
+/// Request payload to be used for `GetObject` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GetObjectRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	/// It is up to clients to use single or multiple stores for their use-case.
+	/// This can be used for client-isolation/ rate-limiting / throttling on the server-side.
+	/// Authorization and billing can also be performed at the `store_id` level.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// `Key` for which the value is to be fetched.
+	///
+	/// Consistency Guarantee:
+	/// Get(read) operations against a `key` are consistent reads and will reflect all previous
+	/// writes, since Put/Write provides read-after-write and read-after-update consistency
+	/// guarantees.
+	///
+	/// Read Isolation:
+	/// Get/Read operations against a `key` are ensured to have read-committed isolation.
+	/// Ref: <https://en.wikipedia.org/wiki/Isolation_(database_systems)#Read_committed>
+	#[prost(string, tag = "2")]
+	pub key: ::prost::alloc::string::String,
+}
+
+/// Server response for `GetObject` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GetObjectResponse {
+	/// Fetched `value` and `version` along with the corresponding `key` in the request.
+	#[prost(message, optional, tag = "2")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub value: ::core::option::Option<KeyValue>,
+}
+
+/// Request payload to be used for `PutObject` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PutObjectRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	/// It is up to clients to use single or multiple stores for their use-case.
+	/// This can be used for client-isolation/ rate-limiting / throttling on the server-side.
+	/// Authorization and billing can also be performed at the `store_id` level.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// `global_version` is a sequence-number/version of the whole store.
+	///
+	/// If present, the write will only succeed if the current server-side `global_version`
+	/// against the `store_id` is same as in the request.
+	/// Clients are expected to store (client-side) the global version against `store_id`.
+	/// The request must contain their client-side value of `global_version` if global versioning
+	/// and conflict detection is desired.
+	///
+	/// For the first write of the store, global version should be '0'. If the write succeeds,
+	/// clients must increment their global version (client-side) by 1.
+	/// The server increments `global_version` (server-side) for every successful write, hence
+	/// this client-side increment is required to ensure matching versions. This updated global
+	/// version should be used in subsequent `PutObjectRequest`s for the store.
+	///
+	/// Requests with a conflicting version will fail with `CONFLICT_EXCEPTION` as ErrorCode.
+	#[prost(int64, optional, tag = "2")]
+	#[serde(with = "crate::json::optional_int64", skip_serializing_if = "Option::is_none")]
+	pub global_version: ::core::option::Option<i64>,
+	/// Items to be written as a result of this `PutObjectRequest`.
+	///
+	/// In an item, each `key` is supplied with its corresponding `value` and `version`.
+	/// The write will succeed if the current DB version against the `key` is the same as in the
+	/// request. Items in a single `PutObjectRequest` must have distinct keys.
+	///
+	/// Multiple items in `transaction_items` of a single `PutObjectRequest` are written in
+	/// a database-transaction in an all-or-nothing fashion.
+	///
+	/// Requests with a conflicting version will fail with `CONFLICT_EXCEPTION` as ErrorCode.
+	#[prost(message, repeated, tag = "3")]
+	pub transaction_items: ::prost::alloc::vec::Vec<KeyValue>,
+	/// Items to be deleted as a result of this `PutObjectRequest`.
+	///
+	/// Each item in the `delete_items` field consists of a `key` and its corresponding `version`.
+	/// The item will only be deleted if the current database version against the `key` is the
+	/// same as the `version` supplied in the request.
+	///
+	/// Requests with a conflicting version will fail with `CONFLICT_EXCEPTION` as ErrorCode.
+	///
+	/// Multiple items in a single `PutObjectRequest` (both `transaction_items` and
+	/// `delete_items`) are written/deleted in a single database-transaction in an all-or-nothing
+	/// fashion.
+	#[prost(message, repeated, tag = "4")]
+	pub delete_items: ::prost::alloc::vec::Vec<KeyValue>,
+	/// If set, all validations (version checks, configured quotas and limits) are performed but
+	/// nothing is committed, with conflicts reported exactly as for a real write. Clients
+	/// recovering from a version desync can use this to probe which writes would succeed before
+	/// mutating anything.
+	#[prost(bool, tag = "5")]
+	pub dry_run: bool,
+}
+
+/// Server response for `PutObject` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PutObjectResponse {}
+
+/// Request payload to be used for `DeleteObject` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeleteObjectRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// Item to be deleted as a result of this `DeleteObjectRequest`.
+	///
+	/// An item consists of a `key` and its corresponding `version`.
+	/// The item will only be deleted if the current database version against the `key` is the
+	/// same as the `version` supplied in the request.
+	///
+	/// This request is idempotent. Calling delete on a non-existent key or on a key whose
+	/// version does not match will NOT result in an error, the response will be success in such
+	/// cases.
+	#[prost(message, optional, tag = "2")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub key_value: ::core::option::Option<KeyValue>,
+}
+
+/// Server response for `DeleteObject` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeleteObjectResponse {}
+
+/// Request payload to be used for `SwapObject` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SwapObjectRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// The item to be written, superseding whatever is currently stored against its key.
+	///
+	/// The item's version follows the semantics of `PutObjectRequest::transaction_items`: the
+	/// write only succeeds if it matches the currently stored version, -1 makes it unconditional
+	/// and 0 requires that the key does not exist yet.
+	#[prost(message, optional, tag = "2")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub item: ::core::option::Option<KeyValue>,
+}
+
+/// Server response for `SwapObject` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SwapObjectResponse {
+	/// The item stored against the key before this write superseded it, absent if the key did
+	/// not exist. Returning it in the same round trip spares clients the race window of a
+	/// separate `GetObject` before the write.
+	#[prost(message, optional, tag = "1")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub previous: ::core::option::Option<KeyValue>,
+}
+
+/// Request payload to be used for `RenameObject` API call to server.
+///
+/// Atomically moves the value stored against one key to another key, sparing clients migrating
+/// their own key schemas the fragile get+put+delete sequence. The move happens in a single
+/// backend transaction: the destination is written and the source deleted all-or-nothing.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenameObjectRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// The key to move the value from. The request fails with [`NoSuchKeyError`] if it does not
+	/// exist.
+	///
+	/// [`NoSuchKeyError`]: crate::error::VssError::NoSuchKeyError
+	#[prost(string, tag = "2")]
+	pub from_key: ::prost::alloc::string::String,
+	/// The expected version of `from_key`, following the semantics of
+	/// [`PutObjectRequest::transaction_items`]; -1 moves whatever is currently stored.
+	#[prost(int64, tag = "3")]
+	#[serde(with = "crate::json::int64")]
+	pub from_version: i64,
+	/// The key to move the value to.
+	#[prost(string, tag = "4")]
+	pub to_key: ::prost::alloc::string::String,
+	/// The expected version of `to_key`, following the semantics of
+	/// [`PutObjectRequest::transaction_items`]: 0 requires that the destination does not exist
+	/// yet, -1 overwrites it unconditionally.
+	#[prost(int64, tag = "5")]
+	#[serde(with = "crate::json::int64")]
+	pub to_version: i64,
+}
+
+/// Server response for `RenameObject` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenameObjectResponse {}
+
+/// Request payload to be used for `AcquireLease` API call to server.
+///
+/// Leases let multiple devices sharing one wallet elect a single active writer instead of
+/// ping-ponging `global_version` conflicts. A lease is kept as a regular versioned key under the
+/// reserved `vss_lease_` prefix, its value holding an encoded [`LeaseState`], so no additional
+/// server-side state is involved and a lease can be inspected with `GetObject`.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AcquireLeaseRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// Names the lease within the store, forming its key as `vss_lease_` + `lease_name`.
+	#[prost(string, tag = "2")]
+	pub lease_name: ::prost::alloc::string::String,
+	/// Identifies the would-be holder (e.g. a device id). Acquiring a lease already held by the
+	/// same holder renews it, so holders refresh their lease by re-acquiring before expiry.
+	#[prost(string, tag = "3")]
+	pub holder_id: ::prost::alloc::string::String,
+	/// How long the lease is held before it expires, after which other holders may acquire it.
+	#[prost(uint32, tag = "4")]
+	pub ttl_secs: u32,
+}
+
+/// Server response for `AcquireLease` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AcquireLeaseResponse {
+	/// Whether the lease is now held by the requested holder. If false, the lease is held by
+	/// another live holder, identified below.
+	#[prost(bool, tag = "1")]
+	pub acquired: bool,
+	/// The holder now holding the lease.
+	#[prost(string, tag = "2")]
+	pub holder_id: ::prost::alloc::string::String,
+	/// When the lease expires, as milliseconds since the UNIX epoch (by the server's clock).
+	#[prost(int64, tag = "3")]
+	#[serde(with = "crate::json::int64")]
+	pub expires_at_millis: i64,
+}
+
+/// Request payload to be used for `ReleaseLease` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReleaseLeaseRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// Names the lease within the store, see [`AcquireLeaseRequest::lease_name`].
+	#[prost(string, tag = "2")]
+	pub lease_name: ::prost::alloc::string::String,
+	/// Identifies the releasing holder. Only the current holder can release a live lease.
+	#[prost(string, tag = "3")]
+	pub holder_id: ::prost::alloc::string::String,
+}
+
+/// Server response for `ReleaseLease` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReleaseLeaseResponse {
+	/// Whether the lease is no longer held: true if it was released or had already expired or
+	/// never existed, false if it is held by another live holder.
+	#[prost(bool, tag = "1")]
+	pub released: bool,
+}
+
+/// The value stored against a lease key, see [`AcquireLeaseRequest`].
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LeaseState {
+	/// The holder currently holding the lease.
+	#[prost(string, tag = "1")]
+	pub holder_id: ::prost::alloc::string::String,
+	/// When the lease expires, as milliseconds since the UNIX epoch (by the server's clock).
+	#[prost(int64, tag = "2")]
+	#[serde(with = "crate::json::int64")]
+	pub expires_at_millis: i64,
+}
+
+/// A device registered under the authenticated user, see [`RegisterDeviceRequest`].
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeviceRecord {
+	/// An opaque client-chosen device identifier.
+	#[prost(string, tag = "1")]
+	pub device_id: ::prost::alloc::string::String,
+	/// A free-form platform description (e.g. `android`, `ios`), as provided at registration.
+	#[prost(string, tag = "2")]
+	pub platform: ::prost::alloc::string::String,
+	/// An opaque push-notification token for the device, as provided at registration. May be
+	/// empty.
+	#[prost(string, tag = "3")]
+	pub push_token: ::prost::alloc::string::String,
+	/// When the device was first registered, as milliseconds since the UNIX epoch (by the
+	/// server's clock). Preserved across re-registrations.
+	#[prost(int64, tag = "4")]
+	#[serde(with = "crate::json::int64")]
+	pub registered_at_millis: i64,
+	/// When the device last (re-)registered, as milliseconds since the UNIX epoch (by the
+	/// server's clock). Devices refresh this by re-registering, e.g. on wallet startup.
+	#[prost(int64, tag = "5")]
+	#[serde(with = "crate::json::int64")]
+	pub last_seen_at_millis: i64,
+}
+
+/// Request payload to be used for `RegisterDevice` API call to server.
+///
+/// Registers (or, for a known `device_id`, refreshes) a device under the authenticated user,
+/// forming the foundation for push notifications and "which devices have access" UX.
+///
+/// Device records are kept as regular versioned keys in the reserved `vss_devices` store, keyed
+/// by `device_id`, so they are persisted by the backend like any other data and a device can be
+/// removed again with `DeleteObject` on that store.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RegisterDeviceRequest {
+	/// An opaque client-chosen device identifier.
+	#[prost(string, tag = "1")]
+	pub device_id: ::prost::alloc::string::String,
+	/// A free-form platform description (e.g. `android`, `ios`).
+	#[prost(string, tag = "2")]
+	pub platform: ::prost::alloc::string::String,
+	/// An opaque push-notification token for the device. May be empty.
+	#[prost(string, tag = "3")]
+	pub push_token: ::prost::alloc::string::String,
+}
+
+/// Server response for `RegisterDevice` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RegisterDeviceResponse {
+	/// The stored record, including the server-assigned timestamps.
+	#[prost(message, optional, tag = "1")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub device: ::core::option::Option<DeviceRecord>,
+}
+
+/// Request payload to be used for `ListDevices` API call to server, listing all devices
+/// registered under the authenticated user. See [`RegisterDeviceRequest`].
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListDevicesRequest {}
+
+/// Server response for `ListDevices` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListDevicesResponse {
+	/// All registered devices, ordered by `device_id`.
+	#[prost(message, repeated, tag = "1")]
+	pub devices: ::prost::alloc::vec::Vec<DeviceRecord>,
+}
+
+/// Request payload to be used for `GrantStoreAccess` API call to server.
+///
+/// Authorizes another user (e.g. a watchtower or support tool) read-only access to one of the
+/// authenticated user's stores. The grantee addresses the shared store by sending the owner's
+/// user token in the `x-vss-store-owner` header on read requests; write requests never honor
+/// grants. Granting is idempotent; a grant is withdrawn again with `RevokeStoreAccess`.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GrantStoreAccessRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// The user token to authorize, as it would authenticate itself to the server.
+	#[prost(string, tag = "2")]
+	pub grantee_token: ::prost::alloc::string::String,
+}
+
+/// Server response for `GrantStoreAccess` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GrantStoreAccessResponse {}
+
+/// Request payload to be used for `RevokeStoreAccess` API call to server.
+///
+/// Withdraws a grant previously issued with `GrantStoreAccess`. Revoking a grant that does not
+/// exist is a no-op.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RevokeStoreAccessRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// The user token whose access to revoke.
+	#[prost(string, tag = "2")]
+	pub grantee_token: ::prost::alloc::string::String,
+}
+
+/// Server response for `RevokeStoreAccess` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RevokeStoreAccessResponse {}
+
+/// Request payload to be used for `ListKeyVersions` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListKeyVersionsRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+	/// A `key_prefix` is a string of characters at the beginning of the key. Prefixes can be
+	/// used as a way to organize key-values in a similar way as directories.
+	///
+	/// If `key_prefix` is specified, the response results will be limited to those keys that
+	/// begin with the specified prefix.
+	#[prost(string, optional, tag = "2")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub key_prefix: ::core::option::Option<::prost::alloc::string::String>,
+	/// `page_size` is used by clients to specify the maximum number of results that can be
+	/// returned by the server.
+	/// The server may further constrain the maximum number of results returned in a single page.
+	/// If the `page_size` is 0 or not set, the server will decide the number of results to be
+	/// returned.
+	#[prost(int32, optional, tag = "3")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub page_size: ::core::option::Option<i32>,
+	/// `page_token` is a pagination token.
+	///
+	/// To query for the next page of results, set this field to the `next_page_token` returned
+	/// by the server for the previous request.
+	#[prost(string, optional, tag = "4")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub page_token: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+/// Server response for `ListKeyVersions` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListKeyVersionsResponse {
+	/// Fetched keys and versions.
+	/// Even though this API reuses the [`KeyValue`] struct, the `value` sub-field will not be set
+	/// by the server.
+	#[prost(message, repeated, tag = "1")]
+	pub key_versions: ::prost::alloc::vec::Vec<KeyValue>,
+	/// `next_page_token` is a pagination token, used to retrieve the next page of results.
+	///
+	/// If `next_page_token` is empty (""), then the "last page" of results has been processed
+	/// and there is no more data to be retrieved.
+	#[prost(string, optional, tag = "2")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub next_page_token: ::core::option::Option<::prost::alloc::string::String>,
+	/// `global_version` is a sequence-number/version of the whole store.
+	///
+	/// `global_version` is only returned in response for the first page of the
+	/// `ListKeyVersionsResponse` and is guaranteed to be read before reading any key-versions.
+	#[prost(int64, optional, tag = "3")]
+	#[serde(with = "crate::json::optional_int64", skip_serializing_if = "Option::is_none")]
+	pub global_version: ::core::option::Option<i64>,
+}
+
+/// Request payload to be used for `GetStoreStats` API call to server.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GetStoreStatsRequest {
+	/// `store_id` is a keyspace identifier.
+	/// Ref: <https://en.wikipedia.org/wiki/Keyspace_(distributed_data_store)>
+	/// All APIs operate within a single `store_id`.
+	#[prost(string, tag = "1")]
+	pub store_id: ::prost::alloc::string::String,
+}
+
+/// Server response for `GetStoreStats` API.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GetStoreStatsResponse {
+	/// The number of keys currently stored in the store, excluding internal bookkeeping keys.
+	#[prost(int64, tag = "1")]
+	#[serde(with = "crate::json::int64")]
+	pub key_count: i64,
+	/// The total size of all stored values in bytes.
+	#[prost(int64, tag = "2")]
+	#[serde(with = "crate::json::int64")]
+	pub total_value_bytes: i64,
+	/// The keys holding the largest stored values, sorted by descending value size.
+	#[prost(message, repeated, tag = "3")]
+	pub largest_keys: ::prost::alloc::vec::Vec<KeyStat>,
+	/// The least recent update timestamp across all keys in the store, in milliseconds since
+	/// the UNIX epoch. `0` for an empty store.
+	#[prost(int64, tag = "4")]
+	#[serde(with = "crate::json::int64")]
+	pub oldest_updated_at_millis: i64,
+	/// The most recent update timestamp across all keys in the store, in milliseconds since
+	/// the UNIX epoch. `0` for an empty store.
+	#[prost(int64, tag = "5")]
+	#[serde(with = "crate::json::int64")]
+	pub newest_updated_at_millis: i64,
+}
+
+/// Per-key statistics, see [`GetStoreStatsResponse::largest_keys`].
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct KeyStat {
+	/// `Key` against which the value is stored.
+	#[prost(string, tag = "1")]
+	pub key: ::prost::alloc::string::String,
+	/// The size of the stored value in bytes.
+	#[prost(int64, tag = "2")]
+	#[serde(with = "crate::json::int64")]
+	pub value_bytes: i64,
+}
+
+/// When HttpStatusCode is not ok (200), the response `content` contains a serialized
+/// [`ErrorResponse`] with the relevant [`ErrorCode`] and message.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ErrorResponse {
+	/// The error code uniquely identifying an error condition.
+	/// It is meant to be read and understood programmatically by code that detects/handles
+	/// errors by type.
+	#[prost(enumeration = "ErrorCode", tag = "1")]
+	#[serde(with = "crate::json::error_code")]
+	pub error_code: i32,
+	/// The error message containing a generic description of the error condition in English.
+	/// It is intended for a human audience only and should not be parsed to extract any
+	/// information programmatically. Client-side code may use it for logging only.
+	#[prost(string, tag = "2")]
+	pub message: ::prost::alloc::string::String,
+	/// A stable machine-readable sub-code refining `error_code`, drawn from the catalog in
+	/// [`sub_codes`]. Empty when no more specific condition applies; clients should treat
+	/// unknown values like an absent sub-code.
+	///
+	/// [`sub_codes`]: crate::error::sub_codes
+	#[prost(string, tag = "3")]
+	pub sub_code: ::prost::alloc::string::String,
+}
+
+/// ErrorCodes to be used in [`ErrorResponse`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ErrorCode {
+	/// Default protobuf Enum value. Will not be used as `ErrorCode` by server.
+	Unknown = 0,
+	/// `CONFLICT_EXCEPTION` is used when the request contains mismatched version (either key or
+	/// global) in `PutObjectRequest`. For more info refer `PutObjectRequest`.
+	ConflictException = 1,
+	/// `INVALID_REQUEST_EXCEPTION` is used in the following cases:
+	///   - The request was missing a required argument.
+	///   - The specified argument was invalid, incomplete or in the wrong format.
+	///   - The request body of the api cannot be deserialized into corresponding protobuf object.
+	InvalidRequestException = 2,
+	/// An internal server error occurred, client is probably at no fault and can safely retry
+	/// this error with exponential backoff.
+	InternalServerException = 3,
+	/// `NO_SUCH_KEY_EXCEPTION` is used when the requested key does not exist.
+	NoSuchKeyException = 4,
+	/// `AUTH_EXCEPTION` is used when the request could not be authenticated or the authenticated
+	/// client is not authorized to perform the requested operation.
+	AuthException = 5,
+}
+
+impl ErrorCode {
+	/// String value of the enum field names used in the ProtoBuf definition.
+	///
+	/// The values are not transformed in any way and thus are considered stable
+	/// (if the ProtoBuf definition does not change) and safe for programmatic use.
+	pub fn as_str_name(&self) -> &'static str {
+		match self {
+			Self::Unknown => "UNKNOWN",
+			Self::ConflictException => "CONFLICT_EXCEPTION",
+			Self::InvalidRequestException => "INVALID_REQUEST_EXCEPTION",
+			Self::InternalServerException => "INTERNAL_SERVER_EXCEPTION",
+			Self::NoSuchKeyException => "NO_SUCH_KEY_EXCEPTION",
+			Self::AuthException => "AUTH_EXCEPTION",
+		}
+	}
+	/// Creates an enum from field names used in the ProtoBuf definition.
+	pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+		match value {
+			"UNKNOWN" => Some(Self::Unknown),
+			"CONFLICT_EXCEPTION" => Some(Self::ConflictException),
+			"INVALID_REQUEST_EXCEPTION" => Some(Self::InvalidRequestException),
+			"INTERNAL_SERVER_EXCEPTION" => Some(Self::InternalServerException),
+			"NO_SUCH_KEY_EXCEPTION" => Some(Self::NoSuchKeyException),
+			"AUTH_EXCEPTION" => Some(Self::AuthException),
+			_ => None,
+		}
+	}
+}
+
+/// A key-value pair along with the corresponding version of the key.
+#[derive(Clone, PartialEq, ::prost::Message, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct KeyValue {
+	/// `Key` against which the value is stored.
+	#[prost(string, tag = "1")]
+	pub key: ::prost::alloc::string::String,
+	/// Version field is used for key-level versioning.
+	/// For first write of key, version should be '0'. If the write succeeds, clients must
+	/// increment their corresponding key version (client-side) by 1.
+	/// The server increments key version (server-side) for every successful write, hence this
+	/// client-side increment is required to ensure matching versions. These updated key versions
+	/// should be used in subsequent `PutObjectRequest`s for the keys.
+	#[prost(int64, tag = "2")]
+	#[serde(with = "crate::json::int64")]
+	pub version: i64,
+	/// Object value in bytes which is stored (in put) and fetched (in get).
+	/// Clients must encrypt this blob client-side before sending it over the wire to server in
+	/// order to preserve privacy and security.
+	///
+	/// Held as [`Bytes`](::prost::bytes::Bytes) so values are reference-counted rather than
+	/// copied as they move between the wire format and the storage backend.
+	#[prost(bytes = "bytes", tag = "3")]
+	#[serde(with = "crate::json::base64_bytes")]
+	pub value: ::prost::bytes::Bytes,
+}
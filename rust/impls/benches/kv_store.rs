@@ -0,0 +1,131 @@
+//! Criterion benchmarks for the [`KvStore`] implementations, covering the operations most
+//! sensitive to backend changes: single puts, large transactional puts, point reads and
+//! paginated listing.
+//!
+//! The in-memory backend is always benchmarked. Set `VSS_BENCH_POSTGRES_DSN` to a reachable
+//! PostgreSQL connection string to also benchmark the PostgreSQL backend.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+
+use api::kv_store::{KvStore, RequestContext};
+use api::types::{GetObjectRequest, KeyValue, ListKeyVersionsRequest, PutObjectRequest};
+use impls::memory_store::MemoryBackendImpl;
+use impls::postgres_store::PostgresBackendImpl;
+
+/// The number of keys seeded for the get and list benchmarks.
+const SEEDED_KEYS: usize = 1000;
+
+const LIST_PAGE_SIZE: i32 = 100;
+
+fn unique_context() -> RequestContext {
+	let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+	RequestContext::new(format!("bench-user-{}", nanos))
+}
+
+fn unconditional_items(count: usize, value: &[u8]) -> Vec<KeyValue> {
+	(0..count)
+		.map(|idx| KeyValue {
+			key: format!("bench-key-{}", idx),
+			version: -1,
+			value: value.to_vec().into(),
+		})
+		.collect()
+}
+
+async fn seed(store: &Arc<dyn KvStore>, context: &RequestContext) {
+	let request = PutObjectRequest {
+		store_id: "bench-store".to_string(),
+		global_version: None,
+		transaction_items: unconditional_items(SEEDED_KEYS, &[0u8; 256]),
+		delete_items: vec![],
+		dry_run: false,
+	};
+	store.put(context.clone(), request).await.unwrap();
+}
+
+fn bench_store(c: &mut Criterion, label: &str, store: Arc<dyn KvStore>, runtime: &Runtime) {
+	let context = unique_context();
+	runtime.block_on(seed(&store, &context));
+
+	c.bench_function(&format!("{}/put_single", label), |b| {
+		b.to_async(runtime).iter(|| async {
+			let request = PutObjectRequest {
+				store_id: "bench-store".to_string(),
+				global_version: None,
+				transaction_items: unconditional_items(1, &[0u8; 256]),
+				delete_items: vec![],
+				dry_run: false,
+			};
+			store.put(context.clone(), request).await.unwrap()
+		})
+	});
+
+	c.bench_function(&format!("{}/put_transaction_100", label), |b| {
+		b.to_async(runtime).iter(|| async {
+			let request = PutObjectRequest {
+				store_id: "bench-store".to_string(),
+				global_version: None,
+				transaction_items: unconditional_items(100, &[0u8; 256]),
+				delete_items: vec![],
+				dry_run: false,
+			};
+			store.put(context.clone(), request).await.unwrap()
+		})
+	});
+
+	c.bench_function(&format!("{}/get", label), |b| {
+		b.to_async(runtime).iter(|| async {
+			let request = GetObjectRequest {
+				store_id: "bench-store".to_string(),
+				key: "bench-key-500".to_string(),
+			};
+			store.get(context.clone(), request).await.unwrap()
+		})
+	});
+
+	c.bench_function(&format!("{}/list_paginated", label), |b| {
+		b.to_async(runtime).iter(|| async {
+			let mut page_token: Option<String> = None;
+			let mut listed = 0;
+			loop {
+				let request = ListKeyVersionsRequest {
+					store_id: "bench-store".to_string(),
+					key_prefix: None,
+					page_size: Some(LIST_PAGE_SIZE),
+					page_token: page_token.clone(),
+				};
+				let response =
+					store.list_key_versions(context.clone(), request).await.unwrap();
+				listed += response.key_versions.len();
+				match response.next_page_token {
+					Some(token) if !token.is_empty() => page_token = Some(token),
+					_ => break,
+				}
+			}
+			assert!(listed >= SEEDED_KEYS);
+		})
+	});
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+	let runtime = Runtime::new().unwrap();
+
+	bench_store(c, "memory", Arc::new(MemoryBackendImpl::new()), &runtime);
+
+	match std::env::var("VSS_BENCH_POSTGRES_DSN") {
+		Ok(dsn) => {
+			let store = runtime.block_on(PostgresBackendImpl::new(&dsn)).unwrap();
+			bench_store(c, "postgres", Arc::new(store), &runtime);
+		},
+		Err(_) => eprintln!(
+			"Set VSS_BENCH_POSTGRES_DSN to also benchmark the PostgreSQL backend."
+		),
+	}
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,254 @@
+//! An [`Authorizer`] validating static API keys against a configurable key set.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+/// The header carrying the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// A source of the API key set, mapping each accepted key onto the `user_token` it
+/// authenticates as. Several keys may map onto the same user, which is what makes rotation
+/// seamless: a new key is added, clients are switched over, then the old key is removed.
+#[async_trait]
+pub trait ApiKeySource: Send + Sync {
+	/// Loads the current key set.
+	async fn load_keys(&self) -> Result<HashMap<String, String>, VssError>;
+}
+
+/// An [`ApiKeySource`] reading a file of `<api_key> <user_token>` lines (e.g. a mounted
+/// Docker/Kubernetes secret). Blank lines and lines starting with `#` are skipped.
+pub struct FileApiKeySource {
+	path: String,
+}
+
+impl FileApiKeySource {
+	/// Constructs a [`FileApiKeySource`] reading the given file.
+	pub fn new(path: String) -> Self {
+		FileApiKeySource { path }
+	}
+}
+
+#[async_trait]
+impl ApiKeySource for FileApiKeySource {
+	async fn load_keys(&self) -> Result<HashMap<String, String>, VssError> {
+		let contents = std::fs::read_to_string(&self.path).map_err(|e| {
+			VssError::InternalServerError(format!(
+				"Failed to read API keys from {}: {}",
+				self.path, e
+			))
+		})?;
+		parse_key_lines(&contents)
+	}
+}
+
+/// An [`ApiKeySource`] reading an environment variable of comma-separated
+/// `<api_key>:<user_token>` pairs.
+pub struct EnvApiKeySource {
+	variable: String,
+}
+
+impl EnvApiKeySource {
+	/// Constructs an [`EnvApiKeySource`] reading the given environment variable.
+	pub fn new(variable: String) -> Self {
+		EnvApiKeySource { variable }
+	}
+}
+
+#[async_trait]
+impl ApiKeySource for EnvApiKeySource {
+	async fn load_keys(&self) -> Result<HashMap<String, String>, VssError> {
+		let contents = std::env::var(&self.variable).map_err(|_| {
+			VssError::InternalServerError(format!(
+				"The {} environment variable is not set.",
+				self.variable
+			))
+		})?;
+		let mut keys = HashMap::new();
+		for pair in contents.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+			match pair.split_once(':') {
+				Some((api_key, user_token)) if !api_key.is_empty() && !user_token.is_empty() => {
+					keys.insert(api_key.to_string(), user_token.to_string());
+				},
+				_ => {
+					return Err(VssError::InternalServerError(format!(
+						"Malformed API key entry in the {} environment variable.",
+						self.variable
+					)))
+				},
+			}
+		}
+		Ok(keys)
+	}
+}
+
+fn parse_key_lines(contents: &str) -> Result<HashMap<String, String>, VssError> {
+	let mut keys = HashMap::new();
+	for line in contents.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let mut fields = line.split_whitespace();
+		match (fields.next(), fields.next(), fields.next()) {
+			(Some(api_key), Some(user_token), None) => {
+				keys.insert(api_key.to_string(), user_token.to_string());
+			},
+			_ => {
+				return Err(VssError::InternalServerError(format!(
+					"Malformed API key line: {}",
+					line
+				)))
+			},
+		}
+	}
+	Ok(keys)
+}
+
+/// An [`Authorizer`] authenticating clients through the [`API_KEY_HEADER`] header, resolved to
+/// a `user_token` via a configurable [`ApiKeySource`].
+///
+/// With a refresh interval set, the key set is re-read from its source once the interval has
+/// elapsed (on the next request, so an idle server polls nothing), letting key additions and
+/// rotations take effect without a restart. A failing refresh keeps the last
+/// successfully-loaded set, so a briefly unreadable source does not lock every client out.
+pub struct ApiKeyAuthorizer {
+	source: Arc<dyn ApiKeySource>,
+	keys: RwLock<HashMap<String, String>>,
+	refresh_interval: Option<Duration>,
+	last_refresh: Mutex<Instant>,
+}
+
+impl ApiKeyAuthorizer {
+	/// Constructs an [`ApiKeyAuthorizer`], loading the initial key set from the given source.
+	pub async fn new(source: Arc<dyn ApiKeySource>) -> Result<Self, VssError> {
+		let keys = source.load_keys().await?;
+		Ok(ApiKeyAuthorizer {
+			source,
+			keys: RwLock::new(keys),
+			refresh_interval: None,
+			last_refresh: Mutex::new(Instant::now()),
+		})
+	}
+
+	/// Returns this authorizer re-reading the key set from its source once the given interval
+	/// has elapsed.
+	pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+		self.refresh_interval = Some(refresh_interval);
+		self
+	}
+
+	async fn maybe_refresh(&self) {
+		let refresh_interval = match self.refresh_interval {
+			Some(refresh_interval) => refresh_interval,
+			None => return,
+		};
+		{
+			let mut last_refresh = self.last_refresh.lock().unwrap();
+			if last_refresh.elapsed() < refresh_interval {
+				return;
+			}
+			// Reset before reloading so concurrent requests don't pile onto the source.
+			*last_refresh = Instant::now();
+		}
+		match self.source.load_keys().await {
+			Ok(keys) => *self.keys.write().unwrap() = keys,
+			Err(e) => warn!("Failed to refresh the API key set, keeping the current one: {}", e),
+		}
+	}
+}
+
+#[async_trait]
+impl Authorizer for ApiKeyAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let api_key = headers.get_header(API_KEY_HEADER).ok_or_else(|| {
+			VssError::AuthError(format!("Missing required header: {}", API_KEY_HEADER))
+		})?;
+		self.maybe_refresh().await;
+		match self.keys.read().unwrap().get(api_key) {
+			Some(user_token) => Ok(AuthResponse::new(user_token.clone())),
+			None => Err(VssError::AuthError("Invalid API key.".to_string())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn headers_with_key(api_key: &str) -> HashMap<String, String> {
+		let mut headers_map = HashMap::new();
+		headers_map.insert(API_KEY_HEADER.to_string(), api_key.to_string());
+		headers_map
+	}
+
+	fn temp_key_file(contents: &str) -> String {
+		let nanos = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let path = std::env::temp_dir().join(format!("vss-api-keys-test-{}", nanos));
+		std::fs::write(&path, contents).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[tokio::test]
+	async fn maps_keys_to_users_from_a_file() {
+		let path = temp_key_file("# comment\nkey-a alice\nkey-b bob\n\nkey-a2 alice\n");
+		let source = Arc::new(FileApiKeySource::new(path));
+		let authorizer = ApiKeyAuthorizer::new(source).await.unwrap();
+
+		let response = authorizer.verify(&headers_with_key("key-a")).await.unwrap();
+		assert_eq!(response.user_token, "alice");
+		// A second key mapping onto the same user, as left in place during a rotation.
+		let response = authorizer.verify(&headers_with_key("key-a2")).await.unwrap();
+		assert_eq!(response.user_token, "alice");
+		let response = authorizer.verify(&headers_with_key("key-b")).await.unwrap();
+		assert_eq!(response.user_token, "bob");
+
+		let result = authorizer.verify(&headers_with_key("unknown")).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		let result = authorizer.verify(&HashMap::new()).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn rejects_malformed_key_files() {
+		let path = temp_key_file("key-a alice extra-field\n");
+		let source = Arc::new(FileApiKeySource::new(path));
+		assert!(ApiKeyAuthorizer::new(source).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn reads_keys_from_the_environment() {
+		std::env::set_var("VSS_API_KEY_TEST_VAR", "key-a:alice, key-b:bob");
+		let source = Arc::new(EnvApiKeySource::new("VSS_API_KEY_TEST_VAR".to_string()));
+		let authorizer = ApiKeyAuthorizer::new(source).await.unwrap();
+
+		let response = authorizer.verify(&headers_with_key("key-b")).await.unwrap();
+		assert_eq!(response.user_token, "bob");
+	}
+
+	#[tokio::test]
+	async fn rotated_keys_take_effect_after_a_refresh() {
+		let path = temp_key_file("key-old alice\n");
+		let source = Arc::new(FileApiKeySource::new(path.clone()));
+		let authorizer = ApiKeyAuthorizer::new(source)
+			.await
+			.unwrap()
+			.with_refresh_interval(Duration::from_secs(0));
+
+		std::fs::write(&path, "key-new alice\n").unwrap();
+		let response = authorizer.verify(&headers_with_key("key-new")).await.unwrap();
+		assert_eq!(response.user_token, "alice");
+		let result = authorizer.verify(&headers_with_key("key-old")).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+}
@@ -0,0 +1,295 @@
+//! An [`Authorizer`] wrapper caching successful verifications.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// How long a successful verification is reused, see [`CachingAuthorizer::with_ttl`].
+const DEFAULT_TTL: Duration = Duration::from_secs(5);
+
+/// The maximum number of cached entries, see [`CachingAuthorizer::with_max_entries`].
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// An [`Authorizer`] wrapper caching successful verifications of the wrapped authorizer for a
+/// short TTL, keyed by a digest of every credential input the wrapped authorizer reads, so
+/// signature and JWT verification is not repeated on every request of a busy client.
+///
+/// The cache key must cover everything the wrapped authorizer bases its decision on: the
+/// credential headers it reads (the `Authorization` header by default, see
+/// [`CachingAuthorizer::with_credential_headers`]) and the peer certificate of the underlying
+/// TLS connection. Keying on less would let one caller's cached identity answer for another
+/// within the TTL.
+///
+/// Only successes are cached: failures stay cheap to produce and must not shadow a client
+/// switching to a valid credential. The TTL bounds how long a token outlives its revocation or
+/// expiry, so it should stay small; requests carrying no credential input bypass the cache
+/// entirely. The cache is capped, dropping new entries (not standing ones) when a flood of
+/// distinct credentials fills it.
+pub struct CachingAuthorizer {
+	inner: Arc<dyn Authorizer>,
+	ttl: Duration,
+	max_entries: usize,
+	credential_headers: Vec<String>,
+	cache: Mutex<HashMap<[u8; 32], (Instant, AuthResponse)>>,
+}
+
+impl CachingAuthorizer {
+	/// Constructs a [`CachingAuthorizer`] wrapping the given authorizer.
+	pub fn new(inner: Arc<dyn Authorizer>) -> Self {
+		CachingAuthorizer {
+			inner,
+			ttl: DEFAULT_TTL,
+			max_entries: DEFAULT_MAX_ENTRIES,
+			credential_headers: vec![AUTHORIZATION_HEADER.to_string()],
+			cache: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Returns this authorizer caching verifications for the given TTL instead of the default
+	/// five seconds.
+	pub fn with_ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Returns this authorizer capping the cache at the given number of entries instead of the
+	/// default 10000.
+	pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+		self.max_entries = max_entries;
+		self
+	}
+
+	/// Returns this authorizer keying the cache on the given headers instead of the
+	/// `Authorization` header alone. Must list every header the wrapped authorizer reads its
+	/// credentials from, e.g. `x-api-key` or the forwarded headers of a remote authorizer; may
+	/// be empty for authorizers deciding on the peer certificate alone.
+	pub fn with_credential_headers(mut self, credential_headers: Vec<String>) -> Self {
+		self.credential_headers = credential_headers;
+		self
+	}
+
+	/// Digests every credential input of the request into the cache key, or returns `None` if
+	/// the request carries none of them (such requests bypass the cache). Names are hashed
+	/// alongside values, so two inputs can never collide across headers.
+	fn cache_key(&self, headers: &dyn RequestHeaders) -> Option<[u8; 32]> {
+		// The digest keys the cache, so raw credentials are never retained in memory.
+		let mut hasher = Sha256::new();
+		let mut has_credential = false;
+		for name in &self.credential_headers {
+			if let Some(value) = headers.get_header(name) {
+				has_credential = true;
+				hasher.update(name.as_bytes());
+				hasher.update([0]);
+				hasher.update(value.as_bytes());
+				hasher.update([0]);
+			}
+		}
+		if let Some(peer_certificate) = headers.peer_certificate() {
+			has_credential = true;
+			hasher.update([1]);
+			hasher.update(peer_certificate);
+		}
+		has_credential.then(|| hasher.finalize().into())
+	}
+}
+
+#[async_trait]
+impl Authorizer for CachingAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let cache_key = match self.cache_key(headers) {
+			Some(cache_key) => cache_key,
+			None => return self.inner.verify(headers).await,
+		};
+		{
+			let mut cache = self.cache.lock().unwrap();
+			cache.retain(|_, (inserted, _)| inserted.elapsed() < self.ttl);
+			if let Some((_, response)) = cache.get(&cache_key) {
+				return Ok(response.clone());
+			}
+		}
+		let response = self.inner.verify(headers).await?;
+		let mut cache = self.cache.lock().unwrap();
+		if cache.len() < self.max_entries {
+			cache.insert(cache_key, (Instant::now(), response.clone()));
+		}
+		Ok(response)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// An [`Authorizer`] test double admitting a fixed token and counting its invocations.
+	struct CountingAuthorizer {
+		invocations: AtomicUsize,
+	}
+
+	#[async_trait]
+	impl Authorizer for CountingAuthorizer {
+		async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+			self.invocations.fetch_add(1, Ordering::SeqCst);
+			match headers.get_header(AUTHORIZATION_HEADER) {
+				Some("Bearer valid") => Ok(AuthResponse::new("user-1".to_string())),
+				_ => Err(VssError::AuthError("Invalid token.".to_string())),
+			}
+		}
+	}
+
+	fn bearer_headers(token: &str) -> HashMap<String, String> {
+		let mut headers = HashMap::new();
+		headers.insert(AUTHORIZATION_HEADER.to_string(), format!("Bearer {}", token));
+		headers
+	}
+
+	#[tokio::test]
+	async fn successful_verifications_are_cached() {
+		let inner = Arc::new(CountingAuthorizer { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::from_secs(60));
+
+		for _ in 0..3 {
+			let response = authorizer.verify(&bearer_headers("valid")).await.unwrap();
+			assert_eq!(response.user_token, "user-1");
+		}
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test]
+	async fn failures_are_not_cached() {
+		let inner = Arc::new(CountingAuthorizer { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::from_secs(60));
+
+		let result = authorizer.verify(&bearer_headers("invalid")).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		let result = authorizer.verify(&bearer_headers("invalid")).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn expired_entries_are_reverified() {
+		let inner = Arc::new(CountingAuthorizer { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::ZERO);
+
+		authorizer.verify(&bearer_headers("valid")).await.unwrap();
+		authorizer.verify(&bearer_headers("valid")).await.unwrap();
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn requests_without_credentials_bypass_the_cache() {
+		let inner = Arc::new(CountingAuthorizer { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::from_secs(60));
+
+		let result = authorizer.verify(&HashMap::new()).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		let result = authorizer.verify(&HashMap::new()).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn configured_credential_headers_key_the_cache() {
+		/// An [`Authorizer`] test double deriving the user from an `x-api-key` header.
+		struct ApiKeyDouble {
+			invocations: AtomicUsize,
+		}
+
+		#[async_trait]
+		impl Authorizer for ApiKeyDouble {
+			async fn verify(
+				&self, headers: &dyn RequestHeaders,
+			) -> Result<AuthResponse, VssError> {
+				self.invocations.fetch_add(1, Ordering::SeqCst);
+				match headers.get_header("x-api-key") {
+					Some(key) => Ok(AuthResponse::new(format!("user-{}", key))),
+					None => Err(VssError::AuthError("Missing API key.".to_string())),
+				}
+			}
+		}
+
+		let api_key_headers = |key: &str| {
+			let mut headers = HashMap::new();
+			headers.insert("x-api-key".to_string(), key.to_string());
+			headers
+		};
+		let inner = Arc::new(ApiKeyDouble { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::from_secs(60))
+			.with_credential_headers(vec!["x-api-key".to_string()]);
+
+		// Distinct keys must never share an entry; repeats of the same key hit the cache.
+		let response = authorizer.verify(&api_key_headers("a")).await.unwrap();
+		assert_eq!(response.user_token, "user-a");
+		let response = authorizer.verify(&api_key_headers("b")).await.unwrap();
+		assert_eq!(response.user_token, "user-b");
+		let response = authorizer.verify(&api_key_headers("a")).await.unwrap();
+		assert_eq!(response.user_token, "user-a");
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn distinct_peer_certificates_never_share_entries() {
+		/// A [`RequestHeaders`] test double presenting a client certificate and no headers.
+		struct CertHeaders {
+			peer_certificate: Vec<u8>,
+		}
+
+		impl RequestHeaders for CertHeaders {
+			fn get_header(&self, _name: &str) -> Option<&str> {
+				None
+			}
+
+			fn peer_certificate(&self) -> Option<&[u8]> {
+				Some(&self.peer_certificate)
+			}
+		}
+
+		/// An [`Authorizer`] test double deriving the user from the peer certificate.
+		struct MtlsDouble {
+			invocations: AtomicUsize,
+		}
+
+		#[async_trait]
+		impl Authorizer for MtlsDouble {
+			async fn verify(
+				&self, headers: &dyn RequestHeaders,
+			) -> Result<AuthResponse, VssError> {
+				self.invocations.fetch_add(1, Ordering::SeqCst);
+				match headers.peer_certificate() {
+					Some(cert) => Ok(AuthResponse::new(hex::encode(cert))),
+					None => Err(VssError::AuthError("No client certificate.".to_string())),
+				}
+			}
+		}
+
+		let inner = Arc::new(MtlsDouble { invocations: AtomicUsize::new(0) });
+		let authorizer = CachingAuthorizer::new(Arc::clone(&inner) as Arc<dyn Authorizer>)
+			.with_ttl(Duration::from_secs(60))
+			.with_credential_headers(vec![]);
+
+		// Two connections presenting different certificates must resolve to their own
+		// identities, even within the TTL; the same certificate hits the cache.
+		let cert_a = CertHeaders { peer_certificate: vec![0xaa; 4] };
+		let cert_b = CertHeaders { peer_certificate: vec![0xbb; 4] };
+		assert_eq!(authorizer.verify(&cert_a).await.unwrap().user_token, "aaaaaaaa");
+		assert_eq!(authorizer.verify(&cert_b).await.unwrap().user_token, "bbbbbbbb");
+		assert_eq!(authorizer.verify(&cert_a).await.unwrap().user_token, "aaaaaaaa");
+		assert_eq!(inner.invocations.load(Ordering::SeqCst), 2);
+	}
+}
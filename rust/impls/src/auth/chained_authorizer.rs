@@ -0,0 +1,117 @@
+//! An [`Authorizer`] combinator trying several authorizers in order.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+/// An [`Authorizer`] trying the given authorizers in order and succeeding on the first match,
+/// letting a server accept several authentication schemes simultaneously, e.g. JWT bearer
+/// tokens alongside signature-based authentication while clients migrate between the two.
+///
+/// A scheme rejecting the request ([`VssError::AuthError`]) falls through to the next one and
+/// the last rejection is returned once all schemes have refused. Any other failure (e.g. an
+/// authorizer unable to reach its key source) aborts the chain immediately: a later scheme
+/// accepting the request would mask the infrastructure problem.
+pub struct ChainedAuthorizer {
+	authorizers: Vec<Arc<dyn Authorizer>>,
+}
+
+impl ChainedAuthorizer {
+	/// Constructs a [`ChainedAuthorizer`] over the given authorizers, tried in order.
+	pub fn new(authorizers: Vec<Arc<dyn Authorizer>>) -> Self {
+		ChainedAuthorizer { authorizers }
+	}
+}
+
+#[async_trait]
+impl Authorizer for ChainedAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let mut last_rejection = None;
+		for authorizer in &self.authorizers {
+			match authorizer.verify(headers).await {
+				Ok(response) => return Ok(response),
+				Err(e @ VssError::AuthError(..)) => last_rejection = Some(e),
+				Err(e) => return Err(e),
+			}
+		}
+		Err(last_rejection
+			.unwrap_or_else(|| VssError::AuthError("No authorizer configured.".to_string())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	/// Accepts requests carrying the given header, mapping its value to the user token.
+	struct HeaderAuthorizer {
+		header: &'static str,
+	}
+
+	#[async_trait]
+	impl Authorizer for HeaderAuthorizer {
+		async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+			match headers.get_header(self.header) {
+				Some(value) => Ok(AuthResponse::new(value.to_string())),
+				None => Err(VssError::AuthError(format!("Missing {} header.", self.header))),
+			}
+		}
+	}
+
+	/// Fails every request with an infrastructure error.
+	struct BrokenAuthorizer;
+
+	#[async_trait]
+	impl Authorizer for BrokenAuthorizer {
+		async fn verify(&self, _headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+			Err(VssError::InternalServerError("Key source unreachable.".to_string()))
+		}
+	}
+
+	fn headers(name: &str, value: &str) -> HashMap<String, String> {
+		let mut headers = HashMap::new();
+		headers.insert(name.to_string(), value.to_string());
+		headers
+	}
+
+	#[tokio::test]
+	async fn the_first_matching_authorizer_wins() {
+		let chain = ChainedAuthorizer::new(vec![
+			Arc::new(HeaderAuthorizer { header: "x-scheme-a" }),
+			Arc::new(HeaderAuthorizer { header: "x-scheme-b" }),
+		]);
+
+		let response = chain.verify(&headers("x-scheme-a", "user-a")).await.unwrap();
+		assert_eq!(response.user_token, "user-a");
+
+		// A rejection by the first scheme falls through to the second.
+		let response = chain.verify(&headers("x-scheme-b", "user-b")).await.unwrap();
+		assert_eq!(response.user_token, "user-b");
+
+		// Once all schemes have refused, the last rejection is returned.
+		let result = chain.verify(&headers("x-scheme-c", "user-c")).await;
+		match result {
+			Err(VssError::AuthError(message)) => {
+				assert_eq!(message, "Missing x-scheme-b header.")
+			},
+			other => panic!("Unexpected result: {:?}", other.map(|r| r.user_token)),
+		}
+	}
+
+	#[tokio::test]
+	async fn infrastructure_failures_abort_the_chain() {
+		let chain = ChainedAuthorizer::new(vec![
+			Arc::new(BrokenAuthorizer),
+			Arc::new(HeaderAuthorizer { header: "x-scheme-b" }),
+		]);
+
+		let result = chain.verify(&headers("x-scheme-b", "user-b")).await;
+		assert!(matches!(result, Err(VssError::InternalServerError(..))));
+	}
+}
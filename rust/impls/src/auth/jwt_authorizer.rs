@@ -0,0 +1,426 @@
+//! An [`Authorizer`] validating JWT bearer tokens.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::{Oaep, RsaPrivateKey};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::auth::revocation::RevocationList;
+use api::auth::{
+	AuthResponse, Authorizer, RequestHeaders, ALLOWED_CIDRS_ATTRIBUTE,
+	ALLOWED_STORE_IDS_ATTRIBUTE, SCOPES_ATTRIBUTE,
+};
+use api::error::VssError;
+
+const AUTHORIZATION_HEADER: &str = "authorization";
+const BEARER_PREFIX: &str = "Bearer ";
+
+#[derive(Deserialize)]
+struct Claims {
+	sub: String,
+	/// The token's unique id, checked against the configured [`RevocationList`] (which requires
+	/// every token to carry one).
+	jti: Option<String>,
+	/// An optional IP-binding constraint: the CIDR ranges the token is valid from, surfaced as
+	/// the [`ALLOWED_CIDRS_ATTRIBUTE`] for the server to enforce against the client IP.
+	///
+	/// [`ALLOWED_CIDRS_ATTRIBUTE`]: api::auth::ALLOWED_CIDRS_ATTRIBUTE
+	allowed_cidrs: Option<Vec<String>>,
+	/// The OAuth-style space-delimited scopes granted to the token (e.g. `read write`),
+	/// surfaced as the [`SCOPES_ATTRIBUTE`] so the server can reject mutating RPCs for
+	/// read-only tokens.
+	///
+	/// [`SCOPES_ATTRIBUTE`]: api::auth::SCOPES_ATTRIBUTE
+	scope: Option<String>,
+	/// The `store_id`s the token is restricted to, surfaced as the
+	/// [`ALLOWED_STORE_IDS_ATTRIBUTE`] so the server rejects requests naming any other store.
+	///
+	/// [`ALLOWED_STORE_IDS_ATTRIBUTE`]: api::auth::ALLOWED_STORE_IDS_ATTRIBUTE
+	store_ids: Option<Vec<String>>,
+}
+
+/// The protected header of a JWE token, restricted to the fields needed to decrypt it.
+#[derive(Deserialize)]
+struct JweHeader {
+	alg: String,
+	enc: String,
+}
+
+/// An [`Authorizer`] validating signed JWT bearer tokens provided in the `Authorization`
+/// header, mapping the token's `sub` claim to the effective `user_token`.
+///
+/// Tokens are required to carry `sub` and `exp` claims, expired tokens are rejected. Tokens must
+/// be signed with the configured algorithm (`RS256` unless overridden via
+/// [`new_with_algorithm`]), enforced against each token's header. With a decryption key
+/// configured via [`with_decryption_key`], JWE-encrypted tokens are accepted as well: the
+/// encrypted envelope is opened first and the nested JWS is verified as usual.
+///
+/// With a [`RevocationList`] configured via [`with_revocation_list`], every token must carry a
+/// `jti` claim and tokens whose `jti` has been denylisted are rejected, so a compromised token
+/// can be invalidated before it expires.
+///
+/// [`new_with_algorithm`]: JwtAuthorizer::new_with_algorithm
+/// [`with_decryption_key`]: JwtAuthorizer::with_decryption_key
+/// [`with_revocation_list`]: JwtAuthorizer::with_revocation_list
+pub struct JwtAuthorizer {
+	decoding_key: DecodingKey,
+	validation: Validation,
+	decryption_key: Option<RsaPrivateKey>,
+	revocation_list: Option<Arc<dyn RevocationList>>,
+}
+
+impl JwtAuthorizer {
+	/// Constructs a [`JwtAuthorizer`] from a PEM-encoded RSA public key used to verify token
+	/// signatures, expecting `RS256`-signed tokens.
+	pub fn new(public_key_pem: &[u8]) -> Result<Self, VssError> {
+		Self::new_with_algorithm(public_key_pem, "RS256")
+	}
+
+	/// Constructs a [`JwtAuthorizer`] expecting tokens signed with the given algorithm, e.g.
+	/// `RS256`, `ES256`, `ES384`, `EdDSA` or `HS256`.
+	///
+	/// For the RSA and ECDSA families and `EdDSA`, `key` is the PEM-encoded public key; for the
+	/// HMAC family, it is the raw shared secret.
+	pub fn new_with_algorithm(key: &[u8], algorithm: &str) -> Result<Self, VssError> {
+		let algorithm: Algorithm = algorithm.parse().map_err(|_| {
+			VssError::InternalServerError(format!("Unknown JWT algorithm: {}", algorithm))
+		})?;
+		let parse_error = |e| {
+			VssError::InternalServerError(format!("Failed to parse JWT public key: {}", e))
+		};
+		let decoding_key = match algorithm {
+			Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+				DecodingKey::from_secret(key)
+			},
+			Algorithm::RS256
+			| Algorithm::RS384
+			| Algorithm::RS512
+			| Algorithm::PS256
+			| Algorithm::PS384
+			| Algorithm::PS512 => DecodingKey::from_rsa_pem(key).map_err(parse_error)?,
+			Algorithm::ES256 | Algorithm::ES384 => {
+				DecodingKey::from_ec_pem(key).map_err(parse_error)?
+			},
+			Algorithm::EdDSA => DecodingKey::from_ed_pem(key).map_err(parse_error)?,
+		};
+		let mut validation = Validation::new(algorithm);
+		validation.set_required_spec_claims(&["exp", "sub"]);
+		Ok(JwtAuthorizer { decoding_key, validation, decryption_key: None, revocation_list: None })
+	}
+
+	/// Returns this authorizer additionally accepting JWE-encrypted tokens (`RSA-OAEP` or
+	/// `RSA-OAEP-256` key encryption with `A256GCM` content encryption), decrypted with the
+	/// given PEM-encoded RSA private key before claim validation.
+	///
+	/// Identity providers encrypt tokens whose claims (e.g. user identifiers) are considered
+	/// sensitive, so they never cross the wire readable.
+	pub fn with_decryption_key(mut self, private_key_pem: &[u8]) -> Result<Self, VssError> {
+		let pem = std::str::from_utf8(private_key_pem).map_err(|_| {
+			VssError::InternalServerError("Failed to parse JWT decryption key: not UTF-8.".to_string())
+		})?;
+		let decryption_key = RsaPrivateKey::from_pkcs8_pem(pem)
+			.or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+			.map_err(|e| {
+				VssError::InternalServerError(format!("Failed to parse JWT decryption key: {}", e))
+			})?;
+		self.decryption_key = Some(decryption_key);
+		Ok(self)
+	}
+
+	/// Returns this authorizer rejecting tokens whose `jti` claim appears on the given
+	/// [`RevocationList`]. Tokens carrying no `jti` claim are rejected outright, as they could
+	/// never be revoked.
+	pub fn with_revocation_list(mut self, revocation_list: Arc<dyn RevocationList>) -> Self {
+		self.revocation_list = Some(revocation_list);
+		self
+	}
+}
+
+/// Opens a compact-serialized JWE token, returning the decrypted payload (the nested JWS).
+fn decrypt_jwe(decryption_key: &RsaPrivateKey, token: &str) -> Result<String, VssError> {
+	let invalid = || VssError::AuthError("Invalid JWE token.".to_string());
+	let parts: Vec<&str> = token.split('.').collect();
+	debug_assert_eq!(parts.len(), 5);
+	let header_bytes = BASE64_URL_SAFE_NO_PAD.decode(parts[0]).map_err(|_| invalid())?;
+	let header: JweHeader = serde_json::from_slice(&header_bytes).map_err(|_| invalid())?;
+	let padding = match header.alg.as_str() {
+		"RSA-OAEP" => Oaep::new::<sha1::Sha1>(),
+		"RSA-OAEP-256" => Oaep::new::<sha2::Sha256>(),
+		_ => {
+			return Err(VssError::AuthError(format!(
+				"Unsupported JWE key encryption algorithm: {}",
+				header.alg
+			)))
+		},
+	};
+	if header.enc != "A256GCM" {
+		return Err(VssError::AuthError(format!(
+			"Unsupported JWE content encryption algorithm: {}",
+			header.enc
+		)));
+	}
+	let encrypted_key = BASE64_URL_SAFE_NO_PAD.decode(parts[1]).map_err(|_| invalid())?;
+	let iv = BASE64_URL_SAFE_NO_PAD.decode(parts[2]).map_err(|_| invalid())?;
+	let ciphertext = BASE64_URL_SAFE_NO_PAD.decode(parts[3]).map_err(|_| invalid())?;
+	let tag = BASE64_URL_SAFE_NO_PAD.decode(parts[4]).map_err(|_| invalid())?;
+	let content_key = decryption_key.decrypt(padding, &encrypted_key).map_err(|_| invalid())?;
+	if content_key.len() != 32 || iv.len() != 12 {
+		return Err(invalid());
+	}
+	// The GCM tag is carried as a separate JWE segment; the AEAD implementation expects it
+	// appended to the ciphertext. The protected header doubles as the authenticated data.
+	let mut sealed = ciphertext;
+	sealed.extend_from_slice(&tag);
+	let cipher = Aes256Gcm::new_from_slice(&content_key).map_err(|_| invalid())?;
+	let payload = Payload { msg: &sealed, aad: parts[0].as_bytes() };
+	let plaintext = cipher.decrypt(Nonce::from_slice(&iv), payload).map_err(|_| invalid())?;
+	String::from_utf8(plaintext).map_err(|_| invalid())
+}
+
+#[async_trait]
+impl Authorizer for JwtAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let header = headers.get_header(AUTHORIZATION_HEADER).ok_or_else(|| {
+			VssError::AuthError("Missing Authorization header.".to_string())
+		})?;
+		let token = header.strip_prefix(BEARER_PREFIX).ok_or_else(|| {
+			VssError::AuthError("Authorization header must be a bearer token.".to_string())
+		})?;
+
+		// A compact JWE has five dot-separated segments, a JWS three.
+		let decrypted;
+		let token = if token.split('.').count() == 5 {
+			let decryption_key = self.decryption_key.as_ref().ok_or_else(|| {
+				VssError::AuthError(
+					"Encrypted JWT tokens require a configured decryption key.".to_string(),
+				)
+			})?;
+			decrypted = decrypt_jwe(decryption_key, token)?;
+			decrypted.as_str()
+		} else {
+			token
+		};
+
+		let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
+			.map_err(|e| VssError::AuthError(format!("Invalid JWT token: {}", e)))?;
+		if let Some(revocation_list) = &self.revocation_list {
+			let jti = token_data.claims.jti.as_deref().ok_or_else(|| {
+				VssError::AuthError("Token carries no jti claim.".to_string())
+			})?;
+			if revocation_list.is_revoked(jti).await? {
+				return Err(VssError::AuthError("Token has been revoked.".to_string()));
+			}
+		}
+		let mut response = AuthResponse::new(token_data.claims.sub);
+		if let Some(allowed_cidrs) = token_data.claims.allowed_cidrs {
+			response
+				.attributes
+				.insert(ALLOWED_CIDRS_ATTRIBUTE.to_string(), allowed_cidrs.join(","));
+		}
+		if let Some(scope) = token_data.claims.scope {
+			response.attributes.insert(SCOPES_ATTRIBUTE.to_string(), scope);
+		}
+		if let Some(store_ids) = token_data.claims.store_ids {
+			response
+				.attributes
+				.insert(ALLOWED_STORE_IDS_ATTRIBUTE.to_string(), store_ids.join(","));
+		}
+		Ok(response)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use aes_gcm::AeadCore;
+	use jsonwebtoken::{encode, EncodingKey, Header};
+	use rsa::rand_core::OsRng;
+	use rsa::RsaPublicKey;
+	use std::collections::HashMap;
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	// A throwaway 2048-bit RSA keypair used both to sign the nested JWS and to encrypt the JWE
+	// envelope, standing in for the identity provider's signing key and the server's key.
+	const TEST_PRIVATE_KEY_PEM: &str = include_str!("fixtures/jwe-test-private-key.pem");
+	const TEST_PUBLIC_KEY_PEM: &str = include_str!("fixtures/jwe-test-public-key.pem");
+
+	fn signed_token(sub: &str) -> String {
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let claims = serde_json::json!({ "sub": sub, "exp": exp });
+		let encoding_key = EncodingKey::from_rsa_pem(TEST_PRIVATE_KEY_PEM.as_bytes()).unwrap();
+		encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).unwrap()
+	}
+
+	fn encrypted_token(jws: &str) -> String {
+		let private_key = RsaPrivateKey::from_pkcs8_pem(TEST_PRIVATE_KEY_PEM).unwrap();
+		let public_key = RsaPublicKey::from(&private_key);
+		let content_key = Aes256Gcm::generate_key(OsRng);
+		let encrypted_key = public_key
+			.encrypt(&mut OsRng, Oaep::new::<sha2::Sha256>(), content_key.as_slice())
+			.unwrap();
+		let header =
+			BASE64_URL_SAFE_NO_PAD.encode(br#"{"alg":"RSA-OAEP-256","enc":"A256GCM","cty":"JWT"}"#);
+		let iv = Aes256Gcm::generate_nonce(OsRng);
+		let cipher = Aes256Gcm::new(&content_key);
+		let payload = Payload { msg: jws.as_bytes(), aad: header.as_bytes() };
+		let mut sealed = cipher.encrypt(&iv, payload).unwrap();
+		let tag = sealed.split_off(sealed.len() - 16);
+		format!(
+			"{}.{}.{}.{}.{}",
+			header,
+			BASE64_URL_SAFE_NO_PAD.encode(&encrypted_key),
+			BASE64_URL_SAFE_NO_PAD.encode(iv),
+			BASE64_URL_SAFE_NO_PAD.encode(&sealed),
+			BASE64_URL_SAFE_NO_PAD.encode(&tag),
+		)
+	}
+
+	fn bearer_headers(token: &str) -> HashMap<String, String> {
+		let mut headers = HashMap::new();
+		headers.insert(AUTHORIZATION_HEADER.to_string(), format!("{}{}", BEARER_PREFIX, token));
+		headers
+	}
+
+	#[tokio::test]
+	async fn encrypted_tokens_are_decrypted_before_validation() {
+		let authorizer = JwtAuthorizer::new(TEST_PUBLIC_KEY_PEM.as_bytes())
+			.unwrap()
+			.with_decryption_key(TEST_PRIVATE_KEY_PEM.as_bytes())
+			.unwrap();
+		let token = encrypted_token(&signed_token("user-1"));
+
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(response.user_token, "user-1");
+
+		// Plain JWS tokens keep working alongside.
+		let response =
+			authorizer.verify(&bearer_headers(&signed_token("user-2"))).await.unwrap();
+		assert_eq!(response.user_token, "user-2");
+
+		// A tampered ciphertext must not decrypt.
+		let mut tampered = token.clone();
+		tampered.truncate(token.len() - 2);
+		let result = authorizer.verify(&bearer_headers(&tampered)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn scope_claims_are_surfaced_as_attributes() {
+		let authorizer = JwtAuthorizer::new(TEST_PUBLIC_KEY_PEM.as_bytes()).unwrap();
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let claims = serde_json::json!({ "sub": "user-1", "exp": exp, "scope": "read" });
+		let encoding_key = EncodingKey::from_rsa_pem(TEST_PRIVATE_KEY_PEM.as_bytes()).unwrap();
+		let token = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).unwrap();
+
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(response.attributes.get(SCOPES_ATTRIBUTE).map(String::as_str), Some("read"));
+
+		// Tokens without a scope claim carry no scope restriction.
+		let response = authorizer.verify(&bearer_headers(&signed_token("user-1"))).await.unwrap();
+		assert!(!response.attributes.contains_key(SCOPES_ATTRIBUTE));
+	}
+
+	#[tokio::test]
+	async fn store_id_claims_are_surfaced_as_attributes() {
+		let authorizer = JwtAuthorizer::new(TEST_PUBLIC_KEY_PEM.as_bytes()).unwrap();
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let claims = serde_json::json!({
+			"sub": "user-1",
+			"exp": exp,
+			"store_ids": ["wallet-a", "wallet-b"],
+		});
+		let encoding_key = EncodingKey::from_rsa_pem(TEST_PRIVATE_KEY_PEM.as_bytes()).unwrap();
+		let token = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).unwrap();
+
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(
+			response.attributes.get(ALLOWED_STORE_IDS_ATTRIBUTE).map(String::as_str),
+			Some("wallet-a,wallet-b")
+		);
+	}
+
+	#[tokio::test]
+	async fn configured_algorithms_are_enforced() {
+		const ES256_PRIVATE_KEY_PEM: &str = include_str!("fixtures/es256-test-private-key.pem");
+		const ES256_PUBLIC_KEY_PEM: &str = include_str!("fixtures/es256-test-public-key.pem");
+		let authorizer =
+			JwtAuthorizer::new_with_algorithm(ES256_PUBLIC_KEY_PEM.as_bytes(), "ES256").unwrap();
+
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let claims = serde_json::json!({ "sub": "user-1", "exp": exp });
+		let encoding_key = EncodingKey::from_ec_pem(ES256_PRIVATE_KEY_PEM.as_bytes()).unwrap();
+		let token = encode(&Header::new(Algorithm::ES256), &claims, &encoding_key).unwrap();
+
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(response.user_token, "user-1");
+
+		// Tokens signed with a different algorithm are rejected by their header alone.
+		let result = authorizer.verify(&bearer_headers(&signed_token("user-1"))).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn hmac_algorithms_use_a_shared_secret() {
+		let authorizer = JwtAuthorizer::new_with_algorithm(b"test-secret", "HS256").unwrap();
+
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let claims = serde_json::json!({ "sub": "user-1", "exp": exp });
+		let encoding_key = EncodingKey::from_secret(b"test-secret");
+		let token = encode(&Header::new(Algorithm::HS256), &claims, &encoding_key).unwrap();
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(response.user_token, "user-1");
+
+		let encoding_key = EncodingKey::from_secret(b"other-secret");
+		let token = encode(&Header::new(Algorithm::HS256), &claims, &encoding_key).unwrap();
+		let result = authorizer.verify(&bearer_headers(&token)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn revoked_jtis_are_rejected_before_expiry() {
+		use crate::auth::revocation::FileRevocationList;
+
+		let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+		let path = std::env::temp_dir().join(format!("vss-jwt-revocation-test-{}", nanos));
+		std::fs::write(&path, "revoked-jti\n").unwrap();
+		let revocation_list =
+			Arc::new(FileRevocationList::new(path.to_str().unwrap().to_string()).unwrap());
+		let authorizer = JwtAuthorizer::new(TEST_PUBLIC_KEY_PEM.as_bytes())
+			.unwrap()
+			.with_revocation_list(revocation_list);
+
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60;
+		let encoding_key = EncodingKey::from_rsa_pem(TEST_PRIVATE_KEY_PEM.as_bytes()).unwrap();
+		let claims = serde_json::json!({ "sub": "user-1", "exp": exp, "jti": "live-jti" });
+		let token = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).unwrap();
+		let response = authorizer.verify(&bearer_headers(&token)).await.unwrap();
+		assert_eq!(response.user_token, "user-1");
+
+		// A still-valid token is rejected the moment its jti lands on the list.
+		let claims = serde_json::json!({ "sub": "user-1", "exp": exp, "jti": "revoked-jti" });
+		let token = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).unwrap();
+		let result = authorizer.verify(&bearer_headers(&token)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		// Tokens without a jti could never be revoked, so they are rejected outright.
+		let result = authorizer.verify(&bearer_headers(&signed_token("user-1"))).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn encrypted_tokens_require_a_decryption_key() {
+		let authorizer = JwtAuthorizer::new(TEST_PUBLIC_KEY_PEM.as_bytes()).unwrap();
+		let token = encrypted_token(&signed_token("user-1"));
+
+		let result = authorizer.verify(&bearer_headers(&token)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+}
@@ -0,0 +1,14 @@
+//! [`Authorizer`] implementations.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+pub mod api_key_authorizer;
+pub mod caching_authorizer;
+pub mod chained_authorizer;
+pub mod jwt_authorizer;
+pub mod mtls_authorizer;
+pub mod nostr_authorizer;
+pub mod oidc_authorizer;
+pub mod remote_authorizer;
+pub mod revocation;
+pub mod signature_validating_authorizer;
@@ -0,0 +1,249 @@
+//! An [`Authorizer`] deriving user identities from mTLS client certificates.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+/// How an [`MtlsAuthorizer`] derives the effective `user_token` from the client certificate.
+#[derive(Clone, Copy, Debug)]
+pub enum ClientIdentity {
+	/// The hex-encoded SHA-256 digest of the certificate's `subjectPublicKeyInfo`, stable
+	/// across certificate renewals as long as the client keeps its key.
+	SpkiSha256,
+	/// The certificate subject's common name, letting the CA assign human-readable identities.
+	SubjectCommonName,
+}
+
+/// An [`Authorizer`] mapping the client certificate of the underlying TLS connection to the
+/// effective `user_token`, either as the SPKI digest or the subject common name.
+///
+/// Certificate validity is not re-checked here: the TLS layer only admits connections whose
+/// client certificate chains to the configured CA, so by the time a request carries a peer
+/// certificate it has already been validated. Requests arriving without one (e.g. on a
+/// plaintext listener) are rejected.
+pub struct MtlsAuthorizer {
+	identity: ClientIdentity,
+}
+
+impl MtlsAuthorizer {
+	/// Constructs an [`MtlsAuthorizer`] deriving user tokens via [`ClientIdentity::SpkiSha256`].
+	pub fn new() -> Self {
+		MtlsAuthorizer { identity: ClientIdentity::SpkiSha256 }
+	}
+
+	/// Returns this authorizer deriving user tokens from the given identity instead.
+	pub fn with_identity(mut self, identity: ClientIdentity) -> Self {
+		self.identity = identity;
+		self
+	}
+}
+
+impl Default for MtlsAuthorizer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A minimal reader over DER elements, sufficient to walk an X.509 certificate.
+struct DerReader<'a> {
+	data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+	fn new(data: &'a [u8]) -> Self {
+		DerReader { data }
+	}
+
+	/// Reads the next element, returning its tag, content and raw bytes (header included).
+	fn next(&mut self) -> Option<(u8, &'a [u8], &'a [u8])> {
+		let (&tag, rest) = self.data.split_first()?;
+		let (&first_length_byte, mut rest) = rest.split_first()?;
+		let length = if first_length_byte & 0x80 == 0 {
+			usize::from(first_length_byte)
+		} else {
+			let length_bytes = usize::from(first_length_byte & 0x7f);
+			if length_bytes == 0 || length_bytes > 4 || rest.len() < length_bytes {
+				return None;
+			}
+			let mut length = 0usize;
+			for &byte in &rest[..length_bytes] {
+				length = (length << 8) | usize::from(byte);
+			}
+			rest = &rest[length_bytes..];
+			length
+		};
+		if rest.len() < length {
+			return None;
+		}
+		let content = &rest[..length];
+		let raw_len = self.data.len() - rest.len() + length;
+		let raw = &self.data[..raw_len];
+		self.data = &rest[length..];
+		Some((tag, content, raw))
+	}
+}
+
+/// The DER tag of a constructed SEQUENCE.
+const DER_SEQUENCE: u8 = 0x30;
+/// The DER tag of a constructed SET.
+const DER_SET: u8 = 0x31;
+/// The DER tag of the explicitly tagged certificate version.
+const DER_CONTEXT_0: u8 = 0xa0;
+/// The encoded attribute type of a subject common name (OID 2.5.4.3).
+const COMMON_NAME_OID: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Walks the certificate up to (and excluding) its subject, returning a reader positioned at
+/// the subject element.
+fn seek_to_subject<'a>(certificate: &'a [u8]) -> Option<DerReader<'a>> {
+	let (tag, content, _) = DerReader::new(certificate).next()?;
+	if tag != DER_SEQUENCE {
+		return None;
+	}
+	let (tag, tbs_certificate, _) = DerReader::new(content).next()?;
+	if tag != DER_SEQUENCE {
+		return None;
+	}
+	let mut reader = DerReader::new(tbs_certificate);
+	// The explicitly tagged version is optional; serial number, signature algorithm, issuer
+	// and validity always precede the subject.
+	let (tag, ..) = reader.next()?;
+	if tag == DER_CONTEXT_0 {
+		reader.next()?;
+	}
+	for _ in 0..3 {
+		reader.next()?;
+	}
+	Some(reader)
+}
+
+/// Extracts the raw `subjectPublicKeyInfo` element of a DER-encoded X.509 certificate.
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+	let mut reader = seek_to_subject(certificate)?;
+	// Skip the subject; the SPKI follows immediately.
+	reader.next()?;
+	let (tag, _, raw) = reader.next()?;
+	if tag != DER_SEQUENCE {
+		return None;
+	}
+	Some(raw)
+}
+
+/// Extracts the subject common name of a DER-encoded X.509 certificate.
+fn subject_common_name(certificate: &[u8]) -> Option<String> {
+	let mut reader = seek_to_subject(certificate)?;
+	let (_, subject, _) = reader.next()?;
+	let mut rdns = DerReader::new(subject);
+	while let Some((tag, rdn, _)) = rdns.next() {
+		if tag != DER_SET {
+			continue;
+		}
+		let mut attributes = DerReader::new(rdn);
+		while let Some((tag, attribute, _)) = attributes.next() {
+			if tag != DER_SEQUENCE {
+				continue;
+			}
+			let mut parts = DerReader::new(attribute);
+			let (_, oid, _) = parts.next()?;
+			if oid != COMMON_NAME_OID {
+				continue;
+			}
+			let (_, value, _) = parts.next()?;
+			return String::from_utf8(value.to_vec()).ok();
+		}
+	}
+	None
+}
+
+#[async_trait]
+impl Authorizer for MtlsAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let certificate = headers.peer_certificate().ok_or_else(|| {
+			VssError::AuthError("No client certificate presented.".to_string())
+		})?;
+		let user_token = match self.identity {
+			ClientIdentity::SpkiSha256 => {
+				let spki = subject_public_key_info(certificate).ok_or_else(|| {
+					VssError::AuthError("Malformed client certificate.".to_string())
+				})?;
+				hex::encode(Sha256::digest(spki))
+			},
+			ClientIdentity::SubjectCommonName => {
+				subject_common_name(certificate).ok_or_else(|| {
+					VssError::AuthError(
+						"Client certificate subject has no common name.".to_string(),
+					)
+				})?
+			},
+		};
+		Ok(AuthResponse::new(user_token))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	// A throwaway self-signed certificate with subject `O=VSS Tests, CN=test-client`.
+	const TEST_CERTIFICATE_DER: &[u8] = include_bytes!("fixtures/mtls-test-client-cert.der");
+
+	/// A header view additionally exposing a peer certificate, standing in for the server's
+	/// TLS transport.
+	struct TlsHeaders {
+		certificate: Option<&'static [u8]>,
+	}
+
+	impl RequestHeaders for TlsHeaders {
+		fn get_header(&self, _name: &str) -> Option<&str> {
+			None
+		}
+
+		fn peer_certificate(&self) -> Option<&[u8]> {
+			self.certificate
+		}
+	}
+
+	#[tokio::test]
+	async fn spki_digests_identify_the_client_key() {
+		let authorizer = MtlsAuthorizer::new();
+		let headers = TlsHeaders { certificate: Some(TEST_CERTIFICATE_DER) };
+
+		let response = authorizer.verify(&headers).await.unwrap();
+		// `openssl x509 -pubkey -noout | openssl pkey -pubin -outform DER | sha256sum` over the
+		// fixture certificate.
+		assert_eq!(
+			response.user_token,
+			"390ad53e5e81954ad807f4da72974410409ef406465fa51376a230329a064c3c"
+		);
+	}
+
+	#[tokio::test]
+	async fn subject_common_names_identify_the_client() {
+		let authorizer = MtlsAuthorizer::new().with_identity(ClientIdentity::SubjectCommonName);
+		let headers = TlsHeaders { certificate: Some(TEST_CERTIFICATE_DER) };
+
+		let response = authorizer.verify(&headers).await.unwrap();
+		assert_eq!(response.user_token, "test-client");
+	}
+
+	#[tokio::test]
+	async fn requests_without_a_certificate_are_rejected() {
+		let authorizer = MtlsAuthorizer::new();
+		let result = authorizer.verify(&TlsHeaders { certificate: None }).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		let mut garbled = TEST_CERTIFICATE_DER.to_vec();
+		garbled.truncate(7);
+		let garbled: &'static [u8] = Box::leak(garbled.into_boxed_slice());
+		let result = authorizer.verify(&TlsHeaders { certificate: Some(garbled) }).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		// The plain HashMap view exposes no certificate either.
+		let result = authorizer.verify(&HashMap::new()).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+}
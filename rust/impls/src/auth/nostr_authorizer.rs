@@ -0,0 +1,318 @@
+//! An [`Authorizer`] validating Nostr NIP-98 HTTP auth events.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use secp256k1::schnorr::Signature;
+use secp256k1::{Message, Secp256k1, VerifyOnly, XOnlyPublicKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+const AUTHORIZATION_HEADER: &str = "authorization";
+const NOSTR_PREFIX: &str = "Nostr ";
+
+/// The event kind reserved for NIP-98 HTTP auth.
+const NIP98_KIND: u64 = 27235;
+
+/// The maximum allowed difference between the event's `created_at` and the server clock.
+const ALLOWED_CLOCK_SKEW_SECS: u64 = 60;
+
+/// A Nostr event, restricted to the fields NIP-98 validation needs.
+#[derive(Deserialize)]
+struct NostrEvent {
+	id: String,
+	pubkey: String,
+	created_at: u64,
+	kind: u64,
+	tags: Vec<Vec<String>>,
+	content: String,
+	sig: String,
+}
+
+/// An [`Authorizer`] accepting NIP-98 HTTP auth events presented as `Authorization: Nostr
+/// <base64(event)>`, mapping the event's signing key (as its `npub` encoding) to the effective
+/// `user_token`.
+///
+/// The event must be of kind `27235`, carry a `created_at` within the allowed clock skew, a
+/// `method` tag of `POST` and a `u` tag whose path matches the request; a `payload` tag, if
+/// present, must match the SHA-256 digest of the request body. Many Lightning wallets already
+/// hold a Nostr identity key, so this needs no separate auth infrastructure; like the
+/// signature-based scheme, users are identified by their keys and need no prior registration.
+pub struct NostrAuthorizer {
+	secp: Secp256k1<VerifyOnly>,
+}
+
+impl NostrAuthorizer {
+	/// Constructs a [`NostrAuthorizer`].
+	pub fn new() -> Self {
+		NostrAuthorizer { secp: Secp256k1::verification_only() }
+	}
+}
+
+impl Default for NostrAuthorizer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Returns the value of the first tag with the given name, if any.
+fn tag_value<'a>(tags: &'a [Vec<String>], name: &str) -> Option<&'a str> {
+	tags.iter()
+		.find(|tag| tag.first().map(String::as_str) == Some(name))
+		.and_then(|tag| tag.get(1))
+		.map(String::as_str)
+}
+
+/// Returns the path component of an absolute URL, e.g. `/vss/putObjects`.
+fn url_path(url: &str) -> &str {
+	let after_scheme = match url.find("://") {
+		Some(position) => &url[position + 3..],
+		None => url,
+	};
+	match after_scheme.find('/') {
+		Some(position) => &after_scheme[position..],
+		None => "/",
+	}
+}
+
+/// The bech32 character set, see BIP-173.
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+	const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+	let mut checksum: u32 = 1;
+	for value in values {
+		let top = checksum >> 25;
+		checksum = ((checksum & 0x1ffffff) << 5) ^ u32::from(*value);
+		for (bit, generator) in GENERATOR.iter().enumerate() {
+			if (top >> bit) & 1 == 1 {
+				checksum ^= generator;
+			}
+		}
+	}
+	checksum
+}
+
+/// Encodes an x-only public key as its bech32 `npub` representation, see NIP-19.
+fn npub_encode(pubkey: &[u8; 32]) -> String {
+	// Regroup the key bytes into 5-bit values, padding the final group with zero bits.
+	let mut data = Vec::with_capacity(52);
+	let mut accumulator: u32 = 0;
+	let mut bits = 0;
+	for byte in pubkey {
+		accumulator = (accumulator << 8) | u32::from(*byte);
+		bits += 8;
+		while bits >= 5 {
+			bits -= 5;
+			data.push(((accumulator >> bits) & 0x1f) as u8);
+		}
+	}
+	if bits > 0 {
+		data.push(((accumulator << (5 - bits)) & 0x1f) as u8);
+	}
+
+	// The checksum covers the expanded human-readable part followed by the data part.
+	let hrp = "npub";
+	let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+	values.push(0);
+	values.extend(hrp.bytes().map(|b| b & 0x1f));
+	values.extend(&data);
+	values.extend([0u8; 6]);
+	let polymod = bech32_polymod(&values) ^ 1;
+
+	let mut encoded = String::with_capacity(hrp.len() + 1 + data.len() + 6);
+	encoded.push_str(hrp);
+	encoded.push('1');
+	for value in data {
+		encoded.push(BECH32_CHARSET[value as usize] as char);
+	}
+	for position in 0..6 {
+		let value = (polymod >> (5 * (5 - position))) & 0x1f;
+		encoded.push(BECH32_CHARSET[value as usize] as char);
+	}
+	encoded
+}
+
+#[async_trait]
+impl Authorizer for NostrAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let invalid = |message: &str| VssError::AuthError(message.to_string());
+		let header = headers
+			.get_header(AUTHORIZATION_HEADER)
+			.ok_or_else(|| invalid("Missing Authorization header."))?;
+		let encoded = header
+			.strip_prefix(NOSTR_PREFIX)
+			.ok_or_else(|| invalid("Authorization header must be a Nostr event."))?;
+		let event_bytes = BASE64_STANDARD
+			.decode(encoded)
+			.map_err(|_| invalid("Invalid Nostr event encoding."))?;
+		let event: NostrEvent = serde_json::from_slice(&event_bytes)
+			.map_err(|_| invalid("Invalid Nostr event."))?;
+
+		if event.kind != NIP98_KIND {
+			return Err(invalid("Nostr event is not an HTTP auth event."));
+		}
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+		if now.abs_diff(event.created_at) > ALLOWED_CLOCK_SKEW_SECS {
+			return Err(invalid("Nostr event timestamp outside of allowed clock skew."));
+		}
+		if tag_value(&event.tags, "method") != Some("POST") {
+			return Err(invalid("Nostr event method tag does not match the request."));
+		}
+		let url = tag_value(&event.tags, "u")
+			.ok_or_else(|| invalid("Nostr event is missing the u tag."))?;
+		if let Some(path) = headers.path() {
+			if url_path(url) != path {
+				return Err(invalid("Nostr event u tag does not match the request path."));
+			}
+		}
+		if let Some(payload) = tag_value(&event.tags, "payload") {
+			let body_sha256 = headers.body_sha256().ok_or_else(|| {
+				invalid("Payload-bound Nostr events are not supported on this transport.")
+			})?;
+			if !payload.eq_ignore_ascii_case(&hex::encode(body_sha256)) {
+				return Err(invalid("Nostr event payload tag does not match the request body."));
+			}
+		}
+
+		// The event id is the digest of the canonical serialization; the signature covers the
+		// id, so both must check out for the event to be authentic.
+		let serialized = serde_json::json!([
+			0,
+			event.pubkey,
+			event.created_at,
+			event.kind,
+			event.tags,
+			event.content,
+		])
+		.to_string();
+		let digest: [u8; 32] = Sha256::digest(serialized.as_bytes()).into();
+		if !event.id.eq_ignore_ascii_case(&hex::encode(digest)) {
+			return Err(invalid("Nostr event id does not match its contents."));
+		}
+		let pubkey_bytes: [u8; 32] = hex::decode(&event.pubkey)
+			.ok()
+			.and_then(|bytes| bytes.try_into().ok())
+			.ok_or_else(|| invalid("Invalid Nostr public key."))?;
+		let pubkey = XOnlyPublicKey::from_slice(&pubkey_bytes)
+			.map_err(|_| invalid("Invalid Nostr public key."))?;
+		let signature_bytes =
+			hex::decode(&event.sig).map_err(|_| invalid("Invalid Nostr signature encoding."))?;
+		let signature = Signature::from_slice(&signature_bytes)
+			.map_err(|_| invalid("Invalid Nostr signature."))?;
+		self.secp
+			.verify_schnorr(&signature, &Message::from_digest(digest), &pubkey)
+			.map_err(|_| invalid("Nostr signature verification failed."))?;
+
+		Ok(AuthResponse::new(npub_encode(&pubkey_bytes)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use secp256k1::{Keypair, SecretKey};
+	use std::collections::HashMap;
+
+	#[test]
+	fn npub_encoding_matches_the_nip19_test_vector() {
+		let pubkey: [u8; 32] =
+			hex::decode("3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d")
+				.unwrap()
+				.try_into()
+				.unwrap();
+		assert_eq!(
+			npub_encode(&pubkey),
+			"npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6"
+		);
+	}
+
+	fn now() -> u64 {
+		SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+	}
+
+	fn signed_event(created_at: u64, kind: u64, tags: Vec<Vec<String>>) -> (String, String) {
+		let secp = Secp256k1::new();
+		let secret_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+		let keypair = Keypair::from_secret_key(&secp, &secret_key);
+		let (pubkey, _) = keypair.x_only_public_key();
+		let pubkey_hex = hex::encode(pubkey.serialize());
+
+		let serialized =
+			serde_json::json!([0, pubkey_hex, created_at, kind, tags, ""]).to_string();
+		let digest: [u8; 32] = Sha256::digest(serialized.as_bytes()).into();
+		let signature =
+			secp.sign_schnorr_no_aux_rand(&Message::from_digest(digest), &keypair);
+		let event = serde_json::json!({
+			"id": hex::encode(digest),
+			"pubkey": pubkey_hex,
+			"created_at": created_at,
+			"kind": kind,
+			"tags": tags,
+			"content": "",
+			"sig": hex::encode(signature.as_ref()),
+		});
+		(BASE64_STANDARD.encode(event.to_string()), npub_encode(&pubkey.serialize()))
+	}
+
+	fn nip98_tags() -> Vec<Vec<String>> {
+		vec![
+			vec!["u".to_string(), "https://vss.example.com/vss/putObjects".to_string()],
+			vec!["method".to_string(), "POST".to_string()],
+		]
+	}
+
+	fn nostr_headers(encoded_event: &str) -> HashMap<String, String> {
+		let mut headers = HashMap::new();
+		headers.insert(
+			AUTHORIZATION_HEADER.to_string(),
+			format!("{}{}", NOSTR_PREFIX, encoded_event),
+		);
+		headers
+	}
+
+	#[tokio::test]
+	async fn valid_events_map_to_the_signing_npub() {
+		let authorizer = NostrAuthorizer::new();
+		let (encoded, npub) = signed_event(now(), NIP98_KIND, nip98_tags());
+
+		let response = authorizer.verify(&nostr_headers(&encoded)).await.unwrap();
+		assert_eq!(response.user_token, npub);
+		assert!(response.user_token.starts_with("npub1"));
+	}
+
+	#[tokio::test]
+	async fn stale_and_foreign_events_are_rejected() {
+		let authorizer = NostrAuthorizer::new();
+
+		let (encoded, _) =
+			signed_event(now() - 2 * ALLOWED_CLOCK_SKEW_SECS, NIP98_KIND, nip98_tags());
+		let result = authorizer.verify(&nostr_headers(&encoded)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		// A validly signed event of another kind is no HTTP auth credential.
+		let (encoded, _) = signed_event(now(), 1, nip98_tags());
+		let result = authorizer.verify(&nostr_headers(&encoded)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn tampered_events_are_rejected() {
+		let authorizer = NostrAuthorizer::new();
+		let (encoded, _) = signed_event(now(), NIP98_KIND, nip98_tags());
+
+		// Re-target the signed event at another URL: the id no longer matches.
+		let mut event: serde_json::Value =
+			serde_json::from_slice(&BASE64_STANDARD.decode(&encoded).unwrap()).unwrap();
+		event["tags"][0][1] = "https://vss.example.com/vss/deleteObject".into();
+		let tampered = BASE64_STANDARD.encode(event.to_string());
+		let result = authorizer.verify(&nostr_headers(&tampered)).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+}
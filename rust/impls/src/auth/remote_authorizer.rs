@@ -0,0 +1,337 @@
+//! An [`Authorizer`] delegating authentication decisions to an external HTTP service.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug_span, Instrument};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+/// How long a decision of the external service is reused before it is asked again, see
+/// [`RemoteAuthorizer::with_cache_ttl`].
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// The headers forwarded to the external service unless overridden, see
+/// [`RemoteAuthorizer::with_forwarded_headers`].
+const DEFAULT_FORWARDED_HEADERS: &[&str] = &["authorization"];
+
+/// The decision returned by the external service.
+#[derive(Deserialize)]
+struct Decision {
+	allowed: bool,
+	#[serde(default)]
+	user_token: Option<String>,
+	#[serde(default)]
+	attributes: Option<HashMap<String, String>>,
+	#[serde(default)]
+	reason: Option<String>,
+}
+
+/// A cached decision, reused until its entry expires. Only decisions are cached, never
+/// infrastructure failures, so an outage of the external service is retried on the next request.
+#[derive(Clone)]
+enum CachedDecision {
+	Allowed(AuthResponse),
+	Denied(String),
+}
+
+/// An [`Authorizer`] POSTing the credential-bearing request headers to a configurable HTTP
+/// endpoint and using the returned decision, so an existing authentication microservice can be
+/// plugged in without implementing the [`Authorizer`] trait in-process.
+///
+/// The request body is a JSON object `{"headers": {"<name>": "<value>", ...}}` carrying the
+/// configured headers. The service answers with
+/// `{"allowed": true, "user_token": "...", "attributes": {...}}` to admit a request or
+/// `{"allowed": false, "reason": "..."}` to reject it; a 401/403 status is treated as a
+/// rejection as well.
+///
+/// Decisions are cached for a short TTL keyed by a digest of the forwarded header values, so a
+/// client hammering the API with the same credential does not hammer the external service with
+/// it. The TTL bounds how long a revoked credential keeps working.
+pub struct RemoteAuthorizer {
+	client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+	endpoint: String,
+	host: String,
+	forwarded_headers: Vec<String>,
+	cache_ttl: Duration,
+	cache: Mutex<HashMap<[u8; 32], (Instant, CachedDecision)>>,
+}
+
+impl RemoteAuthorizer {
+	/// Constructs a [`RemoteAuthorizer`] delegating decisions to the given endpoint URL,
+	/// forwarding the `Authorization` header.
+	pub fn new(endpoint: String) -> Result<Self, VssError> {
+		// The crypto provider is pinned explicitly: with several rustls provider features
+		// enabled somewhere in the dependency graph, relying on the default panics.
+		let connector = HttpsConnectorBuilder::new()
+			.with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+			.map_err(|e| {
+				VssError::InternalServerError(format!("Failed to initialize TLS: {}", e))
+			})?
+			.https_or_http()
+			.enable_http1()
+			.build();
+		let client = Client::builder(TokioExecutor::new()).build::<_, Full<Bytes>>(connector);
+		let uri: Uri = endpoint.parse().map_err(|e| {
+			VssError::InternalServerError(format!("Invalid URL {}: {}", endpoint, e))
+		})?;
+		let host = uri.host().ok_or_else(|| {
+			VssError::InternalServerError(format!("URL {} has no host.", endpoint))
+		})?;
+		let host = match uri.port_u16() {
+			Some(port) => format!("{}:{}", host, port),
+			None => host.to_string(),
+		};
+		Ok(RemoteAuthorizer {
+			client,
+			endpoint,
+			host,
+			forwarded_headers: DEFAULT_FORWARDED_HEADERS.iter().map(|s| s.to_string()).collect(),
+			cache_ttl: DEFAULT_CACHE_TTL,
+			cache: Mutex::new(HashMap::new()),
+		})
+	}
+
+	/// Returns this authorizer forwarding the given (lowercase) headers to the external service
+	/// instead of just `authorization`.
+	pub fn with_forwarded_headers(mut self, forwarded_headers: Vec<String>) -> Self {
+		self.forwarded_headers = forwarded_headers;
+		self
+	}
+
+	/// Returns this authorizer caching decisions for the given TTL instead of the default five
+	/// seconds. A zero TTL disables caching, asking the external service on every request.
+	pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+		self.cache_ttl = cache_ttl;
+		self
+	}
+
+	/// Collects the forwarded headers present on the request and a cache key digesting their
+	/// values, so raw credentials are never held as map keys.
+	fn forwarded(&self, headers: &dyn RequestHeaders) -> (HashMap<String, String>, [u8; 32]) {
+		let mut forwarded = HashMap::new();
+		let mut hasher = Sha256::new();
+		for name in &self.forwarded_headers {
+			if let Some(value) = headers.get_header(name) {
+				hasher.update(name.as_bytes());
+				hasher.update([0u8]);
+				hasher.update(value.as_bytes());
+				hasher.update([0u8]);
+				forwarded.insert(name.clone(), value.to_string());
+			}
+		}
+		(forwarded, hasher.finalize().into())
+	}
+
+	fn cached_decision(&self, cache_key: &[u8; 32]) -> Option<CachedDecision> {
+		let mut cache = self.cache.lock().unwrap();
+		cache.retain(|_, (inserted, _)| inserted.elapsed() < self.cache_ttl);
+		cache.get(cache_key).map(|(_, decision)| decision.clone())
+	}
+
+	async fn ask_service(
+		&self, forwarded: &HashMap<String, String>,
+	) -> Result<CachedDecision, VssError> {
+		let body = serde_json::json!({ "headers": forwarded }).to_string();
+		let request = Request::builder()
+			.method(Method::POST)
+			.uri(&self.endpoint)
+			.header("host", &self.host)
+			.header("content-type", "application/json")
+			.body(Full::new(Bytes::from(body)))
+			.map_err(|e| {
+				VssError::InternalServerError(format!("Failed to build request: {}", e))
+			})?;
+		let response = self
+			.client
+			.request(request)
+			.instrument(debug_span!("remote_authorizer", endpoint = self.endpoint))
+			.await
+			.map_err(|e| {
+				VssError::InternalServerError(format!(
+					"Request to {} failed: {}",
+					self.endpoint, e
+				))
+			})?;
+		let status = response.status();
+		let body = response.into_body().collect().await.map_err(|e| {
+			VssError::InternalServerError(format!("Failed to read response: {}", e))
+		})?;
+		if status == hyper::StatusCode::UNAUTHORIZED || status == hyper::StatusCode::FORBIDDEN {
+			return Ok(CachedDecision::Denied(
+				"Rejected by the authorization service.".to_string(),
+			));
+		}
+		if !status.is_success() {
+			return Err(VssError::InternalServerError(format!(
+				"Request to {} failed with status {}.",
+				self.endpoint, status
+			)));
+		}
+		let decision: Decision = serde_json::from_slice(&body.to_bytes()).map_err(|e| {
+			VssError::InternalServerError(format!(
+				"Failed to parse response from {}: {}",
+				self.endpoint, e
+			))
+		})?;
+		if !decision.allowed {
+			return Ok(CachedDecision::Denied(
+				decision.reason.unwrap_or_else(|| {
+					"Rejected by the authorization service.".to_string()
+				}),
+			));
+		}
+		let user_token = decision.user_token.ok_or_else(|| {
+			VssError::InternalServerError(format!(
+				"The authorization service at {} allowed a request without a user_token.",
+				self.endpoint
+			))
+		})?;
+		let mut response = AuthResponse::new(user_token);
+		if let Some(attributes) = decision.attributes {
+			response.attributes = attributes;
+		}
+		Ok(CachedDecision::Allowed(response))
+	}
+}
+
+#[async_trait]
+impl Authorizer for RemoteAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let (forwarded, cache_key) = self.forwarded(headers);
+		if forwarded.is_empty() {
+			return Err(VssError::AuthError("Missing credentials.".to_string()));
+		}
+		let decision = match self.cached_decision(&cache_key) {
+			Some(decision) => decision,
+			None => {
+				let decision = self.ask_service(&forwarded).await?;
+				if !self.cache_ttl.is_zero() {
+					self.cache
+						.lock()
+						.unwrap()
+						.insert(cache_key, (Instant::now(), decision.clone()));
+				}
+				decision
+			},
+		};
+		match decision {
+			CachedDecision::Allowed(response) => Ok(response),
+			CachedDecision::Denied(reason) => Err(VssError::AuthError(reason)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpListener;
+
+	/// Serves a minimal authorization service answering every POST with the given body, counting
+	/// the requests it saw. Returns its endpoint URL.
+	async fn spawn_auth_service(body: String, requests: Arc<AtomicUsize>) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let endpoint = format!("http://{}/authorize", listener.local_addr().unwrap());
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = Vec::new();
+				let mut buf = [0u8; 1024];
+				while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+					match stream.read(&mut buf).await {
+						Ok(0) | Err(_) => break,
+						Ok(n) => request.extend_from_slice(&buf[..n]),
+					}
+				}
+				requests.fetch_add(1, Ordering::SeqCst);
+				let response = format!(
+					"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+					content-length: {}\r\nconnection: close\r\n\r\n{}",
+					body.len(),
+					body
+				);
+				let _ = stream.write_all(response.as_bytes()).await;
+			}
+		});
+		endpoint
+	}
+
+	fn bearer_headers(token: &str) -> HashMap<String, String> {
+		let mut headers = HashMap::new();
+		headers.insert("authorization".to_string(), format!("Bearer {}", token));
+		headers
+	}
+
+	#[tokio::test]
+	async fn decisions_of_the_external_service_are_applied() {
+		let body = json!({
+			"allowed": true,
+			"user_token": "user-1",
+			"attributes": { "tier": "pro" },
+		})
+		.to_string();
+		let requests = Arc::new(AtomicUsize::new(0));
+		let endpoint = spawn_auth_service(body, Arc::clone(&requests)).await;
+		let authorizer =
+			RemoteAuthorizer::new(endpoint).unwrap().with_cache_ttl(Duration::ZERO);
+
+		let response = authorizer.verify(&bearer_headers("token-1")).await.unwrap();
+		assert_eq!(response.user_token, "user-1");
+		assert_eq!(response.attributes.get("tier").map(String::as_str), Some("pro"));
+
+		// Requests carrying none of the forwarded headers are rejected without asking.
+		let result = authorizer.verify(&HashMap::new()).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+		assert_eq!(requests.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test]
+	async fn denials_surface_the_reported_reason() {
+		let body = json!({ "allowed": false, "reason": "Token revoked." }).to_string();
+		let endpoint = spawn_auth_service(body, Arc::new(AtomicUsize::new(0))).await;
+		let authorizer = RemoteAuthorizer::new(endpoint).unwrap();
+
+		let result = authorizer.verify(&bearer_headers("token-1")).await;
+		match result {
+			Err(VssError::AuthError(reason)) => assert_eq!(reason, "Token revoked."),
+			other => panic!("Unexpected result: {:?}", other.map(|r| r.user_token)),
+		}
+	}
+
+	#[tokio::test]
+	async fn decisions_are_cached_per_credential() {
+		let body = json!({ "allowed": true, "user_token": "user-1" }).to_string();
+		let requests = Arc::new(AtomicUsize::new(0));
+		let endpoint = spawn_auth_service(body, Arc::clone(&requests)).await;
+		let authorizer = RemoteAuthorizer::new(endpoint)
+			.unwrap()
+			.with_cache_ttl(Duration::from_secs(60));
+
+		for _ in 0..3 {
+			let response = authorizer.verify(&bearer_headers("token-1")).await.unwrap();
+			assert_eq!(response.user_token, "user-1");
+		}
+		assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+		// A different credential is a different cache entry.
+		authorizer.verify(&bearer_headers("token-2")).await.unwrap();
+		assert_eq!(requests.load(Ordering::SeqCst), 2);
+	}
+}
@@ -0,0 +1,199 @@
+//! A [`RevocationList`] denylisting token ids (`jti` claims), so a compromised token can be
+//! invalidated before it expires.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use api::error::VssError;
+use api::kv_store::{KvStore, RequestContext};
+use api::types::GetObjectRequest;
+
+/// A denylist of revoked token ids, consulted by the JWT authorizer on every request: a token
+/// whose `jti` claim is listed is rejected even though its signature and expiry still check out.
+#[async_trait]
+pub trait RevocationList: Send + Sync {
+	/// Returns whether the token with the given `jti` has been revoked.
+	async fn is_revoked(&self, jti: &str) -> Result<bool, VssError>;
+}
+
+/// A [`RevocationList`] reading a file of one `jti` per line (e.g. a mounted Docker/Kubernetes
+/// secret). Blank lines and lines starting with `#` are skipped.
+///
+/// With a refresh interval set, the file is re-read once the interval has elapsed (on the next
+/// check, so an idle server polls nothing), letting newly revoked tokens take effect without a
+/// restart. A failing refresh keeps the last successfully-loaded list, so a briefly unreadable
+/// file does not drop any standing revocations.
+pub struct FileRevocationList {
+	path: String,
+	entries: RwLock<HashSet<String>>,
+	refresh_interval: Option<Duration>,
+	last_refresh: Mutex<Instant>,
+}
+
+impl FileRevocationList {
+	/// Constructs a [`FileRevocationList`], loading the initial list from the given file.
+	pub fn new(path: String) -> Result<Self, VssError> {
+		let entries = load_entries(&path)?;
+		Ok(FileRevocationList {
+			path,
+			entries: RwLock::new(entries),
+			refresh_interval: None,
+			last_refresh: Mutex::new(Instant::now()),
+		})
+	}
+
+	/// Returns this list re-reading its file once the given interval has elapsed.
+	pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+		self.refresh_interval = Some(refresh_interval);
+		self
+	}
+
+	fn maybe_refresh(&self) {
+		let refresh_interval = match self.refresh_interval {
+			Some(refresh_interval) => refresh_interval,
+			None => return,
+		};
+		{
+			let mut last_refresh = self.last_refresh.lock().unwrap();
+			if last_refresh.elapsed() < refresh_interval {
+				return;
+			}
+			// Reset before reloading so concurrent requests don't pile onto the file.
+			*last_refresh = Instant::now();
+		}
+		match load_entries(&self.path) {
+			Ok(entries) => *self.entries.write().unwrap() = entries,
+			Err(e) => {
+				warn!("Failed to refresh the revocation list, keeping the current one: {}", e)
+			},
+		}
+	}
+}
+
+fn load_entries(path: &str) -> Result<HashSet<String>, VssError> {
+	let contents = std::fs::read_to_string(path).map_err(|e| {
+		VssError::InternalServerError(format!(
+			"Failed to read the revocation list from {}: {}",
+			path, e
+		))
+	})?;
+	Ok(contents
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(str::to_string)
+		.collect())
+}
+
+#[async_trait]
+impl RevocationList for FileRevocationList {
+	async fn is_revoked(&self, jti: &str) -> Result<bool, VssError> {
+		self.maybe_refresh();
+		Ok(self.entries.read().unwrap().contains(jti))
+	}
+}
+
+/// A [`RevocationList`] backed by a [`KvStore`]: a token is revoked while a key named after its
+/// `jti` exists in the configured store, so revocations can be written (and expired revocations
+/// cleaned up) through the regular storage API and are shared by every server instance on the
+/// same backend.
+pub struct KvStoreRevocationList {
+	store: Arc<dyn KvStore>,
+	user_token: String,
+	store_id: String,
+}
+
+impl KvStoreRevocationList {
+	/// Constructs a [`KvStoreRevocationList`] reading revocations from the given store, under the
+	/// given `user_token` and `store_id`. These should be reserved for the revocation list, so no
+	/// client-writable keys alias a revocation.
+	pub fn new(store: Arc<dyn KvStore>, user_token: String, store_id: String) -> Self {
+		KvStoreRevocationList { store, user_token, store_id }
+	}
+}
+
+#[async_trait]
+impl RevocationList for KvStoreRevocationList {
+	async fn is_revoked(&self, jti: &str) -> Result<bool, VssError> {
+		let context = RequestContext::new(self.user_token.clone());
+		let request = GetObjectRequest {
+			store_id: self.store_id.clone(),
+			key: jti.to_string(),
+		};
+		match self.store.get(context, request).await {
+			Ok(_) => Ok(true),
+			Err(VssError::NoSuchKeyError(..)) => Ok(false),
+			// Fail closed: an unreachable backend must not let revoked tokens through.
+			Err(e) => Err(e),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::memory_store::MemoryBackendImpl;
+	use api::types::{KeyValue, PutObjectRequest};
+
+	fn temp_list_file(contents: &str) -> String {
+		let nanos = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let path = std::env::temp_dir().join(format!("vss-revocation-test-{}", nanos));
+		std::fs::write(&path, contents).unwrap();
+		path.to_str().unwrap().to_string()
+	}
+
+	#[tokio::test]
+	async fn file_lists_match_listed_jtis() {
+		let path = temp_list_file("# revoked on 2024-01-01\njti-1\n\njti-2\n");
+		let list = FileRevocationList::new(path).unwrap();
+
+		assert!(list.is_revoked("jti-1").await.unwrap());
+		assert!(list.is_revoked("jti-2").await.unwrap());
+		assert!(!list.is_revoked("jti-3").await.unwrap());
+	}
+
+	#[tokio::test]
+	async fn file_lists_reload_after_the_refresh_interval() {
+		let path = temp_list_file("jti-1\n");
+		let list = FileRevocationList::new(path.clone())
+			.unwrap()
+			.with_refresh_interval(Duration::from_secs(0));
+
+		std::fs::write(&path, "jti-2\n").unwrap();
+		assert!(list.is_revoked("jti-2").await.unwrap());
+		assert!(!list.is_revoked("jti-1").await.unwrap());
+	}
+
+	#[tokio::test]
+	async fn store_backed_lists_match_stored_keys() {
+		let store = Arc::new(MemoryBackendImpl::new());
+		let context = RequestContext::new("revocations".to_string());
+		let request = PutObjectRequest {
+			store_id: "revoked-jtis".to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: "jti-1".to_string(),
+				version: 0,
+				value: bytes::Bytes::new(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		store.put(context, request).await.unwrap();
+
+		let list = KvStoreRevocationList::new(
+			store,
+			"revocations".to_string(),
+			"revoked-jtis".to_string(),
+		);
+		assert!(list.is_revoked("jti-1").await.unwrap());
+		assert!(!list.is_revoked("jti-2").await.unwrap());
+	}
+}
@@ -0,0 +1,360 @@
+//! An [`Authorizer`] validating secp256k1 signatures over a timestamped challenge.
+//!
+//! [`Authorizer`]: api::auth::Authorizer
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use secp256k1::ecdsa::Signature;
+use secp256k1::{Message, PublicKey, Secp256k1, VerifyOnly};
+use sha2::{Digest, Sha256};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+/// The header carrying the hex-encoded, compressed secp256k1 public key of the client.
+pub const PUBKEY_HEADER: &str = "x-vss-pubkey";
+/// The header carrying the unix timestamp (in seconds) the signature was created at.
+pub const TIMESTAMP_HEADER: &str = "x-vss-timestamp";
+/// The header carrying the hex-encoded, compact-serialized ECDSA signature.
+pub const SIGNATURE_HEADER: &str = "x-vss-signature";
+/// The header carrying the signature scheme version. Absent or `1`, the signature covers only
+/// the timestamped challenge; `2`, it additionally covers the request path and body.
+pub const AUTH_VERSION_HEADER: &str = "x-vss-auth-version";
+
+/// The maximum allowed difference between the signed timestamp and the server clock.
+const ALLOWED_CLOCK_SKEW_SECS: u64 = 60;
+
+/// An [`Authorizer`] authenticating clients through an ECDSA signature over a timestamped
+/// challenge message.
+///
+/// Clients sign `SHA256("vss-auth:{timestamp}")` with their secp256k1 identity key and provide
+/// the public key, timestamp and signature via the [`PUBKEY_HEADER`], [`TIMESTAMP_HEADER`] and
+/// [`SIGNATURE_HEADER`] headers. The hex-encoded public key serves as the effective
+/// `user_token`, i.e. users are identified by their keys and need no prior registration.
+///
+/// A version-1 signature binds to nothing but the timestamp, so a captured header set can be
+/// replayed for arbitrary operations within the clock-skew window. Clients should send
+/// [`AUTH_VERSION_HEADER`] `2` and sign
+/// `SHA256("vss-auth-v2:{timestamp}:{path}:{hex(sha256(body))}")` instead, binding the
+/// signature to the specific request; version 1 remains accepted while deployed clients
+/// migrate.
+///
+/// Since any key that signs a valid challenge becomes a user, an attacker can mint identities
+/// at will. [`with_registration_limit`] caps how many never-before-seen keys a single source IP
+/// (the transport-derived [`RequestHeaders::client_ip`], which forwarded headers cannot spoof)
+/// may introduce per hour; excess registrations are rejected while already-seen keys
+/// authenticate unthrottled. The first-seen set is held in memory, so a restart starts counting
+/// afresh.
+///
+/// [`with_registration_limit`]: Self::with_registration_limit
+pub struct SignatureValidatingAuthorizer {
+	secp: Secp256k1<VerifyOnly>,
+	registration_limit_per_hour: Option<u32>,
+	seen_pubkeys: RwLock<HashSet<String>>,
+	// Maps a source IP to the hour-window it last registered a new key in and the registration
+	// count within that window.
+	registration_windows: Mutex<HashMap<String, (u64, u32)>>,
+}
+
+impl SignatureValidatingAuthorizer {
+	/// Constructs a [`SignatureValidatingAuthorizer`].
+	pub fn new() -> Self {
+		SignatureValidatingAuthorizer {
+			secp: Secp256k1::verification_only(),
+			registration_limit_per_hour: None,
+			seen_pubkeys: RwLock::new(HashSet::new()),
+			registration_windows: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Returns this authorizer capping the number of previously unseen public keys a single
+	/// source IP may introduce per hour.
+	pub fn with_registration_limit(mut self, registrations_per_hour: u32) -> Self {
+		self.registration_limit_per_hour = Some(registrations_per_hour);
+		self
+	}
+
+	/// Returns the message which version-1 clients are expected to sign for the given timestamp.
+	pub fn challenge_message(timestamp: u64) -> Message {
+		let digest = Sha256::digest(format!("vss-auth:{}", timestamp).as_bytes());
+		Message::from_digest(digest.into())
+	}
+
+	/// Returns the message which version-2 clients are expected to sign, binding the signature
+	/// to the request's path and body in addition to the timestamp.
+	pub fn content_challenge_message(
+		timestamp: u64, path: &str, body_sha256: &[u8; 32],
+	) -> Message {
+		let payload =
+			format!("vss-auth-v2:{}:{}:{}", timestamp, path, hex::encode(body_sha256));
+		Message::from_digest(Sha256::digest(payload.as_bytes()).into())
+	}
+}
+
+impl Default for SignatureValidatingAuthorizer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+fn required_header<'a>(
+	headers: &'a dyn RequestHeaders, header: &str,
+) -> Result<&'a str, VssError> {
+	headers
+		.get_header(header)
+		.ok_or_else(|| VssError::AuthError(format!("Missing required header: {}", header)))
+}
+
+#[async_trait]
+impl Authorizer for SignatureValidatingAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let pubkey_hex = required_header(headers, PUBKEY_HEADER)?;
+		let timestamp_str = required_header(headers, TIMESTAMP_HEADER)?;
+		let signature_hex = required_header(headers, SIGNATURE_HEADER)?;
+
+		let timestamp: u64 = timestamp_str
+			.parse()
+			.map_err(|_| VssError::AuthError("Invalid timestamp.".to_string()))?;
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+		if now.abs_diff(timestamp) > ALLOWED_CLOCK_SKEW_SECS {
+			return Err(VssError::AuthError("Timestamp outside of allowed clock skew.".to_string()));
+		}
+
+		let pubkey_bytes = hex::decode(pubkey_hex)
+			.map_err(|_| VssError::AuthError("Invalid public key encoding.".to_string()))?;
+		let pubkey = PublicKey::from_slice(&pubkey_bytes)
+			.map_err(|_| VssError::AuthError("Invalid public key.".to_string()))?;
+		let signature_bytes = hex::decode(signature_hex)
+			.map_err(|_| VssError::AuthError("Invalid signature encoding.".to_string()))?;
+		let signature = Signature::from_compact(&signature_bytes)
+			.map_err(|_| VssError::AuthError("Invalid signature.".to_string()))?;
+
+		let message = match headers.get_header(AUTH_VERSION_HEADER) {
+			None | Some("1") => Self::challenge_message(timestamp),
+			Some("2") => {
+				let unsupported = || {
+					VssError::AuthError(
+						"Content-bound signatures are not supported on this transport."
+							.to_string(),
+					)
+				};
+				let path = headers.path().ok_or_else(unsupported)?;
+				let body_sha256 = headers.body_sha256().ok_or_else(unsupported)?;
+				Self::content_challenge_message(timestamp, path, &body_sha256)
+			},
+			Some(version) => {
+				return Err(VssError::AuthError(format!(
+					"Unsupported signature scheme version: {}",
+					version
+				)))
+			},
+		};
+		self.secp
+			.verify_ecdsa(&message, &signature, &pubkey)
+			.map_err(|_| VssError::AuthError("Signature verification failed.".to_string()))?;
+
+		let user_token = pubkey_hex.to_lowercase();
+		if let Some(limit) = self.registration_limit_per_hour {
+			if !self.seen_pubkeys.read().unwrap().contains(&user_token) {
+				// The transport-derived client IP cannot be chosen via forwarded headers, so an
+				// attacker cannot rotate the throttle key out from under the limit.
+				let source_ip = headers.client_ip().unwrap_or("unknown");
+				let current_hour = now / 3600;
+				let mut windows = self.registration_windows.lock().unwrap();
+				let entry = windows.entry(source_ip.to_string()).or_insert((current_hour, 0));
+				if entry.0 != current_hour {
+					*entry = (current_hour, 0);
+				}
+				if entry.1 >= limit {
+					return Err(VssError::AuthError(
+						"New-user registration limit exceeded; retry later.".to_string(),
+					));
+				}
+				entry.1 += 1;
+				drop(windows);
+				self.seen_pubkeys.write().unwrap().insert(user_token.clone());
+			}
+		}
+
+		Ok(AuthResponse::new(user_token))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use secp256k1::SecretKey;
+	use std::collections::HashMap;
+
+	fn signed_headers(timestamp: u64) -> HashMap<String, String> {
+		signed_headers_for_key(0x42, timestamp)
+	}
+
+	fn signed_headers_for_key(secret_byte: u8, timestamp: u64) -> HashMap<String, String> {
+		let secp = Secp256k1::new();
+		let secret_key = SecretKey::from_slice(&[secret_byte; 32]).unwrap();
+		let pubkey = secret_key.public_key(&secp);
+		let signature =
+			secp.sign_ecdsa(&SignatureValidatingAuthorizer::challenge_message(timestamp), &secret_key);
+
+		let mut headers_map = HashMap::new();
+		headers_map.insert(PUBKEY_HEADER.to_string(), hex::encode(pubkey.serialize()));
+		headers_map.insert(TIMESTAMP_HEADER.to_string(), timestamp.to_string());
+		headers_map
+			.insert(SIGNATURE_HEADER.to_string(), hex::encode(signature.serialize_compact()));
+		headers_map
+	}
+
+	fn now() -> u64 {
+		SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+	}
+
+	/// A header view additionally exposing the request path and body digest, standing in for
+	/// the server's transport.
+	struct BoundHeaders {
+		headers: HashMap<String, String>,
+		path: String,
+		body_sha256: [u8; 32],
+	}
+
+	impl RequestHeaders for BoundHeaders {
+		fn get_header(&self, name: &str) -> Option<&str> {
+			self.headers.get(name).map(String::as_str)
+		}
+
+		fn path(&self) -> Option<&str> {
+			Some(&self.path)
+		}
+
+		fn body_sha256(&self) -> Option<[u8; 32]> {
+			Some(self.body_sha256)
+		}
+	}
+
+	fn content_signed_headers(timestamp: u64, path: &str, body: &[u8]) -> BoundHeaders {
+		let secp = Secp256k1::new();
+		let secret_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+		let pubkey = secret_key.public_key(&secp);
+		let body_sha256: [u8; 32] = Sha256::digest(body).into();
+		let message = SignatureValidatingAuthorizer::content_challenge_message(
+			timestamp,
+			path,
+			&body_sha256,
+		);
+		let signature = secp.sign_ecdsa(&message, &secret_key);
+
+		let mut headers_map = HashMap::new();
+		headers_map.insert(PUBKEY_HEADER.to_string(), hex::encode(pubkey.serialize()));
+		headers_map.insert(TIMESTAMP_HEADER.to_string(), timestamp.to_string());
+		headers_map
+			.insert(SIGNATURE_HEADER.to_string(), hex::encode(signature.serialize_compact()));
+		headers_map.insert(AUTH_VERSION_HEADER.to_string(), "2".to_string());
+		BoundHeaders { headers: headers_map, path: path.to_string(), body_sha256 }
+	}
+
+	#[tokio::test]
+	async fn accepts_valid_signature() {
+		let authorizer = SignatureValidatingAuthorizer::new();
+		let headers_map = signed_headers(now());
+
+		let response = authorizer.verify(&headers_map).await.unwrap();
+		assert_eq!(response.user_token, headers_map[PUBKEY_HEADER]);
+	}
+
+	#[tokio::test]
+	async fn content_bound_signatures_cover_path_and_body() {
+		let authorizer = SignatureValidatingAuthorizer::new();
+		let headers = content_signed_headers(now(), "/vss/putObjects", b"payload");
+		let response = authorizer.verify(&headers).await.unwrap();
+		assert_eq!(response.user_token, headers.headers[PUBKEY_HEADER]);
+
+		// Replaying the captured headers against another operation or body must fail.
+		let mut replayed = content_signed_headers(now(), "/vss/putObjects", b"payload");
+		replayed.path = "/vss/deleteObject".to_string();
+		let result = authorizer.verify(&replayed).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		let mut replayed = content_signed_headers(now(), "/vss/putObjects", b"payload");
+		replayed.body_sha256 = Sha256::digest(b"other-payload").into();
+		let result = authorizer.verify(&replayed).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn unsupported_scheme_versions_are_rejected() {
+		let authorizer = SignatureValidatingAuthorizer::new();
+		let mut headers_map = signed_headers(now());
+		headers_map.insert(AUTH_VERSION_HEADER.to_string(), "3".to_string());
+		let result = authorizer.verify(&headers_map).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		// Version 2 requires a transport exposing the path and body digest.
+		let mut headers_map = signed_headers(now());
+		headers_map.insert(AUTH_VERSION_HEADER.to_string(), "2".to_string());
+		let result = authorizer.verify(&headers_map).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	/// A header view additionally exposing a transport-derived client IP, standing in for the
+	/// server's transport.
+	struct AddressedHeaders {
+		headers: HashMap<String, String>,
+		client_ip: &'static str,
+	}
+
+	impl RequestHeaders for AddressedHeaders {
+		fn get_header(&self, name: &str) -> Option<&str> {
+			self.headers.get(name).map(String::as_str)
+		}
+
+		fn client_ip(&self) -> Option<&str> {
+			Some(self.client_ip)
+		}
+	}
+
+	#[tokio::test]
+	async fn new_user_registrations_are_throttled_per_ip() {
+		let authorizer = SignatureValidatingAuthorizer::new().with_registration_limit(1);
+
+		let from = |secret_byte, client_ip| AddressedHeaders {
+			headers: signed_headers_for_key(secret_byte, now()),
+			client_ip,
+		};
+		let first_key = from(0x01, "203.0.113.9");
+		authorizer.verify(&first_key).await.unwrap();
+
+		// The same IP may not introduce a second unseen key within the hour, and forged
+		// forwarded headers do not move it to another throttle bucket.
+		let mut second_key = from(0x02, "203.0.113.9");
+		second_key.headers.insert("x-forwarded-for".to_string(), "198.51.100.1".to_string());
+		let result = authorizer.verify(&second_key).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+
+		// Already-seen keys keep authenticating, and other IPs have their own budget.
+		authorizer.verify(&first_key).await.unwrap();
+		let other_ip = from(0x02, "198.51.100.1");
+		authorizer.verify(&other_ip).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn rejects_stale_timestamp() {
+		let authorizer = SignatureValidatingAuthorizer::new();
+		let headers_map = signed_headers(now() - 2 * ALLOWED_CLOCK_SKEW_SECS);
+
+		let result = authorizer.verify(&headers_map).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+
+	#[tokio::test]
+	async fn rejects_tampered_signature() {
+		let authorizer = SignatureValidatingAuthorizer::new();
+		let mut headers_map = signed_headers(now());
+		headers_map.insert(TIMESTAMP_HEADER.to_string(), now().wrapping_add(1).to_string());
+
+		let result = authorizer.verify(&headers_map).await;
+		assert!(matches!(result, Err(VssError::AuthError(..))));
+	}
+}
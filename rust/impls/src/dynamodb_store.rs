@@ -0,0 +1,786 @@
+//! A DynamoDB-backed [`KvStore`] implementation.
+//!
+//! Rows map `(user_token, store_id, key)` onto a composite primary key: the partition key joins
+//! the user token and store id, the sort key is the record key, so a store is a contiguous,
+//! `Query`-able slice of one partition. Key-level versioning is enforced with condition
+//! expressions and multi-item [`PutObjectRequest`]s commit via `TransactWriteItems`, keeping the
+//! all-or-nothing semantics of the other backends without a coordinating server.
+//!
+//! The backend speaks the low-level DynamoDB JSON protocol directly (signed with SigV4 via
+//! [`crate::aws`]) rather than pulling in the AWS SDK, mirroring how the S3 backup job and the
+//! Secrets Manager provider are implemented.
+
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use serde_json::{json, Value};
+use tracing::{debug_span, Instrument};
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+use crate::aws::{format_amz_date, sigv4_authorization, AwsCredentials, SigV4Params};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i32 = 1000;
+
+/// The maximum number of operations DynamoDB accepts in a single `TransactWriteItems` call,
+/// bounding the size of an atomic [`PutObjectRequest`] on this backend.
+const MAX_TRANSACT_ITEMS: usize = 100;
+
+/// Separates the user token from the store id in the partition key. Store ids are validated by
+/// the service layer to contain no control characters, so the last separator in a partition key
+/// is unambiguous and no two `(user_token, store_id)` pairs collide.
+const PARTITION_KEY_SEPARATOR: char = '\u{1f}';
+
+/// A [`KvStore`] implementation backed by a single DynamoDB table.
+///
+/// The table uses a string partition key `pk` and string sort key `sk`; records carry the
+/// version as `v` (number), the value as `val` (binary) and the last-update timestamp as `ts`
+/// (number, milliseconds). [`create_table_if_missing`] provisions a matching on-demand table,
+/// mainly for tests and local development against DynamoDB Local.
+///
+/// [`create_table_if_missing`]: DynamoDbBackendImpl::create_table_if_missing
+pub struct DynamoDbBackendImpl {
+	client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+	credentials: AwsCredentials,
+	region: String,
+	endpoint: String,
+	host: String,
+	table_name: String,
+}
+
+/// A failure reported while calling the DynamoDB API, keeping the service-side error code
+/// distinguishable so callers can map conditional failures onto conflict semantics.
+enum CallError {
+	/// The service rejected the request; `code` is the suffix of the reported `__type`.
+	Api { code: String, body: Value },
+	Transport(String),
+}
+
+impl CallError {
+	fn into_internal_error(self) -> VssError {
+		match self {
+			CallError::Api { code, body } => {
+				let message = body["message"]
+					.as_str()
+					.or_else(|| body["Message"].as_str())
+					.unwrap_or_default();
+				VssError::InternalServerError(format!("DynamoDB error {}: {}", code, message))
+			},
+			CallError::Transport(message) => VssError::InternalServerError(message),
+		}
+	}
+}
+
+impl DynamoDbBackendImpl {
+	/// Constructs a [`DynamoDbBackendImpl`] against the given table. Without an explicit
+	/// `endpoint` (e.g. DynamoDB Local), the regional AWS endpoint is used.
+	pub fn new(
+		region: String, endpoint: Option<String>, table_name: String,
+		credentials: AwsCredentials,
+	) -> Result<Self, VssError> {
+		let endpoint = endpoint
+			.unwrap_or_else(|| format!("https://dynamodb.{}.amazonaws.com", region));
+		let uri: Uri = endpoint.parse().map_err(|e| {
+			VssError::InternalServerError(format!("Invalid endpoint {}: {}", endpoint, e))
+		})?;
+		let host = uri.host().ok_or_else(|| {
+			VssError::InternalServerError(format!("Endpoint {} has no host.", endpoint))
+		})?;
+		let host = match uri.port_u16() {
+			Some(port) => format!("{}:{}", host, port),
+			None => host.to_string(),
+		};
+		// The crypto provider is pinned explicitly: with several rustls provider features
+		// enabled somewhere in the dependency graph, relying on the default panics.
+		let connector = HttpsConnectorBuilder::new()
+			.with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+			.map_err(|e| {
+				VssError::InternalServerError(format!("Failed to initialize TLS: {}", e))
+			})?
+			.https_or_http()
+			.enable_http1()
+			.build();
+		let client = Client::builder(TokioExecutor::new()).build::<_, Full<Bytes>>(connector);
+		Ok(DynamoDbBackendImpl { client, credentials, region, endpoint, host, table_name })
+	}
+
+	/// Issues a signed request against the given `DynamoDB_20120810` operation.
+	async fn call(&self, operation: &str, body: Value) -> Result<Value, CallError> {
+		let body = body.to_string();
+		let target = format!("DynamoDB_20120810.{}", operation);
+		let (amz_date, _) = format_amz_date(SystemTime::now());
+		let mut builder = Request::builder()
+			.method(Method::POST)
+			.uri(&self.endpoint)
+			.header("content-type", "application/x-amz-json-1.0")
+			.header("host", &self.host)
+			.header("x-amz-date", &amz_date)
+			.header("x-amz-target", &target);
+		let mut signed_headers = vec![
+			("content-type", "application/x-amz-json-1.0".to_string()),
+			("host", self.host.clone()),
+			("x-amz-date", amz_date.clone()),
+		];
+		if let Some(session_token) = &self.credentials.session_token {
+			builder = builder.header("x-amz-security-token", session_token);
+			signed_headers.push(("x-amz-security-token", session_token.clone()));
+		}
+		signed_headers.push(("x-amz-target", target.clone()));
+
+		let authorization = sigv4_authorization(
+			&self.credentials.access_key_id,
+			&self.credentials.secret_access_key,
+			&SigV4Params {
+				method: &Method::POST,
+				canonical_uri: "/",
+				canonical_query: "",
+				region: &self.region,
+				service: "dynamodb",
+				amz_date: &amz_date,
+				signed_headers: &signed_headers,
+				payload: body.as_bytes(),
+			},
+		);
+		let request = builder
+			.header("authorization", authorization)
+			.body(Full::new(Bytes::from(body)))
+			.map_err(|e| CallError::Transport(format!("Failed to build request: {}", e)))?;
+
+		let response = self
+			.client
+			.request(request)
+			.instrument(debug_span!("dynamodb_store", operation))
+			.await
+			.map_err(|e| {
+				CallError::Transport(format!("Request to {} failed: {}", self.endpoint, e))
+			})?;
+		let status = response.status();
+		let response_body = response
+			.into_body()
+			.collect()
+			.await
+			.map_err(|e| CallError::Transport(format!("Failed to read response: {}", e)))?
+			.to_bytes();
+		let response_json: Value = serde_json::from_slice(&response_body).unwrap_or(Value::Null);
+		if !status.is_success() {
+			// Error types come as `com.amazonaws.dynamodb.v20120810#<Code>`.
+			let code = response_json["__type"]
+				.as_str()
+				.and_then(|error_type| error_type.rsplit('#').next())
+				.unwrap_or("UnknownError")
+				.to_string();
+			return Err(CallError::Api { code, body: response_json });
+		}
+		Ok(response_json)
+	}
+
+	/// Creates the backing table (on-demand billing, `pk`/`sk` string keys) if it does not exist
+	/// yet and waits for it to become active.
+	pub async fn create_table_if_missing(&self) -> Result<(), VssError> {
+		let describe = json!({ "TableName": self.table_name });
+		match self.call("DescribeTable", describe.clone()).await {
+			Ok(_) => return Ok(()),
+			Err(CallError::Api { ref code, .. }) if code == "ResourceNotFoundException" => {},
+			Err(e) => return Err(e.into_internal_error()),
+		}
+		let create = json!({
+			"TableName": self.table_name,
+			"AttributeDefinitions": [
+				{ "AttributeName": "pk", "AttributeType": "S" },
+				{ "AttributeName": "sk", "AttributeType": "S" },
+			],
+			"KeySchema": [
+				{ "AttributeName": "pk", "KeyType": "HASH" },
+				{ "AttributeName": "sk", "KeyType": "RANGE" },
+			],
+			"BillingMode": "PAY_PER_REQUEST",
+		});
+		match self.call("CreateTable", create).await {
+			Ok(_) => {},
+			Err(CallError::Api { ref code, .. }) if code == "ResourceInUseException" => {},
+			Err(e) => return Err(e.into_internal_error()),
+		}
+		loop {
+			let response = self
+				.call("DescribeTable", describe.clone())
+				.await
+				.map_err(CallError::into_internal_error)?;
+			if response["Table"]["TableStatus"].as_str() == Some("ACTIVE") {
+				return Ok(());
+			}
+			tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+		}
+	}
+
+	fn partition_key(user_token: &str, store_id: &str) -> String {
+		format!("{}{}{}", user_token, PARTITION_KEY_SEPARATOR, store_id)
+	}
+
+	fn item_key(partition_key: &str, key: &str) -> Value {
+		json!({ "pk": { "S": partition_key }, "sk": { "S": key } })
+	}
+
+	/// Reads the current version of a single record, with absent records reported as version 0
+	/// (the version a fresh key is expected at).
+	async fn current_version(&self, partition_key: &str, key: &str) -> Result<i64, VssError> {
+		let request = json!({
+			"TableName": self.table_name,
+			"Key": Self::item_key(partition_key, key),
+			"ProjectionExpression": "#v",
+			"ExpressionAttributeNames": { "#v": "v" },
+			"ConsistentRead": true,
+		});
+		let response =
+			self.call("GetItem", request).await.map_err(CallError::into_internal_error)?;
+		if response["Item"].is_object() {
+			attribute_number(&response["Item"]["v"])
+		} else {
+			Ok(0)
+		}
+	}
+
+	/// Queries a full partition page by page, applying `for_item` to every non-global record.
+	async fn for_each_record<F: FnMut(&str, &Value)>(
+		&self, partition_key: &str, projection: &str, mut for_item: F,
+	) -> Result<(), VssError> {
+		let mut exclusive_start_key: Option<Value> = None;
+		loop {
+			let mut request = json!({
+				"TableName": self.table_name,
+				"KeyConditionExpression": "#pk = :pk",
+				"ExpressionAttributeNames": names_for(projection),
+				"ExpressionAttributeValues": { ":pk": { "S": partition_key } },
+				"ProjectionExpression": projection,
+				"ConsistentRead": true,
+			});
+			if let Some(start_key) = &exclusive_start_key {
+				request["ExclusiveStartKey"] = start_key.clone();
+			}
+			let response =
+				self.call("Query", request).await.map_err(CallError::into_internal_error)?;
+			for item in response["Items"].as_array().into_iter().flatten() {
+				let key = item["sk"]["S"].as_str().unwrap_or_default();
+				if key != GLOBAL_VERSION_KEY {
+					for_item(key, item);
+				}
+			}
+			match &response["LastEvaluatedKey"] {
+				last_key if last_key.is_object() => {
+					exclusive_start_key = Some(last_key.clone())
+				},
+				_ => return Ok(()),
+			}
+		}
+	}
+}
+
+/// The target a transaction operation applies to, used to map a cancelled transaction's failed
+/// condition back onto the conflicting key.
+enum TransactTarget {
+	GlobalVersion,
+	Key(String),
+}
+
+/// Builds the condition expression pinning a record at `expected`: fresh keys are expected at
+/// version 0, where the record may simply not exist yet.
+fn version_condition(expected: i64) -> (String, Value) {
+	if expected == 0 {
+		(
+			"attribute_not_exists(sk) OR #v = :expected".to_string(),
+			json!({ ":expected": { "N": expected.to_string() } }),
+		)
+	} else {
+		("#v = :expected".to_string(), json!({ ":expected": { "N": expected.to_string() } }))
+	}
+}
+
+/// Maps a projection expression of `#`-prefixed placeholders to their attribute names.
+fn names_for(projection: &str) -> Value {
+	let mut names = serde_json::Map::new();
+	names.insert("#pk".to_string(), Value::String("pk".to_string()));
+	for placeholder in projection.split(", ") {
+		names.insert(
+			placeholder.to_string(),
+			Value::String(placeholder.trim_start_matches('#').to_string()),
+		);
+	}
+	Value::Object(names)
+}
+
+fn attribute_number(attribute: &Value) -> Result<i64, VssError> {
+	attribute["N"]
+		.as_str()
+		.and_then(|number| number.parse::<i64>().ok())
+		.ok_or_else(|| {
+			VssError::InternalServerError(format!("Malformed number attribute: {}", attribute))
+		})
+}
+
+fn attribute_binary(attribute: &Value) -> Result<Bytes, VssError> {
+	let encoded = attribute["B"].as_str().unwrap_or_default();
+	BASE64_STANDARD.decode(encoded).map(Bytes::from).map_err(|e| {
+		VssError::InternalServerError(format!("Malformed binary attribute: {}", e))
+	})
+}
+
+fn now_millis() -> i64 {
+	SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+#[async_trait]
+impl KvStore for DynamoDbBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let partition_key = Self::partition_key(&context.user_token, &request.store_id);
+		let get_item = json!({
+			"TableName": self.table_name,
+			"Key": Self::item_key(&partition_key, &request.key),
+			"ConsistentRead": true,
+		});
+		let response =
+			self.call("GetItem", get_item).await.map_err(CallError::into_internal_error)?;
+		if !response["Item"].is_object() {
+			return Err(VssError::NoSuchKeyError(request.key));
+		}
+		Ok(GetObjectResponse {
+			value: Some(KeyValue {
+				key: request.key,
+				version: attribute_number(&response["Item"]["v"])?,
+				value: attribute_binary(&response["Item"]["val"])?,
+			}),
+		})
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = std::collections::HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+		let operation_count = request.transaction_items.len()
+			+ request.delete_items.len()
+			+ usize::from(request.global_version.is_some());
+		if operation_count > MAX_TRANSACT_ITEMS {
+			return Err(VssError::InvalidRequestError(format!(
+				"Request exceeds the DynamoDB transaction limit of {} operations.",
+				MAX_TRANSACT_ITEMS
+			)));
+		}
+
+		let partition_key = Self::partition_key(&context.user_token, &request.store_id);
+
+		// A dry run evaluates the version preconditions via reads instead of committing. The
+		// checks are not transactional with later writes, which matches its advisory purpose.
+		if request.dry_run {
+			if let Some(global_version) = request.global_version {
+				if self.current_version(&partition_key, GLOBAL_VERSION_KEY).await?
+					!= global_version
+				{
+					return Err(VssError::ConflictError(format!(
+						"Global version mismatch for store_id: {}",
+						request.store_id
+					)));
+				}
+			}
+			for kv in &request.transaction_items {
+				if kv.version >= 0
+					&& self.current_version(&partition_key, &kv.key).await? != kv.version
+				{
+					return Err(VssError::ConflictError(format!(
+						"Version mismatch for key: {}",
+						kv.key
+					)));
+				}
+			}
+			for kv in &request.delete_items {
+				if kv.version < 0 {
+					continue;
+				}
+				// Deleting an absent record is a conflict even at expected version 0, matching
+				// the other backends.
+				let version = self.current_version(&partition_key, &kv.key).await?;
+				if version != kv.version || version == 0 {
+					return Err(VssError::ConflictError(format!(
+						"Version mismatch for key: {}",
+						kv.key
+					)));
+				}
+			}
+			return Ok(PutObjectResponse {});
+		}
+
+		let ts = now_millis().to_string();
+		let mut transact_items = Vec::with_capacity(operation_count);
+		let mut targets = Vec::with_capacity(operation_count);
+		if let Some(global_version) = request.global_version {
+			let (condition, mut values) = version_condition(global_version);
+			values[":new"] = json!({ "N": (global_version + 1).to_string() });
+			values[":ts"] = json!({ "N": ts });
+			transact_items.push(json!({
+				"Update": {
+					"TableName": self.table_name,
+					"Key": Self::item_key(&partition_key, GLOBAL_VERSION_KEY),
+					"UpdateExpression": "SET #v = :new, #ts = :ts",
+					"ConditionExpression": condition,
+					"ExpressionAttributeNames": { "#v": "v", "#ts": "ts" },
+					"ExpressionAttributeValues": values,
+				}
+			}));
+			targets.push(TransactTarget::GlobalVersion);
+		}
+		for kv in &request.transaction_items {
+			let value = json!({ "B": BASE64_STANDARD.encode(&kv.value) });
+			if kv.version >= 0 {
+				let (condition, values) = version_condition(kv.version);
+				transact_items.push(json!({
+					"Put": {
+						"TableName": self.table_name,
+						"Item": {
+							"pk": { "S": partition_key },
+							"sk": { "S": kv.key },
+							"v": { "N": (kv.version + 1).to_string() },
+							"val": value,
+							"ts": { "N": ts },
+						},
+						"ConditionExpression": condition,
+						"ExpressionAttributeNames": { "#v": "v" },
+						"ExpressionAttributeValues": values,
+					}
+				}));
+			} else {
+				// An unconditional write still advances the stored version.
+				transact_items.push(json!({
+					"Update": {
+						"TableName": self.table_name,
+						"Key": Self::item_key(&partition_key, &kv.key),
+						"UpdateExpression":
+							"SET #v = if_not_exists(#v, :zero) + :one, #val = :val, #ts = :ts",
+						"ExpressionAttributeNames": { "#v": "v", "#val": "val", "#ts": "ts" },
+						"ExpressionAttributeValues": {
+							":zero": { "N": "0" },
+							":one": { "N": "1" },
+							":val": value,
+							":ts": { "N": ts },
+						},
+					}
+				}));
+			}
+			targets.push(TransactTarget::Key(kv.key.clone()));
+		}
+		for kv in &request.delete_items {
+			let mut delete = json!({
+				"TableName": self.table_name,
+				"Key": Self::item_key(&partition_key, &kv.key),
+			});
+			if kv.version >= 0 {
+				let (_, values) = version_condition(kv.version);
+				// Deleting an absent record is a conflict even at expected version 0, so the
+				// fresh-key leniency of `version_condition` does not apply here.
+				delete["ConditionExpression"] = Value::String("#v = :expected".to_string());
+				delete["ExpressionAttributeNames"] = json!({ "#v": "v" });
+				delete["ExpressionAttributeValues"] = values;
+			}
+			transact_items.push(json!({ "Delete": delete }));
+			targets.push(TransactTarget::Key(kv.key.clone()));
+		}
+
+		let transact = json!({ "TransactItems": transact_items });
+		match self.call("TransactWriteItems", transact).await {
+			Ok(_) => Ok(PutObjectResponse {}),
+			Err(CallError::Api { code, body }) if code == "TransactionCanceledException" => {
+				// The cancellation reasons align with the submitted operations; the first failed
+				// condition names the conflicting key.
+				let failed_index = body["CancellationReasons"]
+					.as_array()
+					.into_iter()
+					.flatten()
+					.position(|reason| {
+						reason["Code"].as_str() == Some("ConditionalCheckFailed")
+					});
+				match failed_index.map(|index| &targets[index]) {
+					Some(TransactTarget::GlobalVersion) => Err(VssError::ConflictError(format!(
+						"Global version mismatch for store_id: {}",
+						request.store_id
+					))),
+					Some(TransactTarget::Key(key)) => Err(VssError::ConflictError(format!(
+						"Version mismatch for key: {}",
+						key
+					))),
+					None => {
+						Err(CallError::Api { code, body }.into_internal_error())
+					},
+				}
+			},
+			Err(CallError::Api { code, .. }) if code == "TransactionConflictException" => {
+				Err(VssError::ConflictError(
+					"Write lost a race with a concurrent transaction, please retry.".to_string(),
+				))
+			},
+			Err(e) => Err(e.into_internal_error()),
+		}
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+		let partition_key = Self::partition_key(&context.user_token, &request.store_id);
+		let mut delete_item = json!({
+			"TableName": self.table_name,
+			"Key": Self::item_key(&partition_key, &key_value.key),
+		});
+		if key_value.version >= 0 {
+			delete_item["ConditionExpression"] = Value::String("#v = :expected".to_string());
+			delete_item["ExpressionAttributeNames"] = json!({ "#v": "v" });
+			delete_item["ExpressionAttributeValues"] =
+				json!({ ":expected": { "N": key_value.version.to_string() } });
+		}
+		match self.call("DeleteItem", delete_item).await {
+			Ok(_) => Ok(DeleteObjectResponse {}),
+			// Delete is idempotent, a non-existent key or a mismatched version is not an error.
+			Err(CallError::Api { ref code, .. })
+				if code == "ConditionalCheckFailedException" =>
+			{
+				Ok(DeleteObjectResponse {})
+			},
+			Err(e) => Err(e.into_internal_error()),
+		}
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => page_size.min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE),
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let partition_key = Self::partition_key(&context.user_token, &request.store_id);
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+
+		let mut query = json!({
+			"TableName": self.table_name,
+			"ExpressionAttributeNames": { "#pk": "pk", "#sk": "sk", "#v": "v" },
+			"ExpressionAttributeValues": { ":pk": { "S": partition_key } },
+			"ProjectionExpression": "#sk, #v",
+			"Limit": page_size,
+			"ConsistentRead": true,
+		});
+		if key_prefix.is_empty() {
+			query["KeyConditionExpression"] = Value::String("#pk = :pk".to_string());
+		} else {
+			query["KeyConditionExpression"] =
+				Value::String("#pk = :pk AND begins_with(#sk, :prefix)".to_string());
+			query["ExpressionAttributeValues"][":prefix"] = json!({ "S": key_prefix });
+		}
+		if !page_token.is_empty() {
+			query["ExclusiveStartKey"] = Self::item_key(&partition_key, &page_token);
+		}
+
+		let response =
+			self.call("Query", query).await.map_err(CallError::into_internal_error)?;
+		// Key attributes may not appear in a Query filter expression, so the global-version
+		// record is skipped here; a page may therefore come back one record short of `page_size`
+		// with more records remaining. The page token alone decides whether pagination continues.
+		let mut key_versions = Vec::new();
+		for item in response["Items"].as_array().into_iter().flatten() {
+			let key = item["sk"]["S"].as_str().unwrap_or_default();
+			if key == GLOBAL_VERSION_KEY {
+				continue;
+			}
+			key_versions.push(KeyValue {
+				key: key.to_string(),
+				version: attribute_number(&item["v"])?,
+				value: Bytes::new(),
+			});
+		}
+		let next_page_token = response["LastEvaluatedKey"]["sk"]["S"]
+			.as_str()
+			.map(|last_key| last_key.to_string());
+
+		// The global version is only returned on the first page.
+		let global_version = if page_token.is_empty() {
+			Some(self.current_version(&partition_key, GLOBAL_VERSION_KEY).await?)
+		} else {
+			None
+		};
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let partition_key = Self::partition_key(&context.user_token, &request.store_id);
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats: Vec<KeyStat> = Vec::new();
+		let mut malformed = false;
+		self.for_each_record(&partition_key, "#sk, #val, #ts", |key, item| {
+			let value_bytes = item["val"]["B"]
+				.as_str()
+				.and_then(|encoded| BASE64_STANDARD.decode(encoded).ok())
+				.map(|value| value.len() as i64)
+				.unwrap_or_else(|| {
+					malformed = true;
+					0
+				});
+			let updated_at_millis = attribute_number(&item["ts"]).unwrap_or_default();
+			stats.key_count += 1;
+			stats.total_value_bytes += value_bytes;
+			if stats.oldest_updated_at_millis == 0
+				|| updated_at_millis < stats.oldest_updated_at_millis
+			{
+				stats.oldest_updated_at_millis = updated_at_millis;
+			}
+			stats.newest_updated_at_millis = stats.newest_updated_at_millis.max(updated_at_millis);
+			key_stats.push(KeyStat { key: key.to_string(), value_bytes });
+		})
+		.await?;
+		if malformed {
+			return Err(VssError::InternalServerError(
+				"Malformed binary attribute in store records.".to_string(),
+			));
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for DynamoDbBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		// Partition keys are only enumerable with a table scan; acceptable for the occasional
+		// admin lookup, but nothing request-path code should rely on.
+		let prefix = format!("{}{}", user_token, PARTITION_KEY_SEPARATOR);
+		let mut store_ids = Vec::new();
+		let mut exclusive_start_key: Option<Value> = None;
+		loop {
+			let mut scan = json!({
+				"TableName": self.table_name,
+				"FilterExpression": "begins_with(#pk, :prefix)",
+				"ExpressionAttributeNames": { "#pk": "pk" },
+				"ExpressionAttributeValues": { ":prefix": { "S": prefix } },
+				"ProjectionExpression": "#pk",
+			});
+			if let Some(start_key) = &exclusive_start_key {
+				scan["ExclusiveStartKey"] = start_key.clone();
+			}
+			let response =
+				self.call("Scan", scan).await.map_err(CallError::into_internal_error)?;
+			for item in response["Items"].as_array().into_iter().flatten() {
+				if let Some(partition_key) = item["pk"]["S"].as_str() {
+					let store_id = partition_key[prefix.len()..].to_string();
+					if !store_ids.contains(&store_id) {
+						store_ids.push(store_id);
+					}
+				}
+			}
+			match &response["LastEvaluatedKey"] {
+				last_key if last_key.is_object() => {
+					exclusive_start_key = Some(last_key.clone())
+				},
+				_ => break,
+			}
+		}
+		store_ids.sort();
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let partition_key = Self::partition_key(&user_token, &store_id);
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		self.for_each_record(&partition_key, "#sk, #val", |_, item| {
+			usage.key_count += 1;
+			usage.total_value_bytes += item["val"]["B"]
+				.as_str()
+				.and_then(|encoded| BASE64_STANDARD.decode(encoded).ok())
+				.map(|value| value.len() as i64)
+				.unwrap_or_default();
+		})
+		.await?;
+		Ok(usage)
+	}
+}
+
+#[cfg(all(test, feature = "integration-tests-dynamodb"))]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	async fn test_store() -> DynamoDbBackendImpl {
+		let credentials = AwsCredentials {
+			access_key_id: "local".to_string(),
+			secret_access_key: "local".to_string(),
+			session_token: None,
+		};
+		let store = DynamoDbBackendImpl::new(
+			"us-east-1".to_string(),
+			Some("http://localhost:8000".to_string()),
+			"vss_test".to_string(),
+			credentials,
+		)
+		.unwrap();
+		store.create_table_if_missing().await.unwrap();
+		store
+	}
+
+	define_kv_store_tests!(dynamodb_store_tests, DynamoDbBackendImpl, test_store().await);
+
+	define_kv_store_model_tests!(
+		dynamodb_store_model_tests,
+		DynamoDbBackendImpl,
+		test_store().await,
+		crate::memory_store::MemoryBackendImpl,
+		crate::memory_store::MemoryBackendImpl::new()
+	);
+}
@@ -0,0 +1,781 @@
+//! An etcd-backed [`KvStore`] implementation.
+//!
+//! Records map `(user_token, store_id, key)` onto a flat etcd keyspace under a `vss/` prefix, so
+//! a store is a contiguous, range-readable slice of keys. etcd's native per-key version counter
+//! has exactly the semantics of a VSS record version — 0 while absent, incremented on every put,
+//! reset by a delete — so key-level versioning compares against it directly and multi-item
+//! [`PutObjectRequest`]s commit as a single etcd transaction whose compares pin every touched
+//! key, keeping the all-or-nothing semantics of the other backends.
+//!
+//! The backend speaks the etcd v3 JSON gateway (`/v3/kv/*`) directly rather than pulling in a
+//! gRPC stack, mirroring how the DynamoDB backend talks to its service. Multiple endpoints may
+//! be configured for a highly available cluster; requests fail over to the next endpoint when a
+//! member is unreachable.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use serde_json::{json, Value};
+use tracing::{debug_span, Instrument};
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i64 = 1000;
+
+/// The default `--max-txn-ops` of an etcd server, bounding the size of an atomic
+/// [`PutObjectRequest`] on this backend.
+const MAX_TXN_OPS: usize = 128;
+
+/// The keyspace prefix all records live under, so the cluster may be shared with other users.
+const KEY_PREFIX: &str = "vss/";
+
+/// Separates the user token, store id and record key within an etcd key. Store ids are validated
+/// by the service layer to contain no control characters, so the separators are unambiguous and
+/// no two `(user_token, store_id)` pairs collide.
+const KEY_SEPARATOR: char = '\u{1f}';
+
+/// A [`KvStore`] implementation backed by an etcd cluster.
+///
+/// Record values are stored as the last-update timestamp (8 bytes, big-endian milliseconds)
+/// followed by the payload; versions are etcd's own per-key version counters and are never
+/// stored explicitly.
+pub struct EtcdBackendImpl {
+	client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+	endpoints: Vec<EtcdEndpoint>,
+	/// The endpoint index requests are tried at first, advanced past unreachable members.
+	preferred_endpoint: AtomicUsize,
+	credentials: Option<(String, String)>,
+	/// The cached authentication token, fetched lazily and refreshed when the server reports it
+	/// invalid (etcd tokens expire server-side).
+	auth_token: Mutex<Option<String>>,
+}
+
+struct EtcdEndpoint {
+	url: String,
+	host: String,
+}
+
+/// A failure reported while calling the etcd gateway, keeping transport errors distinguishable
+/// so calls can fail over to another endpoint.
+enum CallError {
+	/// The server rejected the request with the given gRPC code and message.
+	Api { code: i64, message: String },
+	Transport(String),
+}
+
+impl CallError {
+	fn into_internal_error(self) -> VssError {
+		match self {
+			CallError::Api { code, message } => {
+				VssError::InternalServerError(format!("etcd error {}: {}", code, message))
+			},
+			CallError::Transport(message) => VssError::InternalServerError(message),
+		}
+	}
+}
+
+impl EtcdBackendImpl {
+	/// Constructs an [`EtcdBackendImpl`] against the given endpoints (e.g.
+	/// `http://etcd-a.internal:2379`), authenticating with the given user if set.
+	pub fn new(
+		endpoints: Vec<String>, username: Option<String>, password: Option<String>,
+	) -> Result<Self, VssError> {
+		if endpoints.is_empty() {
+			return Err(VssError::InternalServerError(
+				"At least one etcd endpoint must be configured.".to_string(),
+			));
+		}
+		let credentials = match (username, password) {
+			(Some(username), Some(password)) => Some((username, password)),
+			(None, None) => None,
+			_ => {
+				return Err(VssError::InternalServerError(
+					"etcd username and password must be set together.".to_string(),
+				))
+			},
+		};
+		let mut parsed_endpoints = Vec::with_capacity(endpoints.len());
+		for endpoint in endpoints {
+			let url = endpoint.trim_end_matches('/').to_string();
+			let uri: Uri = url.parse().map_err(|e| {
+				VssError::InternalServerError(format!("Invalid endpoint {}: {}", url, e))
+			})?;
+			let host = uri.host().ok_or_else(|| {
+				VssError::InternalServerError(format!("Endpoint {} has no host.", url))
+			})?;
+			let host = match uri.port_u16() {
+				Some(port) => format!("{}:{}", host, port),
+				None => host.to_string(),
+			};
+			parsed_endpoints.push(EtcdEndpoint { url, host });
+		}
+		// The crypto provider is pinned explicitly: with several rustls provider features
+		// enabled somewhere in the dependency graph, relying on the default panics.
+		let connector = HttpsConnectorBuilder::new()
+			.with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+			.map_err(|e| {
+				VssError::InternalServerError(format!("Failed to initialize TLS: {}", e))
+			})?
+			.https_or_http()
+			.enable_http1()
+			.build();
+		let client = Client::builder(TokioExecutor::new()).build::<_, Full<Bytes>>(connector);
+		Ok(EtcdBackendImpl {
+			client,
+			endpoints: parsed_endpoints,
+			preferred_endpoint: AtomicUsize::new(0),
+			credentials,
+			auth_token: Mutex::new(None),
+		})
+	}
+
+	/// Issues a request against the given gateway path, trying each endpoint in turn on
+	/// transport failures and returning the first reachable member's answer.
+	async fn call_any_endpoint(
+		&self, path: &str, body: &Value, auth_token: Option<&str>,
+	) -> Result<Value, CallError> {
+		let preferred = self.preferred_endpoint.load(Ordering::Relaxed);
+		let mut last_error = None;
+		for offset in 0..self.endpoints.len() {
+			let index = (preferred + offset) % self.endpoints.len();
+			let endpoint = &self.endpoints[index];
+			match self.call_endpoint(endpoint, path, body, auth_token).await {
+				Err(CallError::Transport(message)) => {
+					last_error = Some(CallError::Transport(message))
+				},
+				result => {
+					self.preferred_endpoint.store(index, Ordering::Relaxed);
+					return result;
+				},
+			}
+		}
+		Err(last_error.unwrap_or_else(|| {
+			CallError::Transport("No etcd endpoint configured.".to_string())
+		}))
+	}
+
+	async fn call_endpoint(
+		&self, endpoint: &EtcdEndpoint, path: &str, body: &Value, auth_token: Option<&str>,
+	) -> Result<Value, CallError> {
+		let mut builder = Request::builder()
+			.method(Method::POST)
+			.uri(format!("{}{}", endpoint.url, path))
+			.header("content-type", "application/json")
+			.header("host", &endpoint.host);
+		if let Some(auth_token) = auth_token {
+			builder = builder.header("authorization", auth_token);
+		}
+		let request = builder
+			.body(Full::new(Bytes::from(body.to_string())))
+			.map_err(|e| CallError::Transport(format!("Failed to build request: {}", e)))?;
+
+		let response = self
+			.client
+			.request(request)
+			.instrument(debug_span!("etcd_store", path))
+			.await
+			.map_err(|e| {
+				CallError::Transport(format!("Request to {} failed: {}", endpoint.url, e))
+			})?;
+		let status = response.status();
+		let response_body = response
+			.into_body()
+			.collect()
+			.await
+			.map_err(|e| CallError::Transport(format!("Failed to read response: {}", e)))?
+			.to_bytes();
+		let response_json: Value = serde_json::from_slice(&response_body).unwrap_or(Value::Null);
+		if !status.is_success() {
+			return Err(CallError::Api {
+				code: response_json["code"].as_i64().unwrap_or_default(),
+				message: response_json["message"]
+					.as_str()
+					.or_else(|| response_json["error"].as_str())
+					.unwrap_or_default()
+					.to_string(),
+			});
+		}
+		Ok(response_json)
+	}
+
+	/// Returns the authentication token to present, fetching one on first use. With `refresh`
+	/// set, the cached token is discarded first (etcd invalidates tokens server-side).
+	async fn authenticate(&self, refresh: bool) -> Result<Option<String>, VssError> {
+		let (username, password) = match &self.credentials {
+			Some(credentials) => credentials,
+			None => return Ok(None),
+		};
+		if !refresh {
+			if let Some(token) = self.auth_token.lock().unwrap().clone() {
+				return Ok(Some(token));
+			}
+		}
+		let body = json!({ "name": username, "password": password });
+		let response = self
+			.call_any_endpoint("/v3/auth/authenticate", &body, None)
+			.await
+			.map_err(CallError::into_internal_error)?;
+		let token = response["token"].as_str().unwrap_or_default().to_string();
+		*self.auth_token.lock().unwrap() = Some(token.clone());
+		Ok(Some(token))
+	}
+
+	/// Issues an authenticated request against the given gateway path, refreshing an expired
+	/// token once.
+	async fn call(&self, path: &str, body: Value) -> Result<Value, VssError> {
+		let mut refreshed = false;
+		loop {
+			let auth_token = self.authenticate(refreshed).await?;
+			match self.call_any_endpoint(path, &body, auth_token.as_deref()).await {
+				Ok(response) => return Ok(response),
+				Err(CallError::Api { message, .. })
+					if !refreshed && message.contains("invalid auth token") =>
+				{
+					refreshed = true;
+				},
+				Err(e) => return Err(e.into_internal_error()),
+			}
+		}
+	}
+
+	fn store_prefix(user_token: &str, store_id: &str) -> String {
+		format!("{}{}{}{}{}", KEY_PREFIX, user_token, KEY_SEPARATOR, store_id, KEY_SEPARATOR)
+	}
+
+	/// Reads the current version of a single record, with absent records reported as version 0
+	/// (the version a fresh key is expected at).
+	async fn current_version(&self, full_key: &str) -> Result<i64, VssError> {
+		let request = json!({ "key": BASE64_STANDARD.encode(full_key), "keys_only": true });
+		let response = self.call("/v3/kv/range", request).await?;
+		Ok(json_i64(&response["kvs"][0]["version"]))
+	}
+
+	/// Range-reads a full store page by page, applying `for_kv` to every non-global record.
+	async fn for_each_record<F: FnMut(&str, &Value)>(
+		&self, store_prefix: &str, keys_only: bool, mut for_kv: F,
+	) -> Result<(), VssError> {
+		let range_end = BASE64_STANDARD.encode(prefix_end(store_prefix.as_bytes()));
+		let mut start_key = store_prefix.to_string();
+		loop {
+			let request = json!({
+				"key": BASE64_STANDARD.encode(&start_key),
+				"range_end": range_end,
+				"limit": MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+				"keys_only": keys_only,
+			});
+			let response = self.call("/v3/kv/range", request).await?;
+			for kv in response["kvs"].as_array().into_iter().flatten() {
+				let full_key = decode_key(kv)?;
+				let key = full_key.strip_prefix(store_prefix).unwrap_or_default();
+				if key != GLOBAL_VERSION_KEY {
+					for_kv(key, kv);
+				}
+				start_key = format!("{}\u{0}", full_key);
+			}
+			if !response["more"].as_bool().unwrap_or(false) {
+				return Ok(());
+			}
+		}
+	}
+}
+
+/// The record a transaction compare pins, used to map a failed transaction back onto the
+/// conflicting key.
+struct TxnCompare {
+	target: TxnTarget,
+	full_key: String,
+	expected: i64,
+	is_delete: bool,
+}
+
+enum TxnTarget {
+	GlobalVersion,
+	Key(String),
+}
+
+/// Builds the compare pinning a record's etcd version counter at `expected`. Absent records
+/// carry version 0, so the fresh-key case needs no special handling.
+fn version_compare(full_key: &str, expected: i64) -> Value {
+	json!({
+		"key": BASE64_STANDARD.encode(full_key),
+		"target": "VERSION",
+		"result": "EQUAL",
+		"version": expected.to_string(),
+	})
+}
+
+/// Reads an int64 the gateway may emit as a JSON string or omit entirely (proto3 drops zero
+/// values), defaulting to 0.
+fn json_i64(value: &Value) -> i64 {
+	match value {
+		Value::String(number) => number.parse().unwrap_or_default(),
+		Value::Number(number) => number.as_i64().unwrap_or_default(),
+		_ => 0,
+	}
+}
+
+fn decode_key(kv: &Value) -> Result<String, VssError> {
+	let decoded = BASE64_STANDARD
+		.decode(kv["key"].as_str().unwrap_or_default())
+		.map_err(|e| VssError::InternalServerError(format!("Malformed record key: {}", e)))?;
+	String::from_utf8(decoded)
+		.map_err(|e| VssError::InternalServerError(format!("Malformed record key: {}", e)))
+}
+
+/// Returns the first key past all keys starting with `prefix`, for use as a range end.
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+	let mut end = prefix.to_vec();
+	while let Some(last) = end.pop() {
+		if last < 0xff {
+			end.push(last + 1);
+			return end;
+		}
+	}
+	// Unreachable with the `vss/` prefix; `\0` means "no upper bound" to etcd.
+	vec![0]
+}
+
+fn encode_value(updated_at_millis: i64, value: &[u8]) -> Vec<u8> {
+	let mut encoded = Vec::with_capacity(8 + value.len());
+	encoded.extend_from_slice(&updated_at_millis.to_be_bytes());
+	encoded.extend_from_slice(value);
+	encoded
+}
+
+fn decode_value(kv: &Value) -> Result<(i64, Bytes), VssError> {
+	let raw = BASE64_STANDARD
+		.decode(kv["value"].as_str().unwrap_or_default())
+		.map_err(|e| VssError::InternalServerError(format!("Malformed record value: {}", e)))?;
+	if raw.len() < 8 {
+		return Err(VssError::InternalServerError("Malformed record value.".to_string()));
+	}
+	let updated_at_millis = i64::from_be_bytes(raw[..8].try_into().unwrap());
+	Ok((updated_at_millis, Bytes::from(raw[8..].to_vec())))
+}
+
+fn now_millis() -> i64 {
+	SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+#[async_trait]
+impl KvStore for EtcdBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let store_prefix = Self::store_prefix(&context.user_token, &request.store_id);
+		let full_key = format!("{}{}", store_prefix, request.key);
+		let range = json!({ "key": BASE64_STANDARD.encode(&full_key) });
+		let response = self.call("/v3/kv/range", range).await?;
+		let kv = &response["kvs"][0];
+		if !kv.is_object() {
+			return Err(VssError::NoSuchKeyError(request.key));
+		}
+		let (_, value) = decode_value(kv)?;
+		Ok(GetObjectResponse {
+			value: Some(KeyValue { key: request.key, version: json_i64(&kv["version"]), value }),
+		})
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = std::collections::HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+		let operation_count = request.transaction_items.len()
+			+ request.delete_items.len()
+			+ usize::from(request.global_version.is_some());
+		if operation_count > MAX_TXN_OPS {
+			return Err(VssError::InvalidRequestError(format!(
+				"Request exceeds the etcd transaction limit of {} operations.",
+				MAX_TXN_OPS
+			)));
+		}
+
+		let store_prefix = Self::store_prefix(&context.user_token, &request.store_id);
+		let global_key = format!("{}{}", store_prefix, GLOBAL_VERSION_KEY);
+
+		// A dry run evaluates the version preconditions via reads instead of committing. The
+		// checks are not transactional with later writes, which matches its advisory purpose.
+		if request.dry_run {
+			if let Some(global_version) = request.global_version {
+				if self.current_version(&global_key).await? != global_version {
+					return Err(VssError::ConflictError(format!(
+						"Global version mismatch for store_id: {}",
+						request.store_id
+					)));
+				}
+			}
+			for kv in &request.transaction_items {
+				if kv.version >= 0 {
+					let full_key = format!("{}{}", store_prefix, kv.key);
+					if self.current_version(&full_key).await? != kv.version {
+						return Err(VssError::ConflictError(format!(
+							"Version mismatch for key: {}",
+							kv.key
+						)));
+					}
+				}
+			}
+			for kv in &request.delete_items {
+				if kv.version < 0 {
+					continue;
+				}
+				// Deleting an absent record is a conflict even at expected version 0, matching
+				// the other backends.
+				let full_key = format!("{}{}", store_prefix, kv.key);
+				let version = self.current_version(&full_key).await?;
+				if version != kv.version || version == 0 {
+					return Err(VssError::ConflictError(format!(
+						"Version mismatch for key: {}",
+						kv.key
+					)));
+				}
+			}
+			return Ok(PutObjectResponse {});
+		}
+
+		let ts = now_millis();
+		let mut compares = Vec::new();
+		let mut compare_targets: Vec<TxnCompare> = Vec::new();
+		let mut success_ops = Vec::with_capacity(operation_count);
+		if let Some(global_version) = request.global_version {
+			compares.push(version_compare(&global_key, global_version));
+			compare_targets.push(TxnCompare {
+				target: TxnTarget::GlobalVersion,
+				full_key: global_key.clone(),
+				expected: global_version,
+				is_delete: false,
+			});
+			success_ops.push(json!({ "request_put": {
+				"key": BASE64_STANDARD.encode(&global_key),
+				"value": BASE64_STANDARD.encode(encode_value(ts, b"")),
+			} }));
+		}
+		for kv in &request.transaction_items {
+			let full_key = format!("{}{}", store_prefix, kv.key);
+			if kv.version >= 0 {
+				compares.push(version_compare(&full_key, kv.version));
+				compare_targets.push(TxnCompare {
+					target: TxnTarget::Key(kv.key.clone()),
+					full_key: full_key.clone(),
+					expected: kv.version,
+					is_delete: false,
+				});
+			}
+			// An unconditional write needs no compare; etcd advances the version counter on
+			// every put either way.
+			success_ops.push(json!({ "request_put": {
+				"key": BASE64_STANDARD.encode(&full_key),
+				"value": BASE64_STANDARD.encode(encode_value(ts, &kv.value)),
+			} }));
+		}
+		for kv in &request.delete_items {
+			let full_key = format!("{}{}", store_prefix, kv.key);
+			if kv.version >= 0 {
+				if kv.version == 0 {
+					// Deleting an absent record is a conflict even at expected version 0, but an
+					// absent key satisfies `version == 0`; a compare that can never hold forces
+					// the failure branch instead.
+					compares.push(json!({
+						"key": BASE64_STANDARD.encode(&full_key),
+						"target": "VERSION",
+						"result": "LESS",
+						"version": "0",
+					}));
+				} else {
+					compares.push(version_compare(&full_key, kv.version));
+				}
+				compare_targets.push(TxnCompare {
+					target: TxnTarget::Key(kv.key.clone()),
+					full_key: full_key.clone(),
+					expected: kv.version,
+					is_delete: true,
+				});
+			}
+			success_ops.push(json!({ "request_delete_range": {
+				"key": BASE64_STANDARD.encode(&full_key),
+			} }));
+		}
+		// On failure, re-read every pinned record so the response names the conflicting key.
+		let failure_ops: Vec<Value> = compare_targets
+			.iter()
+			.map(|compare| {
+				json!({ "request_range": {
+					"key": BASE64_STANDARD.encode(&compare.full_key),
+					"keys_only": true,
+				} })
+			})
+			.collect();
+
+		let txn = json!({ "compare": compares, "success": success_ops, "failure": failure_ops });
+		let response = self.call("/v3/kv/txn", txn).await?;
+		if response["succeeded"].as_bool().unwrap_or(false) {
+			return Ok(PutObjectResponse {});
+		}
+		let empty = Vec::new();
+		let responses = response["responses"].as_array().unwrap_or(&empty);
+		for (compare, response) in compare_targets.iter().zip(responses) {
+			let version = json_i64(&response["response_range"]["kvs"][0]["version"]);
+			let conflicted = if compare.is_delete {
+				version != compare.expected || version == 0
+			} else {
+				version != compare.expected
+			};
+			if conflicted {
+				return match &compare.target {
+					TxnTarget::GlobalVersion => Err(VssError::ConflictError(format!(
+						"Global version mismatch for store_id: {}",
+						request.store_id
+					))),
+					TxnTarget::Key(key) => {
+						Err(VssError::ConflictError(format!("Version mismatch for key: {}", key)))
+					},
+				};
+			}
+		}
+		// Every compare holds again on the re-read: the conflicting write raced in between.
+		Err(VssError::ConflictError(
+			"Write lost a race with a concurrent transaction, please retry.".to_string(),
+		))
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+		let store_prefix = Self::store_prefix(&context.user_token, &request.store_id);
+		let full_key = format!("{}{}", store_prefix, key_value.key);
+		let encoded_key = BASE64_STANDARD.encode(&full_key);
+		if key_value.version < 0 {
+			self.call("/v3/kv/deleterange", json!({ "key": encoded_key })).await?;
+			return Ok(DeleteObjectResponse {});
+		}
+		// Delete is idempotent, a non-existent key or a mismatched version is not an error, so
+		// the transaction outcome is irrelevant.
+		let txn = json!({
+			"compare": [version_compare(&full_key, key_value.version)],
+			"success": [{ "request_delete_range": { "key": encoded_key } }],
+		});
+		self.call("/v3/kv/txn", txn).await?;
+		Ok(DeleteObjectResponse {})
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => {
+				i64::from(page_size).min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE)
+			},
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let store_prefix = Self::store_prefix(&context.user_token, &request.store_id);
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+
+		// `\0` is the smallest possible continuation, turning the inclusive range start into
+		// "strictly after the last returned key".
+		let start_key = if page_token.is_empty() {
+			format!("{}{}", store_prefix, key_prefix)
+		} else {
+			format!("{}{}\u{0}", store_prefix, page_token)
+		};
+		let range_end = prefix_end(format!("{}{}", store_prefix, key_prefix).as_bytes());
+		let range = json!({
+			"key": BASE64_STANDARD.encode(&start_key),
+			"range_end": BASE64_STANDARD.encode(range_end),
+			"limit": page_size,
+			"keys_only": true,
+		});
+		let response = self.call("/v3/kv/range", range).await?;
+
+		// The global-version record is skipped client-side; a page may therefore come back one
+		// record short of `page_size` with more records remaining. The page token alone decides
+		// whether pagination continues.
+		let mut key_versions = Vec::new();
+		let mut last_key = None;
+		for kv in response["kvs"].as_array().into_iter().flatten() {
+			let full_key = decode_key(kv)?;
+			let key = full_key.strip_prefix(&store_prefix).unwrap_or_default().to_string();
+			last_key = Some(key.clone());
+			if key == GLOBAL_VERSION_KEY {
+				continue;
+			}
+			key_versions.push(KeyValue {
+				key,
+				version: json_i64(&kv["version"]),
+				value: Bytes::new(),
+			});
+		}
+		let next_page_token = if response["more"].as_bool().unwrap_or(false) {
+			last_key
+		} else {
+			None
+		};
+
+		// The global version is only returned on the first page.
+		let global_version = if page_token.is_empty() {
+			let global_key = format!("{}{}", store_prefix, GLOBAL_VERSION_KEY);
+			Some(self.current_version(&global_key).await?)
+		} else {
+			None
+		};
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let store_prefix = Self::store_prefix(&context.user_token, &request.store_id);
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats: Vec<KeyStat> = Vec::new();
+		let mut malformed = false;
+		self.for_each_record(&store_prefix, false, |key, kv| {
+			let (updated_at_millis, value) = match decode_value(kv) {
+				Ok(decoded) => decoded,
+				Err(_) => {
+					malformed = true;
+					(0, Bytes::new())
+				},
+			};
+			let value_bytes = value.len() as i64;
+			stats.key_count += 1;
+			stats.total_value_bytes += value_bytes;
+			if stats.oldest_updated_at_millis == 0
+				|| updated_at_millis < stats.oldest_updated_at_millis
+			{
+				stats.oldest_updated_at_millis = updated_at_millis;
+			}
+			stats.newest_updated_at_millis = stats.newest_updated_at_millis.max(updated_at_millis);
+			key_stats.push(KeyStat { key: key.to_string(), value_bytes });
+		})
+		.await?;
+		if malformed {
+			return Err(VssError::InternalServerError(
+				"Malformed record value in store records.".to_string(),
+			));
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for EtcdBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let user_prefix = format!("{}{}{}", KEY_PREFIX, user_token, KEY_SEPARATOR);
+		let range_end = BASE64_STANDARD.encode(prefix_end(user_prefix.as_bytes()));
+		let mut store_ids: Vec<String> = Vec::new();
+		let mut start_key = user_prefix.clone();
+		loop {
+			let request = json!({
+				"key": BASE64_STANDARD.encode(&start_key),
+				"range_end": range_end,
+				"limit": MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+				"keys_only": true,
+			});
+			let response = self.call("/v3/kv/range", request).await?;
+			for kv in response["kvs"].as_array().into_iter().flatten() {
+				let full_key = decode_key(kv)?;
+				let remainder = full_key.strip_prefix(&user_prefix).unwrap_or_default();
+				if let Some((store_id, _)) = remainder.split_once(KEY_SEPARATOR) {
+					if !store_ids.contains(&store_id.to_string()) {
+						store_ids.push(store_id.to_string());
+					}
+				}
+				start_key = format!("{}\u{0}", full_key);
+			}
+			if !response["more"].as_bool().unwrap_or(false) {
+				break;
+			}
+		}
+		store_ids.sort();
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let store_prefix = Self::store_prefix(&user_token, &store_id);
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		self.for_each_record(&store_prefix, false, |_, kv| {
+			usage.key_count += 1;
+			usage.total_value_bytes += decode_value(kv)
+				.map(|(_, value)| value.len() as i64)
+				.unwrap_or_default();
+		})
+		.await?;
+		Ok(usage)
+	}
+}
+
+#[cfg(all(test, feature = "integration-tests-etcd"))]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	fn test_store() -> EtcdBackendImpl {
+		EtcdBackendImpl::new(vec!["http://localhost:2379".to_string()], None, None).unwrap()
+	}
+
+	define_kv_store_tests!(etcd_store_tests, EtcdBackendImpl, test_store());
+
+	define_kv_store_model_tests!(
+		etcd_store_model_tests,
+		EtcdBackendImpl,
+		test_store(),
+		crate::memory_store::MemoryBackendImpl,
+		crate::memory_store::MemoryBackendImpl::new()
+	);
+}
@@ -0,0 +1,562 @@
+//! A filesystem-backed [`KvStore`] implementation for embedded and air-gapped deployments.
+//!
+//! Each store lives in its own directory (`<root>/<user_token>/<store_id>/`, both components
+//! hex-encoded so arbitrary tokens map onto valid file names), holding one file per record value
+//! and a JSON `manifest` sidecar tracking every key's file name, version, size and timestamp
+//! along with the store's global version. Multi-item [`PutObjectRequest`]s commit through a
+//! write-ahead `journal` recording the value files and the new manifest; a journal is fsynced
+//! into place before any of it is applied and replayed on the next access after a crash, keeping
+//! writes all-or-nothing. All files are written to a temporary name, fsynced and atomically
+//! renamed into place, followed by a directory fsync.
+//!
+//! The backend assumes a single process; the root directory may not be shared.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug_span;
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i32 = 1000;
+
+/// The manifest sidecar tracking a store's records, see the [module documentation](self).
+const MANIFEST_FILE: &str = "manifest";
+
+/// The write-ahead journal a pending commit is staged in.
+const JOURNAL_FILE: &str = "journal";
+
+/// The per-store locks, keyed by `(user_token, store_id)`.
+type StoreLocks = Mutex<HashMap<(String, String), Arc<tokio::sync::Mutex<()>>>>;
+
+/// A [`KvStore`] implementation backed by a directory tree, see the
+/// [module documentation](self) for the on-disk layout.
+pub struct FsBackendImpl {
+	root: PathBuf,
+	// All operations on a store serialize on a per-store lock: readers must not observe a
+	// journal replay or have a value file unlinked between reading the manifest and the value.
+	store_locks: StoreLocks,
+}
+
+/// The sidecar tracking a store's records. Keys order lexicographically in the map, which
+/// `list_key_versions` relies on for pagination.
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct Manifest {
+	global_version: i64,
+	entries: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct ManifestEntry {
+	file: String,
+	version: i64,
+	updated_at_millis: i64,
+	value_bytes: i64,
+}
+
+/// A pending commit: the value files to write, the manifest superseding the current one and the
+/// replaced value files to drop afterwards. Applying a journal is idempotent, so a replay after
+/// a crash mid-apply is safe.
+#[derive(Deserialize, Serialize)]
+struct Journal {
+	files: Vec<JournalFile>,
+	manifest: Manifest,
+	remove: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct JournalFile {
+	name: String,
+	/// The file contents, base64-encoded.
+	contents: String,
+}
+
+fn fs_error(operation: &str, e: std::io::Error) -> VssError {
+	VssError::InternalServerError(format!("Failed to {}: {}", operation, e))
+}
+
+/// Writes a file under its final name via a fsynced temporary and an atomic rename, followed by
+/// a directory fsync so the rename itself is durable.
+fn write_file_atomic(dir: &Path, name: &str, contents: &[u8]) -> Result<(), VssError> {
+	let temp_path = dir.join(format!("{}.tmp", name));
+	let mut file = File::create(&temp_path).map_err(|e| fs_error("create file", e))?;
+	file.write_all(contents).map_err(|e| fs_error("write file", e))?;
+	file.sync_all().map_err(|e| fs_error("sync file", e))?;
+	fs::rename(&temp_path, dir.join(name)).map_err(|e| fs_error("rename file", e))?;
+	sync_dir(dir)
+}
+
+fn sync_dir(dir: &Path) -> Result<(), VssError> {
+	File::open(dir)
+		.and_then(|dir| dir.sync_all())
+		.map_err(|e| fs_error("sync directory", e))
+}
+
+/// Maps an arbitrary path component onto a valid, order-preserving file name.
+fn encode_component(component: &str) -> String {
+	hex::encode(component.as_bytes())
+}
+
+fn decode_component(name: &str) -> Option<String> {
+	hex::decode(name).ok().and_then(|decoded| String::from_utf8(decoded).ok())
+}
+
+/// Names the value file of a record at the given version: keys may be too long for a file name,
+/// so the name carries a key digest instead, unique per live `(key, version)` pair.
+fn value_file_name(key: &str, version: i64) -> String {
+	let digest = Sha256::digest(key.as_bytes());
+	format!("{}-{:x}.val", hex::encode(&digest[..8]), version)
+}
+
+fn now_millis() -> i64 {
+	SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+impl FsBackendImpl {
+	/// Constructs a [`FsBackendImpl`] rooted at the given directory, creating it if missing.
+	pub fn new(root: impl Into<PathBuf>) -> Result<Self, VssError> {
+		let root = root.into();
+		fs::create_dir_all(&root).map_err(|e| fs_error("create root directory", e))?;
+		Ok(FsBackendImpl { root, store_locks: Mutex::new(HashMap::new()) })
+	}
+
+	fn store_lock(&self, user_token: &str, store_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+		let mut store_locks = self.store_locks.lock().unwrap();
+		Arc::clone(
+			store_locks
+				.entry((user_token.to_string(), store_id.to_string()))
+				.or_insert_with(|| Arc::new(tokio::sync::Mutex::new(()))),
+		)
+	}
+
+	fn store_dir(&self, user_token: &str, store_id: &str) -> PathBuf {
+		self.root.join(encode_component(user_token)).join(encode_component(store_id))
+	}
+
+	/// Loads a store's manifest, replaying a leftover journal first. A missing directory or
+	/// manifest is an empty store.
+	fn load_manifest(dir: &Path) -> Result<Manifest, VssError> {
+		let journal_path = dir.join(JOURNAL_FILE);
+		if journal_path.exists() {
+			let contents = fs::read(&journal_path).map_err(|e| fs_error("read journal", e))?;
+			let journal: Journal = serde_json::from_slice(&contents).map_err(|e| {
+				VssError::InternalServerError(format!("Malformed journal: {}", e))
+			})?;
+			Self::apply_journal(dir, &journal)?;
+		}
+		match fs::read(dir.join(MANIFEST_FILE)) {
+			Ok(contents) => serde_json::from_slice(&contents).map_err(|e| {
+				VssError::InternalServerError(format!("Malformed manifest: {}", e))
+			}),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+			Err(e) => Err(fs_error("read manifest", e)),
+		}
+	}
+
+	/// Stages the given commit in the journal, then applies it. Once the journal rename has
+	/// been synced the commit is durable; a crash at any later point replays it.
+	fn commit(dir: &Path, journal: &Journal) -> Result<(), VssError> {
+		let contents = serde_json::to_vec(journal).map_err(|e| {
+			VssError::InternalServerError(format!("Failed to serialize journal: {}", e))
+		})?;
+		write_file_atomic(dir, JOURNAL_FILE, &contents)?;
+		Self::apply_journal(dir, journal)
+	}
+
+	fn apply_journal(dir: &Path, journal: &Journal) -> Result<(), VssError> {
+		for file in &journal.files {
+			let contents = BASE64_STANDARD.decode(&file.contents).map_err(|e| {
+				VssError::InternalServerError(format!("Malformed journal: {}", e))
+			})?;
+			write_file_atomic(dir, &file.name, &contents)?;
+		}
+		let manifest = serde_json::to_vec(&journal.manifest).map_err(|e| {
+			VssError::InternalServerError(format!("Failed to serialize manifest: {}", e))
+		})?;
+		write_file_atomic(dir, MANIFEST_FILE, &manifest)?;
+		// The commit is applied; replaced value files and the journal are only cleanup, and a
+		// crash before the directory sync merely replays the journal once more.
+		for name in &journal.remove {
+			let _ = fs::remove_file(dir.join(name));
+		}
+		fs::remove_file(dir.join(JOURNAL_FILE)).map_err(|e| fs_error("remove journal", e))?;
+		sync_dir(dir)
+	}
+}
+
+#[async_trait]
+impl KvStore for FsBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let store_lock = self.store_lock(&context.user_token, &request.store_id);
+		let _guard = store_lock.lock().await;
+		let _span = debug_span!("fs_store", operation = "get").entered();
+		let dir = self.store_dir(&context.user_token, &request.store_id);
+		let manifest = Self::load_manifest(&dir)?;
+		if let Some(entry) = manifest.entries.get(&request.key) {
+			let value = fs::read(dir.join(&entry.file)).map_err(|e| fs_error("read value", e))?;
+			return Ok(GetObjectResponse {
+				value: Some(KeyValue {
+					key: request.key,
+					version: entry.version,
+					value: Bytes::from(value),
+				}),
+			});
+		}
+		// The global version is tracked in the manifest rather than as a record of its own, but
+		// stays addressable under its reserved key like on the other backends.
+		if request.key == GLOBAL_VERSION_KEY && manifest.global_version > 0 {
+			return Ok(GetObjectResponse {
+				value: Some(KeyValue {
+					key: request.key,
+					version: manifest.global_version,
+					value: Bytes::new(),
+				}),
+			});
+		}
+		Err(VssError::NoSuchKeyError(request.key))
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+
+		let store_lock = self.store_lock(&context.user_token, &request.store_id);
+		let _guard = store_lock.lock().await;
+		let _span = debug_span!("fs_store", operation = "put").entered();
+		let dir = self.store_dir(&context.user_token, &request.store_id);
+		let mut manifest = Self::load_manifest(&dir)?;
+
+		// Check all preconditions before applying anything to keep the write all-or-nothing.
+		if let Some(global_version) = request.global_version {
+			if manifest.global_version != global_version {
+				return Err(VssError::ConflictError(format!(
+					"Global version mismatch for store_id: {}",
+					request.store_id
+				)));
+			}
+		}
+		for kv in &request.transaction_items {
+			let current_version =
+				manifest.entries.get(&kv.key).map(|entry| entry.version).unwrap_or(0);
+			if kv.version >= 0 && current_version != kv.version {
+				return Err(VssError::ConflictError(format!(
+					"Version mismatch for key: {}",
+					kv.key
+				)));
+			}
+		}
+		for kv in &request.delete_items {
+			if kv.version < 0 {
+				continue;
+			}
+			// Deleting an absent record is a conflict even at expected version 0, matching the
+			// other backends; present records always carry a version of at least 1.
+			match manifest.entries.get(&kv.key) {
+				Some(entry) if entry.version == kv.version => {},
+				_ => {
+					return Err(VssError::ConflictError(format!(
+						"Version mismatch for key: {}",
+						kv.key
+					)))
+				},
+			}
+		}
+		if request.dry_run {
+			return Ok(PutObjectResponse {});
+		}
+
+		let updated_at_millis = now_millis();
+		let mut files = Vec::with_capacity(request.transaction_items.len());
+		let mut remove = Vec::new();
+		if request.global_version.is_some() {
+			manifest.global_version += 1;
+		}
+		for kv in &request.transaction_items {
+			let version =
+				manifest.entries.get(&kv.key).map(|entry| entry.version).unwrap_or(0) + 1;
+			let file = value_file_name(&kv.key, version);
+			if let Some(replaced) = manifest.entries.insert(
+				kv.key.clone(),
+				ManifestEntry {
+					file: file.clone(),
+					version,
+					updated_at_millis,
+					value_bytes: kv.value.len() as i64,
+				},
+			) {
+				remove.push(replaced.file);
+			}
+			files.push(JournalFile { name: file, contents: BASE64_STANDARD.encode(&kv.value) });
+		}
+		for kv in &request.delete_items {
+			if let Some(removed) = manifest.entries.remove(&kv.key) {
+				remove.push(removed.file);
+			}
+		}
+
+		fs::create_dir_all(&dir).map_err(|e| fs_error("create store directory", e))?;
+		Self::commit(&dir, &Journal { files, manifest, remove })?;
+		Ok(PutObjectResponse {})
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+		let store_lock = self.store_lock(&context.user_token, &request.store_id);
+		let _guard = store_lock.lock().await;
+		let _span = debug_span!("fs_store", operation = "delete").entered();
+		let dir = self.store_dir(&context.user_token, &request.store_id);
+		let mut manifest = Self::load_manifest(&dir)?;
+		// Delete is idempotent, a non-existent key or a mismatched version is not an error.
+		match manifest.entries.get(&key_value.key) {
+			Some(entry) if key_value.version < 0 || entry.version == key_value.version => {},
+			_ => return Ok(DeleteObjectResponse {}),
+		}
+		let removed = manifest.entries.remove(&key_value.key).expect("checked above");
+		Self::commit(&dir, &Journal { files: Vec::new(), manifest, remove: vec![removed.file] })?;
+		Ok(DeleteObjectResponse {})
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => page_size.min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE),
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+
+		let store_lock = self.store_lock(&context.user_token, &request.store_id);
+		let _guard = store_lock.lock().await;
+		let _span = debug_span!("fs_store", operation = "list_key_versions").entered();
+		let dir = self.store_dir(&context.user_token, &request.store_id);
+		let manifest = Self::load_manifest(&dir)?;
+
+		let range_start = std::ops::Bound::Excluded(page_token.clone());
+		let mut key_versions = Vec::new();
+		for (key, entry) in manifest.entries.range((range_start, std::ops::Bound::Unbounded)) {
+			if key == GLOBAL_VERSION_KEY || !key.starts_with(&key_prefix) {
+				continue;
+			}
+			key_versions.push(KeyValue {
+				key: key.clone(),
+				version: entry.version,
+				value: Bytes::new(),
+			});
+			if key_versions.len() as i32 == page_size {
+				break;
+			}
+		}
+
+		// The global version is only returned on the first page.
+		let global_version =
+			if page_token.is_empty() { Some(manifest.global_version) } else { None };
+		let next_page_token = if key_versions.len() as i32 == page_size {
+			key_versions.last().map(|kv| kv.key.clone())
+		} else {
+			None
+		};
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let store_lock = self.store_lock(&context.user_token, &request.store_id);
+		let _guard = store_lock.lock().await;
+		let _span = debug_span!("fs_store", operation = "get_store_stats").entered();
+		let dir = self.store_dir(&context.user_token, &request.store_id);
+		let manifest = Self::load_manifest(&dir)?;
+
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats = Vec::new();
+		for (key, entry) in &manifest.entries {
+			if key == GLOBAL_VERSION_KEY {
+				continue;
+			}
+			stats.key_count += 1;
+			stats.total_value_bytes += entry.value_bytes;
+			if stats.oldest_updated_at_millis == 0
+				|| entry.updated_at_millis < stats.oldest_updated_at_millis
+			{
+				stats.oldest_updated_at_millis = entry.updated_at_millis;
+			}
+			stats.newest_updated_at_millis =
+				stats.newest_updated_at_millis.max(entry.updated_at_millis);
+			key_stats.push(KeyStat { key: key.clone(), value_bytes: entry.value_bytes });
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for FsBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let user_dir = self.root.join(encode_component(&user_token));
+		let entries = match fs::read_dir(&user_dir) {
+			Ok(entries) => entries,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(fs_error("read user directory", e)),
+		};
+		let mut store_ids = Vec::new();
+		for entry in entries {
+			let entry = entry.map_err(|e| fs_error("read user directory", e))?;
+			if let Some(store_id) = entry.file_name().to_str().and_then(decode_component) {
+				store_ids.push(store_id);
+			}
+		}
+		store_ids.sort();
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let store_lock = self.store_lock(&user_token, &store_id);
+		let _guard = store_lock.lock().await;
+		let dir = self.store_dir(&user_token, &store_id);
+		let manifest = Self::load_manifest(&dir)?;
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		for (key, entry) in &manifest.entries {
+			if key == GLOBAL_VERSION_KEY {
+				continue;
+			}
+			usage.key_count += 1;
+			usage.total_value_bytes += entry.value_bytes;
+		}
+		Ok(usage)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	fn test_store() -> FsBackendImpl {
+		let nanos = SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("vss-fs-store-test-{}", nanos));
+		FsBackendImpl::new(root).unwrap()
+	}
+
+	define_kv_store_tests!(fs_store_tests, FsBackendImpl, test_store());
+
+	define_kv_store_model_tests!(
+		fs_store_model_tests,
+		FsBackendImpl,
+		test_store(),
+		crate::memory_store::MemoryBackendImpl,
+		crate::memory_store::MemoryBackendImpl::new()
+	);
+
+	#[tokio::test]
+	async fn leftover_journals_are_replayed_before_the_next_access() {
+		let store = test_store();
+		let context = RequestContext::new("fs_user".to_string());
+		let request = PutObjectRequest {
+			store_id: "store-1".to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: "k1".to_string(),
+				version: 0,
+				value: Bytes::from_static(b"committed"),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		store.put(context.clone(), request).await.unwrap();
+
+		// Stage a journal as a crashed writer would have left it, without applying it: the next
+		// access must surface the journaled state.
+		let dir = store.store_dir("fs_user", "store-1");
+		let mut manifest = FsBackendImpl::load_manifest(&dir).unwrap();
+		let old_file = manifest.entries.get("k1").unwrap().file.clone();
+		let file = value_file_name("k1", 2);
+		manifest.entries.insert(
+			"k1".to_string(),
+			ManifestEntry {
+				file: file.clone(),
+				version: 2,
+				updated_at_millis: now_millis(),
+				value_bytes: 8,
+			},
+		);
+		let journal = Journal {
+			files: vec![JournalFile {
+				name: file,
+				contents: BASE64_STANDARD.encode(b"replayed"),
+			}],
+			manifest,
+			remove: vec![old_file],
+		};
+		write_file_atomic(&dir, JOURNAL_FILE, &serde_json::to_vec(&journal).unwrap()).unwrap();
+
+		let get_request =
+			GetObjectRequest { store_id: "store-1".to_string(), key: "k1".to_string() };
+		let response = store.get(context, get_request).await.unwrap();
+		let value = response.value.unwrap();
+		assert_eq!(value.version, 2);
+		assert_eq!(value.value, Bytes::from_static(b"replayed"));
+		assert!(!dir.join(JOURNAL_FILE).exists());
+	}
+}
@@ -0,0 +1,18 @@
+//! Storage backend and authorizer implementations for the Versioned Storage Service (VSS).
+
+#![deny(rustdoc::broken_intra_doc_links)]
+
+pub mod auth;
+pub mod aws;
+pub mod dynamodb_store;
+pub mod etcd_store;
+pub mod fs_store;
+pub mod memory_store;
+pub mod migrating_store;
+pub mod migrations;
+pub mod postgres_store;
+pub mod redis_store;
+pub mod sharded_store;
+#[cfg(feature = "sled")]
+pub mod sled_store;
+pub mod tiered_store;
@@ -0,0 +1,619 @@
+//! An in-memory [`KvStore`] implementation.
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tracing::debug_span;
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i32 = 1000;
+
+// Values are held as `Bytes`, so storing and returning them shares the buffer decoded off the
+// wire instead of copying it.
+#[derive(Clone)]
+struct StoredValue {
+	version: i64,
+	value: Bytes,
+	last_updated_millis: i64,
+}
+
+fn now_millis() -> i64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+/// A [`KvStore`] implementation keeping all data in process memory.
+///
+/// All data is lost when the process exits, hence this backend is only suitable for testing.
+pub struct MemoryBackendImpl {
+	// Keyed by (user_token, store_id, key). A `BTreeMap` keeps keys ordered for pagination.
+	inner: Mutex<BTreeMap<(String, String, String), StoredValue>>,
+	max_stores_per_user: Option<u32>,
+	max_keys_per_store: Option<u32>,
+	max_total_bytes_per_user: Option<u64>,
+	max_keys_per_user: Option<u64>,
+}
+
+impl MemoryBackendImpl {
+	/// Constructs an empty [`MemoryBackendImpl`].
+	pub fn new() -> Self {
+		MemoryBackendImpl {
+			inner: Mutex::new(BTreeMap::new()),
+			max_stores_per_user: None,
+			max_keys_per_store: None,
+			max_total_bytes_per_user: None,
+			max_keys_per_user: None,
+		}
+	}
+
+	/// Returns this backend rejecting writes which would create more than the given number of
+	/// distinct stores for a single user, with [`VssError::InvalidRequestError`].
+	pub fn with_max_stores_per_user(mut self, max_stores_per_user: u32) -> Self {
+		self.max_stores_per_user = Some(max_stores_per_user);
+		self
+	}
+
+	/// Returns this backend rejecting writes which would grow a store beyond the given number of
+	/// keys, with [`VssError::InvalidRequestError`]. Overwrites of existing keys always pass;
+	/// deletes in the same request are not credited against the cap.
+	pub fn with_max_keys_per_store(mut self, max_keys_per_store: u32) -> Self {
+		self.max_keys_per_store = Some(max_keys_per_store);
+		self
+	}
+
+	/// Returns this backend rejecting writes which would grow a user's total stored value bytes
+	/// (across all their stores) beyond the given quota, with
+	/// [`VssError::InvalidRequestError`]. Overwrites and deletes within the same request are
+	/// credited, so a full user can always shrink or replace their data.
+	pub fn with_max_total_bytes_per_user(mut self, max_total_bytes_per_user: u64) -> Self {
+		self.max_total_bytes_per_user = Some(max_total_bytes_per_user);
+		self
+	}
+
+	/// Returns this backend rejecting writes which would grow a user's total key count (across
+	/// all their stores) beyond the given quota, with [`VssError::InvalidRequestError`].
+	pub fn with_max_keys_per_user(mut self, max_keys_per_user: u64) -> Self {
+		self.max_keys_per_user = Some(max_keys_per_user);
+		self
+	}
+}
+
+impl Default for MemoryBackendImpl {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[async_trait]
+impl KvStore for MemoryBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let _span = debug_span!("memory_store", operation = "get").entered();
+		let inner = self.inner.lock().unwrap();
+		match inner.get(&(context.user_token, request.store_id, request.key.clone())) {
+			Some(stored) => Ok(GetObjectResponse {
+				value: Some(KeyValue {
+					key: request.key,
+					version: stored.version,
+					value: stored.value.clone(),
+				}),
+			}),
+			None => Err(VssError::NoSuchKeyError(request.key)),
+		}
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+
+		let user_token = context.user_token;
+		let _span = debug_span!("memory_store", operation = "put").entered();
+		let mut inner = self.inner.lock().unwrap();
+
+		// Check all preconditions before applying anything to keep the write all-or-nothing.
+		if let Some(global_version) = request.global_version {
+			let global_key =
+				(user_token.clone(), request.store_id.clone(), GLOBAL_VERSION_KEY.to_string());
+			let current_version = inner.get(&global_key).map(|stored| stored.version).unwrap_or(0);
+			if current_version != global_version {
+				return Err(VssError::ConflictError(format!(
+					"Global version mismatch for store_id: {}",
+					request.store_id
+				)));
+			}
+		}
+		for kv in &request.transaction_items {
+			if kv.version < 0 {
+				continue;
+			}
+			let current_version = inner
+				.get(&(user_token.clone(), request.store_id.clone(), kv.key.clone()))
+				.map(|stored| stored.version)
+				.unwrap_or(0);
+			if current_version != kv.version {
+				return Err(VssError::ConflictError(format!(
+					"Version mismatch for key: {}",
+					kv.key
+				)));
+			}
+		}
+		for kv in &request.delete_items {
+			if kv.version < 0 {
+				continue;
+			}
+			let current_version =
+				inner.get(&(user_token.clone(), request.store_id.clone(), kv.key.clone()));
+			if current_version.map(|stored| stored.version) != Some(kv.version) {
+				return Err(VssError::ConflictError(format!(
+					"Version mismatch for key: {}",
+					kv.key
+				)));
+			}
+		}
+
+		// The store cap only applies to writes which would create a store which does not exist
+		// yet; writes into existing stores must keep working once the cap is lowered.
+		if let Some(max_stores) = self.max_stores_per_user {
+			let creates_rows =
+				!request.transaction_items.is_empty() || request.global_version.is_some();
+			let mut store_exists = false;
+			let mut store_count = 0u32;
+			let mut last_store: Option<&String> = None;
+			for (entry_user_token, entry_store_id, _) in inner.keys() {
+				if *entry_user_token != user_token {
+					continue;
+				}
+				if last_store != Some(entry_store_id) {
+					store_count += 1;
+					last_store = Some(entry_store_id);
+				}
+				if *entry_store_id == request.store_id {
+					store_exists = true;
+				}
+			}
+			if creates_rows && !store_exists && store_count >= max_stores {
+				return Err(VssError::InvalidRequestError(format!(
+					"Reached the maximum number of stores per user ({}).",
+					max_stores
+				)));
+			}
+		}
+
+		// The key cap only counts keys the write would newly create; overwrites of existing keys
+		// must keep working once the cap is lowered.
+		if let Some(max_keys) = self.max_keys_per_store {
+			let new_keys = request
+				.transaction_items
+				.iter()
+				.filter(|kv| {
+					!inner.contains_key(&(
+						user_token.clone(),
+						request.store_id.clone(),
+						kv.key.clone(),
+					))
+				})
+				.count() as u32;
+			if new_keys > 0 {
+				let range_start = Bound::Included((
+					user_token.clone(),
+					request.store_id.clone(),
+					String::new(),
+				));
+				let mut key_count = 0u32;
+				for ((entry_user_token, entry_store_id, key), _) in
+					inner.range((range_start, Bound::Unbounded))
+				{
+					if *entry_user_token != user_token || *entry_store_id != request.store_id {
+						break;
+					}
+					if key != GLOBAL_VERSION_KEY {
+						key_count += 1;
+					}
+				}
+				if key_count + new_keys > max_keys {
+					return Err(VssError::InvalidRequestError(format!(
+						"Reached the maximum number of keys per store ({}).",
+						max_keys
+					)));
+				}
+			}
+		}
+
+		// The quota compares the user's usage after the request (crediting overwritten and
+		// deleted values) against the cap, so a full user can still shrink or replace data.
+		if self.max_total_bytes_per_user.is_some() || self.max_keys_per_user.is_some() {
+			let mut total_bytes: i64 = 0;
+			let mut key_count: i64 = 0;
+			for ((entry_user_token, _, key), stored) in inner.iter() {
+				if *entry_user_token == user_token && key != GLOBAL_VERSION_KEY {
+					total_bytes += stored.value.len() as i64;
+					key_count += 1;
+				}
+			}
+			// The reserved global-version row is excluded, matching the store statistics.
+			for kv in request.transaction_items.iter().filter(|kv| kv.key != GLOBAL_VERSION_KEY) {
+				let entry_key = (user_token.clone(), request.store_id.clone(), kv.key.clone());
+				match inner.get(&entry_key) {
+					Some(stored) => {
+						total_bytes += kv.value.len() as i64 - stored.value.len() as i64;
+					},
+					None => {
+						total_bytes += kv.value.len() as i64;
+						key_count += 1;
+					},
+				}
+			}
+			for kv in request.delete_items.iter().filter(|kv| kv.key != GLOBAL_VERSION_KEY) {
+				let entry_key = (user_token.clone(), request.store_id.clone(), kv.key.clone());
+				if let Some(stored) = inner.get(&entry_key) {
+					total_bytes -= stored.value.len() as i64;
+					key_count -= 1;
+				}
+			}
+			if let Some(max_bytes) = self.max_total_bytes_per_user {
+				if total_bytes > max_bytes as i64 {
+					return Err(VssError::InvalidRequestError(format!(
+						"Exceeded the per-user storage quota of {} bytes.",
+						max_bytes
+					)));
+				}
+			}
+			if let Some(max_keys) = self.max_keys_per_user {
+				if key_count > max_keys as i64 {
+					return Err(VssError::InvalidRequestError(format!(
+						"Exceeded the per-user storage quota of {} keys.",
+						max_keys
+					)));
+				}
+			}
+		}
+
+		// All validations passed; a dry run stops here instead of committing.
+		if request.dry_run {
+			return Ok(PutObjectResponse {});
+		}
+
+		if let Some(global_version) = request.global_version {
+			let global_key =
+				(user_token.clone(), request.store_id.clone(), GLOBAL_VERSION_KEY.to_string());
+			inner.insert(
+				global_key,
+				StoredValue {
+					version: global_version + 1,
+					value: Bytes::new(),
+					last_updated_millis: now_millis(),
+				},
+			);
+		}
+		for kv in &request.transaction_items {
+			let entry_key = (user_token.clone(), request.store_id.clone(), kv.key.clone());
+			let new_version = inner.get(&entry_key).map(|stored| stored.version).unwrap_or(0) + 1;
+			inner.insert(
+				entry_key,
+				StoredValue {
+					version: new_version,
+					value: kv.value.clone(),
+					last_updated_millis: now_millis(),
+				},
+			);
+		}
+		for kv in &request.delete_items {
+			inner.remove(&(user_token.clone(), request.store_id.clone(), kv.key.clone()));
+		}
+		Ok(PutObjectResponse {})
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+
+		let _span = debug_span!("memory_store", operation = "delete").entered();
+		let mut inner = self.inner.lock().unwrap();
+		let entry_key = (context.user_token, request.store_id, key_value.key);
+		// Delete is idempotent, a non-existent key or a mismatched version is not an error.
+		if let Some(stored) = inner.get(&entry_key) {
+			if key_value.version < 0 || stored.version == key_value.version {
+				inner.remove(&entry_key);
+			}
+		}
+		Ok(DeleteObjectResponse {})
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => page_size.min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE),
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let user_token = context.user_token;
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+
+		let _span = debug_span!("memory_store", operation = "list_key_versions").entered();
+		let inner = self.inner.lock().unwrap();
+		let range_start = Bound::Excluded((
+			user_token.clone(),
+			request.store_id.clone(),
+			page_token.clone(),
+		));
+		let mut key_versions = Vec::new();
+		for ((entry_user_token, entry_store_id, key), stored) in
+			inner.range((range_start, Bound::Unbounded))
+		{
+			if *entry_user_token != user_token || *entry_store_id != request.store_id {
+				break;
+			}
+			if key == GLOBAL_VERSION_KEY || !key.starts_with(&key_prefix) {
+				continue;
+			}
+			key_versions.push(KeyValue {
+				key: key.clone(),
+				version: stored.version,
+				value: Bytes::new(),
+			});
+			if key_versions.len() as i32 == page_size {
+				break;
+			}
+		}
+
+		// The global version is only returned on the first page.
+		let global_version = if page_token.is_empty() {
+			let global_key = (user_token, request.store_id, GLOBAL_VERSION_KEY.to_string());
+			Some(inner.get(&global_key).map(|stored| stored.version).unwrap_or(0))
+		} else {
+			None
+		};
+
+		let next_page_token = if key_versions.len() as i32 == page_size {
+			key_versions.last().map(|kv| kv.key.clone())
+		} else {
+			None
+		};
+
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let _span = debug_span!("memory_store", operation = "get_store_stats").entered();
+		let inner = self.inner.lock().unwrap();
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats = Vec::new();
+		for ((entry_user_token, entry_store_id, key), stored) in inner.iter() {
+			if *entry_user_token != context.user_token
+				|| *entry_store_id != request.store_id
+				|| key == GLOBAL_VERSION_KEY
+			{
+				continue;
+			}
+			stats.key_count += 1;
+			stats.total_value_bytes += stored.value.len() as i64;
+			if stats.oldest_updated_at_millis == 0
+				|| stored.last_updated_millis < stats.oldest_updated_at_millis
+			{
+				stats.oldest_updated_at_millis = stored.last_updated_millis;
+			}
+			stats.newest_updated_at_millis =
+				stats.newest_updated_at_millis.max(stored.last_updated_millis);
+			key_stats.push(KeyStat { key: key.clone(), value_bytes: stored.value.len() as i64 });
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for MemoryBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let inner = self.inner.lock().unwrap();
+		let mut store_ids: Vec<String> = Vec::new();
+		for (entry_user_token, store_id, _) in inner.keys() {
+			if *entry_user_token == user_token && store_ids.last() != Some(store_id) {
+				store_ids.push(store_id.clone());
+			}
+		}
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let inner = self.inner.lock().unwrap();
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		for ((entry_user_token, entry_store_id, key), stored) in inner.iter() {
+			if *entry_user_token == user_token
+				&& *entry_store_id == store_id
+				&& key != GLOBAL_VERSION_KEY
+			{
+				usage.key_count += 1;
+				usage.total_value_bytes += stored.value.len() as i64;
+			}
+		}
+		Ok(usage)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	define_kv_store_tests!(memory_store_tests, MemoryBackendImpl, MemoryBackendImpl::new());
+
+	// Checking the model backend against itself mostly exercises the test harness, but also
+	// pins down that the semantics are deterministic under arbitrary operation sequences.
+	define_kv_store_model_tests!(
+		memory_store_model_tests,
+		MemoryBackendImpl,
+		MemoryBackendImpl::new(),
+		MemoryBackendImpl,
+		MemoryBackendImpl::new()
+	);
+
+	fn put_request(store_id: &str, key: &str, version: i64) -> PutObjectRequest {
+		PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: key.to_string(),
+				version,
+				value: b"v".to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		}
+	}
+
+	#[tokio::test]
+	async fn store_limit_blocks_new_stores() {
+		let store = MemoryBackendImpl::new().with_max_stores_per_user(2);
+		let context = RequestContext::new("store_limit_user".to_string());
+
+		store.put(context.clone(), put_request("store-1", "k1", 0)).await.unwrap();
+		store.put(context.clone(), put_request("store-2", "k1", 0)).await.unwrap();
+
+		let err = store.put(context.clone(), put_request("store-3", "k1", 0)).await.unwrap_err();
+		assert!(matches!(err, VssError::InvalidRequestError(..)), "unexpected error: {}", err);
+
+		// Existing stores must remain writable, and other users are unaffected.
+		store.put(context, put_request("store-1", "k2", 0)).await.unwrap();
+		let other_context = RequestContext::new("other_user".to_string());
+		store.put(other_context, put_request("store-3", "k1", 0)).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn key_limit_blocks_new_keys() {
+		let store = MemoryBackendImpl::new().with_max_keys_per_store(2);
+		let context = RequestContext::new("key_limit_user".to_string());
+
+		store.put(context.clone(), put_request("store-1", "k1", 0)).await.unwrap();
+		store.put(context.clone(), put_request("store-1", "k2", 0)).await.unwrap();
+
+		let err = store.put(context.clone(), put_request("store-1", "k3", 0)).await.unwrap_err();
+		assert!(matches!(err, VssError::InvalidRequestError(..)), "unexpected error: {}", err);
+
+		// Overwrites of existing keys must keep working, and other stores are unaffected.
+		store.put(context.clone(), put_request("store-1", "k1", 1)).await.unwrap();
+		store.put(context.clone(), put_request("store-2", "k3", 0)).await.unwrap();
+
+		// Deleting a key frees up room for a new one.
+		let delete_request = DeleteObjectRequest {
+			store_id: "store-1".to_string(),
+			key_value: Some(KeyValue { key: "k2".to_string(), version: -1, value: Bytes::new() }),
+		};
+		store.delete(context.clone(), delete_request).await.unwrap();
+		store.put(context, put_request("store-1", "k3", 0)).await.unwrap();
+	}
+
+	fn sized_put_request(store_id: &str, key: &str, version: i64, size: usize) -> PutObjectRequest {
+		PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: key.to_string(),
+				version,
+				value: vec![0u8; size].into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		}
+	}
+
+	#[tokio::test]
+	async fn byte_quota_blocks_oversized_writes() {
+		let store = MemoryBackendImpl::new().with_max_total_bytes_per_user(10);
+		let context = RequestContext::new("byte_quota_user".to_string());
+
+		// The quota spans all of the user's stores.
+		store.put(context.clone(), sized_put_request("store-1", "k1", 0, 6)).await.unwrap();
+		let err = store
+			.put(context.clone(), sized_put_request("store-2", "k1", 0, 5))
+			.await
+			.unwrap_err();
+		assert!(matches!(err, VssError::InvalidRequestError(..)), "unexpected error: {}", err);
+
+		// Overwriting with a smaller value shrinks usage and is always allowed; the freed bytes
+		// then admit the write which was just rejected. Each user draws from their own budget.
+		store.put(context.clone(), sized_put_request("store-1", "k1", 1, 2)).await.unwrap();
+		store.put(context, sized_put_request("store-2", "k1", 0, 5)).await.unwrap();
+		let other_context = RequestContext::new("other_user".to_string());
+		store.put(other_context, sized_put_request("store-1", "k1", 0, 10)).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn key_quota_spans_all_stores_of_a_user() {
+		let store = MemoryBackendImpl::new().with_max_keys_per_user(2);
+		let context = RequestContext::new("key_quota_user".to_string());
+
+		store.put(context.clone(), put_request("store-1", "k1", 0)).await.unwrap();
+		store.put(context.clone(), put_request("store-2", "k1", 0)).await.unwrap();
+		let err = store.put(context.clone(), put_request("store-2", "k2", 0)).await.unwrap_err();
+		assert!(matches!(err, VssError::InvalidRequestError(..)), "unexpected error: {}", err);
+
+		// Overwrites must keep working at the cap, and a delete within the same request frees
+		// room for the new key.
+		store.put(context.clone(), put_request("store-1", "k1", 1)).await.unwrap();
+		let swap_request = PutObjectRequest {
+			store_id: "store-2".to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: "k2".to_string(),
+				version: 0,
+				value: b"v".to_vec().into(),
+			}],
+			delete_items: vec![KeyValue { key: "k1".to_string(), version: -1, value: Bytes::new() }],
+			dry_run: false,
+		};
+		store.put(context, swap_request).await.unwrap();
+	}
+}
@@ -0,0 +1,430 @@
+//! A [`KvStore`] for migrating live data from one backend to another.
+//!
+//! [`MigratingKvStore`] wraps an old and a new backend and routes every request per store: a
+//! store lives entirely on the old backend until the background copier has moved it, and
+//! entirely on the new backend afterwards — reads try the new backend first and fall back to the
+//! old one. Writes to migrated stores are validated against the new backend and mirrored back
+//! into the old one, so the old backend stays fresh enough for an emergency rollback while the
+//! migration is in flight.
+//!
+//! Version counters are internal to each backend and cannot be replayed through the [`KvStore`]
+//! interface, so a store's versions restart when it is copied. Clients holding a version chain
+//! from before the flip see a single retriable conflict on their next conditional write, re-read
+//! the current version and proceed — the store itself never loses data, as the copier snapshots
+//! each store under the same lock writes take.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use api::error::VssError;
+use api::kv_store::{KvStore, KvStoreAdmin, PoolStatus, RequestContext, StoreUsage};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The number of records the copier moves per write into the new backend.
+const COPY_BATCH_SIZE: usize = 50;
+
+/// The per-store write locks, keyed by `(user_token, store_id)`.
+type WriteLocks = Mutex<HashMap<(String, String), Arc<tokio::sync::Mutex<()>>>>;
+
+/// A [`KvStore`] dual-writing into an old and a new backend during a live migration, see the
+/// [module documentation](self).
+pub struct MigratingKvStore<O: KvStore, N: KvStore> {
+	old: O,
+	new: N,
+	// Writes and the copier serialize per store, so the copier's snapshot of a store is
+	// consistent and the store flips to the new backend atomically.
+	write_locks: WriteLocks,
+}
+
+impl<O: KvStore, N: KvStore> MigratingKvStore<O, N> {
+	/// Constructs a [`MigratingKvStore`] moving data from `old` to `new`. The new backend must
+	/// not hold data the copier did not put there.
+	pub fn new(old: O, new: N) -> Self {
+		MigratingKvStore { old, new, write_locks: Mutex::new(HashMap::new()) }
+	}
+
+	fn write_lock(&self, user_token: &str, store_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+		let mut write_locks = self.write_locks.lock().unwrap();
+		Arc::clone(
+			write_locks
+				.entry((user_token.to_string(), store_id.to_string()))
+				.or_insert_with(|| Arc::new(tokio::sync::Mutex::new(()))),
+		)
+	}
+
+	/// Returns whether the given store has been copied to the new backend, i.e. whether the new
+	/// backend holds any of its records.
+	async fn is_migrated(
+		&self, context: &RequestContext, store_id: &str,
+	) -> Result<bool, VssError> {
+		let request = ListKeyVersionsRequest {
+			store_id: store_id.to_string(),
+			key_prefix: None,
+			page_size: Some(1),
+			page_token: None,
+		};
+		let response = self.new.list_key_versions(context.clone(), request).await?;
+		Ok(!response.key_versions.is_empty() || response.global_version.unwrap_or(0) > 0)
+	}
+
+	/// Strips the version preconditions off a committed request, for mirroring it into the
+	/// non-authoritative backend whose version counters have diverged.
+	fn unconditional(request: &PutObjectRequest) -> PutObjectRequest {
+		PutObjectRequest {
+			store_id: request.store_id.clone(),
+			global_version: None,
+			transaction_items: request
+				.transaction_items
+				.iter()
+				.map(|kv| KeyValue { key: kv.key.clone(), version: -1, value: kv.value.clone() })
+				.collect(),
+			delete_items: request
+				.delete_items
+				.iter()
+				.map(|kv| KeyValue { key: kv.key.clone(), version: -1, value: kv.value.clone() })
+				.collect(),
+			dry_run: false,
+		}
+	}
+
+	/// Copies all not-yet-migrated stores of the given user into the new backend, returning the
+	/// number of records moved. Each store is copied and flipped under its write lock.
+	pub async fn copy_user_data(&self, user_token: &str) -> Result<u64, VssError>
+	where
+		O: KvStoreAdmin,
+	{
+		let mut copied = 0;
+		for store_id in self.old.list_store_ids(user_token.to_string()).await? {
+			copied += self.copy_store_data(user_token, &store_id).await?;
+		}
+		Ok(copied)
+	}
+
+	/// Copies a single store into the new backend unless it is already migrated, returning the
+	/// number of records moved.
+	pub async fn copy_store_data(
+		&self, user_token: &str, store_id: &str,
+	) -> Result<u64, VssError> {
+		let context = RequestContext::new(user_token.to_string());
+		let write_lock = self.write_lock(user_token, store_id);
+		let _guard = write_lock.lock().await;
+		if self.is_migrated(&context, store_id).await? {
+			return Ok(0);
+		}
+
+		let mut copied = 0u64;
+		let mut old_global_version = 0;
+		let mut batch: Vec<KeyValue> = Vec::new();
+		let mut page_token: Option<String> = None;
+		loop {
+			let request = ListKeyVersionsRequest {
+				store_id: store_id.to_string(),
+				key_prefix: None,
+				page_size: None,
+				page_token: page_token.clone(),
+			};
+			let response = self.old.list_key_versions(context.clone(), request).await?;
+			if page_token.is_none() {
+				old_global_version = response.global_version.unwrap_or(0);
+			}
+			for kv in response.key_versions {
+				let get_request = GetObjectRequest {
+					store_id: store_id.to_string(),
+					key: kv.key.clone(),
+				};
+				let value = match self.old.get(context.clone(), get_request).await {
+					Ok(response) => response.value.map(|kv| kv.value).unwrap_or_default(),
+					Err(VssError::NoSuchKeyError(_)) => continue,
+					Err(e) => return Err(e),
+				};
+				batch.push(KeyValue { key: kv.key, version: -1, value });
+				if batch.len() == COPY_BATCH_SIZE {
+					copied += batch.len() as u64;
+					self.put_copy_batch(&context, store_id, std::mem::take(&mut batch)).await?;
+				}
+			}
+			page_token = response.next_page_token;
+			if page_token.is_none() {
+				break;
+			}
+		}
+		if !batch.is_empty() {
+			copied += batch.len() as u64;
+			self.put_copy_batch(&context, store_id, batch).await?;
+		}
+		// A store whose clients use global versioning needs its global-version record on the new
+		// backend too; like the per-key versions, it restarts at the flip.
+		if old_global_version > 0 {
+			let request = PutObjectRequest {
+				store_id: store_id.to_string(),
+				global_version: Some(0),
+				transaction_items: Vec::new(),
+				delete_items: Vec::new(),
+				dry_run: false,
+			};
+			self.new.put(context.clone(), request).await?;
+		}
+		Ok(copied)
+	}
+
+	async fn put_copy_batch(
+		&self, context: &RequestContext, store_id: &str, batch: Vec<KeyValue>,
+	) -> Result<(), VssError> {
+		let request = PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: batch,
+			delete_items: Vec::new(),
+			dry_run: false,
+		};
+		self.new.put(context.clone(), request).await.map(|_| ())
+	}
+}
+
+/// Spawns the background copier, sweeping the given users' stores into the new backend until
+/// all of them are migrated (and picking up stores created on the old backend afterwards, which
+/// can no longer happen once every store is migrated).
+pub fn spawn_copier<O, N>(
+	store: Arc<MigratingKvStore<O, N>>, user_tokens: Vec<String>, interval: Duration,
+) -> tokio::task::JoinHandle<()>
+where
+	O: KvStore + KvStoreAdmin + 'static,
+	N: KvStore + 'static,
+{
+	tokio::spawn(async move {
+		loop {
+			for user_token in &user_tokens {
+				match store.copy_user_data(user_token).await {
+					Ok(0) => {},
+					Ok(copied) => {
+						info!("Migration copier moved {} records of user {}.", copied, user_token)
+					},
+					Err(e) => warn!("Migration copier failed for user {}: {}", user_token, e),
+				}
+			}
+			tokio::time::sleep(interval).await;
+		}
+	})
+}
+
+#[async_trait]
+impl<O: KvStore, N: KvStore> KvStore for MigratingKvStore<O, N> {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		if self.is_migrated(&context, &request.store_id).await? {
+			self.new.get(context, request).await
+		} else {
+			self.old.get(context, request).await
+		}
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		let write_lock = self.write_lock(&context.user_token, &request.store_id);
+		let _guard = write_lock.lock().await;
+		if self.is_migrated(&context, &request.store_id).await? {
+			let result = self.new.put(context.clone(), request.clone()).await?;
+			// Keep the old backend fresh for an emergency rollback. Its copy is best-effort:
+			// reads never consult it for a migrated store.
+			if !request.dry_run {
+				if let Err(e) = self.old.put(context, Self::unconditional(&request)).await {
+					warn!("Failed to mirror write into the old backend: {}", e);
+				}
+			}
+			Ok(result)
+		} else {
+			self.old.put(context, request).await
+		}
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let write_lock = self.write_lock(&context.user_token, &request.store_id);
+		let _guard = write_lock.lock().await;
+		if self.is_migrated(&context, &request.store_id).await? {
+			let result = self.new.delete(context.clone(), request.clone()).await?;
+			// The version check ran against the new backend; drop the old copy unconditionally
+			// so a rollback cannot resurrect the record.
+			let mirror = DeleteObjectRequest {
+				store_id: request.store_id.clone(),
+				key_value: request.key_value.map(|kv| KeyValue {
+					key: kv.key,
+					version: -1,
+					value: kv.value,
+				}),
+			};
+			if let Err(e) = self.old.delete(context, mirror).await {
+				warn!("Failed to mirror delete into the old backend: {}", e);
+			}
+			Ok(result)
+		} else {
+			self.old.delete(context, request).await
+		}
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		if self.is_migrated(&context, &request.store_id).await? {
+			self.new.list_key_versions(context, request).await
+		} else {
+			self.old.list_key_versions(context, request).await
+		}
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		if self.is_migrated(&context, &request.store_id).await? {
+			self.new.get_store_stats(context, request).await
+		} else {
+			self.old.get_store_stats(context, request).await
+		}
+	}
+
+	/// Both backends serve live traffic mid-migration, so readiness requires both.
+	async fn health_check(&self) -> Result<(), VssError> {
+		self.old.health_check().await?;
+		self.new.health_check().await
+	}
+}
+
+#[async_trait]
+impl<O: KvStore + KvStoreAdmin, N: KvStore + KvStoreAdmin> KvStoreAdmin
+	for MigratingKvStore<O, N>
+{
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let mut store_ids = self.old.list_store_ids(user_token.clone()).await?;
+		for store_id in self.new.list_store_ids(user_token).await? {
+			if !store_ids.contains(&store_id) {
+				store_ids.push(store_id);
+			}
+		}
+		store_ids.sort();
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let context = RequestContext::new(user_token.clone());
+		if self.is_migrated(&context, &store_id).await? {
+			self.new.get_store_usage(user_token, store_id).await
+		} else {
+			self.old.get_store_usage(user_token, store_id).await
+		}
+	}
+
+	async fn get_pool_status(&self) -> Result<Option<PoolStatus>, VssError> {
+		self.new.get_pool_status().await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::memory_store::MemoryBackendImpl;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+	use bytes::Bytes;
+
+	fn migrating() -> MigratingKvStore<MemoryBackendImpl, MemoryBackendImpl> {
+		MigratingKvStore::new(MemoryBackendImpl::new(), MemoryBackendImpl::new())
+	}
+
+	define_kv_store_tests!(
+		migrating_store_tests,
+		MigratingKvStore<MemoryBackendImpl, MemoryBackendImpl>,
+		migrating()
+	);
+
+	define_kv_store_model_tests!(
+		migrating_store_model_tests,
+		MigratingKvStore<MemoryBackendImpl, MemoryBackendImpl>,
+		migrating(),
+		MemoryBackendImpl,
+		MemoryBackendImpl::new()
+	);
+
+	fn put_request(store_id: &str, key: &str, version: i64, value: &[u8]) -> PutObjectRequest {
+		PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: key.to_string(),
+				version,
+				value: value.to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		}
+	}
+
+	#[tokio::test]
+	async fn copying_flips_a_store_to_the_new_backend() {
+		let store = migrating();
+		let context = RequestContext::new("migrating_user".to_string());
+		store.put(context.clone(), put_request("store-1", "k1", 0, b"old")).await.unwrap();
+		store.put(context.clone(), put_request("store-1", "k1", 1, b"v2")).await.unwrap();
+
+		let copied = store.copy_user_data("migrating_user").await.unwrap();
+		assert_eq!(copied, 1);
+		// A second sweep finds the store migrated and moves nothing.
+		assert_eq!(store.copy_user_data("migrating_user").await.unwrap(), 0);
+
+		// Change the old copy behind the wrapper's back; the read proves the new backend serves.
+		store
+			.old
+			.put(context.clone(), put_request("store-1", "k1", 2, b"stale"))
+			.await
+			.unwrap();
+		let get_request =
+			GetObjectRequest { store_id: "store-1".to_string(), key: "k1".to_string() };
+		let response = store.get(context.clone(), get_request).await.unwrap();
+		let value = response.value.unwrap();
+		assert_eq!(value.value, Bytes::from_static(b"v2"));
+		// Versions restart at the flip; the copied record sits at version 1.
+		assert_eq!(value.version, 1);
+	}
+
+	#[tokio::test]
+	async fn writes_to_migrated_stores_are_mirrored_into_the_old_backend() {
+		let store = migrating();
+		let context = RequestContext::new("migrating_user".to_string());
+		store.put(context.clone(), put_request("store-1", "k1", 0, b"old")).await.unwrap();
+		store.copy_user_data("migrating_user").await.unwrap();
+
+		// A client still holding the pre-flip version sees one retriable conflict, then
+		// proceeds with the re-read version.
+		let err = store
+			.put(context.clone(), put_request("store-1", "k1", 7, b"post-flip"))
+			.await
+			.unwrap_err();
+		assert!(matches!(err, VssError::ConflictError(..)), "unexpected error: {}", err);
+		store.put(context.clone(), put_request("store-1", "k1", 1, b"post-flip")).await.unwrap();
+
+		let get_request =
+			GetObjectRequest { store_id: "store-1".to_string(), key: "k1".to_string() };
+		let mirrored = store.old.get(context.clone(), get_request.clone()).await.unwrap();
+		assert_eq!(mirrored.value.unwrap().value, Bytes::from_static(b"post-flip"));
+
+		// Deletes reach both backends so a rollback cannot resurrect the record.
+		let delete_request = DeleteObjectRequest {
+			store_id: "store-1".to_string(),
+			key_value: Some(KeyValue { key: "k1".to_string(), version: -1, value: Bytes::new() }),
+		};
+		store.delete(context.clone(), delete_request).await.unwrap();
+		assert!(store.get(context.clone(), get_request.clone()).await.is_err());
+		assert!(store.old.get(context, get_request).await.is_err());
+	}
+}
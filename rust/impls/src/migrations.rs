@@ -0,0 +1,272 @@
+//! A minimal, forward-only schema migration runner for the PostgreSQL backend.
+//!
+//! Applied migrations are tracked in the `vss_migrations` table, new migrations are appended to
+//! [`MIGRATIONS`] and applied in order inside a single transaction. Concurrent callers (e.g.
+//! multiple server instances booting at once) are serialized via an advisory lock.
+
+use api::error::VssError;
+use tokio_postgres::{Client, NoTls};
+
+/// A single schema migration, optionally paired with a statement reverting it.
+pub(crate) struct Migration {
+	/// The statement applying the migration.
+	pub(crate) up: &'static str,
+	/// The statement reverting the migration, or `None` where a rollback would destroy data and
+	/// must be refused.
+	pub(crate) down: Option<&'static str>,
+}
+
+/// The ordered list of schema migrations. Migration `n` (1-indexed) is `MIGRATIONS[n - 1]`.
+///
+/// Entries must never be edited or reordered once released, only appended.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+	Migration {
+		up: "CREATE TABLE vss_db (
+	user_token character varying(120) NOT NULL CHECK (user_token <> ''),
+	store_id character varying(120) NOT NULL CHECK (store_id <> ''),
+	key character varying(600) NOT NULL,
+	value bytea NULL,
+	version bigint NOT NULL,
+	created_at TIMESTAMP WITH TIME ZONE,
+	last_updated_at TIMESTAMP WITH TIME ZONE,
+	PRIMARY KEY (user_token, store_id, key)
+)",
+		// Dropping vss_db would destroy all stored data, this migration can never be rolled back.
+		down: None,
+	},
+	Migration {
+		up: "CREATE TABLE vss_auth_failures (
+	scheme character varying(40) NOT NULL,
+	reason character varying(120) NOT NULL,
+	source_ip character varying(60) NULL,
+	occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+CREATE INDEX vss_auth_failures_occurred_at_idx ON vss_auth_failures (occurred_at)",
+		down: Some("DROP TABLE vss_auth_failures"),
+	},
+	Migration {
+		up: "CREATE TABLE vss_api_keys (
+	api_key character varying(120) NOT NULL CHECK (api_key <> ''),
+	user_token character varying(120) NOT NULL CHECK (user_token <> ''),
+	created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+	PRIMARY KEY (api_key)
+)",
+		down: Some("DROP TABLE vss_api_keys"),
+	},
+	// Per-user usage counters backing the storage quotas, maintained incrementally by every
+	// write and lazily backfilled from an aggregate for users predating the table.
+	Migration {
+		up: "CREATE TABLE vss_user_usage (
+	user_token character varying(120) NOT NULL CHECK (user_token <> ''),
+	key_count bigint NOT NULL,
+	total_value_bytes bigint NOT NULL,
+	PRIMARY KEY (user_token)
+)",
+		down: Some("DROP TABLE vss_user_usage"),
+	},
+];
+
+/// The advisory lock id used to serialize concurrent migration runs.
+const MIGRATION_LOCK_ID: i64 = 0x7653_5300;
+
+fn internal_error(e: tokio_postgres::Error) -> VssError {
+	VssError::InternalServerError(format!("Migrations failure: {}", e))
+}
+
+/// The table tracking applied upstream [`MIGRATIONS`].
+const MIGRATIONS_TABLE: &str = "vss_migrations";
+
+/// The table tracking applied operator-supplied custom migrations, kept separate from
+/// [`MIGRATIONS_TABLE`] so custom statements don't collide with upstream [`MIGRATIONS`].
+const CUSTOM_MIGRATIONS_TABLE: &str = "vss_custom_migrations";
+
+/// Connects to the database at `dsn` and applies all pending migrations, followed by any pending
+/// operator-supplied custom migration statements (e.g. extra indexes or row-level security
+/// policies).
+///
+/// This is the entry point of the `vss-server migrate` subcommand, separating schema changes
+/// from serving for deployments where DDL must not run implicitly at web-server boot.
+pub async fn migrate_database(dsn: &str, custom_statements: &[String]) -> Result<(), VssError> {
+	let (mut client, connection) = tokio_postgres::connect(dsn, NoTls)
+		.await
+		.map_err(|e| VssError::InternalServerError(format!("Failed to connect: {}", e)))?;
+	tokio::spawn(async move {
+		let _ = connection.await;
+	});
+	run_migrations(&mut client).await?;
+	run_custom_migrations(&mut client, custom_statements).await
+}
+
+/// A description of the migrations which [`run_migrations`] would apply, used to review DDL
+/// before a rollout.
+pub struct MigrationPlan {
+	/// The schema version currently applied to the database.
+	pub current_version: usize,
+	/// The schema version the database would be migrated to.
+	pub target_version: usize,
+	/// The statements which would be executed, in order.
+	pub statements: Vec<&'static str>,
+	/// The pending operator-supplied custom migration statements, executed after `statements`.
+	pub custom_statements: Vec<String>,
+}
+
+/// Connects to the database at `dsn` and returns the [`MigrationPlan`] which
+/// [`migrate_database`] would apply, without executing any of it.
+pub async fn plan_database_migrations(
+	dsn: &str, custom_statements: &[String],
+) -> Result<MigrationPlan, VssError> {
+	let (client, connection) = tokio_postgres::connect(dsn, NoTls)
+		.await
+		.map_err(|e| VssError::InternalServerError(format!("Failed to connect: {}", e)))?;
+	tokio::spawn(async move {
+		let _ = connection.await;
+	});
+	let pending = pending_migration_count(&client).await?;
+	let current_version = MIGRATIONS.len() - pending;
+	let applied_custom = applied_version(&client, CUSTOM_MIGRATIONS_TABLE).await?;
+	Ok(MigrationPlan {
+		current_version,
+		target_version: MIGRATIONS.len(),
+		statements: MIGRATIONS[current_version..].iter().map(|m| m.up).collect(),
+		custom_statements: custom_statements
+			.iter()
+			.skip(applied_custom)
+			.cloned()
+			.collect(),
+	})
+}
+
+/// Returns the number of migrations from [`MIGRATIONS`] which have not been applied yet.
+///
+/// This is a read-only check, a missing migration-tracking table counts as nothing applied.
+pub async fn pending_migration_count(client: &Client) -> Result<usize, VssError> {
+	let applied = applied_version(client, MIGRATIONS_TABLE).await?;
+	Ok(MIGRATIONS.len().saturating_sub(applied))
+}
+
+/// Returns the version recorded in the given migration-tracking table, treating a missing table
+/// as version 0.
+async fn applied_version(client: &Client, table: &str) -> Result<usize, VssError> {
+	let row = client
+		.query_one("SELECT to_regclass($1) IS NULL", &[&table])
+		.await
+		.map_err(internal_error)?;
+	let table_missing: bool = row.get(0);
+	if table_missing {
+		return Ok(0);
+	}
+	let row = client
+		.query_one(&format!("SELECT COALESCE(MAX(version), 0) FROM {}", table), &[])
+		.await
+		.map_err(internal_error)?;
+	Ok(row.get::<_, i32>(0) as usize)
+}
+
+/// Applies all pending migrations from [`MIGRATIONS`], creating the migration-tracking table if
+/// it does not exist yet.
+pub async fn run_migrations(client: &mut Client) -> Result<(), VssError> {
+	run_tracked_migrations(client, MIGRATIONS_TABLE, MIGRATIONS.iter().map(|m| m.up)).await
+}
+
+/// Rolls the schema back to `target_version` by executing the down statements of all applied
+/// migrations above it, in reverse order and in a single transaction.
+///
+/// Refuses (without touching the database) if any affected migration has no down statement,
+/// i.e. where a rollback would destroy data. Intended for operator-invoked recovery from
+/// aborted upgrades via `vss-server rollback-schema`, custom migrations are not covered.
+pub async fn rollback_database(dsn: &str, target_version: usize) -> Result<(), VssError> {
+	let (mut client, connection) = tokio_postgres::connect(dsn, NoTls)
+		.await
+		.map_err(|e| VssError::InternalServerError(format!("Failed to connect: {}", e)))?;
+	tokio::spawn(async move {
+		let _ = connection.await;
+	});
+
+	let applied = applied_version(&client, MIGRATIONS_TABLE).await?;
+	if target_version >= applied {
+		return Err(VssError::InvalidRequestError(format!(
+			"Schema is at version {}, nothing to roll back to version {}.",
+			applied, target_version
+		)));
+	}
+	let mut down_statements = Vec::new();
+	for version in ((target_version + 1)..=applied).rev() {
+		match MIGRATIONS[version - 1].down {
+			Some(down) => down_statements.push((version, down)),
+			None => {
+				return Err(VssError::InvalidRequestError(format!(
+					"Migration {} cannot be rolled back without data loss, refusing.",
+					version
+				)))
+			},
+		}
+	}
+
+	let tx = client.transaction().await.map_err(internal_error)?;
+	tx.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_ID])
+		.await
+		.map_err(internal_error)?;
+	for (version, down) in down_statements {
+		tx.batch_execute(down).await.map_err(internal_error)?;
+		tx.execute("DELETE FROM vss_migrations WHERE version = $1", &[&(version as i32)])
+			.await
+			.map_err(internal_error)?;
+	}
+	tx.commit().await.map_err(internal_error)
+}
+
+/// Applies all pending operator-supplied custom migration statements, tracked in a dedicated
+/// version table.
+///
+/// Like [`MIGRATIONS`], custom statements must never be edited or reordered once applied, only
+/// appended.
+pub async fn run_custom_migrations(
+	client: &mut Client, statements: &[String],
+) -> Result<(), VssError> {
+	run_tracked_migrations(
+		client,
+		CUSTOM_MIGRATIONS_TABLE,
+		statements.iter().map(|statement| statement.as_str()),
+	)
+	.await
+}
+
+async fn run_tracked_migrations<'a>(
+	client: &mut Client, table: &str, statements: impl Iterator<Item = &'a str>,
+) -> Result<(), VssError> {
+	client
+		.execute(
+			&format!(
+				"CREATE TABLE IF NOT EXISTS {} (
+					version integer PRIMARY KEY,
+					applied_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+				)",
+				table
+			),
+			&[],
+		)
+		.await
+		.map_err(internal_error)?;
+
+	let tx = client.transaction().await.map_err(internal_error)?;
+	tx.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_ID])
+		.await
+		.map_err(internal_error)?;
+
+	let row = tx
+		.query_one(&format!("SELECT COALESCE(MAX(version), 0) FROM {}", table), &[])
+		.await
+		.map_err(internal_error)?;
+	let current_version = row.get::<_, i32>(0) as usize;
+
+	for (idx, statement) in statements.enumerate().skip(current_version) {
+		tx.batch_execute(statement).await.map_err(internal_error)?;
+		tx.execute(
+			&format!("INSERT INTO {} (version) VALUES ($1)", table),
+			&[&((idx + 1) as i32)],
+		)
+		.await
+		.map_err(internal_error)?;
+	}
+	tx.commit().await.map_err(internal_error)
+}
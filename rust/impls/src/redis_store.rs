@@ -0,0 +1,777 @@
+//! A Redis-backed [`KvStore`] implementation.
+//!
+//! Intended for deployments where Redis with AOF persistence is the only available store. Every
+//! record lives in a hash keyed by `(user_token, store_id, key)` holding the version, value and
+//! last-update timestamp; a per-store sorted set keeps keys ordered for pagination and a per-user
+//! set tracks store ids for the admin API. Writes run as server-side Lua scripts, so the
+//! key-level and global-version checks and the subsequent mutations of a [`PutObjectRequest`]
+//! execute atomically without client-side `WATCH` retry loops.
+//!
+//! The backend speaks RESP directly over a TCP connection rather than pulling in a Redis client
+//! crate, mirroring how the DynamoDB backend implements its wire protocol.
+
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i32 = 1000;
+
+/// Separates the user token, store id and key in Redis key names. Store ids and keys are
+/// validated by the service layer to contain no control characters, so the joined names are
+/// unambiguous.
+const KEY_SEPARATOR: char = '\u{1f}';
+
+/// The Lua script applying a [`PutObjectRequest`]: all version preconditions are checked first
+/// and the mutations only apply once every check passed, keeping the write all-or-nothing.
+///
+/// `KEYS[1]` is the record-hash prefix the record key is appended to, `KEYS[2]` the per-store
+/// key index (a sorted set) and `KEYS[3]` the per-user store-id set. Returns `{'OK'}`,
+/// `{'CONFLICT_GLOBAL'}` or `{'CONFLICT', key}`.
+const PUT_SCRIPT: &str = r#"
+local prefix, zset, stores = KEYS[1], KEYS[2], KEYS[3]
+local global_version, ts, dry_run = ARGV[1], ARGV[2], ARGV[3]
+local store_id, global_key = ARGV[4], ARGV[5]
+local i = 6
+local puts = {}
+for _ = 1, tonumber(ARGV[i]) do
+	puts[#puts + 1] = { key = ARGV[i + 1], version = tonumber(ARGV[i + 2]), value = ARGV[i + 3] }
+	i = i + 3
+end
+i = i + 1
+local deletes = {}
+for _ = 1, tonumber(ARGV[i]) do
+	deletes[#deletes + 1] = { key = ARGV[i + 1], version = tonumber(ARGV[i + 2]) }
+	i = i + 2
+end
+local function current_version(key)
+	local v = redis.call('HGET', prefix .. key, 'v')
+	if v then return tonumber(v) end
+	return 0
+end
+if global_version ~= '' and current_version(global_key) ~= tonumber(global_version) then
+	return { 'CONFLICT_GLOBAL' }
+end
+for _, put in ipairs(puts) do
+	if put.version >= 0 and current_version(put.key) ~= put.version then
+		return { 'CONFLICT', put.key }
+	end
+end
+for _, delete in ipairs(deletes) do
+	if delete.version >= 0 then
+		local v = redis.call('HGET', prefix .. delete.key, 'v')
+		if not v or tonumber(v) ~= delete.version then
+			return { 'CONFLICT', delete.key }
+		end
+	end
+end
+if dry_run == '1' then
+	return { 'OK' }
+end
+if global_version ~= '' then
+	redis.call('HSET', prefix .. global_key, 'v', tonumber(global_version) + 1, 'val', '', 'ts', ts)
+	redis.call('ZADD', zset, 0, global_key)
+end
+for _, put in ipairs(puts) do
+	redis.call('HSET', prefix .. put.key, 'v', current_version(put.key) + 1, 'val', put.value, 'ts', ts)
+	redis.call('ZADD', zset, 0, put.key)
+end
+for _, delete in ipairs(deletes) do
+	redis.call('DEL', prefix .. delete.key)
+	redis.call('ZREM', zset, delete.key)
+end
+if redis.call('ZCARD', zset) == 0 then
+	redis.call('SREM', stores, store_id)
+else
+	redis.call('SADD', stores, store_id)
+end
+return { 'OK' }
+"#;
+
+/// The Lua script applying a single [`DeleteObjectRequest`]. Delete is idempotent, a
+/// non-existent key or a mismatched version is not an error.
+const DELETE_SCRIPT: &str = r#"
+local record, zset, stores = KEYS[1], KEYS[2], KEYS[3]
+local key, version, store_id = ARGV[1], tonumber(ARGV[2]), ARGV[3]
+local v = redis.call('HGET', record, 'v')
+if v and (version < 0 or tonumber(v) == version) then
+	redis.call('DEL', record)
+	redis.call('ZREM', zset, key)
+	if redis.call('ZCARD', zset) == 0 then
+		redis.call('SREM', stores, store_id)
+	end
+end
+return 'OK'
+"#;
+
+/// A single RESP reply off the wire.
+enum RedisReply {
+	Simple(String),
+	Error(String),
+	Integer(i64),
+	Bulk(Option<Vec<u8>>),
+	Array(Option<Vec<RedisReply>>),
+}
+
+impl RedisReply {
+	/// Returns the bulk payload, with both null replies and other reply kinds reported as
+	/// `None`; callers only ever expect bulk payloads where the command guarantees them.
+	fn into_bulk(self) -> Option<Vec<u8>> {
+		match self {
+			RedisReply::Bulk(bulk) => bulk,
+			_ => None,
+		}
+	}
+}
+
+/// A connection to the Redis server, re-established lazily after connection errors.
+struct RedisConnection {
+	stream: BufReader<TcpStream>,
+}
+
+impl RedisConnection {
+	async fn connect(address: &str, password: Option<&str>) -> Result<Self, VssError> {
+		let stream = TcpStream::connect(address).await.map_err(|e| {
+			VssError::InternalServerError(format!("Failed to connect to Redis at {}: {}", address, e))
+		})?;
+		let mut connection = RedisConnection { stream: BufReader::new(stream) };
+		if let Some(password) = password {
+			match connection.command(&[b"AUTH", password.as_bytes()]).await? {
+				RedisReply::Simple(status) if status == "OK" => {},
+				RedisReply::Error(e) => {
+					return Err(VssError::InternalServerError(format!(
+						"Redis authentication failed: {}",
+						e
+					)));
+				},
+				_ => {
+					return Err(VssError::InternalServerError(
+						"Unexpected reply to AUTH.".to_string(),
+					));
+				},
+			}
+		}
+		Ok(connection)
+	}
+
+	/// Sends the given commands in one pipelined batch and reads one reply per command.
+	async fn pipeline(&mut self, commands: &[&[&[u8]]]) -> Result<Vec<RedisReply>, VssError> {
+		let mut encoded = Vec::new();
+		for command in commands {
+			encoded.extend_from_slice(format!("*{}\r\n", command.len()).as_bytes());
+			for argument in *command {
+				encoded.extend_from_slice(format!("${}\r\n", argument.len()).as_bytes());
+				encoded.extend_from_slice(argument);
+				encoded.extend_from_slice(b"\r\n");
+			}
+		}
+		self.stream.get_mut().write_all(&encoded).await.map_err(io_error)?;
+		let mut replies = Vec::with_capacity(commands.len());
+		for _ in commands {
+			replies.push(self.read_reply().await?);
+		}
+		Ok(replies)
+	}
+
+	async fn command(&mut self, command: &[&[u8]]) -> Result<RedisReply, VssError> {
+		let mut replies = self.pipeline(&[command]).await?;
+		Ok(replies.remove(0))
+	}
+
+	async fn read_line(&mut self) -> Result<String, VssError> {
+		let mut line = String::new();
+		self.stream.read_line(&mut line).await.map_err(io_error)?;
+		let line = line.trim_end_matches(['\r', '\n']).to_string();
+		if line.is_empty() {
+			return Err(VssError::InternalServerError("Redis connection closed.".to_string()));
+		}
+		Ok(line)
+	}
+
+	async fn read_reply(&mut self) -> Result<RedisReply, VssError> {
+		// Recursion (for nested arrays) needs a boxed future in async Rust; RESP replies to the
+		// commands used here nest at most one level deep.
+		let line = self.read_line().await?;
+		let (kind, rest) = line.split_at(1);
+		match kind {
+			"+" => Ok(RedisReply::Simple(rest.to_string())),
+			"-" => Ok(RedisReply::Error(rest.to_string())),
+			":" => Ok(RedisReply::Integer(parse_length(rest)?)),
+			"$" => match parse_length(rest)? {
+				length if length < 0 => Ok(RedisReply::Bulk(None)),
+				length => {
+					let mut payload = vec![0u8; length as usize + 2];
+					self.stream.read_exact(&mut payload).await.map_err(io_error)?;
+					payload.truncate(length as usize);
+					Ok(RedisReply::Bulk(Some(payload)))
+				},
+			},
+			"*" => match parse_length(rest)? {
+				length if length < 0 => Ok(RedisReply::Array(None)),
+				length => {
+					let mut elements = Vec::with_capacity(length as usize);
+					for _ in 0..length {
+						elements.push(Box::pin(self.read_reply()).await?);
+					}
+					Ok(RedisReply::Array(Some(elements)))
+				},
+			},
+			_ => Err(VssError::InternalServerError(format!("Malformed Redis reply: {}", line))),
+		}
+	}
+}
+
+fn io_error(e: std::io::Error) -> VssError {
+	VssError::InternalServerError(format!("Redis connection error: {}", e))
+}
+
+fn parse_length(value: &str) -> Result<i64, VssError> {
+	value.parse::<i64>().map_err(|_| {
+		VssError::InternalServerError(format!("Malformed Redis reply length: {}", value))
+	})
+}
+
+fn now_millis() -> i64 {
+	SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+/// A [`KvStore`] implementation backed by a Redis server.
+///
+/// Durability is bounded by the server's persistence configuration; running with AOF persistence
+/// (`appendonly yes`, ideally `appendfsync always`) is strongly recommended, as versioned
+/// clients cannot detect a store silently rolling back to an earlier state.
+pub struct RedisBackendImpl {
+	address: String,
+	password: Option<String>,
+	// A single pipelined connection serves all requests; it is re-established lazily after
+	// connection errors.
+	connection: Mutex<Option<RedisConnection>>,
+}
+
+impl RedisBackendImpl {
+	/// Constructs a [`RedisBackendImpl`] against `address` (`host:port`), authenticating with
+	/// the given password if set. The connection is established lazily on first use.
+	pub fn new(address: String, password: Option<String>) -> Self {
+		RedisBackendImpl { address, password, connection: Mutex::new(None) }
+	}
+
+	/// Sends the given commands on the shared connection, dropping it on connection errors so
+	/// the next call reconnects.
+	async fn pipeline(&self, commands: &[&[&[u8]]]) -> Result<Vec<RedisReply>, VssError> {
+		let mut guard = self.connection.lock().await;
+		if guard.is_none() {
+			*guard =
+				Some(RedisConnection::connect(&self.address, self.password.as_deref()).await?);
+		}
+		let connection = guard.as_mut().expect("connected above");
+		match connection.pipeline(commands).await {
+			Ok(replies) => Ok(replies),
+			Err(e) => {
+				*guard = None;
+				Err(e)
+			},
+		}
+	}
+
+	async fn command(&self, command: &[&[u8]]) -> Result<RedisReply, VssError> {
+		let mut replies = self.pipeline(&[command]).await?;
+		Ok(replies.remove(0))
+	}
+
+	fn record_prefix(user_token: &str, store_id: &str) -> String {
+		format!("vss:r:{}{}{}{}", user_token, KEY_SEPARATOR, store_id, KEY_SEPARATOR)
+	}
+
+	fn key_index(user_token: &str, store_id: &str) -> String {
+		format!("vss:k:{}{}{}", user_token, KEY_SEPARATOR, store_id)
+	}
+
+	fn store_index(user_token: &str) -> String {
+		format!("vss:s:{}", user_token)
+	}
+
+	/// Fetches the `field` hash field of the given record keys in one pipelined batch.
+	async fn record_fields(
+		&self, record_prefix: &str, keys: &[String], field: &str,
+	) -> Result<Vec<Option<Vec<u8>>>, VssError> {
+		let records: Vec<String> =
+			keys.iter().map(|key| format!("{}{}", record_prefix, key)).collect();
+		let commands: Vec<[&[u8]; 3]> = records
+			.iter()
+			.map(|record| [b"HGET" as &[u8], record.as_bytes(), field.as_bytes()])
+			.collect();
+		let commands: Vec<&[&[u8]]> = commands.iter().map(|command| &command[..]).collect();
+		let replies = self.pipeline(&commands).await?;
+		Ok(replies.into_iter().map(RedisReply::into_bulk).collect())
+	}
+
+	/// Returns the next batch of at most `count` keys of the given store strictly after
+	/// `after_key` (or from `min_key` onwards if no key was seen yet), in lexicographic order.
+	async fn key_batch(
+		&self, key_index: &str, after_key: &Option<String>, min_key: &str, count: i32,
+	) -> Result<Vec<String>, VssError> {
+		let min = match after_key {
+			Some(after_key) => format!("({}", after_key),
+			None if min_key.is_empty() => "-".to_string(),
+			None => format!("[{}", min_key),
+		};
+		let count = count.to_string();
+		let reply = self
+			.command(&[
+				b"ZRANGEBYLEX",
+				key_index.as_bytes(),
+				min.as_bytes(),
+				b"+",
+				b"LIMIT",
+				b"0",
+				count.as_bytes(),
+			])
+			.await?;
+		let elements = match reply {
+			RedisReply::Array(Some(elements)) => elements,
+			RedisReply::Error(e) => {
+				return Err(VssError::InternalServerError(format!("Redis error: {}", e)));
+			},
+			_ => {
+				return Err(VssError::InternalServerError(
+					"Unexpected reply to ZRANGEBYLEX.".to_string(),
+				));
+			},
+		};
+		elements
+			.into_iter()
+			.map(|element| {
+				element.into_bulk().and_then(|key| String::from_utf8(key).ok()).ok_or_else(
+					|| VssError::InternalServerError("Malformed key index entry.".to_string()),
+				)
+			})
+			.collect()
+	}
+
+	fn parse_version(field: &Option<Vec<u8>>) -> Result<i64, VssError> {
+		field
+			.as_deref()
+			.and_then(|version| std::str::from_utf8(version).ok())
+			.and_then(|version| version.parse::<i64>().ok())
+			.ok_or_else(|| {
+				VssError::InternalServerError("Malformed record version.".to_string())
+			})
+	}
+}
+
+#[async_trait]
+impl KvStore for RedisBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let record = format!(
+			"{}{}",
+			Self::record_prefix(&context.user_token, &request.store_id),
+			request.key
+		);
+		let reply = self
+			.command(&[b"HMGET", record.as_bytes(), b"v", b"val"])
+			.await?;
+		let mut fields = match reply {
+			RedisReply::Array(Some(fields)) if fields.len() == 2 => fields.into_iter(),
+			_ => {
+				return Err(VssError::InternalServerError(
+					"Unexpected reply to HMGET.".to_string(),
+				));
+			},
+		};
+		let version = fields.next().expect("two fields").into_bulk();
+		if version.is_none() {
+			return Err(VssError::NoSuchKeyError(request.key));
+		}
+		let value = fields.next().expect("two fields").into_bulk().unwrap_or_default();
+		Ok(GetObjectResponse {
+			value: Some(KeyValue {
+				key: request.key,
+				version: Self::parse_version(&version)?,
+				value: Bytes::from(value),
+			}),
+		})
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = std::collections::HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+		let key_index = Self::key_index(&context.user_token, &request.store_id);
+		let store_index = Self::store_index(&context.user_token);
+		let global_version =
+			request.global_version.map(|version| version.to_string()).unwrap_or_default();
+		let ts = now_millis().to_string();
+		let dry_run = if request.dry_run { "1" } else { "0" };
+		let put_count = request.transaction_items.len().to_string();
+		let delete_count = request.delete_items.len().to_string();
+		let versions: Vec<String> = request
+			.transaction_items
+			.iter()
+			.chain(request.delete_items.iter())
+			.map(|kv| kv.version.to_string())
+			.collect();
+
+		let mut command: Vec<&[u8]> = vec![
+			b"EVAL",
+			PUT_SCRIPT.as_bytes(),
+			b"3",
+			record_prefix.as_bytes(),
+			key_index.as_bytes(),
+			store_index.as_bytes(),
+			global_version.as_bytes(),
+			ts.as_bytes(),
+			dry_run.as_bytes(),
+			request.store_id.as_bytes(),
+			GLOBAL_VERSION_KEY.as_bytes(),
+			put_count.as_bytes(),
+		];
+		for (kv, version) in request.transaction_items.iter().zip(versions.iter()) {
+			command.push(kv.key.as_bytes());
+			command.push(version.as_bytes());
+			command.push(&kv.value);
+		}
+		command.push(delete_count.as_bytes());
+		for (kv, version) in request
+			.delete_items
+			.iter()
+			.zip(versions.iter().skip(request.transaction_items.len()))
+		{
+			command.push(kv.key.as_bytes());
+			command.push(version.as_bytes());
+		}
+
+		let reply = self.command(&command).await?;
+		let mut statuses = match reply {
+			RedisReply::Array(Some(statuses)) => statuses.into_iter(),
+			RedisReply::Error(e) => {
+				return Err(VssError::InternalServerError(format!("Redis error: {}", e)));
+			},
+			_ => {
+				return Err(VssError::InternalServerError(
+					"Unexpected reply to the put script.".to_string(),
+				));
+			},
+		};
+		let status = statuses.next().and_then(RedisReply::into_bulk).unwrap_or_default();
+		match status.as_slice() {
+			b"OK" => Ok(PutObjectResponse {}),
+			b"CONFLICT_GLOBAL" => Err(VssError::ConflictError(format!(
+				"Global version mismatch for store_id: {}",
+				request.store_id
+			))),
+			b"CONFLICT" => {
+				let key = statuses
+					.next()
+					.and_then(RedisReply::into_bulk)
+					.and_then(|key| String::from_utf8(key).ok())
+					.unwrap_or_default();
+				Err(VssError::ConflictError(format!("Version mismatch for key: {}", key)))
+			},
+			_ => Err(VssError::InternalServerError(
+				"Unexpected status from the put script.".to_string(),
+			)),
+		}
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+		let record = format!(
+			"{}{}",
+			Self::record_prefix(&context.user_token, &request.store_id),
+			key_value.key
+		);
+		let key_index = Self::key_index(&context.user_token, &request.store_id);
+		let store_index = Self::store_index(&context.user_token);
+		let version = key_value.version.to_string();
+		let reply = self
+			.command(&[
+				b"EVAL",
+				DELETE_SCRIPT.as_bytes(),
+				b"3",
+				record.as_bytes(),
+				key_index.as_bytes(),
+				store_index.as_bytes(),
+				key_value.key.as_bytes(),
+				version.as_bytes(),
+				request.store_id.as_bytes(),
+			])
+			.await?;
+		match reply {
+			// Lua string returns convert to bulk strings on the wire.
+			RedisReply::Bulk(Some(_)) => Ok(DeleteObjectResponse {}),
+			RedisReply::Error(e) => {
+				Err(VssError::InternalServerError(format!("Redis error: {}", e)))
+			},
+			_ => Err(VssError::InternalServerError(
+				"Unexpected reply to the delete script.".to_string(),
+			)),
+		}
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => page_size.min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE),
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+		let key_index = Self::key_index(&context.user_token, &request.store_id);
+
+		// Keys matching the prefix are contiguous in the index, so batches start at the later of
+		// the page token and the prefix and scanning stops at the first key past the prefix.
+		// Batches keep the page filling up even where the global-version record is skipped.
+		let mut after_key = if page_token.is_empty() { None } else { Some(page_token.clone()) };
+		let mut keys = Vec::new();
+		'batches: loop {
+			let batch = self.key_batch(&key_index, &after_key, &key_prefix, page_size).await?;
+			let batch_len = batch.len();
+			for key in batch {
+				if !key.starts_with(&key_prefix) {
+					break 'batches;
+				}
+				after_key = Some(key.clone());
+				if key != GLOBAL_VERSION_KEY {
+					keys.push(key);
+					if keys.len() as i32 == page_size {
+						break 'batches;
+					}
+				}
+			}
+			if (batch_len as i32) < page_size {
+				break;
+			}
+		}
+
+		let versions = self.record_fields(&record_prefix, &keys, "v").await?;
+		let mut key_versions = Vec::with_capacity(keys.len());
+		for (key, version) in keys.into_iter().zip(versions.iter()) {
+			key_versions.push(KeyValue {
+				key,
+				version: Self::parse_version(version)?,
+				value: Bytes::new(),
+			});
+		}
+
+		// The global version is only returned on the first page.
+		let global_version = if page_token.is_empty() {
+			let versions = self
+				.record_fields(&record_prefix, &[GLOBAL_VERSION_KEY.to_string()], "v")
+				.await?;
+			match &versions[0] {
+				Some(_) => Some(Self::parse_version(&versions[0])?),
+				None => Some(0),
+			}
+		} else {
+			None
+		};
+
+		let next_page_token = if key_versions.len() as i32 == page_size {
+			key_versions.last().map(|kv| kv.key.clone())
+		} else {
+			None
+		};
+
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+		let key_index = Self::key_index(&context.user_token, &request.store_id);
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats: Vec<KeyStat> = Vec::new();
+		let mut after_key = None;
+		loop {
+			let batch = self
+				.key_batch(&key_index, &after_key, "", MAX_LIST_KEY_VERSIONS_PAGE_SIZE)
+				.await?;
+			let batch_len = batch.len();
+			after_key = batch.last().cloned();
+			let keys: Vec<String> =
+				batch.into_iter().filter(|key| key != GLOBAL_VERSION_KEY).collect();
+			// HSTRLEN reports value sizes without shipping the values themselves.
+			let records: Vec<String> =
+				keys.iter().map(|key| format!("{}{}", record_prefix, key)).collect();
+			let mut commands: Vec<Vec<&[u8]>> = Vec::with_capacity(records.len() * 2);
+			for record in &records {
+				commands.push(vec![b"HSTRLEN", record.as_bytes(), b"val"]);
+				commands.push(vec![b"HGET", record.as_bytes(), b"ts"]);
+			}
+			let commands: Vec<&[&[u8]]> =
+				commands.iter().map(|command| &command[..]).collect();
+			let mut replies = self.pipeline(&commands).await?.into_iter();
+			for key in keys {
+				let value_bytes = match replies.next() {
+					Some(RedisReply::Integer(length)) => length,
+					_ => {
+						return Err(VssError::InternalServerError(
+							"Unexpected reply to HSTRLEN.".to_string(),
+						));
+					},
+				};
+				let updated_at_millis = replies
+					.next()
+					.and_then(RedisReply::into_bulk)
+					.as_deref()
+					.and_then(|ts| std::str::from_utf8(ts).ok())
+					.and_then(|ts| ts.parse::<i64>().ok())
+					.unwrap_or_default();
+				stats.key_count += 1;
+				stats.total_value_bytes += value_bytes;
+				if stats.oldest_updated_at_millis == 0
+					|| updated_at_millis < stats.oldest_updated_at_millis
+				{
+					stats.oldest_updated_at_millis = updated_at_millis;
+				}
+				stats.newest_updated_at_millis =
+					stats.newest_updated_at_millis.max(updated_at_millis);
+				key_stats.push(KeyStat { key, value_bytes });
+			}
+			if (batch_len as i32) < MAX_LIST_KEY_VERSIONS_PAGE_SIZE {
+				break;
+			}
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for RedisBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let store_index = Self::store_index(&user_token);
+		let reply = self.command(&[b"SMEMBERS", store_index.as_bytes()]).await?;
+		let elements = match reply {
+			RedisReply::Array(Some(elements)) => elements,
+			_ => {
+				return Err(VssError::InternalServerError(
+					"Unexpected reply to SMEMBERS.".to_string(),
+				));
+			},
+		};
+		let mut store_ids = Vec::with_capacity(elements.len());
+		for element in elements {
+			match element.into_bulk().and_then(|store_id| String::from_utf8(store_id).ok()) {
+				Some(store_id) => store_ids.push(store_id),
+				None => {
+					return Err(VssError::InternalServerError(
+						"Malformed store index entry.".to_string(),
+					));
+				},
+			}
+		}
+		store_ids.sort();
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let record_prefix = Self::record_prefix(&user_token, &store_id);
+		let key_index = Self::key_index(&user_token, &store_id);
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		let mut after_key = None;
+		loop {
+			let batch = self
+				.key_batch(&key_index, &after_key, "", MAX_LIST_KEY_VERSIONS_PAGE_SIZE)
+				.await?;
+			let batch_len = batch.len();
+			after_key = batch.last().cloned();
+			for key in batch.into_iter().filter(|key| key != GLOBAL_VERSION_KEY) {
+				let record = format!("{}{}", record_prefix, key);
+				match self.command(&[b"HSTRLEN", record.as_bytes(), b"val"]).await? {
+					RedisReply::Integer(length) => {
+						usage.key_count += 1;
+						usage.total_value_bytes += length;
+					},
+					_ => {
+						return Err(VssError::InternalServerError(
+							"Unexpected reply to HSTRLEN.".to_string(),
+						));
+					},
+				}
+			}
+			if (batch_len as i32) < MAX_LIST_KEY_VERSIONS_PAGE_SIZE {
+				break;
+			}
+		}
+		Ok(usage)
+	}
+}
+
+#[cfg(all(test, feature = "integration-tests-redis"))]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	define_kv_store_tests!(
+		redis_store_tests,
+		RedisBackendImpl,
+		RedisBackendImpl::new("localhost:6379".to_string(), None)
+	);
+
+	define_kv_store_model_tests!(
+		redis_store_model_tests,
+		RedisBackendImpl,
+		RedisBackendImpl::new("localhost:6379".to_string(), None),
+		crate::memory_store::MemoryBackendImpl,
+		crate::memory_store::MemoryBackendImpl::new()
+	);
+}
@@ -0,0 +1,180 @@
+//! A [`KvStore`] partitioning users across multiple backend instances.
+//!
+//! [`ShardedKvStore`] hashes the `user_token` of every request onto one of N configured shards,
+//! so large operators can spread storage across multiple PostgreSQL clusters while keeping a
+//! single server endpoint. All of a user's stores live on one shard, keeping every per-user
+//! guarantee (versioning, atomicity, listings) untouched.
+//!
+//! The shard mapping is a stable hash of the token: it must never change for existing data, so
+//! shards may not be reordered and the shard count may not change without migrating records
+//! between the backends.
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use api::error::VssError;
+use api::kv_store::{KvStore, KvStoreAdmin, PoolStatus, RequestContext, StoreUsage};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// A [`KvStore`] routing each user to one of a fixed set of backend shards, see the
+/// [module documentation](self).
+pub struct ShardedKvStore<S: KvStore> {
+	shards: Vec<S>,
+}
+
+impl<S: KvStore> ShardedKvStore<S> {
+	/// Constructs a [`ShardedKvStore`] over the given shards. The shard order and count are part
+	/// of the data layout and must remain stable across restarts.
+	pub fn new(shards: Vec<S>) -> Result<Self, VssError> {
+		if shards.is_empty() {
+			return Err(VssError::InternalServerError(
+				"At least one shard must be configured.".to_string(),
+			));
+		}
+		Ok(ShardedKvStore { shards })
+	}
+
+	/// Maps a user token onto its shard. SHA-256 rather than the standard library hasher, as the
+	/// mapping must be stable across process restarts and compiler versions.
+	fn shard(&self, user_token: &str) -> &S {
+		let digest = Sha256::digest(user_token.as_bytes());
+		let hash = u64::from_be_bytes(digest[..8].try_into().expect("eight bytes"));
+		&self.shards[(hash % self.shards.len() as u64) as usize]
+	}
+}
+
+#[async_trait]
+impl<S: KvStore> KvStore for ShardedKvStore<S> {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		self.shard(&context.user_token).get(context.clone(), request).await
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		self.shard(&context.user_token).put(context.clone(), request).await
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		self.shard(&context.user_token).delete(context.clone(), request).await
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		self.shard(&context.user_token).list_key_versions(context.clone(), request).await
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		self.shard(&context.user_token).get_store_stats(context.clone(), request).await
+	}
+
+	/// A sharded store is only healthy when every shard is: a single unreachable shard already
+	/// fails requests for the users mapping onto it.
+	async fn health_check(&self) -> Result<(), VssError> {
+		for shard in &self.shards {
+			shard.health_check().await?;
+		}
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl<S: KvStore + KvStoreAdmin> KvStoreAdmin for ShardedKvStore<S> {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		self.shard(&user_token).list_store_ids(user_token.clone()).await
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		self.shard(&user_token).get_store_usage(user_token.clone(), store_id).await
+	}
+
+	/// Aggregates the pool statistics across all shards, or `None` if no shard reports any.
+	async fn get_pool_status(&self) -> Result<Option<PoolStatus>, VssError> {
+		let mut aggregated: Option<PoolStatus> = None;
+		for shard in &self.shards {
+			let status = match shard.get_pool_status().await? {
+				Some(status) => status,
+				None => continue,
+			};
+			match &mut aggregated {
+				Some(aggregated) => {
+					aggregated.connections += status.connections;
+					aggregated.idle_connections += status.idle_connections;
+					aggregated.pending_checkouts += status.pending_checkouts;
+					aggregated.checkouts_direct += status.checkouts_direct;
+					aggregated.checkouts_waited += status.checkouts_waited;
+					aggregated.checkouts_timed_out += status.checkouts_timed_out;
+					aggregated.checkout_wait_time_micros += status.checkout_wait_time_micros;
+					aggregated.connections_created += status.connections_created;
+				},
+				None => aggregated = Some(status),
+			}
+		}
+		Ok(aggregated)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::memory_store::MemoryBackendImpl;
+	use api::types::KeyValue;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	fn sharded() -> ShardedKvStore<MemoryBackendImpl> {
+		let shards = (0..3).map(|_| MemoryBackendImpl::new()).collect();
+		ShardedKvStore::new(shards).unwrap()
+	}
+
+	define_kv_store_tests!(sharded_store_tests, ShardedKvStore<MemoryBackendImpl>, sharded());
+
+	define_kv_store_model_tests!(
+		sharded_store_model_tests,
+		ShardedKvStore<MemoryBackendImpl>,
+		sharded(),
+		MemoryBackendImpl,
+		MemoryBackendImpl::new()
+	);
+
+	#[tokio::test]
+	async fn all_data_of_a_user_lives_on_one_shard() {
+		let store = sharded();
+		for user in ["user-a", "user-b", "user-c", "user-d"] {
+			let context = RequestContext::new(user.to_string());
+			for key in ["k1", "k2", "k3"] {
+				let request = PutObjectRequest {
+					store_id: "store-1".to_string(),
+					global_version: None,
+					transaction_items: vec![KeyValue {
+						key: key.to_string(),
+						version: 0,
+						value: b"v".to_vec().into(),
+					}],
+					delete_items: vec![],
+					dry_run: false,
+				};
+				store.put(context.clone(), request).await.unwrap();
+			}
+			let mut shards_with_data = 0;
+			for shard in &store.shards {
+				if !shard.list_store_ids(user.to_string()).await.unwrap().is_empty() {
+					shards_with_data += 1;
+				}
+			}
+			assert_eq!(shards_with_data, 1, "user {} is spread across shards", user);
+		}
+	}
+}
@@ -0,0 +1,454 @@
+//! An embedded [`KvStore`] implementation backed by [sled].
+//!
+//! Keeps all data in a local on-disk B+-tree, so the whole server compiles into a single static
+//! binary with no native database dependencies — suitable for single-node deployments where
+//! running PostgreSQL is not an option. Multi-item [`PutObjectRequest`]s apply inside a sled
+//! transaction and every write is flushed to disk before the response is returned.
+//!
+//! [sled]: https://docs.rs/sled
+
+use std::collections::HashSet;
+use std::ops::Bound;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use tracing::debug_span;
+
+use api::error::VssError;
+use api::kv_store::{
+	KvStore, KvStoreAdmin, RequestContext, StoreUsage, GLOBAL_VERSION_KEY, MAX_VERSION,
+	STORE_STATS_LARGEST_KEYS,
+};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyStat, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The maximum number of key-versions returned in a single [`KvStore::list_key_versions`] page.
+const MAX_LIST_KEY_VERSIONS_PAGE_SIZE: i32 = 1000;
+
+/// Separates the user token, store id and key in tree keys. Store ids and keys are validated by
+/// the service layer to contain no control characters, so the joined keys are unambiguous and
+/// keep the per-store lexicographic key order needed for pagination.
+const KEY_SEPARATOR: char = '\u{1f}';
+
+/// A stored record: the version and last-update timestamp followed by the value bytes.
+struct Record {
+	version: i64,
+	last_updated_millis: i64,
+	value: Vec<u8>,
+}
+
+impl Record {
+	fn encode(version: i64, last_updated_millis: i64, value: &[u8]) -> Vec<u8> {
+		let mut encoded = Vec::with_capacity(16 + value.len());
+		encoded.extend_from_slice(&version.to_be_bytes());
+		encoded.extend_from_slice(&last_updated_millis.to_be_bytes());
+		encoded.extend_from_slice(value);
+		encoded
+	}
+
+	fn decode(encoded: &[u8]) -> Result<Record, VssError> {
+		if encoded.len() < 16 {
+			return Err(VssError::InternalServerError("Malformed stored record.".to_string()));
+		}
+		Ok(Record {
+			version: i64::from_be_bytes(encoded[..8].try_into().expect("eight bytes")),
+			last_updated_millis: i64::from_be_bytes(encoded[8..16].try_into().expect("eight bytes")),
+			value: encoded[16..].to_vec(),
+		})
+	}
+}
+
+fn now_millis() -> i64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis() as i64
+}
+
+/// A [`KvStore`] implementation embedding a [sled] database in the server process.
+///
+/// Suitable for single-node deployments only: the database directory may not be shared between
+/// processes, so neither horizontal scaling nor zero-downtime restarts are possible.
+///
+/// [sled]: https://docs.rs/sled
+pub struct SledBackendImpl {
+	db: sled::Db,
+}
+
+impl SledBackendImpl {
+	/// Opens (or creates) the database at the given directory.
+	pub fn new(path: &str) -> Result<Self, VssError> {
+		let db = sled::open(path).map_err(|e| {
+			VssError::InternalServerError(format!("Failed to open sled database {}: {}", path, e))
+		})?;
+		Ok(SledBackendImpl { db })
+	}
+
+	/// Opens a temporary database deleted when dropped, for tests and experimentation.
+	pub fn new_temporary() -> Result<Self, VssError> {
+		let db = sled::Config::new().temporary(true).open().map_err(|e| {
+			VssError::InternalServerError(format!("Failed to open temporary sled database: {}", e))
+		})?;
+		Ok(SledBackendImpl { db })
+	}
+
+	fn record_prefix(user_token: &str, store_id: &str) -> String {
+		format!("{}{}{}{}", user_token, KEY_SEPARATOR, store_id, KEY_SEPARATOR)
+	}
+
+	/// Flushes all buffered writes to disk; sled alone only flushes periodically, which would
+	/// let acknowledged writes roll back on a crash.
+	async fn flush(&self) -> Result<(), VssError> {
+		self.db.flush_async().await.map(|_| ()).map_err(|e| {
+			VssError::InternalServerError(format!("Failed to flush sled database: {}", e))
+		})
+	}
+}
+
+fn storage_error(e: sled::Error) -> VssError {
+	VssError::InternalServerError(format!("sled error: {}", e))
+}
+
+#[async_trait]
+impl KvStore for SledBackendImpl {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		let _span = debug_span!("sled_store", operation = "get").entered();
+		let record_key = format!(
+			"{}{}",
+			Self::record_prefix(&context.user_token, &request.store_id),
+			request.key
+		);
+		match self.db.get(record_key.as_bytes()).map_err(storage_error)? {
+			Some(encoded) => {
+				let record = Record::decode(&encoded)?;
+				Ok(GetObjectResponse {
+					value: Some(KeyValue {
+						key: request.key,
+						version: record.version,
+						value: Bytes::from(record.value),
+					}),
+				})
+			},
+			None => Err(VssError::NoSuchKeyError(request.key)),
+		}
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		// The proto requires distinct keys per request; with duplicates, the result would depend
+		// on the item order. Versions beyond `MAX_VERSION` could never have been handed out and
+		// would let the subsequent increment overflow, so they are rejected upfront.
+		let mut seen_keys = HashSet::new();
+		for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+			if !seen_keys.insert(kv.key.as_str()) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Duplicate key in request: {}",
+					kv.key
+				)));
+			}
+			if kv.version < -1 || kv.version > MAX_VERSION {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid version {} for key: {}",
+					kv.version, kv.key
+				)));
+			}
+		}
+		if let Some(global_version) = request.global_version {
+			if !(0..=MAX_VERSION).contains(&global_version) {
+				return Err(VssError::InvalidRequestError(format!(
+					"Invalid global version: {}",
+					global_version
+				)));
+			}
+		}
+
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+		let global_key = format!("{}{}", record_prefix, GLOBAL_VERSION_KEY);
+		let record_keys: Vec<String> = request
+			.transaction_items
+			.iter()
+			.chain(request.delete_items.iter())
+			.map(|kv| format!("{}{}", record_prefix, kv.key))
+			.collect();
+		let ts = now_millis();
+
+		// The closure may run multiple times on internal conflicts; all version checks pass
+		// before anything is applied, keeping the write all-or-nothing.
+		let span = debug_span!("sled_store", operation = "put").entered();
+		let result = self.db.transaction(|tx| {
+			let current_version = |record_key: &str| -> Result<
+				i64,
+				ConflictableTransactionError<VssError>,
+			> {
+				match tx.get(record_key.as_bytes())? {
+					Some(encoded) => Record::decode(&encoded)
+						.map(|record| record.version)
+						.map_err(ConflictableTransactionError::Abort),
+					None => Ok(0),
+				}
+			};
+			if let Some(global_version) = request.global_version {
+				if current_version(&global_key)? != global_version {
+					return Err(ConflictableTransactionError::Abort(VssError::ConflictError(
+						format!("Global version mismatch for store_id: {}", request.store_id),
+					)));
+				}
+			}
+			let (put_keys, delete_keys) = record_keys.split_at(request.transaction_items.len());
+			let mut put_versions = Vec::with_capacity(request.transaction_items.len());
+			for (kv, record_key) in request.transaction_items.iter().zip(put_keys.iter()) {
+				let current = current_version(record_key)?;
+				if kv.version >= 0 && current != kv.version {
+					return Err(ConflictableTransactionError::Abort(VssError::ConflictError(
+						format!("Version mismatch for key: {}", kv.key),
+					)));
+				}
+				put_versions.push(current + 1);
+			}
+			for (kv, record_key) in request.delete_items.iter().zip(delete_keys.iter()) {
+				if kv.version < 0 {
+					continue;
+				}
+				let current = match tx.get(record_key.as_bytes())? {
+					Some(encoded) => Some(
+						Record::decode(&encoded)
+							.map(|record| record.version)
+							.map_err(ConflictableTransactionError::Abort)?,
+					),
+					None => None,
+				};
+				if current != Some(kv.version) {
+					return Err(ConflictableTransactionError::Abort(VssError::ConflictError(
+						format!("Version mismatch for key: {}", kv.key),
+					)));
+				}
+			}
+
+			// All validations passed; a dry run stops here instead of committing.
+			if request.dry_run {
+				return Ok(());
+			}
+
+			if let Some(global_version) = request.global_version {
+				tx.insert(
+					global_key.as_bytes(),
+					Record::encode(global_version + 1, ts, &[]),
+				)?;
+			}
+			for ((kv, record_key), new_version) in
+				request.transaction_items.iter().zip(put_keys.iter()).zip(put_versions.iter())
+			{
+				tx.insert(record_key.as_bytes(), Record::encode(*new_version, ts, &kv.value))?;
+			}
+			for record_key in delete_keys {
+				tx.remove(record_key.as_bytes())?;
+			}
+			Ok(())
+		});
+		// The entered span may not be held across the flush await.
+		drop(span);
+		match result {
+			Ok(()) => {},
+			Err(TransactionError::Abort(e)) => return Err(e),
+			Err(TransactionError::Storage(e)) => return Err(storage_error(e)),
+		}
+		if !request.dry_run {
+			self.flush().await?;
+		}
+		Ok(PutObjectResponse {})
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let key_value = request
+			.key_value
+			.ok_or_else(|| VssError::InvalidRequestError("key_value must be set".to_string()))?;
+		let record_key = format!(
+			"{}{}",
+			Self::record_prefix(&context.user_token, &request.store_id),
+			key_value.key
+		);
+
+		let span = debug_span!("sled_store", operation = "delete").entered();
+		let result = self.db.transaction(|tx| {
+			// Delete is idempotent, a non-existent key or a mismatched version is not an error.
+			if let Some(encoded) = tx.get(record_key.as_bytes())? {
+				let record =
+					Record::decode(&encoded).map_err(ConflictableTransactionError::Abort)?;
+				if key_value.version < 0 || record.version == key_value.version {
+					tx.remove(record_key.as_bytes())?;
+				}
+			}
+			Ok(())
+		});
+		// The entered span may not be held across the flush await.
+		drop(span);
+		match result {
+			Ok(()) => {},
+			Err(TransactionError::Abort(e)) => return Err(e),
+			Err(TransactionError::Storage(e)) => return Err(storage_error(e)),
+		}
+		self.flush().await?;
+		Ok(DeleteObjectResponse {})
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		let page_size = match request.page_size {
+			Some(page_size) if page_size > 0 => page_size.min(MAX_LIST_KEY_VERSIONS_PAGE_SIZE),
+			_ => MAX_LIST_KEY_VERSIONS_PAGE_SIZE,
+		};
+		let key_prefix = request.key_prefix.unwrap_or_default();
+		let page_token = request.page_token.unwrap_or_default();
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+
+		let _span = debug_span!("sled_store", operation = "list_key_versions").entered();
+		let range_start =
+			Bound::Excluded(format!("{}{}", record_prefix, page_token).into_bytes());
+		let mut key_versions = Vec::new();
+		for entry in self.db.range((range_start, Bound::Unbounded)) {
+			let (record_key, encoded) = entry.map_err(storage_error)?;
+			let key = match record_key.strip_prefix(record_prefix.as_bytes()) {
+				Some(key) => String::from_utf8_lossy(key).into_owned(),
+				None => break,
+			};
+			if key == GLOBAL_VERSION_KEY || !key.starts_with(&key_prefix) {
+				continue;
+			}
+			key_versions.push(KeyValue {
+				key,
+				version: Record::decode(&encoded)?.version,
+				value: Bytes::new(),
+			});
+			if key_versions.len() as i32 == page_size {
+				break;
+			}
+		}
+
+		// The global version is only returned on the first page.
+		let global_version = if page_token.is_empty() {
+			let global_key = format!("{}{}", record_prefix, GLOBAL_VERSION_KEY);
+			match self.db.get(global_key.as_bytes()).map_err(storage_error)? {
+				Some(encoded) => Some(Record::decode(&encoded)?.version),
+				None => Some(0),
+			}
+		} else {
+			None
+		};
+
+		let next_page_token = if key_versions.len() as i32 == page_size {
+			key_versions.last().map(|kv| kv.key.clone())
+		} else {
+			None
+		};
+
+		Ok(ListKeyVersionsResponse { key_versions, next_page_token, global_version })
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		let record_prefix = Self::record_prefix(&context.user_token, &request.store_id);
+
+		let _span = debug_span!("sled_store", operation = "get_store_stats").entered();
+		let mut stats = GetStoreStatsResponse::default();
+		let mut key_stats = Vec::new();
+		for entry in self.db.scan_prefix(record_prefix.as_bytes()) {
+			let (record_key, encoded) = entry.map_err(storage_error)?;
+			let key = match record_key.strip_prefix(record_prefix.as_bytes()) {
+				Some(key) => String::from_utf8_lossy(key).into_owned(),
+				None => continue,
+			};
+			if key == GLOBAL_VERSION_KEY {
+				continue;
+			}
+			let record = Record::decode(&encoded)?;
+			stats.key_count += 1;
+			stats.total_value_bytes += record.value.len() as i64;
+			if stats.oldest_updated_at_millis == 0
+				|| record.last_updated_millis < stats.oldest_updated_at_millis
+			{
+				stats.oldest_updated_at_millis = record.last_updated_millis;
+			}
+			stats.newest_updated_at_millis =
+				stats.newest_updated_at_millis.max(record.last_updated_millis);
+			key_stats.push(KeyStat { key, value_bytes: record.value.len() as i64 });
+		}
+		key_stats.sort_by(|a, b| b.value_bytes.cmp(&a.value_bytes).then(a.key.cmp(&b.key)));
+		key_stats.truncate(STORE_STATS_LARGEST_KEYS);
+		stats.largest_keys = key_stats;
+		Ok(stats)
+	}
+}
+
+#[async_trait]
+impl KvStoreAdmin for SledBackendImpl {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		let user_prefix = format!("{}{}", user_token, KEY_SEPARATOR);
+		let mut store_ids: Vec<String> = Vec::new();
+		for entry in self.db.scan_prefix(user_prefix.as_bytes()) {
+			let (record_key, _) = entry.map_err(storage_error)?;
+			let rest = match record_key.strip_prefix(user_prefix.as_bytes()) {
+				Some(rest) => rest,
+				None => continue,
+			};
+			let store_id = match rest.split(|byte| *byte == KEY_SEPARATOR as u8).next() {
+				Some(store_id) => String::from_utf8_lossy(store_id).into_owned(),
+				None => continue,
+			};
+			if store_ids.last() != Some(&store_id) {
+				store_ids.push(store_id);
+			}
+		}
+		Ok(store_ids)
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		let record_prefix = Self::record_prefix(&user_token, &store_id);
+		let mut usage = StoreUsage { key_count: 0, total_value_bytes: 0 };
+		for entry in self.db.scan_prefix(record_prefix.as_bytes()) {
+			let (record_key, encoded) = entry.map_err(storage_error)?;
+			let key = match record_key.strip_prefix(record_prefix.as_bytes()) {
+				Some(key) => key,
+				None => continue,
+			};
+			if key == GLOBAL_VERSION_KEY.as_bytes() {
+				continue;
+			}
+			usage.key_count += 1;
+			usage.total_value_bytes += Record::decode(&encoded)?.value.len() as i64;
+		}
+		Ok(usage)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	define_kv_store_tests!(
+		sled_store_tests,
+		SledBackendImpl,
+		SledBackendImpl::new_temporary().unwrap()
+	);
+
+	define_kv_store_model_tests!(
+		sled_store_model_tests,
+		SledBackendImpl,
+		SledBackendImpl::new_temporary().unwrap(),
+		crate::memory_store::MemoryBackendImpl,
+		crate::memory_store::MemoryBackendImpl::new()
+	);
+}
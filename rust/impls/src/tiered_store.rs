@@ -0,0 +1,278 @@
+//! A tiered [`KvStore`] serving reads from a fast cache in front of a durable backend.
+//!
+//! [`TieredKvStore`] writes through to the durable store first — it remains the single source of
+//! truth — and mirrors successful conditional writes into the fast store, so hot, frequently
+//! rewritten keys (e.g. channel manager state) are re-cached on every write and served from the
+//! cache on reads. Entries whose mirror write conflicts (a fast store stale relative to the
+//! durable one, e.g. a Redis cache surviving a restart) are invalidated and served from the
+//! durable store instead: the cache can only ever miss, never return a wrong version.
+//!
+//! The fast store must not hold pre-existing data the durable store does not know about.
+//! `list_key_versions` and store statistics always come from the durable store, as the cache
+//! only holds the written-through subset of a store's keys.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tracing::warn;
+
+use api::error::VssError;
+use api::kv_store::{KvStore, KvStoreAdmin, PoolStatus, RequestContext, StoreUsage};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse,
+};
+
+/// The per-store write locks, keyed by `(user_token, store_id)`.
+type WriteLocks = Mutex<HashMap<(String, String), Arc<tokio::sync::Mutex<()>>>>;
+
+/// A [`KvStore`] layering a fast store `F` (in-memory, Redis) over a durable store `S`
+/// (PostgreSQL), see the [module documentation](self) for the caching semantics.
+pub struct TieredKvStore<F: KvStore, S: KvStore> {
+	fast: F,
+	durable: S,
+	// Writes to the same store serialize on a per-store lock so mirror writes reach the fast
+	// store in durable-store commit order; without it, two racing writers could interleave their
+	// mirrors and leave a stale version cached.
+	write_locks: WriteLocks,
+}
+
+impl<F: KvStore, S: KvStore> TieredKvStore<F, S> {
+	/// Constructs a [`TieredKvStore`] over the given stores. The fast store should start empty;
+	/// pre-existing entries in sync with the durable store are served, anything else is
+	/// invalidated on its next write.
+	pub fn new(fast: F, durable: S) -> Self {
+		TieredKvStore { fast, durable, write_locks: Mutex::new(HashMap::new()) }
+	}
+
+	fn write_lock(&self, user_token: &str, store_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+		let mut write_locks = self.write_locks.lock().unwrap();
+		Arc::clone(
+			write_locks
+				.entry((user_token.to_string(), store_id.to_string()))
+				.or_insert_with(|| Arc::new(tokio::sync::Mutex::new(()))),
+		)
+	}
+
+	/// Drops the given key from the fast store. A failed invalidation is only logged: the fast
+	/// store is likely unreachable altogether, in which case reads fall back to the durable
+	/// store anyway.
+	async fn invalidate(&self, context: &RequestContext, store_id: &str, key: &str) {
+		let request = DeleteObjectRequest {
+			store_id: store_id.to_string(),
+			key_value: Some(KeyValue {
+				key: key.to_string(),
+				version: -1,
+				value: Bytes::new(),
+			}),
+		};
+		if let Err(e) = self.fast.delete(context.clone(), request).await {
+			warn!("Failed to invalidate cached key {}: {}", key, e);
+		}
+	}
+
+	/// Mirrors a successfully committed conditional write into the fast store. An entry in sync
+	/// with the durable store moves to the new version; a stale or missing entry conflicts and
+	/// is invalidated instead.
+	async fn mirror_put(&self, context: &RequestContext, store_id: &str, kv: &KeyValue) {
+		let request = PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: vec![kv.clone()],
+			delete_items: Vec::new(),
+			dry_run: false,
+		};
+		if self.fast.put(context.clone(), request).await.is_err() {
+			self.invalidate(context, store_id, &kv.key).await;
+		}
+	}
+}
+
+#[async_trait]
+impl<F: KvStore, S: KvStore> KvStore for TieredKvStore<F, S> {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		// Any fast-store miss or failure falls back to the durable store; reads never populate
+		// the cache, only write-through does.
+		match self.fast.get(context.clone(), request.clone()).await {
+			Ok(response) => Ok(response),
+			Err(_) => self.durable.get(context, request).await,
+		}
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		let write_lock = self.write_lock(&context.user_token, &request.store_id);
+		let _guard = write_lock.lock().await;
+		let result = self.durable.put(context.clone(), request.clone()).await;
+		// A dry run commits nothing, so there is nothing to mirror or invalidate.
+		if request.dry_run {
+			return result;
+		}
+		match &result {
+			Ok(_) => {
+				for kv in &request.transaction_items {
+					if kv.version >= 0 {
+						self.mirror_put(&context, &request.store_id, kv).await;
+					} else {
+						// The new durable version of an unconditional write is unknown here.
+						self.invalidate(&context, &request.store_id, &kv.key).await;
+					}
+				}
+				for kv in &request.delete_items {
+					self.invalidate(&context, &request.store_id, &kv.key).await;
+				}
+			},
+			Err(_) => {
+				// On conflicts (and other failures) nothing was committed, but the conflict may
+				// mean the cache is the stale party; drop the named keys to be safe.
+				for kv in request.transaction_items.iter().chain(request.delete_items.iter()) {
+					self.invalidate(&context, &request.store_id, &kv.key).await;
+				}
+			},
+		}
+		result
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let write_lock = self.write_lock(&context.user_token, &request.store_id);
+		let _guard = write_lock.lock().await;
+		let result = self.durable.delete(context.clone(), request.clone()).await;
+		// Whether the (idempotent) delete removed anything is not reported back, so the cached
+		// entry is dropped either way.
+		if let Some(key_value) = &request.key_value {
+			self.invalidate(&context, &request.store_id, &key_value.key).await;
+		}
+		result
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		// The cache only holds the written-through subset of a store's keys, so listings must
+		// come from the durable store.
+		self.durable.list_key_versions(context, request).await
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		self.durable.get_store_stats(context, request).await
+	}
+}
+
+#[async_trait]
+impl<F: KvStore, S: KvStore + KvStoreAdmin> KvStoreAdmin for TieredKvStore<F, S> {
+	async fn list_store_ids(&self, user_token: String) -> Result<Vec<String>, VssError> {
+		self.durable.list_store_ids(user_token).await
+	}
+
+	async fn get_store_usage(
+		&self, user_token: String, store_id: String,
+	) -> Result<StoreUsage, VssError> {
+		self.durable.get_store_usage(user_token, store_id).await
+	}
+
+	async fn get_pool_status(&self) -> Result<Option<PoolStatus>, VssError> {
+		self.durable.get_pool_status().await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::memory_store::MemoryBackendImpl;
+	use api::{define_kv_store_model_tests, define_kv_store_tests};
+
+	fn tiered() -> TieredKvStore<MemoryBackendImpl, MemoryBackendImpl> {
+		TieredKvStore::new(MemoryBackendImpl::new(), MemoryBackendImpl::new())
+	}
+
+	define_kv_store_tests!(
+		tiered_store_tests,
+		TieredKvStore<MemoryBackendImpl, MemoryBackendImpl>,
+		tiered()
+	);
+
+	define_kv_store_model_tests!(
+		tiered_store_model_tests,
+		TieredKvStore<MemoryBackendImpl, MemoryBackendImpl>,
+		tiered(),
+		MemoryBackendImpl,
+		MemoryBackendImpl::new()
+	);
+
+	fn put_request(store_id: &str, key: &str, version: i64, value: &[u8]) -> PutObjectRequest {
+		PutObjectRequest {
+			store_id: store_id.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: key.to_string(),
+				version,
+				value: value.to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		}
+	}
+
+	#[tokio::test]
+	async fn conditional_writes_are_served_from_the_cache() {
+		let store = tiered();
+		let context = RequestContext::new("tiered_user".to_string());
+		store.put(context.clone(), put_request("store-1", "k1", 0, b"cached")).await.unwrap();
+
+		// Change the durable copy behind the cache's back; the stale read proves the hit.
+		store
+			.durable
+			.put(context.clone(), put_request("store-1", "k1", 1, b"durable"))
+			.await
+			.unwrap();
+		let get_request =
+			GetObjectRequest { store_id: "store-1".to_string(), key: "k1".to_string() };
+		let response = store.get(context.clone(), get_request.clone()).await.unwrap();
+		assert_eq!(response.value.unwrap().value, Bytes::from_static(b"cached"));
+
+		// An unconditional write-through invalidates the entry, falling back to the durable
+		// store with the then-current version.
+		store.put(context.clone(), put_request("store-1", "k1", -1, b"newer")).await.unwrap();
+		let response = store.get(context.clone(), get_request).await.unwrap();
+		let value = response.value.unwrap();
+		assert_eq!(value.value, Bytes::from_static(b"newer"));
+		assert_eq!(value.version, 3);
+	}
+
+	#[tokio::test]
+	async fn stale_cache_entries_are_invalidated_not_served() {
+		let store = tiered();
+		let context = RequestContext::new("tiered_user".to_string());
+		// Simulate a cache surviving from before the durable store moved on.
+		store.fast.put(context.clone(), put_request("store-1", "k1", 0, b"stale")).await.unwrap();
+		store
+			.durable
+			.put(context.clone(), put_request("store-1", "k1", 0, b"old"))
+			.await
+			.unwrap();
+		store
+			.durable
+			.put(context.clone(), put_request("store-1", "k1", 1, b"current"))
+			.await
+			.unwrap();
+
+		// The next write-through conflicts in the cache and drops the stale entry.
+		store.put(context.clone(), put_request("store-1", "k1", 2, b"written")).await.unwrap();
+		let get_request =
+			GetObjectRequest { store_id: "store-1".to_string(), key: "k1".to_string() };
+		let response = store.get(context, get_request).await.unwrap();
+		let value = response.value.unwrap();
+		assert_eq!(value.value, Bytes::from_static(b"written"));
+		assert_eq!(value.version, 3);
+	}
+}
@@ -0,0 +1,684 @@
+//! The admin API, serving administrative operations which are not part of the client-facing VSS
+//! API.
+//!
+//! The admin API is only mounted if an `admin_api_config` is present in the server configuration
+//! and requires every request to carry the configured admin token as a bearer token. Responses
+//! are JSON, consumed by the `vss-admin` companion CLI.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use serde_json::json;
+use tracing::warn;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use api::kv_store::{KvStore, KvStoreAdmin, RequestContext};
+use api::types::{
+	DeleteObjectRequest, GetStoreStatsRequest, KeyValue, ListKeyVersionsRequest, PutObjectRequest,
+};
+
+pub const ADMIN_PATH_PREFIX: &str = "/admin";
+
+/// Server-side administrative state consulted by [`VssService`] on every request.
+///
+/// [`VssService`]: crate::vss_service::VssService
+#[derive(Default)]
+pub struct AdminState {
+	/// While set, all write operations are rejected with `503 Service Unavailable`.
+	pub maintenance_mode: AtomicBool,
+	/// Users which are rejected with `401 Unauthorized` on every operation.
+	pub suspended_users: RwLock<HashSet<String>>,
+	/// Stores (keyed by `user_token` and `store_id`) on which all delete operations are rejected
+	/// with `400 Bad Request` until protection is lifted, guarding critical records (e.g. channel
+	/// state) against buggy client cleanup code.
+	pub delete_protected_stores: RwLock<HashSet<(String, String)>>,
+	/// Stores (keyed by `user_token` and `store_id`) which are frozen read-only: all writes are
+	/// rejected with `400 Bad Request` until the store is unarchived, while reads keep working.
+	/// Operators keeping churned users' data for recovery can freeze it this way.
+	pub archived_stores: RwLock<HashSet<(String, String)>>,
+	/// Feature flags gating experimental behaviors, see [`FeatureFlags`].
+	pub features: FeatureFlags,
+	/// While set, the readiness endpoint reports `503 Service Unavailable` and answered requests
+	/// ask clients to close their connections, so load balancers rotate the instance out while
+	/// in-flight requests finish.
+	pub draining: AtomicBool,
+}
+
+/// Runtime-toggleable feature flags gating experimental behaviors, so operators can enable
+/// capabilities gradually per deployment instead of via compile-time cargo features only.
+///
+/// Defaults are overridden by the `features` config section at startup and adjustable at any
+/// time through the admin API. RPCs behind a disabled flag answer HTTP 404, as if unknown.
+pub struct FeatureFlags {
+	/// Whether the `renameObject` RPC is served.
+	pub rename_object: AtomicBool,
+	/// Whether the `grantStoreAccess`/`revokeStoreAccess` RPCs are served and the read-only
+	/// grant header is honored.
+	pub store_grants: AtomicBool,
+	/// Whether large list pages are streamed frame-by-frame instead of buffered in full.
+	pub streamed_list_responses: AtomicBool,
+}
+
+impl Default for FeatureFlags {
+	fn default() -> Self {
+		Self {
+			rename_object: AtomicBool::new(false),
+			store_grants: AtomicBool::new(false),
+			streamed_list_responses: AtomicBool::new(true),
+		}
+	}
+}
+
+impl FeatureFlags {
+	/// The flags by their config and admin API names.
+	fn entries(&self) -> [(&'static str, &AtomicBool); 3] {
+		[
+			("rename_object", &self.rename_object),
+			("store_grants", &self.store_grants),
+			("streamed_list_responses", &self.streamed_list_responses),
+		]
+	}
+
+	/// Overrides the defaults with the configured states, leaving unconfigured flags untouched.
+	pub fn apply(&self, config: &crate::config::FeaturesConfig) {
+		let configured = [
+			("rename_object", config.rename_object),
+			("store_grants", config.store_grants),
+			("streamed_list_responses", config.streamed_list_responses),
+		];
+		for (name, enabled) in configured {
+			if let Some(enabled) = enabled {
+				self.set(name, enabled).expect("configured names match");
+			}
+		}
+	}
+
+	/// Sets a flag by name, failing on unknown names.
+	pub fn set(&self, name: &str, enabled: bool) -> Result<(), String> {
+		for (flag_name, flag) in self.entries() {
+			if flag_name == name {
+				flag.store(enabled, Ordering::Release);
+				return Ok(());
+			}
+		}
+		Err(format!("Unknown feature flag: {}", name))
+	}
+}
+
+impl AdminState {
+	pub fn is_user_suspended(&self, user_token: &str) -> bool {
+		self.suspended_users.read().unwrap().contains(user_token)
+	}
+
+	pub fn is_store_delete_protected(&self, user_token: &str, store_id: &str) -> bool {
+		self.delete_protected_stores
+			.read()
+			.unwrap()
+			.contains(&(user_token.to_string(), store_id.to_string()))
+	}
+
+	pub fn is_store_archived(&self, user_token: &str, store_id: &str) -> bool {
+		self.archived_stores
+			.read()
+			.unwrap()
+			.contains(&(user_token.to_string(), store_id.to_string()))
+	}
+}
+
+/// A handle to the process-wide reloadable tracing filter, letting the admin API adjust the
+/// effective log level at runtime — e.g. bump `vss_server` to DEBUG during an incident —
+/// without restarting the server and losing in-flight connections.
+pub struct LogFilterHandle {
+	handle: reload::Handle<EnvFilter, Registry>,
+	default_directives: String,
+}
+
+impl LogFilterHandle {
+	pub fn new(handle: reload::Handle<EnvFilter, Registry>, default_directives: String) -> Self {
+		Self { handle, default_directives }
+	}
+
+	/// The filter directives the server started with.
+	pub fn default_directives(&self) -> &str {
+		&self.default_directives
+	}
+
+	/// Replaces the effective filter, e.g. `"info,vss_server=debug"`.
+	pub fn set(&self, directives: &str) -> Result<(), String> {
+		let filter = EnvFilter::try_new(directives)
+			.map_err(|e| format!("Invalid filter directives: {}", e))?;
+		self.handle.reload(filter).map_err(|e| format!("Failed to reload filter: {}", e))
+	}
+
+	/// Restores the filter the server started with.
+	pub fn reset(&self) -> Result<(), String> {
+		self.set(&self.default_directives)
+	}
+}
+
+pub struct AdminService {
+	admin_token: String,
+	state: Arc<AdminState>,
+	store: Arc<dyn KvStore>,
+	admin_store: Arc<dyn KvStoreAdmin>,
+	log_filter: Option<Arc<LogFilterHandle>>,
+	mutation_log_path: Option<String>,
+}
+
+impl AdminService {
+	pub fn new(
+		admin_token: String, state: Arc<AdminState>, store: Arc<dyn KvStore>,
+		admin_store: Arc<dyn KvStoreAdmin>,
+	) -> Self {
+		Self { admin_token, state, store, admin_store, log_filter: None, mutation_log_path: None }
+	}
+
+	pub fn with_log_filter(mut self, log_filter: Arc<LogFilterHandle>) -> Self {
+		self.log_filter = Some(log_filter);
+		self
+	}
+
+	pub fn with_mutation_log_path(mut self, path: String) -> Self {
+		self.mutation_log_path = Some(path);
+		self
+	}
+
+	pub async fn handle(
+		&self, request: Request<Incoming>,
+	) -> Result<Response<Full<Bytes>>, hyper::http::Error> {
+		let authorized = request
+			.headers()
+			.get(hyper::header::AUTHORIZATION)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("Bearer "))
+			.map(|token| token == self.admin_token)
+			.unwrap_or(false);
+		if !authorized {
+			return json_error_response(StatusCode::UNAUTHORIZED, "Invalid admin token.");
+		}
+
+		let method = request.method().clone();
+		let path = request.uri().path().trim_start_matches(ADMIN_PATH_PREFIX).to_owned();
+		let segments: Vec<&str> =
+			path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+		match (&method, segments.as_slice()) {
+			(&Method::GET, ["users", user_token, "stores"]) => {
+				match self.admin_store.list_store_ids(user_token.to_string()).await {
+					Ok(store_ids) => json_response(json!({ "store_ids": store_ids })),
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			(&Method::GET, ["users", user_token, "stores", store_id, "usage"]) => {
+				match self
+					.admin_store
+					.get_store_usage(user_token.to_string(), store_id.to_string())
+					.await
+				{
+					Ok(usage) => json_response(json!({
+						"key_count": usage.key_count,
+						"total_value_bytes": usage.total_value_bytes,
+					})),
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			(&Method::GET, ["users", user_token, "stores", store_id, "stats"]) => {
+				let request = GetStoreStatsRequest { store_id: store_id.to_string() };
+				let context = RequestContext::new(user_token.to_string());
+				match self.store.get_store_stats(context, request).await {
+					Ok(stats) => json_response(json!({
+						"key_count": stats.key_count,
+						"total_value_bytes": stats.total_value_bytes,
+						"largest_keys": stats
+							.largest_keys
+							.iter()
+							.map(|key_stat| {
+								json!({ "key": key_stat.key, "value_bytes": key_stat.value_bytes })
+							})
+							.collect::<Vec<_>>(),
+						"oldest_updated_at_millis": stats.oldest_updated_at_millis,
+						"newest_updated_at_millis": stats.newest_updated_at_millis,
+					})),
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			(&Method::POST, ["users", user_token, "stores", store_id, "protect"]) => {
+				self.state
+					.delete_protected_stores
+					.write()
+					.unwrap()
+					.insert((user_token.to_string(), store_id.to_string()));
+				json_response(json!({ "delete_protected": true }))
+			},
+			(&Method::POST, ["users", user_token, "stores", store_id, "unprotect"]) => {
+				self.state
+					.delete_protected_stores
+					.write()
+					.unwrap()
+					.remove(&(user_token.to_string(), store_id.to_string()));
+				json_response(json!({ "delete_protected": false }))
+			},
+			(&Method::POST, ["users", user_token, "stores", store_id, "archive"]) => {
+				self.state
+					.archived_stores
+					.write()
+					.unwrap()
+					.insert((user_token.to_string(), store_id.to_string()));
+				json_response(json!({ "archived": true }))
+			},
+			(&Method::POST, ["users", user_token, "stores", store_id, "unarchive"]) => {
+				self.state
+					.archived_stores
+					.write()
+					.unwrap()
+					.remove(&(user_token.to_string(), store_id.to_string()));
+				json_response(json!({ "archived": false }))
+			},
+			(&Method::POST, ["users", user_token, "suspend"]) => {
+				self.state.suspended_users.write().unwrap().insert(user_token.to_string());
+				json_response(json!({ "suspended": true }))
+			},
+			(&Method::POST, ["users", user_token, "unsuspend"]) => {
+				self.state.suspended_users.write().unwrap().remove(*user_token);
+				json_response(json!({ "suspended": false }))
+			},
+			(&Method::POST, ["maintenance"]) => {
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let enabled = match serde_json::from_slice::<serde_json::Value>(&body_bytes)
+					.ok()
+					.and_then(|body| body.get("enabled").and_then(|value| value.as_bool()))
+				{
+					Some(enabled) => enabled,
+					None => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Expected body: {\"enabled\": <bool>}",
+						)
+					},
+				};
+				self.state.maintenance_mode.store(enabled, Ordering::Release);
+				json_response(json!({ "maintenance_mode": enabled }))
+			},
+			(&Method::POST, ["logLevel"]) => {
+				let log_filter = match &self.log_filter {
+					Some(log_filter) => Arc::clone(log_filter),
+					None => {
+						return json_error_response(
+							StatusCode::NOT_FOUND,
+							"Runtime log level adjustment is not available.",
+						)
+					},
+				};
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let body = match serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+					Ok(body) => body,
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Expected body: {\"filter\": <directives>[, \"revert_after_secs\": <int>]} or {\"reset\": true}",
+						)
+					},
+				};
+				if body.get("reset").and_then(|value| value.as_bool()) == Some(true) {
+					return match log_filter.reset() {
+						Ok(()) => json_response(
+							json!({ "filter": log_filter.default_directives() }),
+						),
+						Err(e) => json_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+					};
+				}
+				let directives = match body.get("filter").and_then(|value| value.as_str()) {
+					Some(directives) => directives,
+					None => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Expected body: {\"filter\": <directives>[, \"revert_after_secs\": <int>]} or {\"reset\": true}",
+						)
+					},
+				};
+				if let Err(e) = log_filter.set(directives) {
+					return json_error_response(StatusCode::BAD_REQUEST, &e);
+				}
+				let revert_after_secs =
+					body.get("revert_after_secs").and_then(|value| value.as_u64());
+				if let Some(secs) = revert_after_secs {
+					tokio::spawn(async move {
+						tokio::time::sleep(Duration::from_secs(secs)).await;
+						if let Err(e) = log_filter.reset() {
+							warn!("Failed to revert log filter: {}", e);
+						}
+					});
+				}
+				json_response(
+					json!({ "filter": directives, "revert_after_secs": revert_after_secs }),
+				)
+			},
+			(&Method::POST, ["drain"]) => {
+				// An optional body may schedule a process exit, for load balancers or init
+				// systems which never stop probing a drained instance on their own.
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let exit_after_secs = if body_bytes.is_empty() {
+					None
+				} else {
+					match serde_json::from_slice::<serde_json::Value>(&body_bytes)
+						.ok()
+						.and_then(|body| {
+							body.get("exit_after_secs").and_then(|value| value.as_u64())
+						}) {
+						Some(secs) => Some(secs),
+						None => {
+							return json_error_response(
+								StatusCode::BAD_REQUEST,
+								"Expected body: {\"exit_after_secs\": <int>}",
+							)
+						},
+					}
+				};
+				self.state.draining.store(true, Ordering::Release);
+				warn!("Draining: readiness now reports 503, connections close after use.");
+				if let Some(secs) = exit_after_secs {
+					let state = Arc::clone(&self.state);
+					tokio::spawn(async move {
+						tokio::time::sleep(Duration::from_secs(secs)).await;
+						// An undrain in the meantime aborts the scheduled exit.
+						if state.draining.load(Ordering::Acquire) {
+							warn!("Drain timeout of {}s elapsed, exiting.", secs);
+							std::process::exit(0);
+						}
+					});
+				}
+				json_response(json!({ "draining": true, "exit_after_secs": exit_after_secs }))
+			},
+			(&Method::POST, ["undrain"]) => {
+				self.state.draining.store(false, Ordering::Release);
+				json_response(json!({ "draining": false }))
+			},
+			(&Method::GET, ["features"]) => json_response(self.features_json()),
+			(&Method::POST, ["features"]) => {
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let flags = match serde_json::from_slice::<serde_json::Value>(&body_bytes)
+					.ok()
+					.and_then(|body| body.as_object().cloned())
+				{
+					Some(flags) if !flags.is_empty() => flags,
+					_ => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Expected body: {\"<flag>\": <bool>, ...}",
+						)
+					},
+				};
+				for (name, value) in &flags {
+					let enabled = match value.as_bool() {
+						Some(enabled) => enabled,
+						None => {
+							return json_error_response(
+								StatusCode::BAD_REQUEST,
+								"Expected body: {\"<flag>\": <bool>, ...}",
+							)
+						},
+					};
+					if let Err(e) = self.state.features.set(name, enabled) {
+						return json_error_response(StatusCode::BAD_REQUEST, &e);
+					}
+				}
+				json_response(self.features_json())
+			},
+			(&Method::GET, ["poolStatus"]) => {
+				match self.admin_store.get_pool_status().await {
+					Ok(Some(status)) => json_response(json!({
+						"connections": status.connections,
+						"idle_connections": status.idle_connections,
+						"pending_checkouts": status.pending_checkouts,
+						"checkouts_direct": status.checkouts_direct,
+						"checkouts_waited": status.checkouts_waited,
+						"checkouts_timed_out": status.checkouts_timed_out,
+						"checkout_wait_time_micros": status.checkout_wait_time_micros,
+						"connections_created": status.connections_created,
+					})),
+					Ok(None) => json_error_response(
+						StatusCode::NOT_FOUND,
+						"The configured backend has no connection pool.",
+					),
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			(&Method::POST, ["users", user_token, "backup"]) => {
+				// An optional body may supply a key to seal the dump with, so the artifact
+				// doesn't rely on filesystem permissions alone once written to disk.
+				let user_token = user_token.to_string();
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let encryption_key = if body_bytes.is_empty() {
+					None
+				} else {
+					let key_hex = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+						.ok()
+						.and_then(|body| {
+							body.get("encryption_key")
+								.and_then(|value| value.as_str())
+								.map(str::to_string)
+						});
+					match key_hex.as_deref().map(crate::backup::parse_backup_key) {
+						Some(Ok(key)) => Some(key),
+						Some(Err(e)) => return json_error_response(StatusCode::BAD_REQUEST, &e),
+						None => {
+							return json_error_response(
+								StatusCode::BAD_REQUEST,
+								"Expected body: {\"encryption_key\": <64 hex characters>}",
+							)
+						},
+					}
+				};
+				match self.backup_user(&user_token).await {
+					Ok(backup) => match encryption_key {
+						Some(key) => json_response(crate::backup::encrypt_backup(
+							&key,
+							backup.to_string().as_bytes(),
+						)),
+						None => json_response(backup),
+					},
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			(&Method::POST, ["users", user_token, "stores", store_id, "restore"]) => {
+				let user_token = user_token.to_string();
+				let store_id = store_id.to_string();
+				let mutation_log_path = match &self.mutation_log_path {
+					Some(path) => path.clone(),
+					None => {
+						return json_error_response(
+							StatusCode::NOT_FOUND,
+							"Point-in-time restore requires mutation_log_config.",
+						)
+					},
+				};
+				let body_bytes = match request.into_body().collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(_) => {
+						return json_error_response(
+							StatusCode::BAD_REQUEST,
+							"Failed to read request body.",
+						)
+					},
+				};
+				let timestamp_millis =
+					match serde_json::from_slice::<serde_json::Value>(&body_bytes)
+						.ok()
+						.and_then(|body| {
+							body.get("timestamp_millis").and_then(|value| value.as_u64())
+						}) {
+						Some(timestamp_millis) => timestamp_millis,
+						None => {
+							return json_error_response(
+								StatusCode::BAD_REQUEST,
+								"Expected body: {\"timestamp_millis\": <int>}",
+							)
+						},
+					};
+				let target = match crate::mutation_log::store_state_at(
+					&mutation_log_path,
+					&user_token,
+					&store_id,
+					timestamp_millis,
+				) {
+					Ok(target) => target,
+					Err(e) => return json_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+				};
+				match self.restore_store(&user_token, &store_id, target).await {
+					Ok((restored_keys, deleted_keys)) => json_response(json!({
+						"store_id": store_id,
+						"timestamp_millis": timestamp_millis,
+						"restored_keys": restored_keys,
+						"deleted_keys": deleted_keys,
+					})),
+					Err(e) => internal_error_response(&e),
+				}
+			},
+			_ => json_error_response(StatusCode::NOT_FOUND, "Unknown admin endpoint."),
+		}
+	}
+
+	/// The current states of all feature flags.
+	fn features_json(&self) -> serde_json::Value {
+		let mut flags = serde_json::Map::new();
+		for (name, flag) in self.state.features.entries() {
+			flags.insert(name.to_string(), json!(flag.load(Ordering::Acquire)));
+		}
+		serde_json::Value::Object(flags)
+	}
+
+	/// Dumps all stores of the given user into a single JSON document, so operators can kick off
+	/// an ad-hoc backup through the CLI.
+	async fn backup_user(&self, user_token: &str) -> Result<serde_json::Value, api::error::VssError> {
+		crate::backup::dump_user(&self.store, &self.admin_store, user_token).await
+	}
+
+	/// Rewrites a single store to the given target contents, deleting keys not in the target and
+	/// overwriting the rest, without touching any other store or user.
+	///
+	/// The writes go through the regular [`KvStore`], so they are version-checked against the
+	/// server-assigned counters (not the versions at the restore point), logged and replicated
+	/// like any other mutation. Clients of the store must resync after a restore.
+	async fn restore_store(
+		&self, user_token: &str, store_id: &str,
+		target: std::collections::HashMap<String, Vec<u8>>,
+	) -> Result<(usize, usize), api::error::VssError> {
+		let mut deleted_keys = 0;
+		let mut page_token: Option<String> = None;
+		loop {
+			let request = ListKeyVersionsRequest {
+				store_id: store_id.to_string(),
+				key_prefix: None,
+				page_size: None,
+				page_token: page_token.clone(),
+			};
+			let response = self
+				.store
+				.list_key_versions(RequestContext::new(user_token.to_string()), request)
+				.await?;
+			for key_version in &response.key_versions {
+				if target.contains_key(&key_version.key) {
+					continue;
+				}
+				let delete_request = DeleteObjectRequest {
+					store_id: store_id.to_string(),
+					key_value: Some(KeyValue {
+						key: key_version.key.clone(),
+						version: -1,
+						value: Default::default(),
+					}),
+				};
+				self.store
+					.delete(RequestContext::new(user_token.to_string()), delete_request)
+					.await?;
+				deleted_keys += 1;
+			}
+			match response.next_page_token {
+				Some(token) if !token.is_empty() => page_token = Some(token),
+				_ => break,
+			}
+		}
+		let restored_keys = target.len();
+		for (key, value) in target {
+			let put_request = PutObjectRequest {
+				store_id: store_id.to_string(),
+				global_version: None,
+				transaction_items: vec![KeyValue { key, version: -1, value: value.into() }],
+				delete_items: vec![],
+				dry_run: false,
+			};
+			self.store.put(RequestContext::new(user_token.to_string()), put_request).await?;
+		}
+		Ok((restored_keys, deleted_keys))
+	}
+}
+
+fn json_response(
+	body: serde_json::Value,
+) -> Result<Response<Full<Bytes>>, hyper::http::Error> {
+	Response::builder()
+		.status(StatusCode::OK)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(Full::new(Bytes::from(body.to_string())))
+}
+
+fn json_error_response(
+	status_code: StatusCode, message: &str,
+) -> Result<Response<Full<Bytes>>, hyper::http::Error> {
+	Response::builder()
+		.status(status_code)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(Full::new(Bytes::from(json!({ "error": message }).to_string())))
+}
+
+fn internal_error_response(
+	error: &api::error::VssError,
+) -> Result<Response<Full<Bytes>>, hyper::http::Error> {
+	json_error_response(StatusCode::INTERNAL_SERVER_ERROR, &error.to_string())
+}
@@ -0,0 +1,228 @@
+//! Opt-in in-process error-rate alerting, see [`AlertMonitor`].
+//!
+//! Small deployments often run without a full monitoring stack. The monitor keeps a sliding
+//! window of response statuses and, whenever a configured rate threshold is breached, logs the
+//! breach, reports it to Sentry (if Sentry is configured) and posts a JSON notification to a
+//! webhook. Repeat alerts for the same metric are suppressed for a cooldown, so a sustained
+//! breach does not flood the channels.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{Method, Request, StatusCode};
+use serde_json::json;
+use tracing::warn;
+
+use crate::capture::now_millis;
+use crate::config::AlertConfig;
+
+/// The default minimum number of requests in the window before rates are evaluated.
+pub const DEFAULT_ALERT_MIN_REQUESTS: u64 = 20;
+
+/// The response status of one finished request, reduced to the alerted-on categories.
+struct Sample {
+	ts_millis: u64,
+	server_error: bool,
+	conflict: bool,
+	auth_failure: bool,
+}
+
+/// A breached threshold, ready for emission.
+struct Alert {
+	metric: &'static str,
+	rate: f64,
+	threshold: f64,
+	requests: usize,
+}
+
+struct Inner {
+	samples: VecDeque<Sample>,
+	last_alert_millis: HashMap<&'static str, u64>,
+}
+
+/// Evaluates response outcomes against the configured rate thresholds, see [`AlertConfig`].
+pub struct AlertMonitor {
+	config: AlertConfig,
+	inner: Mutex<Inner>,
+}
+
+impl AlertMonitor {
+	pub fn new(config: AlertConfig) -> Self {
+		let inner = Inner { samples: VecDeque::new(), last_alert_millis: HashMap::new() };
+		Self { config, inner: Mutex::new(inner) }
+	}
+
+	/// Records the response status of a finished request and emits alerts for any threshold
+	/// breached over the current window. Never fails the request: emission failures are logged.
+	pub fn observe(&self, status: StatusCode) {
+		for alert in self.observe_at(status, now_millis()) {
+			self.emit(alert);
+		}
+	}
+
+	fn observe_at(&self, status: StatusCode, now_millis: u64) -> Vec<Alert> {
+		let mut inner = self.inner.lock().unwrap();
+		inner.samples.push_back(Sample {
+			ts_millis: now_millis,
+			server_error: status.is_server_error(),
+			// Conditional requests surface version conflicts as 412, see `handle_request`.
+			conflict: status == StatusCode::CONFLICT || status == StatusCode::PRECONDITION_FAILED,
+			auth_failure: status == StatusCode::UNAUTHORIZED,
+		});
+		let window_millis = self.config.window_secs * 1000;
+		let cutoff = now_millis.saturating_sub(window_millis);
+		while inner.samples.front().map(|sample| sample.ts_millis < cutoff).unwrap_or(false) {
+			inner.samples.pop_front();
+		}
+		let requests = inner.samples.len();
+		let min_requests = self.config.min_requests.unwrap_or(DEFAULT_ALERT_MIN_REQUESTS);
+		if (requests as u64) < min_requests {
+			return Vec::new();
+		}
+
+		let mut server_errors = 0;
+		let mut conflicts = 0;
+		let mut auth_failures = 0;
+		for sample in &inner.samples {
+			server_errors += sample.server_error as usize;
+			conflicts += sample.conflict as usize;
+			auth_failures += sample.auth_failure as usize;
+		}
+		let checks = [
+			("server_error_rate", self.config.server_error_rate_threshold, server_errors),
+			("conflict_rate", self.config.conflict_rate_threshold, conflicts),
+			("auth_failure_rate", self.config.auth_failure_rate_threshold, auth_failures),
+		];
+		let cooldown_millis = self.config.cooldown_secs.unwrap_or(self.config.window_secs) * 1000;
+		let mut alerts = Vec::new();
+		for (metric, threshold, count) in checks {
+			let threshold = match threshold {
+				Some(threshold) => threshold,
+				None => continue,
+			};
+			let rate = count as f64 / requests as f64;
+			if rate < threshold {
+				continue;
+			}
+			let in_cooldown = inner
+				.last_alert_millis
+				.get(metric)
+				.map(|last| now_millis < last + cooldown_millis)
+				.unwrap_or(false);
+			if in_cooldown {
+				continue;
+			}
+			inner.last_alert_millis.insert(metric, now_millis);
+			alerts.push(Alert { metric, rate, threshold, requests });
+		}
+		alerts
+	}
+
+	fn emit(&self, alert: Alert) {
+		let message = format!(
+			"Alert: {} at {:.1}% over the last {}s ({} requests), threshold {:.1}%.",
+			alert.metric,
+			alert.rate * 100.0,
+			self.config.window_secs,
+			alert.requests,
+			alert.threshold * 100.0,
+		);
+		warn!("{}", message);
+		// A no-op unless Sentry reporting is configured.
+		sentry::capture_message(&message, sentry::Level::Error);
+		if let Some(webhook_url) = &self.config.webhook_url {
+			let webhook_url = webhook_url.clone();
+			let body = json!({
+				"alert": alert.metric,
+				"rate": alert.rate,
+				"threshold": alert.threshold,
+				"window_secs": self.config.window_secs,
+				"requests": alert.requests,
+				"message": message,
+			});
+			tokio::spawn(async move {
+				let request = Request::builder()
+					.method(Method::POST)
+					.uri(&webhook_url)
+					.header(hyper::header::CONTENT_TYPE, "application/json")
+					.body(Full::new(Bytes::from(body.to_string())))
+					.expect("valid request");
+				if let Err(e) = crate::secrets::execute_request(request).await {
+					warn!("Failed to deliver alert webhook: {}", e);
+				}
+			});
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config() -> AlertConfig {
+		AlertConfig {
+			window_secs: 60,
+			min_requests: Some(10),
+			cooldown_secs: Some(120),
+			server_error_rate_threshold: Some(0.2),
+			conflict_rate_threshold: None,
+			auth_failure_rate_threshold: Some(0.5),
+			webhook_url: None,
+		}
+	}
+
+	#[test]
+	fn thresholds_alert_once_per_cooldown() {
+		let monitor = AlertMonitor::new(config());
+		// Below min_requests nothing alerts, however bad the rate.
+		for _ in 0..9 {
+			assert!(monitor.observe_at(StatusCode::INTERNAL_SERVER_ERROR, 1_000).is_empty());
+		}
+		// The tenth sample crosses min_requests at a 100% 5xx rate.
+		let alerts = monitor.observe_at(StatusCode::INTERNAL_SERVER_ERROR, 1_000);
+		assert_eq!(alerts.len(), 1);
+		assert_eq!(alerts[0].metric, "server_error_rate");
+		assert_eq!(alerts[0].requests, 10);
+		// Still breached, but within the cooldown.
+		assert!(monitor.observe_at(StatusCode::INTERNAL_SERVER_ERROR, 61_000).is_empty());
+		// Past the cooldown a sustained breach alerts again, exactly once.
+		let mut alerts = Vec::new();
+		for _ in 0..10 {
+			alerts.extend(monitor.observe_at(StatusCode::INTERNAL_SERVER_ERROR, 121_001));
+		}
+		assert_eq!(alerts.len(), 1);
+	}
+
+	#[test]
+	fn healthy_traffic_clears_the_window() {
+		let monitor = AlertMonitor::new(config());
+		for _ in 0..10 {
+			monitor.observe_at(StatusCode::UNAUTHORIZED, 1_000);
+		}
+		// An hour later the failures have left the window; fresh successes stay quiet even
+		// though the all-time failure rate would still breach.
+		for _ in 0..9 {
+			assert!(monitor.observe_at(StatusCode::OK, 3_600_000).is_empty());
+		}
+		let alerts = monitor.observe_at(StatusCode::UNAUTHORIZED, 3_600_000);
+		assert!(alerts.is_empty());
+	}
+
+	#[test]
+	fn metrics_are_evaluated_independently() {
+		let monitor = AlertMonitor::new(config());
+		// Five conflicts and five auth failures: conflicts have no threshold configured, the
+		// auth-failure rate sits exactly at its 50% threshold.
+		for _ in 0..5 {
+			monitor.observe_at(StatusCode::CONFLICT, 1_000);
+		}
+		for _ in 0..4 {
+			assert!(monitor.observe_at(StatusCode::UNAUTHORIZED, 1_000).is_empty());
+		}
+		let alerts = monitor.observe_at(StatusCode::UNAUTHORIZED, 1_000);
+		assert_eq!(alerts.len(), 1);
+		assert_eq!(alerts[0].metric, "auth_failure_rate");
+	}
+}
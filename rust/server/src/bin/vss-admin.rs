@@ -0,0 +1,213 @@
+//! A companion CLI for the VSS server's admin API, covering routine operational tasks (store
+//! inspection, usage checks, user suspension, maintenance mode and ad-hoc backups) without
+//! requiring direct database access.
+
+use std::process::exit;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request, StatusCode};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+
+const USAGE: &str = "Usage: vss-admin <server_url> <admin_token> <command> [args]
+
+Commands:
+  list-stores <user_token>             List the ids of all stores written by the user.
+  usage <user_token> <store_id>        Show key count and total value size of a store.
+  stats <user_token> <store_id>        Show keyspace statistics of a store (key count, total
+                                       bytes, largest keys, oldest/newest update timestamps).
+  protect <user_token> <store_id>      Refuse all deletes on the store until unprotected.
+  unprotect <user_token> <store_id>    Lift a store's deletion protection.
+  archive <user_token> <store_id>      Freeze the store read-only until unarchived.
+  unarchive <user_token> <store_id>    Lift a store's archival, making it writable again.
+  suspend <user_token>                 Reject all requests of the user.
+  unsuspend <user_token>               Lift a previous suspension.
+  maintenance <on|off>                 Toggle maintenance mode (rejects all writes).
+  drain [exit_secs]                    Flip the instance to draining: readiness reports 503 and
+                                       connections close after their current request, optionally
+                                       exiting the process after the given number of seconds.
+  undrain                              Return a draining instance to service (and abort a
+                                       scheduled exit).
+  pool-status                          Show backend connection-pool statistics.
+  features                             List the feature flags and their current states.
+  feature <name> <on|off>              Toggle a feature flag at runtime.
+  backup <user_token> [key_hex]        Dump all stores of the user as JSON to stdout. With a
+                                       256-bit hex key the dump is emitted as an encrypted
+                                       envelope instead of plaintext.
+  restore <user_token> <store_id> <timestamp_millis>
+                                       Roll a single store back to its contents at the given
+                                       Unix timestamp, reconstructed from the mutation log.
+                                       Other stores and users are untouched; clients of the
+                                       store must resync afterwards.
+  log-level <filter> [revert_secs]     Set the tracing filter (e.g. \"info,vss_server=debug\"),
+                                       optionally reverting after the given number of seconds.
+  log-level reset                      Restore the filter the server started with.";
+
+fn usage_error(message: &str) -> ! {
+	eprintln!("{}\n\n{}", message, USAGE);
+	exit(2);
+}
+
+#[tokio::main]
+async fn main() {
+	let args: Vec<String> = std::env::args().collect();
+	if args.len() < 4 {
+		usage_error("Missing arguments.");
+	}
+	let server_url = args[1].trim_end_matches('/');
+	let admin_token = &args[2];
+	let command = args[3].as_str();
+	let command_args = &args[4..];
+
+	let (method, path, body) = match (command, command_args) {
+		("list-stores", [user_token]) => {
+			(Method::GET, format!("/admin/users/{}/stores", user_token), None)
+		},
+		("usage", [user_token, store_id]) => {
+			(Method::GET, format!("/admin/users/{}/stores/{}/usage", user_token, store_id), None)
+		},
+		("stats", [user_token, store_id]) => {
+			(Method::GET, format!("/admin/users/{}/stores/{}/stats", user_token, store_id), None)
+		},
+		("protect", [user_token, store_id]) => (
+			Method::POST,
+			format!("/admin/users/{}/stores/{}/protect", user_token, store_id),
+			None,
+		),
+		("unprotect", [user_token, store_id]) => (
+			Method::POST,
+			format!("/admin/users/{}/stores/{}/unprotect", user_token, store_id),
+			None,
+		),
+		("archive", [user_token, store_id]) => (
+			Method::POST,
+			format!("/admin/users/{}/stores/{}/archive", user_token, store_id),
+			None,
+		),
+		("unarchive", [user_token, store_id]) => (
+			Method::POST,
+			format!("/admin/users/{}/stores/{}/unarchive", user_token, store_id),
+			None,
+		),
+		("suspend", [user_token]) => {
+			(Method::POST, format!("/admin/users/{}/suspend", user_token), None)
+		},
+		("unsuspend", [user_token]) => {
+			(Method::POST, format!("/admin/users/{}/unsuspend", user_token), None)
+		},
+		("maintenance", [state]) => {
+			let enabled = match state.as_str() {
+				"on" => true,
+				"off" => false,
+				_ => usage_error("Maintenance state must be 'on' or 'off'."),
+			};
+			(
+				Method::POST,
+				"/admin/maintenance".to_string(),
+				Some(format!("{{\"enabled\": {}}}", enabled)),
+			)
+		},
+		("drain", []) => (Method::POST, "/admin/drain".to_string(), None),
+		("drain", [exit_secs]) => {
+			let exit_after_secs = exit_secs
+				.parse::<u64>()
+				.unwrap_or_else(|_| usage_error("exit_secs must be a number of seconds."));
+			(
+				Method::POST,
+				"/admin/drain".to_string(),
+				Some(serde_json::json!({ "exit_after_secs": exit_after_secs }).to_string()),
+			)
+		},
+		("undrain", []) => (Method::POST, "/admin/undrain".to_string(), None),
+		("pool-status", []) => (Method::GET, "/admin/poolStatus".to_string(), None),
+		("features", []) => (Method::GET, "/admin/features".to_string(), None),
+		("feature", [name, state]) => {
+			let enabled = match state.as_str() {
+				"on" => true,
+				"off" => false,
+				_ => usage_error("Feature state must be 'on' or 'off'."),
+			};
+			let mut body = serde_json::Map::new();
+			body.insert(name.to_string(), serde_json::Value::Bool(enabled));
+			(
+				Method::POST,
+				"/admin/features".to_string(),
+				Some(serde_json::Value::Object(body).to_string()),
+			)
+		},
+		("log-level", [filter]) if filter == "reset" => (
+			Method::POST,
+			"/admin/logLevel".to_string(),
+			Some(serde_json::json!({ "reset": true }).to_string()),
+		),
+		("log-level", [filter]) => (
+			Method::POST,
+			"/admin/logLevel".to_string(),
+			Some(serde_json::json!({ "filter": filter }).to_string()),
+		),
+		("log-level", [filter, revert_secs]) => {
+			let revert_after_secs = revert_secs
+				.parse::<u64>()
+				.unwrap_or_else(|_| usage_error("revert_secs must be a number of seconds."));
+			(
+				Method::POST,
+				"/admin/logLevel".to_string(),
+				Some(
+					serde_json::json!({ "filter": filter, "revert_after_secs": revert_after_secs })
+						.to_string(),
+				),
+			)
+		},
+		("backup", [user_token]) => {
+			(Method::POST, format!("/admin/users/{}/backup", user_token), None)
+		},
+		("backup", [user_token, key_hex]) => (
+			Method::POST,
+			format!("/admin/users/{}/backup", user_token),
+			Some(serde_json::json!({ "encryption_key": key_hex }).to_string()),
+		),
+		("restore", [user_token, store_id, timestamp_millis]) => {
+			let timestamp_millis = timestamp_millis.parse::<u64>().unwrap_or_else(|_| {
+				usage_error("timestamp_millis must be a Unix timestamp in milliseconds.")
+			});
+			(
+				Method::POST,
+				format!("/admin/users/{}/stores/{}/restore", user_token, store_id),
+				Some(serde_json::json!({ "timestamp_millis": timestamp_millis }).to_string()),
+			)
+		},
+		_ => usage_error("Unknown command or wrong number of arguments."),
+	};
+
+	let request = Request::builder()
+		.method(method)
+		.uri(format!("{}{}", server_url, path))
+		.header(hyper::header::AUTHORIZATION, format!("Bearer {}", admin_token))
+		.body(Full::new(Bytes::from(body.unwrap_or_default())))
+		.unwrap();
+
+	let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+	let response = match client.request(request).await {
+		Ok(response) => response,
+		Err(e) => {
+			eprintln!("Failed to reach server at {}: {}", server_url, e);
+			exit(1);
+		},
+	};
+
+	let status = response.status();
+	let body_bytes = match response.into_body().collect().await {
+		Ok(body) => body.to_bytes(),
+		Err(e) => {
+			eprintln!("Failed to read response body: {}", e);
+			exit(1);
+		},
+	};
+	let body_str = String::from_utf8_lossy(&body_bytes);
+	if status != StatusCode::OK {
+		eprintln!("Request failed with status {}: {}", status, body_str);
+		exit(1);
+	}
+	println!("{}", body_str);
+}
@@ -0,0 +1,257 @@
+//! A small load-generation tool replaying realistic wallet traffic against a running VSS server,
+//! used as a reference workload when sizing instances.
+//!
+//! Each simulated wallet starts with a startup storm (a full `listKeyVersions` walk followed by a
+//! `getObject` per key), then issues bursts of conditional puts building per-key version chains.
+//! Two "devices" share each wallet's store and race conditional writes on the same keys, so a
+//! realistic share of requests fails with `CONFLICT`. Latency percentiles are reported per
+//! operation kind, alongside conflict and error rates.
+
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request, StatusCode};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use prost::Message;
+
+use api::types::{
+	GetObjectRequest, GetObjectResponse, KeyValue, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest,
+};
+
+const USAGE: &str = "Usage: vss-load-test <server_url> [--users <n>] [--duration-secs <n>]
+
+Replays wallet traffic (startup list+get storms, bursty conditional puts with version chains)
+against a running VSS server and reports latency percentiles and conflict rates.
+
+Options:
+  --users <n>           The number of simulated wallets (default: 10).
+  --duration-secs <n>   How long to generate write traffic for (default: 30).";
+
+/// The number of keys in each simulated wallet's store, mirroring a wallet with a handful of
+/// channel monitors plus manager state.
+const KEYS_PER_USER: usize = 20;
+
+/// The number of conditional puts issued per burst.
+const BURST_SIZE: usize = 10;
+
+const PAUSE_BETWEEN_BURSTS: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct Metrics {
+	latencies_micros: Vec<u64>,
+	conflicts: u64,
+	errors: u64,
+}
+
+#[derive(Default)]
+struct Report {
+	startup_list: Mutex<Metrics>,
+	startup_get: Mutex<Metrics>,
+	put: Mutex<Metrics>,
+}
+
+fn usage_error(message: &str) -> ! {
+	eprintln!("{}\n\n{}", message, USAGE);
+	exit(2);
+}
+
+fn parse_flag(args: &[String], flag: &str, default: u64) -> u64 {
+	match args.iter().position(|arg| arg == flag) {
+		Some(position) => match args.get(position + 1).and_then(|value| value.parse().ok()) {
+			Some(value) => value,
+			None => usage_error(&format!("{} requires a numeric argument.", flag)),
+		},
+		None => default,
+	}
+}
+
+#[tokio::main]
+async fn main() {
+	let args: Vec<String> = std::env::args().collect();
+	if args.len() < 2 || args[1].starts_with("--") {
+		usage_error("Missing server URL.");
+	}
+	let server_url = args[1].trim_end_matches('/').to_string();
+	let users = parse_flag(&args, "--users", 10);
+	let duration = Duration::from_secs(parse_flag(&args, "--duration-secs", 30));
+
+	let report = Arc::new(Report::default());
+	let deadline = Instant::now() + duration;
+
+	println!("Running against {} with {} users for {:?}...", server_url, users, duration);
+	let mut tasks = Vec::new();
+	for user in 0..users {
+		// Two devices share each wallet and race conditional writes on the same keys.
+		for device in 0..2 {
+			let server_url = server_url.clone();
+			let report = Arc::clone(&report);
+			tasks.push(tokio::spawn(async move {
+				run_wallet(&server_url, user, device, deadline, &report).await;
+			}));
+		}
+	}
+	for task in tasks {
+		let _ = task.await;
+	}
+
+	print_metrics("startup list", &report.startup_list.lock().unwrap());
+	print_metrics("startup get", &report.startup_get.lock().unwrap());
+	print_metrics("put", &report.put.lock().unwrap());
+}
+
+async fn run_wallet(
+	server_url: &str, user: u64, device: u64, deadline: Instant, report: &Report,
+) {
+	let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+	let store_id = format!("load-test-{}", user);
+
+	// Startup storm: walk all key versions, then fetch every key, mirroring a wallet reloading
+	// its full state on launch.
+	let mut keys = Vec::new();
+	let mut page_token: Option<String> = None;
+	loop {
+		let request = ListKeyVersionsRequest {
+			store_id: store_id.clone(),
+			key_prefix: None,
+			page_size: None,
+			page_token: page_token.clone(),
+		};
+		let started_at = Instant::now();
+		let response: Option<ListKeyVersionsResponse> = execute(
+			&client,
+			&format!("{}/vss/listKeyVersions", server_url),
+			request.encode_to_vec(),
+			&report.startup_list,
+			started_at,
+		)
+		.await;
+		let response = match response {
+			Some(response) => response,
+			None => break,
+		};
+		keys.extend(response.key_versions.into_iter().map(|kv| kv.key));
+		match response.next_page_token {
+			Some(token) if !token.is_empty() => page_token = Some(token),
+			_ => break,
+		}
+	}
+	for key in &keys {
+		let request = GetObjectRequest { store_id: store_id.clone(), key: key.clone() };
+		let started_at = Instant::now();
+		let _: Option<GetObjectResponse> = execute(
+			&client,
+			&format!("{}/vss/getObject", server_url),
+			request.encode_to_vec(),
+			&report.startup_get,
+			started_at,
+		)
+		.await;
+	}
+
+	// Steady state: bursts of conditional puts building version chains. The locally tracked
+	// versions go stale whenever the other device wins a race, producing realistic conflicts.
+	let mut versions = [0i64; KEYS_PER_USER];
+	let mut next_key = device as usize;
+	while Instant::now() < deadline {
+		for _ in 0..BURST_SIZE {
+			let key_idx = next_key % KEYS_PER_USER;
+			next_key += 1;
+			let request = PutObjectRequest {
+				store_id: store_id.clone(),
+				global_version: None,
+				transaction_items: vec![KeyValue {
+					key: format!("channel-monitor-{}", key_idx),
+					version: versions[key_idx],
+					value: vec![0u8; 1024].into(),
+				}],
+				delete_items: vec![],
+				dry_run: false,
+			};
+			let started_at = Instant::now();
+			let succeeded: Option<api::types::PutObjectResponse> = execute(
+				&client,
+				&format!("{}/vss/putObjects", server_url),
+				request.encode_to_vec(),
+				&report.put,
+				started_at,
+			)
+			.await;
+			if succeeded.is_some() {
+				versions[key_idx] += 1;
+			} else {
+				// Refresh the version on the next attempt after a lost race.
+				versions[key_idx] = -1;
+			}
+		}
+		tokio::time::sleep(PAUSE_BETWEEN_BURSTS).await;
+	}
+}
+
+/// Issues a single request, records its latency and outcome, and returns the decoded response
+/// on success.
+async fn execute<R: Message + Default>(
+	client: &Client<hyper_util::client::legacy::connect::HttpConnector, Full<Bytes>>, uri: &str,
+	body: Vec<u8>, metrics: &Mutex<Metrics>, started_at: Instant,
+) -> Option<R> {
+	let request = Request::builder()
+		.method(Method::POST)
+		.uri(uri)
+		.body(Full::new(Bytes::from(body)))
+		.unwrap();
+	let response = client.request(request).await;
+	let elapsed_micros = started_at.elapsed().as_micros() as u64;
+
+	let response = {
+		let mut metrics = metrics.lock().unwrap();
+		metrics.latencies_micros.push(elapsed_micros);
+		let response = match response {
+			Ok(response) => response,
+			Err(_) => {
+				metrics.errors += 1;
+				return None;
+			},
+		};
+		match response.status() {
+			StatusCode::OK => response,
+			StatusCode::CONFLICT => {
+				metrics.conflicts += 1;
+				return None;
+			},
+			StatusCode::NOT_FOUND => return None,
+			_ => {
+				metrics.errors += 1;
+				return None;
+			},
+		}
+	};
+
+	let body_bytes = response.into_body().collect().await.ok()?.to_bytes();
+	R::decode(body_bytes).ok()
+}
+
+fn print_metrics(label: &str, metrics: &Metrics) {
+	let mut latencies = metrics.latencies_micros.clone();
+	if latencies.is_empty() {
+		println!("{:>14}: no requests issued", label);
+		return;
+	}
+	latencies.sort_unstable();
+	let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p) as usize];
+	println!(
+		"{:>14}: {} requests, p50 {}us, p90 {}us, p99 {}us, max {}us, conflicts {} ({:.2}%), errors {}",
+		label,
+		latencies.len(),
+		percentile(0.50),
+		percentile(0.90),
+		percentile(0.99),
+		latencies[latencies.len() - 1],
+		metrics.conflicts,
+		metrics.conflicts as f64 * 100.0 / latencies.len() as f64,
+		metrics.errors,
+	);
+}
@@ -0,0 +1,165 @@
+//! Re-issues a captured traffic shape (see the server's `capture_config`) against another server
+//! instance, for debugging and for validating backend migrations against real traffic shapes.
+//!
+//! Captured records carry only operation, sizes and anonymized store ids, hence replayed
+//! requests use synthetic keys and values of the recorded sizes. Puts are replayed
+//! unconditionally (the original version chains are not recorded), so recorded conflict
+//! responses are expected to come back as successes.
+
+use std::fs;
+use std::process::exit;
+use std::time::Instant;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use prost::Message;
+
+use api::types::{
+	DeleteObjectRequest, GetObjectRequest, KeyValue, ListKeyVersionsRequest, PutObjectRequest,
+};
+use vss_server::capture::CaptureEntry;
+
+const USAGE: &str = "Usage: vss-replay <capture_file> <server_url>
+
+Re-issues the traffic shape recorded in a capture file (see the server's capture_config) against
+the given server instance and reports per-operation counts and status mismatches.";
+
+#[tokio::main]
+async fn main() {
+	let args: Vec<String> = std::env::args().collect();
+	if args.len() != 3 {
+		eprintln!("{}", USAGE);
+		exit(2);
+	}
+	let capture_contents = fs::read_to_string(&args[1]).unwrap_or_else(|e| {
+		eprintln!("Failed to read capture file {}: {}", args[1], e);
+		exit(1);
+	});
+	let server_url = args[2].trim_end_matches('/');
+
+	let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+	let mut replayed = 0u64;
+	let mut errors = 0u64;
+	let mut status_mismatches = 0u64;
+	let started_at = Instant::now();
+
+	for (line_number, line) in capture_contents.lines().enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let entry: CaptureEntry = match serde_json::from_str(line) {
+			Ok(entry) => entry,
+			Err(e) => {
+				eprintln!("Skipping malformed record on line {}: {}", line_number + 1, e);
+				continue;
+			},
+		};
+
+		let (path, body) = match build_request(&entry) {
+			Some(request) => request,
+			None => {
+				eprintln!(
+					"Skipping unknown operation {:?} on line {}.",
+					entry.operation,
+					line_number + 1
+				);
+				continue;
+			},
+		};
+		let request = Request::builder()
+			.method(Method::POST)
+			.uri(format!("{}/vss/{}", server_url, path))
+			.body(Full::new(Bytes::from(body)))
+			.unwrap();
+		match client.request(request).await {
+			Ok(response) => {
+				replayed += 1;
+				let status = response.status();
+				let _ = response.into_body().collect().await;
+				if status.as_u16() != replayed_status_expectation(&entry) {
+					status_mismatches += 1;
+				}
+			},
+			Err(e) => {
+				errors += 1;
+				eprintln!("Request on line {} failed: {}", line_number + 1, e);
+			},
+		}
+	}
+
+	println!(
+		"Replayed {} requests in {:?} ({} transport errors, {} status mismatches vs. capture).",
+		replayed,
+		started_at.elapsed(),
+		errors,
+		status_mismatches
+	);
+}
+
+/// Builds the request path and protobuf body matching a captured record's shape.
+fn build_request(entry: &CaptureEntry) -> Option<(&'static str, Vec<u8>)> {
+	let store_id = format!("replay-{}", entry.store);
+	match entry.operation.as_str() {
+		"get" => {
+			let request = GetObjectRequest { store_id, key: "replay-key".to_string() };
+			Some(("getObject", request.encode_to_vec()))
+		},
+		"put" => {
+			let item_count = entry.item_count.max(1);
+			let value_size = entry.value_bytes / item_count;
+			let transaction_items = (0..item_count)
+				.map(|idx| KeyValue {
+					key: format!("replay-key-{}", idx),
+					version: -1,
+					value: vec![0u8; value_size].into(),
+				})
+				.collect();
+			let request = PutObjectRequest {
+				store_id,
+				global_version: None,
+				transaction_items,
+				delete_items: vec![],
+				dry_run: false,
+			};
+			Some(("putObjects", request.encode_to_vec()))
+		},
+		"delete" => {
+			let request = DeleteObjectRequest {
+				store_id,
+				key_value: Some(KeyValue {
+					key: "replay-key".to_string(),
+					version: -1,
+					value: Default::default(),
+				}),
+			};
+			Some(("deleteObject", request.encode_to_vec()))
+		},
+		"list" => {
+			let request = ListKeyVersionsRequest {
+				store_id,
+				key_prefix: None,
+				page_size: entry.page_size,
+				page_token: None,
+			};
+			Some(("listKeyVersions", request.encode_to_vec()))
+		},
+		_ => None,
+	}
+}
+
+/// Returns the status a replayed request is expected to come back with for a captured record.
+///
+/// Conditional outcomes (conflicts, missing keys) depend on state the capture does not carry, so
+/// only those statuses reproducible from shape alone are compared.
+fn replayed_status_expectation(entry: &CaptureEntry) -> u16 {
+	match entry.status {
+		// Conflicts were recorded against version state which is not replayed.
+		409 => 200,
+		// Missing keys depend on the target instance's data.
+		404 => 200,
+		status => status,
+	}
+}
@@ -0,0 +1,123 @@
+//! Opt-in capture of sanitized request/response records, see [`CaptureLog`].
+//!
+//! Captured files can be re-issued against another server instance with the `vss-replay`
+//! companion tool, e.g. to validate a backend migration against real traffic shapes.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// A single sanitized request/response record.
+///
+/// Records deliberately carry only the traffic shape: operation, sizes, item counts and the
+/// response status. Keys, values and user tokens are never written, and store ids are replaced
+/// by a truncated hash which keeps requests of one store correlated without exposing the id.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CaptureEntry {
+	/// Unix timestamp of the request in milliseconds.
+	pub ts_millis: u64,
+	/// The operation, one of `get`, `put`, `delete` and `list`.
+	pub operation: String,
+	/// The anonymized store id.
+	pub store: String,
+	/// The size of the protobuf-encoded request body in bytes.
+	pub request_bytes: usize,
+	/// The number of transaction and delete items carried by a `put`, 1 otherwise.
+	pub item_count: usize,
+	/// The total size of all values carried by a `put` in bytes, 0 otherwise.
+	pub value_bytes: usize,
+	/// The requested page size of a `list`, if any.
+	pub page_size: Option<i32>,
+	/// The HTTP status code of the response.
+	pub status: u16,
+	/// The size of the protobuf-encoded response body in bytes.
+	pub response_bytes: usize,
+}
+
+/// Appends sanitized [`CaptureEntry`] records as JSON lines to the configured file.
+pub struct CaptureLog {
+	file: Mutex<File>,
+}
+
+impl CaptureLog {
+	/// Opens the capture file at the given path, creating it if necessary and appending to it
+	/// otherwise.
+	pub fn new(path: &str) -> Result<Self, String> {
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.map_err(|e| format!("Failed to open capture file {}: {}", path, e))?;
+		Ok(Self { file: Mutex::new(file) })
+	}
+
+	/// Appends a record. Failures are logged and never fail the captured request.
+	pub fn record(&self, entry: CaptureEntry) {
+		let mut line = match serde_json::to_string(&entry) {
+			Ok(line) => line,
+			Err(e) => {
+				warn!("Failed to serialize capture record: {}", e);
+				return;
+			},
+		};
+		line.push('\n');
+		let mut file = self.file.lock().unwrap();
+		if let Err(e) = file.write_all(line.as_bytes()) {
+			warn!("Failed to write capture record: {}", e);
+		}
+	}
+}
+
+/// Replaces an identifier (a store id or user token) with a truncated hash, keeping its traffic
+/// correlated across capture records and trace spans without exposing the identifier itself.
+pub fn anonymize_identifier(value: &str) -> String {
+	hex::encode(&Sha256::digest(value.as_bytes())[..8])
+}
+
+/// Replaces a store id with a truncated hash, keeping a store's requests correlated in the
+/// capture without exposing the id itself.
+pub fn anonymize_store_id(store_id: &str) -> String {
+	anonymize_identifier(store_id)
+}
+
+/// Returns the current unix timestamp in milliseconds.
+pub fn now_millis() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_are_sanitized_json_lines() {
+		let path = std::env::temp_dir().join("vss-capture-test.jsonl");
+		let path_str = path.to_str().unwrap().to_string();
+		let _ = std::fs::remove_file(&path);
+
+		let capture_log = CaptureLog::new(&path_str).unwrap();
+		capture_log.record(CaptureEntry {
+			ts_millis: now_millis(),
+			operation: "put".to_string(),
+			store: anonymize_store_id("wallet-store-1"),
+			request_bytes: 128,
+			item_count: 2,
+			value_bytes: 64,
+			page_size: None,
+			status: 200,
+			response_bytes: 2,
+		});
+
+		let contents = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let entry: CaptureEntry = serde_json::from_str(contents.trim()).unwrap();
+		assert_eq!(entry.operation, "put");
+		assert!(!contents.contains("wallet-store-1"));
+		assert_eq!(entry.store, anonymize_store_id("wallet-store-1"));
+	}
+}
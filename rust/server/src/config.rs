@@ -0,0 +1,939 @@
+//! Server configuration, read from a TOML file provided at startup.
+
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::secrets::SecretProviderConfig;
+
+/// The top-level server configuration.
+#[derive(Deserialize)]
+pub struct Config {
+	pub server_config: ServerConfig,
+	/// If set, the listener terminates TLS itself instead of relying on a fronting reverse proxy,
+	/// optionally requiring client certificates, see [`TlsConfig`].
+	pub tls_config: Option<TlsConfig>,
+	/// The storage backend serving this deployment. Defaults to [`BackendConfig::Postgres`].
+	#[serde(default)]
+	pub backend: BackendConfig,
+	/// Configuration of the PostgreSQL backend, required unless another backend is set.
+	pub postgresql_config: Option<PostgresqlConfig>,
+	/// Configuration of the DynamoDB backend, required with `backend = "dynamodb"`.
+	pub dynamodb_config: Option<DynamodbConfig>,
+	/// Configuration of the etcd backend, required with `backend = "etcd"`.
+	pub etcd_config: Option<EtcdConfig>,
+	/// Configuration of the filesystem backend, required with `backend = "fs"`.
+	pub fs_config: Option<FsConfig>,
+	/// Configuration of the Redis backend, required with `backend = "redis"`.
+	pub redis_config: Option<RedisConfig>,
+	/// Configuration of the embedded sled backend, required with `backend = "sled"`. Only
+	/// available when built with the `sled` feature.
+	#[cfg(feature = "sled")]
+	pub sled_config: Option<SledConfig>,
+	/// If set, requests are authenticated as JWT bearer tokens. Otherwise, all requests are
+	/// mapped to a fixed user without any authentication.
+	pub jwt_authorizer_config: Option<JwtAuthorizerConfig>,
+	/// If set, requests are authenticated by an API key resolved to a `user_token` via the
+	/// configured key set. May not be combined with `jwt_authorizer_config`.
+	pub api_key_authorizer_config: Option<ApiKeyAuthorizerConfig>,
+	/// If set, requests are authenticated as OIDC bearer tokens validated against the issuer's
+	/// published signing keys. May not be combined with the other authorizer configs.
+	pub oidc_authorizer_config: Option<OidcAuthorizerConfig>,
+	/// If set, requests are authenticated by the mTLS client certificate of the underlying
+	/// connection. Requires `tls_config` with `client_ca_path` set; may not be combined with the
+	/// other authorizer configs.
+	pub mtls_authorizer_config: Option<MtlsAuthorizerConfig>,
+	/// If set, authentication decisions are delegated to an external HTTP service. May not be
+	/// combined with the other authorizer configs.
+	pub remote_authorizer_config: Option<RemoteAuthorizerConfig>,
+	/// Configuration of the unauthenticated fallback, only taking effect if no
+	/// `jwt_authorizer_config` is set.
+	pub noop_authorizer_config: Option<NoopAuthorizerConfig>,
+	/// If set, successful verifications of the configured authorizer are cached for a short TTL,
+	/// see [`CachingAuthorizer`].
+	///
+	/// [`CachingAuthorizer`]: impls::auth::caching_authorizer::CachingAuthorizer
+	pub auth_cache_config: Option<AuthCacheConfig>,
+	/// If set, credential-less requests are served under random, expiring trial identities with
+	/// a tight write quota, see [`TrialRegistry`].
+	///
+	/// [`TrialRegistry`]: crate::trial::TrialRegistry
+	pub trial_config: Option<TrialConfig>,
+	/// If set, the admin API is mounted under `/admin`. Otherwise, the admin API is disabled.
+	pub admin_api_config: Option<AdminApiConfig>,
+	/// Tenants served by this deployment, matched by `store_id` prefix in configuration order.
+	#[serde(default)]
+	pub tenant_config: Vec<TenantConfig>,
+	/// If set, requests are rate limited per authenticated user (and failed authentication
+	/// attempts per client IP) with a token bucket, see [`RateLimiter`].
+	///
+	/// [`RateLimiter`]: crate::rate_limit::RateLimiter
+	pub rate_limit_config: Option<RateLimitConfig>,
+	/// If set, overrides the default states of the feature flags gating experimental behaviors,
+	/// see [`FeatureFlags`].
+	///
+	/// [`FeatureFlags`]: crate::admin_service::FeatureFlags
+	pub features: Option<FeaturesConfig>,
+	/// If set, only a keyed hash of authenticated user tokens is used for storage, logging and
+	/// rate limiting, see [`UserTokenHasher`].
+	///
+	/// [`UserTokenHasher`]: crate::vss_service::UserTokenHasher
+	pub user_token_hashing_config: Option<UserTokenHashingConfig>,
+	/// If set, failed authentication attempts are recorded as structured audit events, see
+	/// [`AuthFailureAuditLog`].
+	///
+	/// [`AuthFailureAuditLog`]: api::auth::AuthFailureAuditLog
+	pub auth_audit_config: Option<AuthAuditConfig>,
+	/// If set, sanitized request/response records are captured to a file, see [`CaptureLog`].
+	///
+	/// [`CaptureLog`]: crate::capture::CaptureLog
+	pub capture_config: Option<CaptureConfig>,
+	/// If set, writes are asynchronously replicated to a peer VSS instance, see
+	/// [`ReplicatedKvStore`].
+	///
+	/// [`ReplicatedKvStore`]: crate::replication::ReplicatedKvStore
+	pub replication_config: Option<ReplicationConfig>,
+	/// If set, every applied mutation is appended to a sequence-numbered log file, see
+	/// [`MutationLog`].
+	///
+	/// [`MutationLog`]: crate::mutation_log::MutationLog
+	pub mutation_log_config: Option<MutationLogConfig>,
+	/// If set, the configured users' stores are periodically dumped to S3-compatible object
+	/// storage, see [`BackupJob`].
+	///
+	/// [`BackupJob`]: crate::backup::BackupJob
+	pub backup_config: Option<BackupConfig>,
+	/// If set, OpenTelemetry metrics are pushed to an OTLP endpoint, see [`RequestMetrics`].
+	///
+	/// [`RequestMetrics`]: crate::metrics::RequestMetrics
+	pub metrics_config: Option<MetricsConfig>,
+	/// If set, errors and sampled performance transactions are reported to Sentry.
+	pub sentry_config: Option<SentryConfig>,
+	/// If set, error rates are evaluated in-process against alert thresholds, see
+	/// [`AlertMonitor`].
+	///
+	/// [`AlertMonitor`]: crate::alerts::AlertMonitor
+	pub alert_config: Option<AlertConfig>,
+}
+
+/// Configuration of TLS termination on the listener.
+#[derive(Deserialize)]
+pub struct TlsConfig {
+	/// Path to the PEM-encoded server certificate chain, leaf first.
+	pub cert_path: String,
+	/// Path to the PEM-encoded server private key.
+	pub key_path: String,
+	/// If set, clients must present a certificate chaining to a CA in this PEM bundle; the
+	/// handshake is refused otherwise. Required for `mtls_authorizer_config`.
+	pub client_ca_path: Option<String>,
+}
+
+/// Configuration of the HTTP endpoint.
+#[derive(Deserialize)]
+pub struct ServerConfig {
+	pub host: String,
+	pub port: u16,
+	/// The maximum accepted request body size in bytes. Larger requests are rejected with HTTP
+	/// 413. Defaults to [`DEFAULT_MAX_REQUEST_BODY_BYTES`].
+	///
+	/// [`DEFAULT_MAX_REQUEST_BODY_BYTES`]: crate::vss_service::DEFAULT_MAX_REQUEST_BODY_BYTES
+	pub max_request_body_bytes: Option<usize>,
+	/// The maximum accepted `key` length in characters. Defaults to the width of the PostgreSQL
+	/// `key` column (600). Longer keys are rejected with HTTP 400.
+	pub max_key_length: Option<usize>,
+	/// The maximum accepted `store_id` length in characters. Defaults to the width of the
+	/// PostgreSQL `store_id` column (120). Longer store ids are rejected with HTTP 400.
+	pub max_store_id_length: Option<usize>,
+	/// The W3C `baggage` entries (e.g. a client app version or device id) propagated into
+	/// request attributes and span annotations. Entries not listed here are ignored.
+	pub baggage_keys: Option<Vec<String>>,
+	/// The addresses (or CIDR ranges) of reverse proxies fronting this server. `X-Forwarded-For`
+	/// is only honored on connections from one of these; the effective client IP is then the
+	/// nearest forwarded hop not itself a trusted proxy. If unset, the client IP is always the
+	/// peer address of the connection, so forwarded headers cannot spoof IP-bound credentials or
+	/// per-IP rate limits.
+	pub trusted_proxies: Option<Vec<String>>,
+	/// The maximum number of distinct `store_id`s a single user may create. Writes to further
+	/// stores are rejected with HTTP 400. Unlimited if unset.
+	pub max_stores_per_user: Option<u32>,
+	/// The maximum number of keys a single store may hold. Writes which would grow a store beyond
+	/// the cap are rejected with HTTP 400. Unlimited if unset.
+	pub max_keys_per_store: Option<u32>,
+	/// The maximum total value bytes a single user may store, across all their stores. Writes
+	/// which would exceed the quota are rejected with HTTP 400. Unlimited if unset.
+	pub max_total_bytes_per_user: Option<u64>,
+	/// The maximum number of keys a single user may store, across all their stores. Writes which
+	/// would exceed the quota are rejected with HTTP 400. Unlimited if unset.
+	pub max_keys_per_user: Option<u64>,
+}
+
+/// The storage backend serving a deployment.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendConfig {
+	/// The PostgreSQL backend, configured via `postgresql_config`.
+	#[default]
+	Postgres,
+	/// The in-memory backend. All data is lost when the process exits, hence this is only
+	/// suitable for development and CI.
+	InMemory,
+	/// The DynamoDB backend, configured via `dynamodb_config`, with AWS credentials taken from
+	/// the standard `AWS_*` environment variables.
+	#[serde(rename = "dynamodb")]
+	DynamoDb,
+	/// The etcd backend, configured via `etcd_config`.
+	Etcd,
+	/// The filesystem backend, configured via `fs_config`.
+	Fs,
+	/// The Redis backend, configured via `redis_config`.
+	Redis,
+	/// The embedded sled backend, configured via `sled_config`. Only available when built with
+	/// the `sled` feature.
+	#[cfg(feature = "sled")]
+	Sled,
+}
+
+impl Config {
+	/// Returns the PostgreSQL configuration, required for the default backend and for all
+	/// database-touching subcommands.
+	pub fn require_postgresql_config(&self) -> Result<&PostgresqlConfig, String> {
+		self.postgresql_config.as_ref().ok_or_else(|| {
+			"postgresql_config must be set unless backend = \"in_memory\" is configured."
+				.to_string()
+		})
+	}
+
+	/// Returns the DynamoDB configuration, required with `backend = "dynamodb"`.
+	pub fn require_dynamodb_config(&self) -> Result<&DynamodbConfig, String> {
+		self.dynamodb_config
+			.as_ref()
+			.ok_or_else(|| "dynamodb_config must be set with backend = \"dynamodb\".".to_string())
+	}
+
+	/// Returns the etcd configuration, required with `backend = "etcd"`.
+	pub fn require_etcd_config(&self) -> Result<&EtcdConfig, String> {
+		self.etcd_config
+			.as_ref()
+			.ok_or_else(|| "etcd_config must be set with backend = \"etcd\".".to_string())
+	}
+
+	/// Returns the filesystem configuration, required with `backend = "fs"`.
+	pub fn require_fs_config(&self) -> Result<&FsConfig, String> {
+		self.fs_config
+			.as_ref()
+			.ok_or_else(|| "fs_config must be set with backend = \"fs\".".to_string())
+	}
+
+	/// Returns the Redis configuration, required with `backend = "redis"`.
+	pub fn require_redis_config(&self) -> Result<&RedisConfig, String> {
+		self.redis_config
+			.as_ref()
+			.ok_or_else(|| "redis_config must be set with backend = \"redis\".".to_string())
+	}
+
+	/// Returns the sled configuration, required with `backend = "sled"`.
+	#[cfg(feature = "sled")]
+	pub fn require_sled_config(&self) -> Result<&SledConfig, String> {
+		self.sled_config
+			.as_ref()
+			.ok_or_else(|| "sled_config must be set with backend = \"sled\".".to_string())
+	}
+}
+
+/// Configuration of the PostgreSQL storage backend.
+#[derive(Clone, Deserialize)]
+pub struct PostgresqlConfig {
+	/// A full connection string passed through to tokio-postgres verbatim, allowing options the
+	/// discrete fields below cannot express (e.g. `sslmode`, `application_name` or multi-host
+	/// syntax). May not be combined with the discrete fields, and is overridden by the
+	/// `VSS_POSTGRESQL_DSN` environment variable.
+	pub dsn: Option<String>,
+	pub username: Option<String>,
+	/// The password, provided inline. Alternatively, set `password_file` or
+	/// `password_provider`.
+	pub password: Option<String>,
+	/// Path to a file holding the password (e.g. a mounted Docker/Kubernetes secret).
+	pub password_file: Option<String>,
+	/// An external secret provider the password is fetched from, see
+	/// [`SecretProviderConfig`].
+	pub password_provider: Option<SecretProviderConfig>,
+	pub host: Option<String>,
+	pub port: Option<u16>,
+	pub database: Option<String>,
+	/// Full connection strings of multiple PostgreSQL clusters users are partitioned across by
+	/// a stable hash of their token, see [`ShardedKvStore`]. The order and count are part of the
+	/// data layout and must never change once data has been written. May not be combined with
+	/// `dsn` or the discrete fields.
+	///
+	/// [`ShardedKvStore`]: impls::sharded_store::ShardedKvStore
+	pub shard_dsns: Option<Vec<String>>,
+	/// If set, backend operations taking longer than this many milliseconds are logged at WARN
+	/// level with their kind, duration and row count.
+	pub slow_query_threshold_ms: Option<u64>,
+	/// Additional operator-supplied migration statements (e.g. extra indexes, partitioning or
+	/// row-level security policies), applied after the built-in schema migrations and tracked in
+	/// a separate version table. Entries must never be edited or reordered once applied, only
+	/// appended.
+	#[serde(default)]
+	pub custom_migrations: Vec<String>,
+	/// How long the server keeps retrying (with backoff) to reach an unavailable database at
+	/// startup before giving up, in seconds. Common in docker-compose and Kubernetes start
+	/// ordering. Defaults to 60; 0 fails fast on the first error.
+	pub startup_max_wait_secs: Option<u64>,
+}
+
+impl PostgresqlConfig {
+	/// Returns the full connection string if one is configured via `dsn` or the
+	/// `VSS_POSTGRESQL_DSN` environment variable, rejecting configs mixing `dsn` with the
+	/// discrete fields.
+	pub fn dsn_override(&self) -> Result<Option<String>, String> {
+		let discrete_fields_set = self.username.is_some()
+			|| self.password.is_some()
+			|| self.password_file.is_some()
+			|| self.password_provider.is_some()
+			|| self.host.is_some()
+			|| self.port.is_some()
+			|| self.database.is_some();
+		if self.dsn.is_some() && discrete_fields_set {
+			return Err(
+				"dsn may not be combined with the discrete postgresql_config fields.".to_string()
+			);
+		}
+		if self.shard_dsns.is_some() && (self.dsn.is_some() || discrete_fields_set) {
+			return Err("shard_dsns may not be combined with dsn or the discrete \
+				postgresql_config fields."
+				.to_string());
+		}
+		Ok(std::env::var("VSS_POSTGRESQL_DSN").ok().or_else(|| self.dsn.clone()))
+	}
+
+	/// Renders the discrete fields as a PostgreSQL connection string using the given password.
+	pub fn connection_string_with_password(
+		&self, password: &str,
+	) -> Result<String, String> {
+		match (&self.username, &self.host, self.port, &self.database) {
+			(Some(username), Some(host), Some(port), Some(database)) => Ok(format!(
+				"postgresql://{}:{}@{}:{}/{}",
+				username, password, host, port, database
+			)),
+			_ => Err(
+				"username, host, port and database must all be set when no dsn is configured."
+					.to_string(),
+			),
+		}
+	}
+}
+
+/// Configuration of the DynamoDB storage backend, see [`DynamoDbBackendImpl`].
+///
+/// [`DynamoDbBackendImpl`]: impls::dynamodb_store::DynamoDbBackendImpl
+#[derive(Clone, Deserialize)]
+pub struct DynamodbConfig {
+	/// The AWS region the table lives in.
+	pub region: String,
+	/// The DynamoDB table holding all records. The table must use a string partition key `pk`
+	/// and a string sort key `sk`; with `create_table = true` a matching on-demand table is
+	/// created at startup if missing.
+	pub table_name: String,
+	/// Overrides the regional AWS endpoint, e.g. `http://localhost:8000` for DynamoDB Local.
+	pub endpoint: Option<String>,
+	/// If set, the table is created at startup if it does not exist yet. Mainly for development
+	/// and CI; production tables should be provisioned with the deployment tooling.
+	pub create_table: Option<bool>,
+}
+
+/// Configuration of the etcd storage backend, see [`EtcdBackendImpl`].
+///
+/// [`EtcdBackendImpl`]: impls::etcd_store::EtcdBackendImpl
+#[derive(Clone, Deserialize)]
+pub struct EtcdConfig {
+	/// The endpoints of the etcd cluster members, e.g. `http://etcd-a.internal:2379`. Requests
+	/// fail over to the next endpoint when a member is unreachable.
+	pub endpoints: Vec<String>,
+	/// The etcd user to authenticate as, if the cluster has authentication enabled. Requires a
+	/// password.
+	pub username: Option<String>,
+	/// The password, provided inline. Alternatively, `password_file` may point at a file holding
+	/// the password, e.g. a mounted Docker/Kubernetes secret.
+	pub password: Option<String>,
+	pub password_file: Option<String>,
+}
+
+impl EtcdConfig {
+	/// Returns the password, resolving `password_file` if set.
+	pub fn resolve_password(&self) -> Result<Option<String>, String> {
+		if self.password.is_none() && self.password_file.is_none() {
+			return Ok(None);
+		}
+		read_secret(&self.password, &self.password_file, "password").map(Some)
+	}
+}
+
+/// Configuration of the filesystem storage backend, see [`FsBackendImpl`].
+///
+/// [`FsBackendImpl`]: impls::fs_store::FsBackendImpl
+#[derive(Clone, Deserialize)]
+pub struct FsConfig {
+	/// The root directory holding all stores. Created if missing; may not be shared between
+	/// processes.
+	pub path: String,
+}
+
+/// Configuration of the Redis storage backend, see [`RedisBackendImpl`].
+///
+/// [`RedisBackendImpl`]: impls::redis_store::RedisBackendImpl
+#[derive(Clone, Deserialize)]
+pub struct RedisConfig {
+	/// The `host:port` address of the Redis server. Durability is bounded by the server's
+	/// persistence configuration; AOF persistence is strongly recommended.
+	pub address: String,
+	/// The password sent via `AUTH`, if the server requires one. Alternatively, `password_file`
+	/// may point at a file holding the password, e.g. a mounted Docker/Kubernetes secret.
+	pub password: Option<String>,
+	pub password_file: Option<String>,
+}
+
+impl RedisConfig {
+	/// Returns the password, resolving `password_file` if set.
+	pub fn resolve_password(&self) -> Result<Option<String>, String> {
+		if self.password.is_none() && self.password_file.is_none() {
+			return Ok(None);
+		}
+		read_secret(&self.password, &self.password_file, "password").map(Some)
+	}
+}
+
+/// Configuration of the embedded sled storage backend, see [`SledBackendImpl`].
+///
+/// [`SledBackendImpl`]: impls::sled_store::SledBackendImpl
+#[cfg(feature = "sled")]
+#[derive(Clone, Deserialize)]
+pub struct SledConfig {
+	/// The directory holding the database. Created if missing; may not be shared between
+	/// processes.
+	pub path: String,
+}
+
+/// Configuration of anonymous trial identities, see [`TrialRegistry`].
+///
+/// [`TrialRegistry`]: crate::trial::TrialRegistry
+#[derive(Clone, Deserialize)]
+pub struct TrialConfig {
+	/// How long an issued trial identity stays valid. Expired identities are rejected and their
+	/// data is purged by the background sweep.
+	pub ttl_secs: u64,
+	/// The maximum number of items a trial identity may write over its lifetime. Defaults to
+	/// [`DEFAULT_TRIAL_MAX_ITEMS`].
+	///
+	/// [`DEFAULT_TRIAL_MAX_ITEMS`]: crate::trial::DEFAULT_TRIAL_MAX_ITEMS
+	pub max_items: Option<u64>,
+	/// The maximum number of value bytes a trial identity may write over its lifetime. Defaults
+	/// to [`DEFAULT_TRIAL_MAX_VALUE_BYTES`].
+	///
+	/// [`DEFAULT_TRIAL_MAX_VALUE_BYTES`]: crate::trial::DEFAULT_TRIAL_MAX_VALUE_BYTES
+	pub max_value_bytes: Option<u64>,
+	/// How often expired trial identities are swept and their data purged. Defaults to the
+	/// configured `ttl_secs`.
+	pub purge_interval_secs: Option<u64>,
+}
+
+/// Configuration of the JWT authorizer, see [`JwtAuthorizer`].
+///
+/// [`JwtAuthorizer`]: impls::auth::jwt_authorizer::JwtAuthorizer
+#[derive(Deserialize)]
+pub struct JwtAuthorizerConfig {
+	/// Path to the PEM-encoded RSA public key used to verify token signatures. Alternatively,
+	/// set `public_key_pem_provider`.
+	pub public_key_pem_path: Option<String>,
+	/// An external secret provider the PEM-encoded RSA public key is fetched from, see
+	/// [`SecretProviderConfig`]. With a refresh interval configured, rotated keys take effect
+	/// without a restart.
+	pub public_key_pem_provider: Option<SecretProviderConfig>,
+	/// The signature algorithm tokens must use, e.g. `RS256` (the default), `ES256`, `ES384`,
+	/// `EdDSA` or `HS256`, enforced against each token's header. With an HMAC algorithm, the
+	/// key file holds the raw shared secret instead of a PEM public key.
+	pub algorithm: Option<String>,
+	/// Path to a PEM-encoded RSA private key used to decrypt JWE-encrypted tokens (`RSA-OAEP` or
+	/// `RSA-OAEP-256` with `A256GCM`) before the nested JWS is verified. Leave unset unless the
+	/// identity provider encrypts its tokens.
+	pub decryption_key_pem_path: Option<String>,
+	/// Path to a revocation list file of one `jti` per line (`#` comments and blank lines are
+	/// skipped). With the file configured, every token must carry a `jti` claim and tokens whose
+	/// `jti` is listed are rejected, so a compromised token can be invalidated before it expires.
+	pub revocation_list_path: Option<String>,
+	/// If set, the revocation list file is re-read once the interval has elapsed, letting new
+	/// revocations take effect without a restart.
+	pub revocation_list_refresh_secs: Option<u64>,
+}
+
+/// Configuration of the API key authorizer, see [`ApiKeyAuthorizer`]. May not be combined with
+/// `jwt_authorizer_config`.
+///
+/// [`ApiKeyAuthorizer`]: impls::auth::api_key_authorizer::ApiKeyAuthorizer
+#[derive(Deserialize)]
+pub struct ApiKeyAuthorizerConfig {
+	/// Where the key set mapping API keys onto `user_token`s is loaded from.
+	pub source: ApiKeySourceConfig,
+	/// Path to a file of `<api_key> <user_token>` lines, required with `source = "file"`.
+	pub path: Option<String>,
+	/// The environment variable holding comma-separated `<api_key>:<user_token>` pairs,
+	/// required with `source = "env"`.
+	pub env_var: Option<String>,
+	/// If set, the key set is re-read once the interval has elapsed, letting key rotations take
+	/// effect without a restart.
+	pub refresh_interval_secs: Option<u64>,
+}
+
+/// Configuration of the OIDC authorizer, see [`OidcAuthorizer`]. May not be combined with
+/// `jwt_authorizer_config` or `api_key_authorizer_config`.
+///
+/// [`OidcAuthorizer`]: impls::auth::oidc_authorizer::OidcAuthorizer
+#[derive(Deserialize)]
+pub struct OidcAuthorizerConfig {
+	/// The issuer URL whose tokens are trusted. The signing keys are discovered from
+	/// `<issuer>/.well-known/openid-configuration` at startup and re-fetched when a token
+	/// references an unknown key id, so provider key rotations take effect without a restart.
+	pub issuer: String,
+	/// The audience (`aud` claim) tokens must be issued for.
+	pub audience: String,
+	/// The minimum interval between key re-fetches triggered by unknown key ids, bounding how
+	/// often invalid tokens can make the server contact the provider (default: 60).
+	pub refresh_cooldown_secs: Option<u64>,
+}
+
+/// Configuration of authentication result caching, see [`CachingAuthorizer`].
+///
+/// Caching spares the per-request signature or JWT verification cost at high QPS. Only
+/// successes are cached, keyed by a digest of the `Authorization` header; the TTL bounds how
+/// long a token outlives its revocation or expiry, so it should stay small.
+///
+/// [`CachingAuthorizer`]: impls::auth::caching_authorizer::CachingAuthorizer
+#[derive(Deserialize)]
+pub struct AuthCacheConfig {
+	/// How long a successful verification is reused, in seconds.
+	pub ttl_secs: u64,
+	/// The maximum number of cached entries; new entries are dropped while the cache is full
+	/// (default: 10000).
+	pub max_entries: Option<usize>,
+}
+
+/// Configuration of the external authorization service, see [`RemoteAuthorizer`]. May not be
+/// combined with the other authorizer configs.
+///
+/// [`RemoteAuthorizer`]: impls::auth::remote_authorizer::RemoteAuthorizer
+#[derive(Deserialize)]
+pub struct RemoteAuthorizerConfig {
+	/// The endpoint URL the credential-bearing request headers are POSTed to. The service answers
+	/// with `{"allowed": true, "user_token": "..."}` or `{"allowed": false, "reason": "..."}`.
+	pub url: String,
+	/// The (lowercase) headers forwarded to the service. Defaults to just `authorization`.
+	pub forwarded_headers: Option<Vec<String>>,
+	/// How long a decision is reused before the service is asked again, in seconds (default: 5).
+	/// 0 disables caching; the TTL bounds how long a revoked credential keeps working.
+	pub cache_ttl_secs: Option<u64>,
+}
+
+/// Configuration of the mTLS client certificate authorizer, see [`MtlsAuthorizer`]. Requires a
+/// `tls_config` with `client_ca_path` set, so every admitted connection already carries a
+/// CA-validated client certificate by the time requests are authorized. May not be combined with
+/// the other authorizer configs.
+///
+/// [`MtlsAuthorizer`]: impls::auth::mtls_authorizer::MtlsAuthorizer
+#[derive(Deserialize)]
+pub struct MtlsAuthorizerConfig {
+	/// How the `user_token` is derived from the client certificate. Defaults to
+	/// [`MtlsIdentityConfig::SpkiSha256`].
+	#[serde(default)]
+	pub identity: MtlsIdentityConfig,
+}
+
+/// How an [`MtlsAuthorizerConfig`] derives the `user_token` from the client certificate.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MtlsIdentityConfig {
+	/// The hex-encoded SHA-256 digest of the certificate's `subjectPublicKeyInfo`, stable across
+	/// certificate renewals as long as the client keeps its key.
+	#[default]
+	SpkiSha256,
+	/// The certificate subject's common name, letting the CA assign human-readable identities.
+	SubjectCommonName,
+}
+
+/// The source an [`ApiKeyAuthorizerConfig`] loads its key set from.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeySourceConfig {
+	/// A file of `<api_key> <user_token>` lines, e.g. a mounted Docker/Kubernetes secret.
+	File,
+	/// An environment variable of comma-separated `<api_key>:<user_token>` pairs.
+	Env,
+	/// The `vss_api_keys` table of the PostgreSQL backend. Only available with
+	/// `backend = "postgres"` (unsharded).
+	Database,
+}
+
+/// Configuration of the unauthenticated fallback, see [`NoopAuthorizer`].
+///
+/// [`NoopAuthorizer`]: api::auth::NoopAuthorizer
+#[derive(Deserialize)]
+pub struct NoopAuthorizerConfig {
+	/// The fixed `user_token` every request is mapped to. Alternatively, set `trusted_header`.
+	pub fixed_user_token: Option<String>,
+	/// The name of a header the `user_token` is taken from, suitable for deployments behind an
+	/// already-authenticating reverse proxy (e.g. `X-Auth-User`). The header must only ever be
+	/// settable by the proxy; requests missing it are rejected.
+	pub trusted_header: Option<String>,
+}
+
+/// Configuration of a single tenant, see [`TenantRegistry`].
+///
+/// [`TenantRegistry`]: crate::tenants::TenantRegistry
+#[derive(Deserialize)]
+pub struct TenantConfig {
+	/// A human-readable identifier, used in logs and rate-limiter bookkeeping.
+	pub name: String,
+	/// The tenant matches all requests whose `store_id` starts with this prefix.
+	pub store_id_prefix: String,
+	/// If set, requests of this tenant are authenticated with a dedicated JWT authorizer (e.g.
+	/// using the signing key of the wallet app the tenant belongs to) instead of the server-wide
+	/// default authorizer.
+	pub jwt_authorizer_config: Option<JwtAuthorizerConfig>,
+	/// If set, the maximum number of requests a single user of this tenant may issue per minute.
+	pub rate_limit_per_minute: Option<u32>,
+}
+
+/// Configuration of per-caller request rate limiting, see [`RateLimiter`].
+///
+/// [`RateLimiter`]: crate::rate_limit::RateLimiter
+#[derive(Clone, Deserialize)]
+pub struct RateLimitConfig {
+	/// The sustained number of requests per second a single caller may issue per operation.
+	pub requests_per_second: f64,
+	/// The number of requests a caller may burst above the sustained rate, absorbing legitimate
+	/// spikes (e.g. a wallet syncing after being offline). Defaults to the sustained rate.
+	pub burst_size: Option<f64>,
+	/// Overrides of the sustained rate for individual operations, keyed by the operation name as
+	/// recorded in metrics (`get`, `put`, `delete`, `list`, ...).
+	#[serde(default)]
+	pub operations: std::collections::HashMap<String, f64>,
+}
+
+/// Configuration of asynchronous active-active replication, see [`ReplicatedKvStore`].
+///
+/// [`ReplicatedKvStore`]: crate::replication::ReplicatedKvStore
+#[derive(Deserialize)]
+pub struct ReplicationConfig {
+	/// The base URL of the peer VSS instance writes are forwarded to, e.g.
+	/// `http://region-b.internal:8080`.
+	pub peer_url: String,
+	/// If set, the authenticated user token of each forwarded write is sent to the peer in this
+	/// header. The peer should map it back unchanged, e.g. via a trusted-header
+	/// `noop_authorizer_config`, so forwarded writes land under the same user.
+	pub user_token_header: Option<String>,
+	/// The number of writes buffered for the peer before new writes are dropped from replication.
+	/// Defaults to [`DEFAULT_REPLICATION_QUEUE_SIZE`].
+	///
+	/// [`DEFAULT_REPLICATION_QUEUE_SIZE`]: crate::replication::DEFAULT_REPLICATION_QUEUE_SIZE
+	pub queue_size: Option<usize>,
+}
+
+/// Configuration of the mutation log, see [`MutationLog`].
+///
+/// [`MutationLog`]: crate::mutation_log::MutationLog
+#[derive(Deserialize)]
+pub struct MutationLogConfig {
+	/// Path of the append-only log file. An existing log is appended to, continuing its sequence
+	/// numbers. Unlike the capture log, records carry full keys and values, so the file must be
+	/// protected like the database itself.
+	pub path: String,
+}
+
+/// Configuration of scheduled backups to S3-compatible object storage, see [`BackupJob`].
+///
+/// Credentials are taken from the standard `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` (and
+/// optionally `AWS_SESSION_TOKEN`) environment variables.
+///
+/// [`BackupJob`]: crate::backup::BackupJob
+#[derive(Clone, Deserialize)]
+pub struct BackupConfig {
+	/// How often a backup run starts, in seconds.
+	pub interval_secs: u64,
+	/// The users whose stores are backed up. With user token hashing configured, entries must be
+	/// the hashed tokens (as printed by the admin API).
+	pub user_tokens: Vec<String>,
+	/// Whether every run uploads a full dump per user, or skips users whose stores are unchanged
+	/// since the last successful upload. Defaults to [`BackupMode::Full`].
+	#[serde(default)]
+	pub mode: BackupMode,
+	/// The bucket artifacts are uploaded to.
+	pub s3_bucket: String,
+	/// The AWS region the bucket lives in. For non-AWS endpoints, any stable value accepted by
+	/// the provider's signature validation (commonly `us-east-1`).
+	pub s3_region: String,
+	/// A custom S3-compatible endpoint (e.g. a MinIO deployment). Defaults to the AWS endpoint
+	/// of the configured region. Requests use path-style addressing either way.
+	pub s3_endpoint: Option<String>,
+	/// A prefix prepended to every uploaded object key, e.g. `vss-backups/`.
+	#[serde(default)]
+	pub key_prefix: String,
+	/// How many artifacts to keep per user; older ones are deleted after each successful upload.
+	/// Unlimited if unset.
+	pub retention_count: Option<u32>,
+	/// A 256-bit hex key artifacts are sealed with before upload (see the admin backup API),
+	/// provided inline. Alternatively, set `encryption_key_file`. Unencrypted if neither is set.
+	pub encryption_key: Option<String>,
+	/// Path to a file holding the encryption key (e.g. a mounted Docker/Kubernetes secret).
+	pub encryption_key_file: Option<String>,
+}
+
+impl BackupConfig {
+	/// Resolves the configured artifact encryption key, if any.
+	pub fn resolve_encryption_key(&self) -> Result<Option<String>, String> {
+		if self.encryption_key.is_none() && self.encryption_key_file.is_none() {
+			return Ok(None);
+		}
+		read_secret(&self.encryption_key, &self.encryption_key_file, "encryption_key").map(Some)
+	}
+}
+
+/// Whether scheduled backups dump every user each run or only changed ones, see
+/// [`BackupConfig::mode`].
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupMode {
+	/// Every run uploads a fresh dump of every configured user.
+	#[default]
+	Full,
+	/// Users whose stores report no updates since the last successful upload are skipped. Falls
+	/// back to full dumps on backends without store statistics support.
+	Incremental,
+}
+
+/// Configuration of OpenTelemetry metrics export, see [`RequestMetrics`].
+///
+/// [`RequestMetrics`]: crate::metrics::RequestMetrics
+#[derive(Deserialize)]
+pub struct MetricsConfig {
+	/// The OTLP/HTTP endpoint metrics are periodically pushed to, e.g.
+	/// `http://localhost:4318/v1/metrics`.
+	pub otlp_endpoint: String,
+}
+
+/// Configuration of Sentry error and performance reporting.
+///
+/// The request spans (and their per-statement backend child spans) are forwarded as Sentry
+/// performance transactions, so latency breakdowns land in the same tool as error reports.
+#[derive(Deserialize)]
+pub struct SentryConfig {
+	/// The Sentry DSN events and transactions are reported to.
+	pub dsn: String,
+	/// The fraction of requests recorded as performance transactions, between 0.0 and 1.0.
+	pub traces_sample_rate: f32,
+}
+
+/// Configured states of the feature flags gating experimental behaviors, see [`FeatureFlags`].
+/// Flags not listed keep their defaults. All flags remain adjustable at runtime through the
+/// admin API.
+///
+/// [`FeatureFlags`]: crate::admin_service::FeatureFlags
+#[derive(Clone, Deserialize)]
+pub struct FeaturesConfig {
+	/// Whether the `renameObject` RPC is served. Defaults to false.
+	pub rename_object: Option<bool>,
+	/// Whether the `grantStoreAccess`/`revokeStoreAccess` RPCs are served and the read-only
+	/// grant header is honored. Defaults to false.
+	pub store_grants: Option<bool>,
+	/// Whether large list pages are streamed frame-by-frame instead of buffered in full.
+	/// Defaults to true.
+	pub streamed_list_responses: Option<bool>,
+}
+
+/// Configuration of in-process error-rate alerting, see [`AlertMonitor`].
+///
+/// Rates are fractions of all responses in the sliding window, between 0.0 and 1.0. Metrics
+/// without a threshold are not evaluated.
+///
+/// [`AlertMonitor`]: crate::alerts::AlertMonitor
+#[derive(Clone, Deserialize)]
+pub struct AlertConfig {
+	/// The length of the sliding window rates are computed over, in seconds.
+	pub window_secs: u64,
+	/// The minimum number of requests in the window before rates are evaluated, so a handful of
+	/// failures on an idle server does not alert. Defaults to [`DEFAULT_ALERT_MIN_REQUESTS`].
+	///
+	/// [`DEFAULT_ALERT_MIN_REQUESTS`]: crate::alerts::DEFAULT_ALERT_MIN_REQUESTS
+	pub min_requests: Option<u64>,
+	/// How long repeat alerts for the same metric are suppressed, in seconds. Defaults to the
+	/// window length.
+	pub cooldown_secs: Option<u64>,
+	/// Alerts when the fraction of responses with a 5xx status exceeds this threshold.
+	pub server_error_rate_threshold: Option<f64>,
+	/// Alerts when the fraction of version-conflict responses (409, or 412 for conditional
+	/// requests) exceeds this threshold.
+	pub conflict_rate_threshold: Option<f64>,
+	/// Alerts when the fraction of 401 responses exceeds this threshold.
+	pub auth_failure_rate_threshold: Option<f64>,
+	/// If set, breaches are additionally POSTed to this URL as a JSON notification.
+	pub webhook_url: Option<String>,
+}
+
+/// Configuration of user token hashing, see [`UserTokenHasher`].
+///
+/// The pepper namespaces all stored data: it must be set before the first write and never be
+/// changed or removed afterwards, as doing so makes all previously stored data unreachable.
+///
+/// [`UserTokenHasher`]: crate::vss_service::UserTokenHasher
+#[derive(Deserialize)]
+pub struct UserTokenHashingConfig {
+	/// The pepper keyed into the hash, provided inline. Alternatively, set `pepper_file`.
+	pub pepper: Option<String>,
+	/// Path to a file holding the pepper (e.g. a mounted Docker/Kubernetes secret).
+	pub pepper_file: Option<String>,
+}
+
+impl UserTokenHashingConfig {
+	pub fn resolve_pepper(&self) -> Result<String, String> {
+		read_secret(&self.pepper, &self.pepper_file, "pepper")
+	}
+}
+
+/// Configuration of the authentication failure audit log, see [`AuthFailureAuditLog`].
+///
+/// [`AuthFailureAuditLog`]: api::auth::AuthFailureAuditLog
+#[derive(Deserialize)]
+pub struct AuthAuditConfig {
+	/// Recorded events older than this many days are periodically pruned.
+	pub retention_days: u32,
+}
+
+/// Configuration of request capture, see [`CaptureLog`].
+///
+/// [`CaptureLog`]: crate::capture::CaptureLog
+#[derive(Deserialize)]
+pub struct CaptureConfig {
+	/// The file sanitized request/response records are appended to, replayable with the
+	/// `vss-replay` companion tool.
+	pub path: String,
+}
+
+/// Configuration of the admin API, see [`AdminService`].
+///
+/// [`AdminService`]: crate::admin_service::AdminService
+#[derive(Deserialize)]
+pub struct AdminApiConfig {
+	/// The bearer token required on every admin API request, provided inline. Alternatively,
+	/// set `admin_token_file`.
+	pub admin_token: Option<String>,
+	/// Path to a file holding the admin token (e.g. a mounted Docker/Kubernetes secret).
+	pub admin_token_file: Option<String>,
+}
+
+impl AdminApiConfig {
+	pub fn resolve_admin_token(&self) -> Result<String, String> {
+		read_secret(&self.admin_token, &self.admin_token_file, "admin_token")
+	}
+}
+
+/// Resolves a sensitive config value which can be provided either inline or via a `*_file`
+/// variant pointing at a file (whose trailing newline, if any, is stripped).
+pub fn read_secret(
+	inline: &Option<String>, file: &Option<String>, name: &str,
+) -> Result<String, String> {
+	match (inline, file) {
+		(Some(_), Some(_)) => Err(format!("Only one of {0} and {0}_file may be set.", name)),
+		(Some(value), None) => Ok(value.clone()),
+		(None, Some(path)) => {
+			let contents = fs::read_to_string(path)
+				.map_err(|e| format!("Failed to read {}_file {}: {}", name, path, e))?;
+			Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+		},
+		(None, None) => Err(format!("Either {0} or {0}_file must be set.", name)),
+	}
+}
+
+pub fn parse_config(config_file_contents: &str) -> Result<Config, toml::de::Error> {
+	toml::from_str(config_file_contents)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dsn_rejects_mixing_with_discrete_fields() {
+		let config: Config = parse_config(
+			r#"
+			[server_config]
+			host = "127.0.0.1"
+			port = 8080
+			[postgresql_config]
+			dsn = "postgresql://postgres@localhost/postgres?application_name=vss"
+			"#,
+		)
+		.unwrap();
+		assert_eq!(
+			config.postgresql_config.unwrap().dsn_override().unwrap().as_deref(),
+			Some("postgresql://postgres@localhost/postgres?application_name=vss")
+		);
+
+		let config: Config = parse_config(
+			r#"
+			[server_config]
+			host = "127.0.0.1"
+			port = 8080
+			[postgresql_config]
+			dsn = "postgresql://postgres@localhost/postgres"
+			username = "postgres"
+			"#,
+		)
+		.unwrap();
+		assert!(config.postgresql_config.unwrap().dsn_override().is_err());
+	}
+
+	#[test]
+	fn backend_selection_defaults_to_postgres() {
+		let config: Config = parse_config(
+			r#"
+			[server_config]
+			host = "127.0.0.1"
+			port = 8080
+			[postgresql_config]
+			dsn = "postgresql://postgres@localhost/postgres"
+			"#,
+		)
+		.unwrap();
+		assert_eq!(config.backend, BackendConfig::Postgres);
+		assert!(config.require_postgresql_config().is_ok());
+
+		// The in-memory backend requires no postgresql_config section at all.
+		let config: Config = parse_config(
+			r#"
+			backend = "in_memory"
+			[server_config]
+			host = "127.0.0.1"
+			port = 8080
+			"#,
+		)
+		.unwrap();
+		assert_eq!(config.backend, BackendConfig::InMemory);
+		assert!(config.require_postgresql_config().is_err());
+	}
+
+	#[test]
+	fn read_secret_rejects_ambiguous_config() {
+		let result =
+			read_secret(&Some("inline".to_string()), &Some("/some/path".to_string()), "password");
+		assert!(result.is_err());
+		assert!(read_secret(&None, &None, "password").is_err());
+	}
+
+	#[test]
+	fn read_secret_reads_file_and_strips_trailing_newline() {
+		let path = std::env::temp_dir().join("vss-config-test-secret");
+		fs::write(&path, "s3cret\n").unwrap();
+		let secret =
+			read_secret(&None, &Some(path.to_str().unwrap().to_string()), "password").unwrap();
+		fs::remove_file(&path).unwrap();
+		assert_eq!(secret, "s3cret");
+	}
+}
@@ -0,0 +1,166 @@
+//! The device registry, tracking which devices operate under a user.
+//!
+//! Device records are kept as regular versioned keys in the reserved [`DEVICE_STORE_ID`] store,
+//! keyed by `device_id` and holding an encoded [`DeviceRecord`]. Like leases, the registry is
+//! thus persisted by whatever backend is configured and needs no server-side state; a device can
+//! be removed again with a plain delete on that store.
+
+use std::sync::Arc;
+
+use prost::Message;
+
+use api::error::VssError;
+use api::kv_store::{KvStore, RequestContext, SWAP_MAX_ATTEMPTS};
+use api::types::{
+	DeviceRecord, GetObjectRequest, KeyValue, ListDevicesRequest, ListDevicesResponse,
+	ListKeyVersionsRequest, PutObjectRequest, RegisterDeviceRequest, RegisterDeviceResponse,
+};
+
+use crate::capture::now_millis;
+
+/// The reserved store device records are kept in, see [`RegisterDeviceRequest`].
+///
+/// [`RegisterDeviceRequest`]: api::types::RegisterDeviceRequest
+pub const DEVICE_STORE_ID: &str = "vss_devices";
+
+/// Registers the device, or refreshes its record if the `device_id` is already known: platform
+/// and push token are replaced, `last_seen_at_millis` is bumped and `registered_at_millis` is
+/// preserved.
+pub(crate) async fn register(
+	store: Arc<dyn KvStore>, context: RequestContext, request: RegisterDeviceRequest,
+) -> Result<RegisterDeviceResponse, VssError> {
+	let mut attempts = SWAP_MAX_ATTEMPTS;
+	loop {
+		let get_request = GetObjectRequest {
+			store_id: DEVICE_STORE_ID.to_string(),
+			key: request.device_id.clone(),
+		};
+		let (existing, version) = match store.get(context.clone(), get_request).await {
+			Ok(response) => match response.value {
+				Some(kv) => (DeviceRecord::decode(&kv.value[..]).ok(), kv.version),
+				None => (None, 0),
+			},
+			Err(VssError::NoSuchKeyError(..)) => (None, 0),
+			Err(e) => return Err(e),
+		};
+		let now = now_millis() as i64;
+		let record = DeviceRecord {
+			device_id: request.device_id.clone(),
+			platform: request.platform.clone(),
+			push_token: request.push_token.clone(),
+			registered_at_millis: existing
+				.map(|record| record.registered_at_millis)
+				.unwrap_or(now),
+			last_seen_at_millis: now,
+		};
+		let put_request = PutObjectRequest {
+			store_id: DEVICE_STORE_ID.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: request.device_id.clone(),
+				version,
+				value: record.encode_to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		attempts -= 1;
+		match store.put(context.clone(), put_request).await {
+			Ok(..) => return Ok(RegisterDeviceResponse { device: Some(record) }),
+			Err(VssError::ConflictError(..)) if attempts > 0 => continue,
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+/// Lists all devices registered under the user, ordered by `device_id`.
+///
+/// Records which fail to decode (e.g. written directly through the put API) are skipped rather
+/// than failing the whole listing.
+pub(crate) async fn list(
+	store: Arc<dyn KvStore>, context: RequestContext, _request: ListDevicesRequest,
+) -> Result<ListDevicesResponse, VssError> {
+	let mut devices = Vec::new();
+	let mut page_token: Option<String> = None;
+	loop {
+		let list_request = ListKeyVersionsRequest {
+			store_id: DEVICE_STORE_ID.to_string(),
+			key_prefix: None,
+			page_size: None,
+			page_token: page_token.clone(),
+		};
+		let response = store.list_key_versions(context.clone(), list_request).await?;
+		for key_version in &response.key_versions {
+			let get_request = GetObjectRequest {
+				store_id: DEVICE_STORE_ID.to_string(),
+				key: key_version.key.clone(),
+			};
+			let get_response = match store.get(context.clone(), get_request).await {
+				Ok(get_response) => get_response,
+				// The device was removed between the listing and the read.
+				Err(VssError::NoSuchKeyError(..)) => continue,
+				Err(e) => return Err(e),
+			};
+			if let Some(kv) = get_response.value {
+				if let Ok(record) = DeviceRecord::decode(&kv.value[..]) {
+					devices.push(record);
+				}
+			}
+		}
+		match response.next_page_token {
+			Some(token) if !token.is_empty() => page_token = Some(token),
+			_ => break,
+		}
+	}
+	Ok(ListDevicesResponse { devices })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use impls::memory_store::MemoryBackendImpl;
+
+	fn register_request(device_id: &str, platform: &str) -> RegisterDeviceRequest {
+		RegisterDeviceRequest {
+			device_id: device_id.to_string(),
+			platform: platform.to_string(),
+			push_token: "token".to_string(),
+		}
+	}
+
+	#[tokio::test]
+	async fn devices_register_refresh_and_list() {
+		let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+		let context = RequestContext::new("user".to_string());
+
+		let response =
+			register(Arc::clone(&store), context.clone(), register_request("dev-b", "android"))
+				.await
+				.unwrap();
+		let registered_at = response.device.unwrap().registered_at_millis;
+		register(Arc::clone(&store), context.clone(), register_request("dev-a", "ios"))
+			.await
+			.unwrap();
+
+		// Re-registering replaces the mutable fields but preserves the registration timestamp.
+		let response =
+			register(Arc::clone(&store), context.clone(), register_request("dev-b", "desktop"))
+				.await
+				.unwrap();
+		let device = response.device.unwrap();
+		assert_eq!(device.platform, "desktop");
+		assert_eq!(device.registered_at_millis, registered_at);
+		assert!(device.last_seen_at_millis >= registered_at);
+
+		let response =
+			list(Arc::clone(&store), context.clone(), ListDevicesRequest {}).await.unwrap();
+		let device_ids: Vec<&str> =
+			response.devices.iter().map(|device| device.device_id.as_str()).collect();
+		assert_eq!(device_ids, ["dev-a", "dev-b"]);
+
+		// Another user's registry is empty.
+		let other_context = RequestContext::new("other_user".to_string());
+		let response = list(Arc::clone(&store), other_context, ListDevicesRequest {}).await.unwrap();
+		assert!(response.devices.is_empty());
+	}
+}
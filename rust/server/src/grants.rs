@@ -0,0 +1,222 @@
+//! Read-only store sharing across users.
+//!
+//! A user can authorize another user token (e.g. a watchtower or support tool) read-only access
+//! to a specific store via the `GrantStoreAccess` API. Grants are persisted as regular versioned
+//! keys in a reserved system namespace of the backing store, so like leases and device records
+//! they survive restarts with whatever backend is configured — a watchtower's access must not
+//! silently disappear when the server rotates. An in-memory table mirrors the persisted grants
+//! for synchronous lookups on the request path; it is rebuilt from the store at startup. A
+//! granted caller names the owner in the `x-vss-store-owner` request header and, once the grant
+//! checks out, reads the store under the owner's token.
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use api::error::VssError;
+use api::kv_store::{KvStore, RequestContext};
+use api::types::{
+	DeleteObjectRequest, GetObjectRequest, KeyValue, ListKeyVersionsRequest, PutObjectRequest,
+};
+
+/// The request header a grantee names the store owner in, see [`GrantStoreAccessRequest`].
+///
+/// [`GrantStoreAccessRequest`]: api::types::GrantStoreAccessRequest
+pub const GRANT_OWNER_HEADER: &str = "x-vss-store-owner";
+
+/// The reserved system user token and store id grants are persisted under. Kept out of any real
+/// user's namespace so user-scoped quotas and admin tooling never touch it.
+const GRANTS_USER_TOKEN: &str = "vss_grants";
+const GRANTS_STORE_ID: &str = "vss_grants";
+
+/// The persisted form of a single grant. Tokens may be arbitrary strings, so the record is kept
+/// in the value (as JSON, like the capture and mutation logs) while the key is a digest of the
+/// triple.
+#[derive(Debug, Serialize, Deserialize)]
+struct GrantRecord {
+	owner_token: String,
+	grantee_token: String,
+	store_id: String,
+}
+
+/// The table of active read-only store grants, keyed by owner, grantee and store.
+///
+/// With user token hashing configured, entries hold hashed tokens like every other server-side
+/// bookkeeping structure.
+pub struct GrantRegistry {
+	store: Arc<dyn KvStore>,
+	grants: RwLock<HashSet<(String, String, String)>>,
+}
+
+impl GrantRegistry {
+	pub fn new(store: Arc<dyn KvStore>) -> Self {
+		GrantRegistry { store, grants: RwLock::new(HashSet::new()) }
+	}
+
+	/// Rebuilds the in-memory table from the persisted grants; called once at startup, before
+	/// the service answers requests. Records which fail to decode are skipped rather than
+	/// failing startup.
+	pub async fn load(&self) -> Result<(), VssError> {
+		let mut page_token: Option<String> = None;
+		loop {
+			let list_request = ListKeyVersionsRequest {
+				store_id: GRANTS_STORE_ID.to_string(),
+				key_prefix: None,
+				page_size: None,
+				page_token: page_token.clone(),
+			};
+			let response = self.store.list_key_versions(Self::context(), list_request).await?;
+			for key_version in &response.key_versions {
+				let get_request = GetObjectRequest {
+					store_id: GRANTS_STORE_ID.to_string(),
+					key: key_version.key.clone(),
+				};
+				let get_response = match self.store.get(Self::context(), get_request).await {
+					Ok(get_response) => get_response,
+					// The grant was revoked between the listing and the read.
+					Err(VssError::NoSuchKeyError(..)) => continue,
+					Err(e) => return Err(e),
+				};
+				if let Some(kv) = get_response.value {
+					if let Ok(record) = serde_json::from_slice::<GrantRecord>(&kv.value) {
+						self.grants.write().unwrap().insert((
+							record.owner_token,
+							record.grantee_token,
+							record.store_id,
+						));
+					}
+				}
+			}
+			match response.next_page_token {
+				Some(token) if !token.is_empty() => page_token = Some(token),
+				_ => break,
+			}
+		}
+		Ok(())
+	}
+
+	/// Authorizes `grantee_token` read-only access to the owner's store. Idempotent. The grant
+	/// is written through to the backing store before it becomes visible.
+	pub async fn grant(
+		&self, owner_token: String, grantee_token: String, store_id: String,
+	) -> Result<(), VssError> {
+		let record = GrantRecord {
+			owner_token: owner_token.clone(),
+			grantee_token: grantee_token.clone(),
+			store_id: store_id.clone(),
+		};
+		let value = serde_json::to_vec(&record)
+			.map_err(|e| VssError::InternalServerError(format!("Failed to encode grant: {}", e)))?;
+		let put_request = PutObjectRequest {
+			store_id: GRANTS_STORE_ID.to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: Self::grant_key(&owner_token, &grantee_token, &store_id),
+				version: -1,
+				value: value.into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		self.store.put(Self::context(), put_request).await?;
+		self.grants.write().unwrap().insert((owner_token, grantee_token, store_id));
+		Ok(())
+	}
+
+	/// Withdraws a previously issued grant. Revoking an absent grant is a no-op.
+	pub async fn revoke(
+		&self, owner_token: &str, grantee_token: &str, store_id: &str,
+	) -> Result<(), VssError> {
+		let delete_request = DeleteObjectRequest {
+			store_id: GRANTS_STORE_ID.to_string(),
+			key_value: Some(KeyValue {
+				key: Self::grant_key(owner_token, grantee_token, store_id),
+				version: -1,
+				value: Default::default(),
+			}),
+		};
+		self.store.delete(Self::context(), delete_request).await?;
+		self.grants.write().unwrap().remove(&(
+			owner_token.to_string(),
+			grantee_token.to_string(),
+			store_id.to_string(),
+		));
+		Ok(())
+	}
+
+	/// Returns whether `grantee_token` is currently authorized to read the owner's store.
+	pub fn is_granted(&self, owner_token: &str, grantee_token: &str, store_id: &str) -> bool {
+		self.grants.read().unwrap().contains(&(
+			owner_token.to_string(),
+			grantee_token.to_string(),
+			store_id.to_string(),
+		))
+	}
+
+	fn context() -> RequestContext {
+		RequestContext::new(GRANTS_USER_TOKEN.to_string())
+	}
+
+	/// Maps a grant onto its storage key. Tokens and store ids may be arbitrary strings, so a
+	/// digest sidesteps both separator ambiguity and the key length limit.
+	fn grant_key(owner_token: &str, grantee_token: &str, store_id: &str) -> String {
+		let mut hasher = Sha256::new();
+		hasher.update(owner_token.as_bytes());
+		hasher.update([0]);
+		hasher.update(grantee_token.as_bytes());
+		hasher.update([0]);
+		hasher.update(store_id.as_bytes());
+		hex::encode(hasher.finalize())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use impls::memory_store::MemoryBackendImpl;
+
+	#[tokio::test]
+	async fn grants_are_scoped_to_owner_grantee_and_store() {
+		let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+		let registry = GrantRegistry::new(store);
+		registry
+			.grant("owner".to_string(), "watchtower".to_string(), "store".to_string())
+			.await
+			.unwrap();
+
+		assert!(registry.is_granted("owner", "watchtower", "store"));
+		// A grant covers exactly one (owner, grantee, store) combination.
+		assert!(!registry.is_granted("owner", "watchtower", "other_store"));
+		assert!(!registry.is_granted("owner", "other_user", "store"));
+		assert!(!registry.is_granted("other_owner", "watchtower", "store"));
+
+		registry.revoke("owner", "watchtower", "store").await.unwrap();
+		assert!(!registry.is_granted("owner", "watchtower", "store"));
+		// Revoking again is a no-op.
+		registry.revoke("owner", "watchtower", "store").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn grants_survive_a_restart() {
+		let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+		let registry = GrantRegistry::new(Arc::clone(&store));
+		registry
+			.grant("owner".to_string(), "watchtower".to_string(), "store".to_string())
+			.await
+			.unwrap();
+		registry
+			.grant("owner".to_string(), "support".to_string(), "other_store".to_string())
+			.await
+			.unwrap();
+		registry.revoke("owner", "support", "other_store").await.unwrap();
+
+		// A fresh registry over the same backend — as after a server restart — reloads the
+		// issued grants, and only those.
+		let reloaded = GrantRegistry::new(store);
+		reloaded.load().await.unwrap();
+		assert!(reloaded.is_granted("owner", "watchtower", "store"));
+		assert!(!reloaded.is_granted("owner", "support", "other_store"));
+	}
+}
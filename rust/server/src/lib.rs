@@ -0,0 +1,22 @@
+//! Building blocks of the VSS server: configuration parsing, secret resolution, tenant routing
+//! and the HTTP services.
+//!
+//! These modules are consumed by the `vss-server` binary and by the end-to-end tests in
+//! `tests/`, which boot the real HTTP service against the in-memory backend.
+
+pub mod admin_service;
+pub mod alerts;
+pub mod backup;
+pub mod capture;
+pub mod config;
+pub mod devices;
+pub mod grants;
+pub mod lease;
+pub mod metrics;
+pub mod mutation_log;
+pub mod rate_limit;
+pub mod replication;
+pub mod secrets;
+pub mod tenants;
+pub mod trial;
+pub mod vss_service;
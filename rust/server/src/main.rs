@@ -0,0 +1,1237 @@
+//! A server-side implementation of the Versioned Storage Service (VSS).
+
+use std::fs;
+use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use api::auth::{AuthFailureAuditLog, Authorizer, NoopAuthorizer};
+use api::kv_store::{KvStore, KvStoreAdmin};
+use impls::auth::api_key_authorizer::{
+	ApiKeyAuthorizer, ApiKeySource, EnvApiKeySource, FileApiKeySource, API_KEY_HEADER,
+};
+use impls::auth::caching_authorizer::CachingAuthorizer;
+use impls::auth::jwt_authorizer::JwtAuthorizer;
+use impls::auth::mtls_authorizer::{ClientIdentity, MtlsAuthorizer};
+use impls::auth::oidc_authorizer::OidcAuthorizer;
+use impls::auth::remote_authorizer::RemoteAuthorizer;
+use impls::auth::revocation::{FileRevocationList, RevocationList};
+use impls::dynamodb_store::DynamoDbBackendImpl;
+use impls::etcd_store::EtcdBackendImpl;
+use impls::fs_store::FsBackendImpl;
+use impls::memory_store::MemoryBackendImpl;
+use impls::postgres_store::{DsnSource, PostgresBackendImpl};
+use impls::redis_store::RedisBackendImpl;
+use impls::sharded_store::ShardedKvStore;
+#[cfg(feature = "sled")]
+use impls::sled_store::SledBackendImpl;
+
+use vss_server::admin_service::{AdminService, AdminState, LogFilterHandle};
+use vss_server::alerts::AlertMonitor;
+use vss_server::backup::{parse_backup_key, BackupJob};
+use vss_server::capture::CaptureLog;
+use vss_server::config::{
+	self, ApiKeyAuthorizerConfig, ApiKeySourceConfig, BackendConfig, Config, DynamodbConfig,
+	JwtAuthorizerConfig, MtlsAuthorizerConfig, MtlsIdentityConfig,
+	NoopAuthorizerConfig, OidcAuthorizerConfig, PostgresqlConfig, RemoteAuthorizerConfig,
+	TlsConfig,
+};
+use vss_server::metrics::{init_meter_provider, RequestMetrics};
+use vss_server::mutation_log::{MutationLog, MutationLoggingKvStore};
+use vss_server::rate_limit::RateLimiter;
+use vss_server::replication::{ReplicatedKvStore, DEFAULT_REPLICATION_QUEUE_SIZE};
+use vss_server::secrets::{self, AwsCredentials, ResolvedSecret, RotatingAuthorizer};
+use vss_server::tenants::{Tenant, TenantRegistry};
+use vss_server::trial::TrialRegistry;
+use vss_server::vss_service::{UserTokenHasher, ValidationLimits, VssService};
+
+/// The maximum number of concurrently processed requests per HTTP/2 connection, bounding what a
+/// single multiplexing client (e.g. a reverse proxy) can have in flight at once.
+const MAX_CONCURRENT_HTTP2_STREAMS: u32 = 256;
+
+/// How long the server keeps retrying to reach an unavailable database at startup by default,
+/// see `startup_max_wait_secs` in [`PostgresqlConfig`].
+const DEFAULT_STARTUP_MAX_WAIT_SECS: u64 = 60;
+
+fn main() {
+	let mut args: Vec<String> = std::env::args().collect();
+	let smoke_test = args.iter().any(|arg| arg == "--smoke-test");
+	let require_migrated = args.iter().any(|arg| arg == "--require-migrated");
+	let dry_run = args.iter().any(|arg| arg == "--dry-run");
+	args.retain(|arg| arg != "--smoke-test" && arg != "--require-migrated" && arg != "--dry-run");
+	let rollback_to = match args.iter().position(|arg| arg == "--to") {
+		Some(position) => {
+			if position + 1 >= args.len() {
+				eprintln!("--to requires a version argument.");
+				exit(1);
+			}
+			let target_version = args[position + 1].parse::<usize>().unwrap_or_else(|_| {
+				eprintln!("Invalid version: {}", args[position + 1]);
+				exit(1);
+			});
+			args.drain(position..=position + 1);
+			Some(target_version)
+		},
+		None => None,
+	};
+	let migrate = args.len() > 1 && args[1] == "migrate";
+	let rollback_schema = args.len() > 1 && args[1] == "rollback-schema";
+	if migrate || rollback_schema {
+		args.remove(1);
+	}
+	if args.len() != 2 || (rollback_schema && rollback_to.is_none()) {
+		eprintln!(
+			"Usage: {} [migrate [--dry-run] | rollback-schema --to <version>] <config_file_path> [--smoke-test] [--require-migrated]",
+			args[0]
+		);
+		exit(1);
+	}
+	let config_file_contents = fs::read_to_string(&args[1]).unwrap_or_else(|e| {
+		eprintln!("Failed to read config file {}: {}", args[1], e);
+		exit(1);
+	});
+	let config = config::parse_config(&config_file_contents).unwrap_or_else(|e| {
+		eprintln!("Failed to parse config file {}: {}", args[1], e);
+		exit(1);
+	});
+
+	// The level filter (INFO unless overridden via RUST_LOG) is wrapped in a reload layer, so
+	// the admin API can adjust the effective filter at runtime without a restart.
+	let env_filter = tracing_subscriber::EnvFilter::builder()
+		.with_default_directive(tracing::level_filters::LevelFilter::INFO.into())
+		.from_env_lossy();
+	let default_directives = env_filter.to_string();
+	let (filter_layer, reload_handle) = tracing_subscriber::reload::Layer::new(env_filter);
+	let log_filter = Arc::new(LogFilterHandle::new(reload_handle, default_directives));
+
+	// Sentry is initialized before the async runtime starts and the guard is held until exit,
+	// flushing pending events on drop. With Sentry configured, the tracing subscriber gains a
+	// layer forwarding the request spans (and their backend child spans) as sampled performance
+	// transactions.
+	let _sentry_guard = match &config.sentry_config {
+		Some(sentry_config) => {
+			let mut options = sentry::ClientOptions::new()
+				.traces_sample_rate(sentry_config.traces_sample_rate);
+			options.release = sentry::release_name!();
+			let guard = sentry::init((sentry_config.dsn.clone(), options));
+			tracing_subscriber::registry()
+				.with(filter_layer)
+				.with(tracing_subscriber::fmt::layer())
+				.with(sentry_tracing::layer().span_filter(|metadata| metadata.is_span()))
+				.init();
+			Some(guard)
+		},
+		None => {
+			tracing_subscriber::registry()
+				.with(filter_layer)
+				.with(tracing_subscriber::fmt::layer())
+				.init();
+			None
+		},
+	};
+
+	let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+	runtime.block_on(async {
+		if migrate {
+			let postgres_config = config.require_postgresql_config().unwrap_or_else(|e| {
+				error!("Invalid config: {}", e);
+				exit(1);
+			});
+			let dsn = resolve_dsn(postgres_config).await.unwrap_or_else(|e| {
+				error!("Invalid postgresql_config: {}", e);
+				exit(1);
+			});
+			let custom_migrations = &postgres_config.custom_migrations;
+			if dry_run {
+				match impls::migrations::plan_database_migrations(&dsn, custom_migrations).await {
+					Ok(plan) => {
+						println!("Current schema version: {}", plan.current_version);
+						println!("Target schema version:  {}", plan.target_version);
+						if plan.statements.is_empty() && plan.custom_statements.is_empty() {
+							println!("No pending migrations.");
+						} else {
+							for (offset, statement) in plan.statements.iter().enumerate() {
+								println!(
+									"-- Migration {}:\n{};",
+									plan.current_version + offset + 1,
+									statement
+								);
+							}
+							for statement in &plan.custom_statements {
+								println!("-- Custom migration:\n{};", statement);
+							}
+						}
+						exit(0);
+					},
+					Err(e) => {
+						error!("Failed to plan schema migrations: {}", e);
+						exit(1);
+					},
+				}
+			}
+			match impls::migrations::migrate_database(&dsn, custom_migrations).await {
+				Ok(()) => {
+					info!("Schema migrations applied.");
+					exit(0);
+				},
+				Err(e) => {
+					error!("Failed to apply schema migrations: {}", e);
+					exit(1);
+				},
+			}
+		}
+		if rollback_schema {
+			let postgres_config = config.require_postgresql_config().unwrap_or_else(|e| {
+				error!("Invalid config: {}", e);
+				exit(1);
+			});
+			let dsn = resolve_dsn(postgres_config).await.unwrap_or_else(|e| {
+				error!("Invalid postgresql_config: {}", e);
+				exit(1);
+			});
+			let target_version = rollback_to.expect("checked above");
+			match impls::migrations::rollback_database(&dsn, target_version).await {
+				Ok(()) => {
+					info!("Schema rolled back to version {}.", target_version);
+					exit(0);
+				},
+				Err(e) => {
+					error!("Failed to roll back schema: {}", e);
+					exit(1);
+				},
+			}
+		}
+		if smoke_test {
+			match run_smoke_test(config).await {
+				Ok(()) => {
+					info!("Smoke test passed.");
+					exit(0);
+				},
+				Err(e) => {
+					error!("Smoke test failed: {}", e);
+					exit(1);
+				},
+			}
+		}
+		if let Err(e) = run_server(config, require_migrated, log_filter).await {
+			error!("Failed to run server: {}", e);
+			exit(1);
+		}
+	});
+}
+
+/// A cheap post-deploy verification: connects to the configured backend, performs a
+/// put/get/delete round trip against a reserved internal store and verifies that the configured
+/// authorizer can be constructed.
+async fn run_smoke_test(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+	let mut api_key_source: Option<Arc<dyn ApiKeySource>> = None;
+	let store: Arc<dyn KvStore> = match config.backend {
+		BackendConfig::InMemory => Arc::new(MemoryBackendImpl::new()),
+		BackendConfig::Postgres => {
+			let postgres_config = config.require_postgresql_config()?;
+			let backend =
+				Arc::new(PostgresBackendImpl::new(&resolve_dsn(postgres_config).await?).await?);
+			api_key_source = Some(backend.clone());
+			backend
+		},
+		BackendConfig::DynamoDb => {
+			Arc::new(new_dynamodb_backend(config.require_dynamodb_config()?).await?)
+		},
+		BackendConfig::Etcd => {
+			let etcd_config = config.require_etcd_config()?;
+			Arc::new(EtcdBackendImpl::new(
+				etcd_config.endpoints.clone(),
+				etcd_config.username.clone(),
+				etcd_config.resolve_password()?,
+			)?)
+		},
+		BackendConfig::Fs => Arc::new(FsBackendImpl::new(&config.require_fs_config()?.path)?),
+		BackendConfig::Redis => {
+			let redis_config = config.require_redis_config()?;
+			Arc::new(RedisBackendImpl::new(
+				redis_config.address.clone(),
+				redis_config.resolve_password()?,
+			))
+		},
+		#[cfg(feature = "sled")]
+		BackendConfig::Sled => {
+			Arc::new(SledBackendImpl::new(&config.require_sled_config()?.path)?)
+		},
+	};
+	select_authorizer(&config, api_key_source).await?;
+
+	let context = api::kv_store::RequestContext::new("vss-internal-smoke-test".to_string());
+	let store_id = "vss-smoke-test".to_string();
+	let nanos =
+		std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+	let key = format!("smoke-test-{}", nanos);
+	let value = bytes::Bytes::copy_from_slice(key.as_bytes());
+
+	let put_request = api::types::PutObjectRequest {
+		store_id: store_id.clone(),
+		global_version: None,
+		transaction_items: vec![api::types::KeyValue {
+			key: key.clone(),
+			version: 0,
+			value: value.clone(),
+		}],
+		delete_items: vec![],
+		dry_run: false,
+	};
+	store.put(context.clone(), put_request).await?;
+
+	let get_request = api::types::GetObjectRequest { store_id: store_id.clone(), key: key.clone() };
+	let get_response = store.get(context.clone(), get_request).await?;
+	let key_value = get_response.value.ok_or("Get returned an empty response.")?;
+	if key_value.value != value {
+		return Err("Read back a different value than was written.".into());
+	}
+
+	let delete_request = api::types::DeleteObjectRequest {
+		store_id,
+		key_value: Some(api::types::KeyValue {
+			key,
+			version: key_value.version,
+			value: bytes::Bytes::new(),
+		}),
+	};
+	store.delete(context, delete_request).await?;
+	Ok(())
+}
+
+/// Resolves the connection string (once, without background refresh), for one-shot uses like
+/// the `migrate` subcommand.
+/// Constructs the DynamoDB backend from its configuration, with credentials taken from the
+/// standard `AWS_*` environment variables, optionally creating the table for local development.
+async fn new_dynamodb_backend(
+	dynamodb_config: &DynamodbConfig,
+) -> Result<DynamoDbBackendImpl, Box<dyn std::error::Error>> {
+	let credentials = AwsCredentials::from_env()?;
+	let backend = DynamoDbBackendImpl::new(
+		dynamodb_config.region.clone(),
+		dynamodb_config.endpoint.clone(),
+		dynamodb_config.table_name.clone(),
+		credentials,
+	)?;
+	if dynamodb_config.create_table.unwrap_or(false) {
+		backend.create_table_if_missing().await?;
+	}
+	Ok(backend)
+}
+
+/// Connects to a PostgreSQL backend, retrying with backoff while the database is unavailable.
+///
+/// The database may briefly be unavailable when the server boots (common with docker-compose
+/// and Kubernetes start ordering): retry with backoff up to the configured max wait instead of
+/// exiting. The listener is only bound once the backend is reachable, so readiness probes keep
+/// failing while the server waits.
+///
+/// With --require-migrated, refuse startup on a pending schema migration instead of running DDL
+/// implicitly at boot.
+async fn connect_postgres_backend(
+	postgres_config: &PostgresqlConfig, dsn_source: Arc<dyn DsnSource>, require_migrated: bool,
+) -> Result<PostgresBackendImpl, Box<dyn std::error::Error>> {
+	let max_wait = Duration::from_secs(
+		postgres_config.startup_max_wait_secs.unwrap_or(DEFAULT_STARTUP_MAX_WAIT_SECS),
+	);
+	let started_at = std::time::Instant::now();
+	let mut retry_delay = Duration::from_millis(500);
+	let backend = loop {
+		let result = if require_migrated {
+			PostgresBackendImpl::connect_with_dsn_source(Arc::clone(&dsn_source)).await
+		} else {
+			match PostgresBackendImpl::new_with_dsn_source(Arc::clone(&dsn_source)).await {
+				Ok(backend) => backend
+					.apply_custom_migrations(&postgres_config.custom_migrations)
+					.await
+					.map(|()| backend),
+				Err(e) => Err(e),
+			}
+		};
+		match result {
+			Ok(backend) => break backend,
+			Err(e) if started_at.elapsed() + retry_delay <= max_wait => {
+				warn!("Failed to connect to backend ({}), retrying in {:?}.", e, retry_delay);
+				tokio::time::sleep(retry_delay).await;
+				retry_delay = (retry_delay * 2).min(Duration::from_secs(10));
+			},
+			Err(e) => return Err(e.into()),
+		}
+	};
+	Ok(match postgres_config.slow_query_threshold_ms {
+		Some(threshold_ms) => {
+			backend.with_slow_query_threshold(Duration::from_millis(threshold_ms))
+		},
+		None => backend,
+	})
+}
+
+async fn resolve_dsn(postgres_config: &PostgresqlConfig) -> Result<String, String> {
+	if let Some(dsn) = postgres_config.dsn_override()? {
+		return Ok(dsn);
+	}
+	let password = resolve_postgres_password(postgres_config).await?;
+	postgres_config.connection_string_with_password(&password.current())
+}
+
+async fn resolve_postgres_password(
+	postgres_config: &PostgresqlConfig,
+) -> Result<ResolvedSecret, String> {
+	secrets::resolve_secret(
+		&postgres_config.password,
+		&postgres_config.password_file,
+		&postgres_config.password_provider,
+		"password",
+	)
+	.await
+}
+
+/// A [`DsnSource`] rendering the connection string from the current (possibly rotated) password.
+struct PostgresDsnSource {
+	postgres_config: Arc<PostgresqlConfig>,
+	password: ResolvedSecret,
+}
+
+impl DsnSource for PostgresDsnSource {
+	fn dsn(&self) -> String {
+		self.postgres_config
+			.connection_string_with_password(&self.password.current())
+			.expect("discrete fields were validated at startup")
+	}
+}
+
+/// A [`DsnSource`] for a fixed, fully operator-supplied connection string.
+struct StaticDsnSource(String);
+
+impl DsnSource for StaticDsnSource {
+	fn dsn(&self) -> String {
+		self.0.clone()
+	}
+}
+
+async fn build_authorizer(
+	jwt_authorizer_config: Option<&JwtAuthorizerConfig>,
+	noop_authorizer_config: Option<&NoopAuthorizerConfig>,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	match jwt_authorizer_config {
+		Some(jwt_config) => build_jwt_authorizer(jwt_config).await,
+		None => build_noop_authorizer(noop_authorizer_config),
+	}
+}
+
+/// Builds the server-wide authorizer from whichever authorizer config is set, enforcing that at
+/// most one of them is.
+async fn select_authorizer(
+	config: &Config, api_key_source: Option<Arc<dyn ApiKeySource>>,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let configured = usize::from(config.jwt_authorizer_config.is_some())
+		+ usize::from(config.api_key_authorizer_config.is_some())
+		+ usize::from(config.oidc_authorizer_config.is_some())
+		+ usize::from(config.mtls_authorizer_config.is_some())
+		+ usize::from(config.remote_authorizer_config.is_some());
+	if configured > 1 {
+		return Err("At most one of jwt_authorizer_config, api_key_authorizer_config, \
+			oidc_authorizer_config, mtls_authorizer_config and remote_authorizer_config may be \
+			set."
+			.into());
+	}
+	let authorizer = if let Some(mtls_config) = &config.mtls_authorizer_config {
+		build_mtls_authorizer(config, mtls_config)?
+	} else if let Some(remote_config) = &config.remote_authorizer_config {
+		build_remote_authorizer(remote_config)?
+	} else if let Some(api_key_config) = &config.api_key_authorizer_config {
+		build_api_key_authorizer(api_key_config, api_key_source).await?
+	} else if let Some(oidc_config) = &config.oidc_authorizer_config {
+		build_oidc_authorizer(oidc_config).await?
+	} else {
+		build_authorizer(
+			config.jwt_authorizer_config.as_ref(),
+			config.noop_authorizer_config.as_ref(),
+		)
+		.await?
+	};
+	match &config.auth_cache_config {
+		Some(cache_config) => {
+			// The cache key must cover every credential input the wrapped authorizer reads, or
+			// one caller's cached identity could answer for another within the TTL.
+			let credential_headers = if config.mtls_authorizer_config.is_some() {
+				// The peer certificate is always part of the key; no headers are read.
+				vec![]
+			} else if config.api_key_authorizer_config.is_some() {
+				vec![API_KEY_HEADER.to_string()]
+			} else if let Some(remote_config) = &config.remote_authorizer_config {
+				remote_config
+					.forwarded_headers
+					.clone()
+					.unwrap_or_else(|| vec!["authorization".to_string()])
+			} else {
+				vec!["authorization".to_string()]
+			};
+			let caching = CachingAuthorizer::new(authorizer)
+				.with_ttl(Duration::from_secs(cache_config.ttl_secs))
+				.with_credential_headers(credential_headers);
+			let caching = match cache_config.max_entries {
+				Some(max_entries) => caching.with_max_entries(max_entries),
+				None => caching,
+			};
+			Ok(Arc::new(caching))
+		},
+		None => Ok(authorizer),
+	}
+}
+
+/// Builds the mTLS authorizer, enforcing that the listener actually terminates TLS and demands
+/// client certificates — without that, no request would ever carry one and every request would
+/// be rejected.
+fn build_mtls_authorizer(
+	config: &Config, mtls_config: &MtlsAuthorizerConfig,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	match &config.tls_config {
+		Some(tls_config) if tls_config.client_ca_path.is_some() => {},
+		_ => {
+			return Err(
+				"mtls_authorizer_config requires tls_config with client_ca_path set.".into()
+			)
+		},
+	}
+	let identity = match mtls_config.identity {
+		MtlsIdentityConfig::SpkiSha256 => ClientIdentity::SpkiSha256,
+		MtlsIdentityConfig::SubjectCommonName => ClientIdentity::SubjectCommonName,
+	};
+	Ok(Arc::new(MtlsAuthorizer::new().with_identity(identity)))
+}
+
+fn build_remote_authorizer(
+	remote_config: &RemoteAuthorizerConfig,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let authorizer = RemoteAuthorizer::new(remote_config.url.clone())?;
+	let authorizer = match &remote_config.forwarded_headers {
+		Some(forwarded_headers) => authorizer.with_forwarded_headers(forwarded_headers.clone()),
+		None => authorizer,
+	};
+	let authorizer = match remote_config.cache_ttl_secs {
+		Some(cache_ttl_secs) => authorizer.with_cache_ttl(Duration::from_secs(cache_ttl_secs)),
+		None => authorizer,
+	};
+	Ok(Arc::new(authorizer))
+}
+
+async fn build_oidc_authorizer(
+	oidc_config: &OidcAuthorizerConfig,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let authorizer =
+		OidcAuthorizer::new(oidc_config.issuer.clone(), oidc_config.audience.clone()).await?;
+	let authorizer = match oidc_config.refresh_cooldown_secs {
+		Some(refresh_cooldown_secs) => {
+			authorizer.with_refresh_cooldown(Duration::from_secs(refresh_cooldown_secs))
+		},
+		None => authorizer,
+	};
+	Ok(Arc::new(authorizer))
+}
+
+/// Builds the API key authorizer; `db_source` is the backend handle serving the `database`
+/// source, set only on backends carrying the `vss_api_keys` table.
+async fn build_api_key_authorizer(
+	api_key_config: &ApiKeyAuthorizerConfig, db_source: Option<Arc<dyn ApiKeySource>>,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let source: Arc<dyn ApiKeySource> = match api_key_config.source {
+		ApiKeySourceConfig::File => {
+			let path = api_key_config
+				.path
+				.clone()
+				.ok_or("path must be set with api key source = \"file\".")?;
+			Arc::new(FileApiKeySource::new(path))
+		},
+		ApiKeySourceConfig::Env => {
+			let env_var = api_key_config
+				.env_var
+				.clone()
+				.ok_or("env_var must be set with api key source = \"env\".")?;
+			Arc::new(EnvApiKeySource::new(env_var))
+		},
+		ApiKeySourceConfig::Database => db_source.ok_or(
+			"The database API key source is only available with the unsharded PostgreSQL \
+			backend.",
+		)?,
+	};
+	let authorizer = ApiKeyAuthorizer::new(source).await?;
+	let authorizer = match api_key_config.refresh_interval_secs {
+		Some(refresh_interval_secs) => {
+			authorizer.with_refresh_interval(Duration::from_secs(refresh_interval_secs))
+		},
+		None => authorizer,
+	};
+	Ok(Arc::new(authorizer))
+}
+
+fn build_noop_authorizer(
+	noop_authorizer_config: Option<&NoopAuthorizerConfig>,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let (fixed_user_token, trusted_header) = match noop_authorizer_config {
+		Some(noop_config) => {
+			(noop_config.fixed_user_token.as_ref(), noop_config.trusted_header.as_ref())
+		},
+		None => (None, None),
+	};
+	match (fixed_user_token, trusted_header) {
+		(Some(_), Some(_)) => {
+			Err("Only one of fixed_user_token and trusted_header may be set.".into())
+		},
+		(Some(user_token), None) => {
+			warn!("No authorizer configured, all requests will be mapped to user {}.", user_token);
+			Ok(Arc::new(NoopAuthorizer::with_fixed_user_token(user_token.clone())))
+		},
+		(None, Some(header)) => {
+			warn!("No authorizer configured, user tokens are taken from the {} header.", header);
+			Ok(Arc::new(NoopAuthorizer::with_trusted_header(header.clone())))
+		},
+		(None, None) => {
+			warn!("No authorizer configured, all requests will be mapped to a single user.");
+			Ok(Arc::new(NoopAuthorizer::new()))
+		},
+	}
+}
+
+fn new_jwt_authorizer(
+	public_key_pem: &[u8], algorithm: &str, decryption_key_pem: Option<&[u8]>,
+	revocation_list: Option<&Arc<dyn RevocationList>>,
+) -> Result<JwtAuthorizer, api::error::VssError> {
+	let authorizer = JwtAuthorizer::new_with_algorithm(public_key_pem, algorithm)?;
+	let authorizer = match decryption_key_pem {
+		Some(pem) => authorizer.with_decryption_key(pem)?,
+		None => authorizer,
+	};
+	match revocation_list {
+		Some(revocation_list) => Ok(authorizer.with_revocation_list(Arc::clone(revocation_list))),
+		None => Ok(authorizer),
+	}
+}
+
+async fn build_jwt_authorizer(
+	jwt_config: &JwtAuthorizerConfig,
+) -> Result<Arc<dyn Authorizer>, Box<dyn std::error::Error>> {
+	let decryption_key_pem = match &jwt_config.decryption_key_pem_path {
+		Some(path) => Some(fs::read(path)?),
+		None => None,
+	};
+	let algorithm = jwt_config.algorithm.clone().unwrap_or_else(|| "RS256".to_string());
+	let revocation_list: Option<Arc<dyn RevocationList>> = match &jwt_config.revocation_list_path
+	{
+		Some(path) => {
+			let revocation_list = FileRevocationList::new(path.clone())?;
+			let revocation_list = match jwt_config.revocation_list_refresh_secs {
+				Some(refresh_secs) => {
+					revocation_list.with_refresh_interval(Duration::from_secs(refresh_secs))
+				},
+				None => revocation_list,
+			};
+			Some(Arc::new(revocation_list))
+		},
+		None => None,
+	};
+	match (&jwt_config.public_key_pem_path, &jwt_config.public_key_pem_provider) {
+		(Some(_), Some(_)) => {
+			Err("Only one of public_key_pem_path and public_key_pem_provider may be set.".into())
+		},
+		(Some(path), None) => {
+			let public_key_pem = fs::read(path)?;
+			Ok(Arc::new(new_jwt_authorizer(
+				&public_key_pem,
+				&algorithm,
+				decryption_key_pem.as_deref(),
+				revocation_list.as_ref(),
+			)?))
+		},
+		(None, Some(provider)) => {
+			let pem = secrets::resolve_secret(
+				&None,
+				&None,
+				&jwt_config.public_key_pem_provider,
+				"public_key_pem",
+			)
+			.await?;
+			let initial: Arc<dyn Authorizer> = Arc::new(new_jwt_authorizer(
+				pem.current().as_bytes(),
+				&algorithm,
+				decryption_key_pem.as_deref(),
+				revocation_list.as_ref(),
+			)?);
+			let authorizer = Arc::new(RotatingAuthorizer::new(initial));
+			if let Some(interval_secs) =
+				provider.refresh_interval_secs().filter(|interval| *interval > 0)
+			{
+				let authorizer = Arc::clone(&authorizer);
+				let mut last_pem = pem.current();
+				tokio::spawn(async move {
+					loop {
+						tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+						let current_pem = pem.current();
+						if current_pem == last_pem {
+							continue;
+						}
+						match new_jwt_authorizer(
+							current_pem.as_bytes(),
+							&algorithm,
+							decryption_key_pem.as_deref(),
+							revocation_list.as_ref(),
+						) {
+							Ok(new_authorizer) => {
+								authorizer.swap(Arc::new(new_authorizer));
+								last_pem = current_pem;
+							},
+							Err(e) => warn!("Rotated JWT public key is invalid: {}", e),
+						}
+					}
+				});
+			}
+			Ok(authorizer)
+		},
+		(None, None) => {
+			Err("Either public_key_pem_path or public_key_pem_provider must be set.".into())
+		},
+	}
+}
+
+/// Rejects the per-user cap and quota options on backends which do not implement them.
+fn reject_unsupported_caps(
+	server_config: &config::ServerConfig, backend: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+	if server_config.max_stores_per_user.is_some()
+		|| server_config.max_keys_per_store.is_some()
+		|| server_config.max_total_bytes_per_user.is_some()
+		|| server_config.max_keys_per_user.is_some()
+	{
+		return Err(format!(
+			"max_stores_per_user, max_keys_per_store, max_total_bytes_per_user and \
+			max_keys_per_user are not supported on the {} backend.",
+			backend
+		)
+		.into());
+	}
+	Ok(())
+}
+
+/// The handles into the configured storage backend used by the running server.
+struct BackendHandles {
+	store: Arc<dyn KvStore>,
+	admin_store: Arc<dyn KvStoreAdmin>,
+	/// Set for backends supporting the authentication failure audit log.
+	audit_capable: Option<Arc<dyn AuthFailureAuditLog>>,
+	/// Set for backends supporting the database-backed API key source.
+	api_key_source: Option<Arc<dyn ApiKeySource>>,
+}
+
+async fn run_server(
+	config: Config, require_migrated: bool, log_filter: Arc<LogFilterHandle>,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let BackendHandles { store, admin_store, audit_capable, api_key_source } = match config.backend
+	{
+		BackendConfig::InMemory => {
+			warn!("Using the in-memory backend, all data is lost when the process exits.");
+			let mut backend = MemoryBackendImpl::new();
+			if let Some(max_stores) = config.server_config.max_stores_per_user {
+				backend = backend.with_max_stores_per_user(max_stores);
+			}
+			if let Some(max_keys) = config.server_config.max_keys_per_store {
+				backend = backend.with_max_keys_per_store(max_keys);
+			}
+			if let Some(max_bytes) = config.server_config.max_total_bytes_per_user {
+				backend = backend.with_max_total_bytes_per_user(max_bytes);
+			}
+			if let Some(max_keys) = config.server_config.max_keys_per_user {
+				backend = backend.with_max_keys_per_user(max_keys);
+			}
+			let backend = Arc::new(backend);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		BackendConfig::Postgres if config
+			.require_postgresql_config()?
+			.shard_dsns
+			.is_some() =>
+		{
+			let postgres_config = Arc::new(config.require_postgresql_config()?.clone());
+			// Runs the validation rejecting shard_dsns mixed with dsn or the discrete fields.
+			postgres_config.dsn_override()?;
+			let shard_dsns = postgres_config.shard_dsns.clone().expect("checked above");
+			if shard_dsns.is_empty() {
+				return Err("shard_dsns must name at least one cluster.".into());
+			}
+			let mut shards = Vec::with_capacity(shard_dsns.len());
+			for dsn in shard_dsns {
+				let dsn_source: Arc<dyn DsnSource> = Arc::new(StaticDsnSource(dsn));
+				let backend =
+					connect_postgres_backend(&postgres_config, dsn_source, require_migrated)
+						.await?;
+				// The caps and quotas are user-scoped and every user lives wholly on one shard,
+				// so applying them per shard enforces them exactly.
+				let backend = match config.server_config.max_stores_per_user {
+					Some(max_stores) => backend.with_max_stores_per_user(max_stores),
+					None => backend,
+				};
+				let backend = match config.server_config.max_keys_per_store {
+					Some(max_keys) => backend.with_max_keys_per_store(max_keys),
+					None => backend,
+				};
+				let backend = match config.server_config.max_total_bytes_per_user {
+					Some(max_bytes) => backend.with_max_total_bytes_per_user(max_bytes),
+					None => backend,
+				};
+				let backend = match config.server_config.max_keys_per_user {
+					Some(max_keys) => backend.with_max_keys_per_user(max_keys),
+					None => backend,
+				};
+				shards.push(backend);
+			}
+			info!("Partitioning users across {} PostgreSQL shards.", shards.len());
+			let backend = Arc::new(ShardedKvStore::new(shards)?);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		BackendConfig::Postgres => {
+			let postgres_config = Arc::new(config.require_postgresql_config()?.clone());
+			let dsn_source: Arc<dyn DsnSource> = match postgres_config.dsn_override()? {
+				Some(dsn) => Arc::new(StaticDsnSource(dsn)),
+				None => {
+					let password = resolve_postgres_password(&postgres_config).await?;
+					// Validate the discrete fields once so rendering cannot fail later.
+					postgres_config.connection_string_with_password(&password.current())?;
+					Arc::new(PostgresDsnSource {
+						postgres_config: Arc::clone(&postgres_config),
+						password,
+					})
+				},
+			};
+			let backend =
+				connect_postgres_backend(&postgres_config, dsn_source, require_migrated).await?;
+			let backend = match config.server_config.max_stores_per_user {
+				Some(max_stores) => backend.with_max_stores_per_user(max_stores),
+				None => backend,
+			};
+			let backend = match config.server_config.max_keys_per_store {
+				Some(max_keys) => backend.with_max_keys_per_store(max_keys),
+				None => backend,
+			};
+			let backend = match config.server_config.max_total_bytes_per_user {
+				Some(max_bytes) => backend.with_max_total_bytes_per_user(max_bytes),
+				None => backend,
+			};
+			let backend = match config.server_config.max_keys_per_user {
+				Some(max_keys) => backend.with_max_keys_per_user(max_keys),
+				None => backend,
+			};
+			let backend = Arc::new(backend);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend.clone(),
+				audit_capable: Some(backend.clone()),
+				api_key_source: Some(backend),
+			}
+		},
+		BackendConfig::DynamoDb => {
+			reject_unsupported_caps(&config.server_config, "DynamoDB")?;
+			let backend =
+				Arc::new(new_dynamodb_backend(config.require_dynamodb_config()?).await?);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		BackendConfig::Etcd => {
+			reject_unsupported_caps(&config.server_config, "etcd")?;
+			let etcd_config = config.require_etcd_config()?;
+			let backend = Arc::new(EtcdBackendImpl::new(
+				etcd_config.endpoints.clone(),
+				etcd_config.username.clone(),
+				etcd_config.resolve_password()?,
+			)?);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		BackendConfig::Fs => {
+			reject_unsupported_caps(&config.server_config, "filesystem")?;
+			let backend = Arc::new(FsBackendImpl::new(&config.require_fs_config()?.path)?);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		BackendConfig::Redis => {
+			reject_unsupported_caps(&config.server_config, "Redis")?;
+			let redis_config = config.require_redis_config()?;
+			let backend = Arc::new(RedisBackendImpl::new(
+				redis_config.address.clone(),
+				redis_config.resolve_password()?,
+			));
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+		#[cfg(feature = "sled")]
+		BackendConfig::Sled => {
+			reject_unsupported_caps(&config.server_config, "sled")?;
+			let backend = Arc::new(SledBackendImpl::new(&config.require_sled_config()?.path)?);
+			BackendHandles {
+				store: backend.clone(),
+				admin_store: backend,
+				audit_capable: None,
+				api_key_source: None,
+			}
+		},
+	};
+
+	// With a mutation log configured, every write applied to the local backend (including
+	// replicated writes arriving from a peer) is recorded for recovery and replay.
+	let store: Arc<dyn KvStore> = match &config.mutation_log_config {
+		Some(mutation_log_config) => {
+			let mutation_log = Arc::new(MutationLog::new(&mutation_log_config.path)?);
+			Arc::new(MutationLoggingKvStore::new(store, mutation_log))
+		},
+		None => store,
+	};
+
+	// With replication configured, writes are applied locally first and forwarded to the peer in
+	// the background; reads and admin operations always stay local.
+	let store: Arc<dyn KvStore> = match &config.replication_config {
+		Some(replication_config) => Arc::new(ReplicatedKvStore::new(
+			store,
+			replication_config.peer_url.clone(),
+			replication_config.user_token_header.clone(),
+			replication_config.queue_size.unwrap_or(DEFAULT_REPLICATION_QUEUE_SIZE),
+		)),
+		None => store,
+	};
+
+	let audit_log: Option<Arc<dyn AuthFailureAuditLog>> = match &config.auth_audit_config {
+		Some(audit_config) => {
+			let audit_log = audit_capable
+				.ok_or("auth_audit_config requires the PostgreSQL backend.")?;
+			let pruning_log = Arc::clone(&audit_log);
+			let retention_days = audit_config.retention_days;
+			tokio::spawn(async move {
+				loop {
+					match pruning_log.prune_auth_failures(retention_days).await {
+						Ok(0) => {},
+						Ok(pruned) => info!("Pruned {} expired auth audit events.", pruned),
+						Err(e) => warn!("Failed to prune auth audit events: {}", e),
+					}
+					tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+				}
+			});
+			Some(audit_log)
+		},
+		None => None,
+	};
+
+	let authorizer = select_authorizer(&config, api_key_source).await?;
+
+	let mut tenants = Vec::new();
+	for tenant_config in &config.tenant_config {
+		let tenant_authorizer: Option<Arc<dyn Authorizer>> =
+			match &tenant_config.jwt_authorizer_config {
+				Some(jwt_config) => Some(build_jwt_authorizer(jwt_config).await?),
+				None => None,
+			};
+		tenants.push(Tenant {
+			name: tenant_config.name.clone(),
+			store_id_prefix: tenant_config.store_id_prefix.clone(),
+			authorizer: tenant_authorizer,
+			rate_limit_per_minute: tenant_config.rate_limit_per_minute,
+		});
+	}
+	let tenants = Arc::new(TenantRegistry::new(tenants));
+
+	let admin_state = Arc::new(AdminState::default());
+	if let Some(features) = &config.features {
+		admin_state.features.apply(features);
+	}
+	let admin_service = match &config.admin_api_config {
+		Some(admin_config) => {
+			let admin_token = admin_config.resolve_admin_token()?;
+			let mut admin_service = AdminService::new(
+				admin_token,
+				Arc::clone(&admin_state),
+				Arc::clone(&store),
+				Arc::clone(&admin_store),
+			)
+			.with_log_filter(log_filter);
+			if let Some(mutation_log_config) = &config.mutation_log_config {
+				admin_service =
+					admin_service.with_mutation_log_path(mutation_log_config.path.clone());
+			}
+			Some(Arc::new(admin_service))
+		},
+		None => None,
+	};
+
+	let user_token_hasher = match &config.user_token_hashing_config {
+		Some(hashing_config) => {
+			Some(Arc::new(UserTokenHasher::new(hashing_config.resolve_pepper()?)))
+		},
+		None => None,
+	};
+
+	let trials = match &config.trial_config {
+		Some(trial_config) => {
+			info!(
+				"Serving credential-less requests as trial identities expiring after {}s.",
+				trial_config.ttl_secs
+			);
+			let trials = Arc::new(TrialRegistry::new(
+				trial_config.clone(),
+				Arc::clone(&store),
+				Arc::clone(&admin_store),
+				user_token_hasher.clone(),
+			));
+			let purge_interval_secs =
+				trial_config.purge_interval_secs.unwrap_or(trial_config.ttl_secs).max(1);
+			let purge_trials = Arc::clone(&trials);
+			tokio::spawn(async move {
+				loop {
+					tokio::time::sleep(Duration::from_secs(purge_interval_secs)).await;
+					match purge_trials.purge_expired().await {
+						Ok(0) => {},
+						Ok(purged) => info!("Purged {} expired trial identities.", purged),
+						Err(e) => warn!("Failed to purge expired trial identities: {}", e),
+					}
+				}
+			});
+			Some(trials)
+		},
+		None => None,
+	};
+
+	let addr = format!("{}:{}", config.server_config.host, config.server_config.port);
+	let listener = TcpListener::bind(&addr).await?;
+	let tls_acceptor = match &config.tls_config {
+		Some(tls_config) => Some(build_tls_acceptor(tls_config)?),
+		None => None,
+	};
+	match &config.tls_config {
+		Some(tls_config) if tls_config.client_ca_path.is_some() => {
+			info!("VSS server listening on {} (TLS, client certificates required)", addr)
+		},
+		Some(_) => info!("VSS server listening on {} (TLS)", addr),
+		None => info!("VSS server listening on {}", addr),
+	}
+
+	let backup_store = Arc::clone(&store);
+	let service = VssService::new(
+		store,
+		authorizer,
+		tenants,
+		admin_state,
+		admin_service,
+		user_token_hasher,
+		audit_log,
+	);
+	service
+		.load_grants()
+		.await
+		.map_err(|e| format!("Failed to load persisted store grants: {}", e))?;
+	let service = match config.server_config.max_request_body_bytes {
+		Some(max_request_body_bytes) => service.with_max_request_body_bytes(max_request_body_bytes),
+		None => service,
+	};
+	let mut validation_limits = ValidationLimits::default();
+	if let Some(max_key_length) = config.server_config.max_key_length {
+		validation_limits.max_key_length = max_key_length;
+	}
+	if let Some(max_store_id_length) = config.server_config.max_store_id_length {
+		validation_limits.max_store_id_length = max_store_id_length;
+	}
+	let service = service.with_validation_limits(validation_limits);
+	let service = match &config.server_config.baggage_keys {
+		Some(baggage_keys) => service.with_baggage_keys(baggage_keys.clone()),
+		None => service,
+	};
+	let service = match &config.server_config.trusted_proxies {
+		Some(trusted_proxies) => service.with_trusted_proxies(trusted_proxies.clone()),
+		None => service,
+	};
+	let service = match &config.capture_config {
+		Some(capture_config) => {
+			warn!("Request capture is enabled, writing to {}.", capture_config.path);
+			service.with_capture_log(Arc::new(CaptureLog::new(&capture_config.path)?))
+		},
+		None => service,
+	};
+	let metrics = match &config.metrics_config {
+		Some(metrics_config) => {
+			info!("Pushing OpenTelemetry metrics to {}.", metrics_config.otlp_endpoint);
+			// The provider is installed globally and flushes on its periodic schedule for the
+			// lifetime of the process.
+			let _meter_provider = init_meter_provider(&metrics_config.otlp_endpoint)?;
+			Some(Arc::new(RequestMetrics::new()))
+		},
+		None => None,
+	};
+	let service = match &metrics {
+		Some(metrics) => service.with_metrics(Arc::clone(metrics)),
+		None => service,
+	};
+	let service = match &config.alert_config {
+		Some(alert_config) => {
+			info!("Alerting on error rates over a {}s window.", alert_config.window_secs);
+			service.with_alerts(Arc::new(AlertMonitor::new(alert_config.clone())))
+		},
+		None => service,
+	};
+	let service = match trials {
+		Some(trials) => service.with_trials(trials),
+		None => service,
+	};
+	let service = match &config.rate_limit_config {
+		Some(rate_limit_config) => {
+			let burst_size =
+				rate_limit_config.burst_size.unwrap_or(rate_limit_config.requests_per_second);
+			let rate_limiter =
+				RateLimiter::new(rate_limit_config.requests_per_second, burst_size)
+					.with_operation_limits(rate_limit_config.operations.clone());
+			service.with_rate_limiter(Arc::new(rate_limiter))
+		},
+		None => service,
+	};
+	if let Some(backup_config) = &config.backup_config {
+		let credentials = AwsCredentials::from_env()
+			.map_err(|e| format!("backup_config requires AWS credentials: {}", e))?;
+		let encryption_key = match backup_config.resolve_encryption_key()? {
+			Some(key_hex) => Some(parse_backup_key(&key_hex)?),
+			None => None,
+		};
+		info!(
+			"Scheduling backups of {} users to bucket {} every {}s.",
+			backup_config.user_tokens.len(),
+			backup_config.s3_bucket,
+			backup_config.interval_secs
+		);
+		BackupJob::new(
+			Arc::clone(&backup_store),
+			admin_store,
+			backup_config.clone(),
+			credentials,
+			encryption_key,
+			metrics.clone(),
+		)
+		.spawn();
+	}
+	// Serve HTTP/1.1 and (prior-knowledge/h2c) HTTP/2 on the same listener, negotiated per
+	// connection, so reverse proxies and future gRPC transports can multiplex requests over a
+	// single connection.
+	let mut connection_builder = auto::Builder::new(TokioExecutor::new());
+	connection_builder
+		.http2()
+		.max_concurrent_streams(MAX_CONCURRENT_HTTP2_STREAMS)
+		.adaptive_window(true);
+	loop {
+		let (stream, peer_addr) = match listener.accept().await {
+			Ok(accepted) => accepted,
+			Err(e) => {
+				warn!("Failed to accept connection: {}", e);
+				continue;
+			},
+		};
+		let service = service.clone().with_peer_addr(peer_addr);
+		let connection_builder = connection_builder.clone();
+		let tls_acceptor = tls_acceptor.clone();
+		tokio::spawn(async move {
+			let result = match tls_acceptor {
+				Some(tls_acceptor) => {
+					let tls_stream = match tls_acceptor.accept(stream).await {
+						Ok(tls_stream) => tls_stream,
+						Err(e) => {
+							warn!("TLS handshake with {} failed: {}", peer_addr, e);
+							return;
+						},
+					};
+					let peer_certificate = tls_stream
+						.get_ref()
+						.1
+						.peer_certificates()
+						.and_then(|certs| certs.first())
+						.map(|cert| Arc::new(cert.as_ref().to_vec()));
+					let service = match peer_certificate {
+						Some(peer_certificate) => service.with_peer_certificate(peer_certificate),
+						None => service,
+					};
+					connection_builder.serve_connection(TokioIo::new(tls_stream), service).await
+				},
+				None => connection_builder.serve_connection(TokioIo::new(stream), service).await,
+			};
+			if let Err(e) = result {
+				warn!("Failed to serve connection: {}", e);
+			}
+		});
+	}
+}
+
+/// Builds the TLS acceptor terminating connections per the given [`TlsConfig`]. With
+/// `client_ca_path` set, the handshake requires a client certificate chaining to one of the
+/// listed CAs, so requests on admitted connections always carry a validated peer certificate.
+fn build_tls_acceptor(tls_config: &TlsConfig) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+	let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(
+		fs::File::open(&tls_config.cert_path)
+			.map_err(|e| format!("Failed to read cert_path {}: {}", tls_config.cert_path, e))?,
+	))
+	.collect::<Result<Vec<_>, _>>()?;
+	let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(
+		fs::File::open(&tls_config.key_path)
+			.map_err(|e| format!("Failed to read key_path {}: {}", tls_config.key_path, e))?,
+	))?
+	.ok_or_else(|| format!("No private key found in key_path {}.", tls_config.key_path))?;
+	// The crypto provider is pinned explicitly: with several rustls provider features enabled
+	// somewhere in the dependency graph, relying on the default panics.
+	let provider = Arc::new(rustls::crypto::ring::default_provider());
+	let builder = rustls::ServerConfig::builder_with_provider(Arc::clone(&provider))
+		.with_safe_default_protocol_versions()?;
+	let builder = match &tls_config.client_ca_path {
+		Some(client_ca_path) => {
+			let mut roots = rustls::RootCertStore::empty();
+			for cert in rustls_pemfile::certs(&mut std::io::BufReader::new(
+				fs::File::open(client_ca_path).map_err(|e| {
+					format!("Failed to read client_ca_path {}: {}", client_ca_path, e)
+				})?,
+			)) {
+				roots.add(cert?)?;
+			}
+			let verifier =
+				rustls::server::WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+					.build()?;
+			builder.with_client_cert_verifier(verifier)
+		},
+		None => builder.with_no_client_auth(),
+	};
+	let mut server_config = builder.with_single_cert(certs, key)?;
+	// Offer both protocols via ALPN, matching the h1/h2 auto-negotiation of the plaintext
+	// listener.
+	server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
@@ -0,0 +1,281 @@
+//! Opt-in OpenTelemetry metrics export, see [`RequestMetrics`].
+//!
+//! Operators already running an OTLP collector get request counts, latencies, payload sizes and
+//! backend durations without a separate Prometheus integration: instruments are pushed
+//! periodically to the configured OTLP endpoint, attributed with the operation and the HTTP
+//! status they were recorded under.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::{MetricExporter, WithExportConfig};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::metrics::data::ResourceMetrics;
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider, Temporality};
+use opentelemetry_sdk::Resource;
+use tracing::{info, warn};
+
+use crate::capture::now_millis;
+
+/// Initializes the process-global meter provider, pushing to the given OTLP endpoint (e.g.
+/// `http://localhost:4318/v1/metrics`).
+///
+/// The returned provider is also installed globally and stays alive for the lifetime of the
+/// process, flushing on its periodic schedule.
+pub fn init_meter_provider(otlp_endpoint: &str) -> Result<SdkMeterProvider, String> {
+	let exporter = MetricExporter::builder()
+		.with_http()
+		.with_endpoint(otlp_endpoint)
+		.build()
+		.map_err(|e| format!("Failed to build OTLP metric exporter: {}", e))?;
+	let provider = SdkMeterProvider::builder()
+		.with_reader(PeriodicReader::builder(FailureIsolatingExporter::new(exporter)).build())
+		.with_resource(Resource::builder().with_service_name("vss-server").build())
+		.build();
+	global::set_meter_provider(provider.clone());
+	Ok(provider)
+}
+
+/// Consecutive export failures before the circuit opens and export attempts are suspended.
+const CIRCUIT_OPEN_FAILURE_THRESHOLD: u32 = 3;
+
+/// How long an open circuit suppresses export attempts before the next probe.
+const CIRCUIT_OPEN_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Tracks consecutive export failures and, past a threshold, opens for a cooldown period during
+/// which attempts should be skipped. Kept separate from the exporter wrapper so the state
+/// transitions are testable without a collector.
+struct CircuitBreaker {
+	consecutive_failures: AtomicU32,
+	open_until_millis: AtomicU64,
+	dropped_batches: AtomicU64,
+}
+
+impl CircuitBreaker {
+	fn new() -> Self {
+		CircuitBreaker {
+			consecutive_failures: AtomicU32::new(0),
+			open_until_millis: AtomicU64::new(0),
+			dropped_batches: AtomicU64::new(0),
+		}
+	}
+
+	/// Whether an export should be attempted now. If the circuit is open, the batch is counted
+	/// as dropped instead.
+	fn should_attempt(&self, now_millis: u64) -> bool {
+		if now_millis < self.open_until_millis.load(Ordering::Acquire) {
+			self.dropped_batches.fetch_add(1, Ordering::Relaxed);
+			false
+		} else {
+			true
+		}
+	}
+
+	/// Records a successful export, returning `Some(dropped_batch_count)` if the breaker is
+	/// thereby recovering from a failure episode.
+	fn record_success(&self) -> Option<u64> {
+		let dropped = self.dropped_batches.swap(0, Ordering::Relaxed);
+		let had_failures = self.consecutive_failures.swap(0, Ordering::Relaxed) > 0;
+		let was_open = self.open_until_millis.swap(0, Ordering::Release) > 0;
+		if had_failures || was_open {
+			Some(dropped)
+		} else {
+			None
+		}
+	}
+
+	/// Records a failed export (the batch is dropped either way, the SDK does not retry),
+	/// returning `true` if the failure opened the circuit.
+	fn record_failure(&self, now_millis: u64) -> bool {
+		self.dropped_batches.fetch_add(1, Ordering::Relaxed);
+		let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+		if failures >= CIRCUIT_OPEN_FAILURE_THRESHOLD {
+			self.open_until_millis
+				.store(now_millis + CIRCUIT_OPEN_COOLDOWN.as_millis() as u64, Ordering::Release);
+			true
+		} else {
+			false
+		}
+	}
+}
+
+/// Wraps the OTLP exporter so collector outages never degrade the data path: the periodic
+/// reader already aggregates in place (bounded memory, no growing queue), and this wrapper
+/// swallows export errors — after [`CIRCUIT_OPEN_FAILURE_THRESHOLD`] consecutive failures it
+/// stops attempting exports for [`CIRCUIT_OPEN_COOLDOWN`], logging one line when the circuit
+/// opens and one (with the dropped batch count) when export recovers, instead of an error per
+/// export interval.
+struct FailureIsolatingExporter {
+	inner: MetricExporter,
+	breaker: CircuitBreaker,
+}
+
+impl FailureIsolatingExporter {
+	fn new(inner: MetricExporter) -> Self {
+		FailureIsolatingExporter { inner, breaker: CircuitBreaker::new() }
+	}
+}
+
+impl PushMetricExporter for FailureIsolatingExporter {
+	async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+		if !self.breaker.should_attempt(now_millis()) {
+			return Ok(());
+		}
+		match self.inner.export(metrics).await {
+			Ok(()) => {
+				if let Some(dropped) = self.breaker.record_success() {
+					info!("Metrics export recovered, {} batches were dropped.", dropped);
+				}
+				Ok(())
+			},
+			Err(e) => {
+				if self.breaker.record_failure(now_millis()) {
+					warn!(
+						"Metrics export failed {} times in a row (last: {}), dropping metrics for {:?}.",
+						CIRCUIT_OPEN_FAILURE_THRESHOLD, e, CIRCUIT_OPEN_COOLDOWN
+					);
+				}
+				Ok(())
+			},
+		}
+	}
+
+	fn force_flush(&self) -> OTelSdkResult {
+		self.inner.force_flush()
+	}
+
+	fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+		self.inner.shutdown_with_timeout(timeout)
+	}
+
+	fn temporality(&self) -> Temporality {
+		self.inner.temporality()
+	}
+}
+
+/// The instruments recorded for every handled VSS API request, see
+/// [`VssService::with_metrics`].
+///
+/// [`VssService::with_metrics`]: crate::vss_service::VssService::with_metrics
+pub struct RequestMetrics {
+	requests: Counter<u64>,
+	request_duration: Histogram<f64>,
+	backend_duration: Histogram<f64>,
+	request_body_bytes: Histogram<u64>,
+	response_body_bytes: Histogram<u64>,
+	auth_attempts: Counter<u64>,
+	backup_user_runs: Counter<u64>,
+}
+
+impl RequestMetrics {
+	/// Creates the instruments on the globally installed meter provider.
+	pub fn new() -> Self {
+		let meter = global::meter("vss-server");
+		RequestMetrics {
+			requests: meter
+				.u64_counter("vss.requests")
+				.with_description("Handled VSS API requests.")
+				.build(),
+			request_duration: meter
+				.f64_histogram("vss.request.duration")
+				.with_unit("s")
+				.with_description("End-to-end request handling time.")
+				.build(),
+			backend_duration: meter
+				.f64_histogram("vss.backend.duration")
+				.with_unit("s")
+				.with_description("Time spent in the storage backend.")
+				.build(),
+			request_body_bytes: meter
+				.u64_histogram("vss.request.body_bytes")
+				.with_unit("By")
+				.with_description("Request body sizes.")
+				.build(),
+			response_body_bytes: meter
+				.u64_histogram("vss.response.body_bytes")
+				.with_unit("By")
+				.with_description("Response body sizes.")
+				.build(),
+			auth_attempts: meter
+				.u64_counter("vss.auth.attempts")
+				.with_description("Authentication attempts by scheme and outcome.")
+				.build(),
+			backup_user_runs: meter
+				.u64_counter("vss.backup.user_runs")
+				.with_description("Per-user scheduled backup attempts by outcome.")
+				.build(),
+		}
+	}
+
+	/// Records one authentication attempt under its scheme (bearer, signature, none) and its
+	/// outcome (`ok` or a failure category), so broken clients and expired credentials can be
+	/// told apart from outright attacks.
+	pub(crate) fn record_auth(&self, scheme: &'static str, outcome: &'static str) {
+		let attributes = [KeyValue::new("scheme", scheme), KeyValue::new("outcome", outcome)];
+		self.auth_attempts.add(1, &attributes);
+	}
+
+	/// Records one per-user scheduled backup attempt under its outcome (`ok`,
+	/// `skipped_unchanged` or `error`), so silently failing disaster recovery can be alerted on.
+	pub(crate) fn record_backup(&self, outcome: &'static str) {
+		self.backup_user_runs.add(1, &[KeyValue::new("outcome", outcome)]);
+	}
+
+	/// Records one handled request under its operation and response status.
+	pub(crate) fn record(
+		&self, operation: &'static str, status: u16, request_body_bytes: usize,
+		response_body_bytes: usize, request_duration: Duration, backend_duration: Duration,
+	) {
+		let attributes =
+			[KeyValue::new("operation", operation), KeyValue::new("status", status as i64)];
+		self.requests.add(1, &attributes);
+		self.request_duration.record(request_duration.as_secs_f64(), &attributes);
+		self.backend_duration.record(backend_duration.as_secs_f64(), &attributes);
+		self.request_body_bytes.record(request_body_bytes as u64, &attributes);
+		self.response_body_bytes.record(response_body_bytes as u64, &attributes);
+	}
+}
+
+impl Default for RequestMetrics {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn circuit_opens_after_consecutive_failures_and_recovers() {
+		let breaker = CircuitBreaker::new();
+		assert!(breaker.should_attempt(1_000));
+		assert!(!breaker.record_failure(1_000));
+		assert!(!breaker.record_failure(1_000));
+		assert!(breaker.should_attempt(1_000));
+		assert!(breaker.record_failure(1_000));
+
+		// Open: attempts are skipped and counted until the cooldown elapses.
+		assert!(!breaker.should_attempt(1_000));
+		assert!(!breaker.should_attempt(2_000));
+		let reopened_at = 1_000 + CIRCUIT_OPEN_COOLDOWN.as_millis() as u64;
+		assert!(breaker.should_attempt(reopened_at));
+
+		// Recovery reports the three failed batches plus the two skipped ones.
+		assert_eq!(breaker.record_success(), Some(5));
+		assert_eq!(breaker.record_success(), None);
+	}
+
+	#[test]
+	fn isolated_failures_do_not_open_the_circuit() {
+		let breaker = CircuitBreaker::new();
+		for _ in 0..10 {
+			assert!(!breaker.record_failure(1_000));
+			assert_eq!(breaker.record_success(), Some(1));
+		}
+		assert!(breaker.should_attempt(1_000));
+	}
+}
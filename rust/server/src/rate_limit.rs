@@ -0,0 +1,169 @@
+//! Per-caller request rate limiting.
+//!
+//! Unlike the per-tenant fixed-window limits in [`tenants`], this limiter applies to every
+//! request the server handles: authenticated requests are keyed by `user_token`, failed
+//! authentication attempts by client IP, so neither a busy client nor a brute-forcing one can
+//! monopolize the backend.
+//!
+//! [`tenants`]: crate::tenants
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// How many buckets are kept before saturated ones are pruned, bounding the memory a rotating
+/// flood of distinct callers can pin.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// A token-bucket rate limiter keyed by caller and operation.
+///
+/// Each caller/operation pair holds a bucket of `burst_size` tokens refilled at the configured
+/// rate; a request takes one token and is rejected when the bucket is empty. The burst absorbs
+/// legitimate spikes (e.g. a wallet syncing after being offline) while the refill rate bounds
+/// sustained throughput. Operations without an override share the default rate.
+pub struct RateLimiter {
+	requests_per_second: f64,
+	burst_size: f64,
+	operation_limits: HashMap<String, f64>,
+	buckets: Mutex<HashMap<(String, &'static str), Bucket>>,
+}
+
+struct Bucket {
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl RateLimiter {
+	/// Constructs a [`RateLimiter`] allowing the given sustained rate per caller and operation,
+	/// with bursts up to `burst_size` requests.
+	pub fn new(requests_per_second: f64, burst_size: f64) -> Self {
+		RateLimiter {
+			requests_per_second,
+			burst_size,
+			operation_limits: HashMap::new(),
+			buckets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Returns this limiter overriding the sustained rate of the listed operations (e.g. a
+	/// tighter limit on `put` than on `get`).
+	pub fn with_operation_limits(mut self, operation_limits: HashMap<String, f64>) -> Self {
+		self.operation_limits = operation_limits;
+		self
+	}
+
+	/// Records a request of the given caller against its bucket, returning `false` if the bucket
+	/// is exhausted and the request must be rejected.
+	pub fn check(&self, key: &str, operation: &'static str) -> bool {
+		self.check_at(key, operation, Instant::now())
+	}
+
+	fn check_at(&self, key: &str, operation: &'static str, now: Instant) -> bool {
+		let rate = *self.operation_limits.get(operation).unwrap_or(&self.requests_per_second);
+		let capacity = self.burst_size.max(1.0);
+		let mut buckets = self.buckets.lock().unwrap();
+		if buckets.len() >= PRUNE_THRESHOLD {
+			// Buckets idle long enough to have refilled entirely would be recreated as full
+			// anyway. Refill only happens on access, so the stored token count alone never
+			// reaches capacity again — credit the elapsed time at each bucket's own rate.
+			let operation_limits = &self.operation_limits;
+			let requests_per_second = self.requests_per_second;
+			buckets.retain(|(_, operation), bucket| {
+				let rate = *operation_limits.get(*operation).unwrap_or(&requests_per_second);
+				let elapsed = now.saturating_duration_since(bucket.last_refill);
+				bucket.tokens + elapsed.as_secs_f64() * rate < capacity
+			});
+		}
+		let bucket = buckets
+			.entry((key.to_string(), operation))
+			.or_insert(Bucket { tokens: capacity, last_refill: now });
+		let elapsed = now.saturating_duration_since(bucket.last_refill);
+		bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(capacity);
+		bucket.last_refill = now;
+		if bucket.tokens < 1.0 {
+			return false;
+		}
+		bucket.tokens -= 1.0;
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn bursts_are_absorbed_and_sustained_rates_enforced() {
+		let limiter = RateLimiter::new(1.0, 2.0);
+		let start = Instant::now();
+
+		assert!(limiter.check_at("user-a", "get", start));
+		assert!(limiter.check_at("user-a", "get", start));
+		// The burst is spent; within the same instant nothing has refilled.
+		assert!(!limiter.check_at("user-a", "get", start));
+
+		// One second later, one token has refilled.
+		let later = start + Duration::from_secs(1);
+		assert!(limiter.check_at("user-a", "get", later));
+		assert!(!limiter.check_at("user-a", "get", later));
+	}
+
+	#[test]
+	fn buckets_are_isolated_per_caller_and_operation() {
+		let limiter = RateLimiter::new(1.0, 1.0);
+		let start = Instant::now();
+
+		assert!(limiter.check_at("user-a", "get", start));
+		assert!(!limiter.check_at("user-a", "get", start));
+		// Other callers and other operations draw from their own buckets.
+		assert!(limiter.check_at("user-b", "get", start));
+		assert!(limiter.check_at("user-a", "put", start));
+	}
+
+	#[test]
+	fn idle_buckets_are_pruned_once_refilled() {
+		let limiter = RateLimiter::new(1.0, 2.0);
+		let start = Instant::now();
+
+		// A rotating flood of distinct callers fills the map to the prune threshold.
+		for i in 0..PRUNE_THRESHOLD {
+			assert!(limiter.check_at(&format!("user-{}", i), "get", start));
+		}
+		assert_eq!(limiter.buckets.lock().unwrap().len(), PRUNE_THRESHOLD);
+
+		// Two seconds later every one of them has refilled in full (1 token spent, rate 1/s),
+		// so the next check sweeps them all rather than letting the map grow unbounded.
+		let later = start + Duration::from_secs(2);
+		assert!(limiter.check_at("late-caller", "get", later));
+		assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+
+		// A caller still mid-refill survives the sweep: having spent its full burst, it has only
+		// recovered 1.5 of its 2 tokens when the sweep runs, unlike the single-request callers.
+		assert!(limiter.check_at("busy-caller", "get", later));
+		assert!(limiter.check_at("busy-caller", "get", later));
+		for i in 0..PRUNE_THRESHOLD - 1 {
+			assert!(limiter.check_at(&format!("other-{}", i), "get", later));
+		}
+		assert!(limiter.check_at("trigger", "get", later + Duration::from_millis(1500)));
+		let buckets = limiter.buckets.lock().unwrap();
+		assert!(buckets.contains_key(&("busy-caller".to_string(), "get")));
+		assert!(buckets.contains_key(&("trigger".to_string(), "get")));
+		assert_eq!(buckets.len(), 2);
+	}
+
+	#[test]
+	fn operation_overrides_take_precedence() {
+		let mut operation_limits = HashMap::new();
+		operation_limits.insert("put".to_string(), 10.0);
+		let limiter = RateLimiter::new(1.0, 1.0).with_operation_limits(operation_limits);
+		let start = Instant::now();
+
+		assert!(limiter.check_at("user-a", "put", start));
+		// The put bucket refills at ten per second, the get bucket at one.
+		let later = start + Duration::from_millis(100);
+		assert!(limiter.check_at("user-a", "put", later));
+		assert!(limiter.check_at("user-a", "get", start));
+		assert!(!limiter.check_at("user-a", "get", later));
+	}
+}
@@ -0,0 +1,175 @@
+//! Asynchronous active-active replication between two VSS instances.
+//!
+//! Each instance applies writes to its local backend first and forwards them to the configured
+//! peer in the background, so regional failover does not require a single cross-region database.
+//! Forwarded writes go through the peer's regular conditional-write path, hence key and global
+//! versions double as the conflict detection mechanism: a write which lost a cross-region race
+//! fails the peer's version check and is rejected (and logged) there instead of overwriting the
+//! newer data. Reads are always served locally.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Method, Request, StatusCode};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use prost::Message;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use api::error::VssError;
+use api::kv_store::{KvStore, RequestContext};
+use api::types::{
+	DeleteObjectRequest, DeleteObjectResponse, GetObjectRequest, GetObjectResponse,
+	GetStoreStatsRequest, GetStoreStatsResponse, ListKeyVersionsRequest, ListKeyVersionsResponse,
+	PutObjectRequest, PutObjectResponse,
+};
+
+/// The default number of writes buffered for the peer before new writes are dropped from
+/// replication (local writes keep succeeding regardless).
+pub const DEFAULT_REPLICATION_QUEUE_SIZE: usize = 1024;
+
+/// The number of delivery attempts per write before it is dropped from replication.
+const FORWARD_ATTEMPTS: u32 = 3;
+
+/// The delay between delivery attempts of the same write.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+enum ReplicatedWrite {
+	Put(String, PutObjectRequest),
+	Delete(String, DeleteObjectRequest),
+}
+
+/// A [`KvStore`] wrapper forwarding every successful local write to a peer VSS instance in the
+/// background.
+///
+/// Replication is asynchronous by design: a slow or unreachable peer never blocks or fails local
+/// writes. Writes which cannot be delivered (queue overflow, persistent peer errors) are logged
+/// and dropped, leaving the peers to be re-synced out of band. Both peers should share the same
+/// user token hashing configuration, and the peer should map the forwarded token back unchanged,
+/// e.g. via a trusted-header `noop_authorizer_config`.
+pub struct ReplicatedKvStore {
+	inner: Arc<dyn KvStore>,
+	sender: mpsc::Sender<ReplicatedWrite>,
+}
+
+impl ReplicatedKvStore {
+	/// Wraps the given store, spawning a background task forwarding writes to the VSS instance at
+	/// `peer_url`. With `user_token_header` set, the authenticated user token of each write is
+	/// sent to the peer in that header.
+	pub fn new(
+		inner: Arc<dyn KvStore>, peer_url: String, user_token_header: Option<String>,
+		queue_size: usize,
+	) -> Self {
+		let (sender, receiver) = mpsc::channel(queue_size);
+		tokio::spawn(run_forwarder(receiver, peer_url, user_token_header));
+		ReplicatedKvStore { inner, sender }
+	}
+
+	fn enqueue(&self, write: ReplicatedWrite) {
+		if self.sender.try_send(write).is_err() {
+			warn!("Replication queue is full, dropping write; peers need to be re-synced.");
+		}
+	}
+}
+
+#[async_trait]
+impl KvStore for ReplicatedKvStore {
+	async fn get(
+		&self, context: RequestContext, request: GetObjectRequest,
+	) -> Result<GetObjectResponse, VssError> {
+		self.inner.get(context, request).await
+	}
+
+	async fn put(
+		&self, context: RequestContext, request: PutObjectRequest,
+	) -> Result<PutObjectResponse, VssError> {
+		let response = self.inner.put(context.clone(), request.clone()).await?;
+		// Dry runs commit nothing locally, so there is nothing to forward to the peer.
+		if !request.dry_run {
+			self.enqueue(ReplicatedWrite::Put(context.user_token, request));
+		}
+		Ok(response)
+	}
+
+	async fn delete(
+		&self, context: RequestContext, request: DeleteObjectRequest,
+	) -> Result<DeleteObjectResponse, VssError> {
+		let response = self.inner.delete(context.clone(), request.clone()).await?;
+		self.enqueue(ReplicatedWrite::Delete(context.user_token, request));
+		Ok(response)
+	}
+
+	async fn list_key_versions(
+		&self, context: RequestContext, request: ListKeyVersionsRequest,
+	) -> Result<ListKeyVersionsResponse, VssError> {
+		self.inner.list_key_versions(context, request).await
+	}
+
+	async fn get_store_stats(
+		&self, context: RequestContext, request: GetStoreStatsRequest,
+	) -> Result<GetStoreStatsResponse, VssError> {
+		self.inner.get_store_stats(context, request).await
+	}
+
+	async fn health_check(&self) -> Result<(), VssError> {
+		self.inner.health_check().await
+	}
+}
+
+async fn run_forwarder(
+	mut receiver: mpsc::Receiver<ReplicatedWrite>, peer_url: String,
+	user_token_header: Option<String>,
+) {
+	let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+	while let Some(write) = receiver.recv().await {
+		let (path, user_token, body) = match &write {
+			ReplicatedWrite::Put(user_token, request) => {
+				("putObjects", user_token, request.encode_to_vec())
+			},
+			ReplicatedWrite::Delete(user_token, request) => {
+				("deleteObject", user_token, request.encode_to_vec())
+			},
+		};
+		forward(&client, &peer_url, path, user_token, body, &user_token_header).await;
+	}
+}
+
+/// Forwards a single write, retrying transient failures. Writes are delivered strictly in local
+/// commit order, so a conflict response is authoritative: the peer accepted a conflicting write
+/// first and the forwarded one lost the cross-region race.
+async fn forward(
+	client: &Client<hyper_util::client::legacy::connect::HttpConnector, Full<Bytes>>,
+	peer_url: &str, path: &str, user_token: &str, body: Vec<u8>,
+	user_token_header: &Option<String>,
+) {
+	for attempt in 1..=FORWARD_ATTEMPTS {
+		let mut builder =
+			Request::builder().method(Method::POST).uri(format!("{}/vss/{}", peer_url, path));
+		if let Some(header) = user_token_header {
+			builder = builder.header(header, user_token);
+		}
+		let request = builder.body(Full::new(Bytes::from(body.clone()))).expect("valid request");
+		match client.request(request).await {
+			Ok(response) if response.status() == StatusCode::OK => return,
+			Ok(response) if response.status() == StatusCode::CONFLICT => {
+				warn!("Peer rejected replicated write to {} as conflicting.", path);
+				return;
+			},
+			Ok(response) => {
+				warn!("Peer returned {} for replicated write to {}.", response.status(), path)
+			},
+			Err(e) => warn!("Failed to forward replicated write to {}: {}", path, e),
+		}
+		if attempt < FORWARD_ATTEMPTS {
+			tokio::time::sleep(RETRY_DELAY).await;
+		}
+	}
+	warn!(
+		"Dropping replicated write after {} attempts; peers need to be re-synced.",
+		FORWARD_ATTEMPTS
+	);
+}
@@ -0,0 +1,346 @@
+//! Pluggable secret providers, allowing sensitive config values (the PostgreSQL password, JWT
+//! verification keys) to be fetched from HashiCorp Vault or AWS Secrets Manager instead of being
+//! written into the config file.
+//!
+//! Providers may be configured with a refresh interval, in which case the secret is periodically
+//! re-fetched in the background so mid-life credential rotation takes effect without a restart.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Uri};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use api::auth::{AuthResponse, Authorizer, RequestHeaders};
+use api::error::VssError;
+
+use crate::config::read_secret;
+
+// The AWS SigV4 signing machinery lives next to the DynamoDB backend in `impls`; re-export it
+// here for the S3 backup job and the Secrets Manager provider.
+pub use impls::aws::{hmac_sha256, AwsCredentials};
+pub(crate) use impls::aws::{format_amz_date, sha256_hex, sigv4_authorization, SigV4Params};
+
+/// Configuration of an external secret provider, selected via the `provider` key.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "provider")]
+pub enum SecretProviderConfig {
+	/// Reads the secret from a HashiCorp Vault KV version 2 store.
+	#[serde(rename = "vault")]
+	Vault(VaultProviderConfig),
+	/// Reads the secret from AWS Secrets Manager.
+	#[serde(rename = "aws-secrets-manager")]
+	AwsSecretsManager(AwsSecretsManagerProviderConfig),
+}
+
+impl SecretProviderConfig {
+	pub fn refresh_interval_secs(&self) -> Option<u64> {
+		match self {
+			SecretProviderConfig::Vault(config) => config.refresh_interval_secs,
+			SecretProviderConfig::AwsSecretsManager(config) => config.refresh_interval_secs,
+		}
+	}
+
+	fn build(&self) -> Result<Box<dyn SecretProvider>, String> {
+		match self {
+			SecretProviderConfig::Vault(config) => {
+				let token = read_secret(&config.token, &config.token_file, "token")?;
+				Ok(Box::new(VaultSecretProvider { config: config.clone(), token }))
+			},
+			SecretProviderConfig::AwsSecretsManager(config) => {
+				let credentials = AwsCredentials::from_env()?;
+				Ok(Box::new(AwsSecretsManagerProvider { config: config.clone(), credentials }))
+			},
+		}
+	}
+}
+
+/// Configuration of a HashiCorp Vault KV version 2 secret provider.
+#[derive(Clone, Deserialize)]
+pub struct VaultProviderConfig {
+	/// The Vault address, e.g. `https://vault.example.com:8200`.
+	pub address: String,
+	/// The Vault token, provided inline. Alternatively, set `token_file`.
+	pub token: Option<String>,
+	/// Path to a file holding the Vault token (e.g. the sink of a Vault agent sidecar).
+	pub token_file: Option<String>,
+	/// The mount point of the KV version 2 secrets engine, e.g. `secret`.
+	pub mount: String,
+	/// The path of the secret below the mount point, e.g. `vss/postgres`.
+	pub path: String,
+	/// The field of the secret holding the value, e.g. `password`.
+	pub field: String,
+	/// If set, the secret is re-fetched every `refresh_interval_secs` seconds so credential
+	/// rotation takes effect without a restart. Otherwise, it is fetched once at startup.
+	pub refresh_interval_secs: Option<u64>,
+}
+
+/// Configuration of an AWS Secrets Manager secret provider.
+///
+/// AWS credentials are taken from the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and (if set)
+/// `AWS_SESSION_TOKEN` environment variables, so they never appear in the config file.
+#[derive(Clone, Deserialize)]
+pub struct AwsSecretsManagerProviderConfig {
+	/// The AWS region, e.g. `us-east-1`.
+	pub region: String,
+	/// The name or ARN of the secret.
+	pub secret_id: String,
+	/// If set, the secret string is parsed as JSON and this field is extracted. Otherwise, the
+	/// whole secret string is used verbatim.
+	pub json_field: Option<String>,
+	/// Overrides the service endpoint, e.g. for testing against a local emulator. Defaults to
+	/// the regional `secretsmanager` endpoint.
+	pub endpoint: Option<String>,
+	/// If set, the secret is re-fetched every `refresh_interval_secs` seconds so credential
+	/// rotation takes effect without a restart. Otherwise, it is fetched once at startup.
+	pub refresh_interval_secs: Option<u64>,
+}
+
+/// An external source of a single secret value.
+#[async_trait]
+trait SecretProvider: Send + Sync {
+	/// Fetches the current value of the secret.
+	async fn fetch(&self) -> Result<String, String>;
+}
+
+/// A sensitive config value, either fixed or periodically refreshed from an external provider.
+#[derive(Clone)]
+pub struct ResolvedSecret {
+	value: Arc<RwLock<String>>,
+}
+
+impl ResolvedSecret {
+	/// Returns the current value of the secret.
+	pub fn current(&self) -> String {
+		self.value.read().unwrap().clone()
+	}
+}
+
+/// Resolves a sensitive config value from exactly one of its inline, `*_file` or `*_provider`
+/// variants, fetching the initial value and spawning a background refresh task if a provider
+/// with a refresh interval is configured.
+pub async fn resolve_secret(
+	inline: &Option<String>, file: &Option<String>, provider: &Option<SecretProviderConfig>,
+	name: &str,
+) -> Result<ResolvedSecret, String> {
+	let (provider, interval_secs) = match provider {
+		None => {
+			let value = read_secret(inline, file, name)?;
+			return Ok(ResolvedSecret { value: Arc::new(RwLock::new(value)) });
+		},
+		Some(provider_config) => {
+			if inline.is_some() || file.is_some() {
+				return Err(format!(
+					"Only one of {0}, {0}_file and {0}_provider may be set.",
+					name
+				));
+			}
+			(provider_config.build()?, provider_config.refresh_interval_secs())
+		},
+	};
+
+	let initial = provider
+		.fetch()
+		.await
+		.map_err(|e| format!("Failed to fetch {} from secret provider: {}", name, e))?;
+	let value = Arc::new(RwLock::new(initial));
+
+	if let Some(interval_secs) = interval_secs.filter(|interval| *interval > 0) {
+		let value = Arc::clone(&value);
+		let name = name.to_string();
+		tokio::spawn(async move {
+			loop {
+				tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+				match provider.fetch().await {
+					Ok(new_value) => {
+						let mut current = value.write().unwrap();
+						if *current != new_value {
+							info!("Secret {} was rotated, new value takes effect.", name);
+							*current = new_value;
+						}
+					},
+					// Keep serving with the last known value, rotated credentials stay valid
+					// for a grace period on any sane rotation schedule.
+					Err(e) => warn!("Failed to refresh secret {}: {}", name, e),
+				}
+			}
+		});
+	}
+	Ok(ResolvedSecret { value })
+}
+
+/// An [`Authorizer`] whose inner implementation can be swapped at runtime, used to pick up
+/// rotated verification keys without restarting the server.
+pub struct RotatingAuthorizer {
+	inner: RwLock<Arc<dyn Authorizer>>,
+}
+
+impl RotatingAuthorizer {
+	pub fn new(inner: Arc<dyn Authorizer>) -> Self {
+		Self { inner: RwLock::new(inner) }
+	}
+
+	pub fn swap(&self, inner: Arc<dyn Authorizer>) {
+		*self.inner.write().unwrap() = inner;
+	}
+}
+
+#[async_trait]
+impl Authorizer for RotatingAuthorizer {
+	async fn verify(&self, headers: &dyn RequestHeaders) -> Result<AuthResponse, VssError> {
+		let inner = Arc::clone(&*self.inner.read().unwrap());
+		inner.verify(headers).await
+	}
+}
+
+struct VaultSecretProvider {
+	config: VaultProviderConfig,
+	token: String,
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+	async fn fetch(&self) -> Result<String, String> {
+		let uri = format!(
+			"{}/v1/{}/data/{}",
+			self.config.address.trim_end_matches('/'),
+			self.config.mount,
+			self.config.path
+		);
+		let request = Request::builder()
+			.method(Method::GET)
+			.uri(&uri)
+			.header("x-vault-token", &self.token)
+			.body(Full::new(Bytes::new()))
+			.map_err(|e| format!("Failed to build request: {}", e))?;
+		let body = execute_request(request).await?;
+		let response: serde_json::Value = serde_json::from_slice(&body)
+			.map_err(|e| format!("Failed to parse Vault response: {}", e))?;
+		// KV version 2 nests the secret fields below data.data.
+		response["data"]["data"][&self.config.field]
+			.as_str()
+			.map(|value| value.to_string())
+			.ok_or_else(|| {
+				format!("Vault secret {} has no string field {}.", uri, self.config.field)
+			})
+	}
+}
+
+struct AwsSecretsManagerProvider {
+	config: AwsSecretsManagerProviderConfig,
+	credentials: AwsCredentials,
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecretsManagerProvider {
+	async fn fetch(&self) -> Result<String, String> {
+		let endpoint = match &self.config.endpoint {
+			Some(endpoint) => endpoint.clone(),
+			None => format!("https://secretsmanager.{}.amazonaws.com", self.config.region),
+		};
+		let uri: Uri =
+			endpoint.parse().map_err(|e| format!("Invalid endpoint {}: {}", endpoint, e))?;
+		let host = uri.host().ok_or_else(|| format!("Endpoint {} has no host.", endpoint))?;
+		let host = match uri.port_u16() {
+			Some(port) => format!("{}:{}", host, port),
+			None => host.to_string(),
+		};
+
+		let body = serde_json::json!({ "SecretId": self.config.secret_id }).to_string();
+		let (amz_date, _) = format_amz_date(SystemTime::now());
+		let mut builder = Request::builder()
+			.method(Method::POST)
+			.uri(&endpoint)
+			.header("content-type", "application/x-amz-json-1.1")
+			.header("host", &host)
+			.header("x-amz-date", &amz_date)
+			.header("x-amz-target", "secretsmanager.GetSecretValue");
+		let mut signed_headers =
+			vec![("content-type", "application/x-amz-json-1.1".to_string()), ("host", host)];
+		signed_headers.push(("x-amz-date", amz_date.clone()));
+		if let Some(session_token) = &self.credentials.session_token {
+			builder = builder.header("x-amz-security-token", session_token);
+			signed_headers.push(("x-amz-security-token", session_token.clone()));
+		}
+		signed_headers.push(("x-amz-target", "secretsmanager.GetSecretValue".to_string()));
+
+		let authorization = sigv4_authorization(
+			&self.credentials.access_key_id,
+			&self.credentials.secret_access_key,
+			&SigV4Params {
+				method: &Method::POST,
+				canonical_uri: "/",
+				canonical_query: "",
+				region: &self.config.region,
+				service: "secretsmanager",
+				amz_date: &amz_date,
+				signed_headers: &signed_headers,
+				payload: body.as_bytes(),
+			},
+		);
+		let request = builder
+			.header("authorization", authorization)
+			.body(Full::new(Bytes::from(body)))
+			.map_err(|e| format!("Failed to build request: {}", e))?;
+
+		let response_body = execute_request(request).await?;
+		let response: serde_json::Value = serde_json::from_slice(&response_body)
+			.map_err(|e| format!("Failed to parse Secrets Manager response: {}", e))?;
+		let secret_string = response["SecretString"]
+			.as_str()
+			.ok_or_else(|| format!("Secret {} has no SecretString.", self.config.secret_id))?;
+		match &self.config.json_field {
+			None => Ok(secret_string.to_string()),
+			Some(json_field) => {
+				let secret_json: serde_json::Value = serde_json::from_str(secret_string)
+					.map_err(|e| format!("SecretString is not valid JSON: {}", e))?;
+				secret_json[json_field]
+					.as_str()
+					.map(|value| value.to_string())
+					.ok_or_else(|| {
+						format!(
+							"Secret {} has no string field {}.",
+							self.config.secret_id, json_field
+						)
+					})
+			},
+		}
+	}
+}
+
+pub(crate) async fn execute_request(request: Request<Full<Bytes>>) -> Result<Bytes, String> {
+	// The crypto provider is pinned explicitly: with several rustls provider features enabled
+	// somewhere in the dependency graph, relying on the default panics.
+	let connector = hyper_rustls::HttpsConnectorBuilder::new()
+		.with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+		.map_err(|e| format!("Failed to initialize TLS: {}", e))?
+		.https_or_http()
+		.enable_http1()
+		.build();
+	let client = Client::builder(TokioExecutor::new()).build::<_, Full<Bytes>>(connector);
+	let uri = request.uri().clone();
+	let response =
+		client.request(request).await.map_err(|e| format!("Request to {} failed: {}", uri, e))?;
+	let status = response.status();
+	let body = response
+		.into_body()
+		.collect()
+		.await
+		.map_err(|e| format!("Failed to read response from {}: {}", uri, e))?
+		.to_bytes();
+	if !status.is_success() {
+		return Err(format!(
+			"Request to {} failed with status {}: {}",
+			uri,
+			status,
+			String::from_utf8_lossy(&body)
+		));
+	}
+	Ok(body)
+}
@@ -0,0 +1,115 @@
+//! Multi-tenant support.
+//!
+//! Operators can define tenants in the server configuration, each matched by a `store_id`
+//! prefix. A tenant may bring its own [`Authorizer`] (e.g. the JWT signing key of the wallet app
+//! it belongs to) and per-user rate limits, providing isolation when several applications are
+//! served from a single deployment.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use api::auth::Authorizer;
+
+/// A single tenant, resolved per request via [`TenantRegistry::resolve`].
+pub struct Tenant {
+	/// A human-readable identifier, used in logs and rate-limiter bookkeeping.
+	pub name: String,
+	/// The tenant matches all requests whose `store_id` starts with this prefix.
+	pub store_id_prefix: String,
+	/// If set, requests of this tenant are authenticated with this authorizer instead of the
+	/// server-wide default.
+	pub authorizer: Option<Arc<dyn Authorizer>>,
+	/// If set, the maximum number of requests a single user of this tenant may issue per minute.
+	pub rate_limit_per_minute: Option<u32>,
+}
+
+/// The set of configured tenants.
+///
+/// Requests which match no tenant fall back to the server-wide default authorizer and are not
+/// subject to any tenant limits.
+#[derive(Default)]
+pub struct TenantRegistry {
+	tenants: Vec<Tenant>,
+	rate_limiter: RateLimiter,
+}
+
+impl TenantRegistry {
+	pub fn new(tenants: Vec<Tenant>) -> Self {
+		Self { tenants, rate_limiter: RateLimiter::default() }
+	}
+
+	/// Resolves the tenant responsible for the given `store_id`, if any. Tenants are matched in
+	/// configuration order, the first matching prefix wins.
+	pub fn resolve(&self, store_id: &str) -> Option<&Tenant> {
+		self.tenants.iter().find(|tenant| store_id.starts_with(&tenant.store_id_prefix))
+	}
+
+	/// Records a request of the given user against the tenant's rate limit, returning `false` if
+	/// the limit is exhausted and the request must be rejected.
+	pub fn check_rate_limit(&self, tenant: &Tenant, user_token: &str) -> bool {
+		match tenant.rate_limit_per_minute {
+			Some(limit) => {
+				self.rate_limiter.record(&format!("{}/{}", tenant.name, user_token), limit)
+			},
+			None => true,
+		}
+	}
+}
+
+/// A simple fixed-window (per minute) in-memory rate limiter.
+#[derive(Default)]
+struct RateLimiter {
+	// Maps a bucket key to the minute-window it was last used in and the request count within
+	// that window.
+	windows: Mutex<HashMap<String, (u64, u32)>>,
+}
+
+impl RateLimiter {
+	fn record(&self, bucket: &str, limit: u32) -> bool {
+		let current_minute =
+			SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() / 60;
+		let mut windows = self.windows.lock().unwrap();
+		let entry = windows.entry(bucket.to_string()).or_insert((current_minute, 0));
+		if entry.0 != current_minute {
+			*entry = (current_minute, 0);
+		}
+		if entry.1 >= limit {
+			return false;
+		}
+		entry.1 += 1;
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn test_tenant(rate_limit_per_minute: Option<u32>) -> Tenant {
+		Tenant {
+			name: "tenant".to_string(),
+			store_id_prefix: "tenant-".to_string(),
+			authorizer: None,
+			rate_limit_per_minute,
+		}
+	}
+
+	#[test]
+	fn resolves_by_store_id_prefix() {
+		let registry = TenantRegistry::new(vec![test_tenant(None)]);
+		assert!(registry.resolve("tenant-store").is_some());
+		assert!(registry.resolve("other-store").is_none());
+	}
+
+	#[test]
+	fn rate_limit_is_enforced_per_user() {
+		let registry = TenantRegistry::new(vec![test_tenant(Some(2))]);
+		let tenant = registry.resolve("tenant-store").unwrap();
+		assert!(registry.check_rate_limit(tenant, "user-a"));
+		assert!(registry.check_rate_limit(tenant, "user-a"));
+		assert!(!registry.check_rate_limit(tenant, "user-a"));
+		// Other users are unaffected.
+		assert!(registry.check_rate_limit(tenant, "user-b"));
+	}
+}
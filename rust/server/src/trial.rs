@@ -0,0 +1,359 @@
+//! Anonymous trial identities for credential-less onboarding.
+//!
+//! With `trial_config` set, a request carrying no credentials is served under a random,
+//! server-issued identity instead of collapsing onto the unauthenticated fallback's single
+//! token: the first such request is answered with an [`TRIAL_TOKEN_HEADER`] response header the
+//! client persists (cookie-style) and presents on subsequent requests. Trial identities carry a
+//! deliberately tight write quota and expire after the configured TTL, after which their data is
+//! purged by a background sweep — unless the client upgrades by presenting the trial token
+//! alongside a real credential, which migrates the data into the authenticated user's namespace.
+//!
+//! Like user suspensions and store grants, the trial table is in-memory and not persisted
+//! across restarts: an identity issued before a restart is rejected afterwards and the client
+//! starts over, which is acceptable for pre-auth trial data.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+
+use api::error::VssError;
+use api::kv_store::{KvStore, KvStoreAdmin, RequestContext};
+use api::types::{
+	DeleteObjectRequest, GetObjectRequest, KeyValue, ListKeyVersionsRequest, PutObjectRequest,
+};
+
+use crate::config::TrialConfig;
+use crate::vss_service::UserTokenHasher;
+
+/// The header a trial identity is issued in (on the first credential-less response) and
+/// presented in (on subsequent requests, and alongside a real credential to upgrade).
+pub const TRIAL_TOKEN_HEADER: &str = "x-vss-trial-token";
+
+/// The default maximum number of items a trial identity may write.
+pub const DEFAULT_TRIAL_MAX_ITEMS: u64 = 100;
+/// The default maximum number of value bytes a trial identity may write.
+pub const DEFAULT_TRIAL_MAX_VALUE_BYTES: u64 = 256 * 1024;
+
+struct TrialAccount {
+	expires_at_millis: u64,
+	/// Cumulative written items and value bytes. The quota counts writes rather than live
+	/// usage — a deliberately coarse cap that needs no backend round trip per request.
+	items_written: u64,
+	value_bytes_written: u64,
+}
+
+/// The table of issued trial identities, and the data migration/purge operations tied to their
+/// lifecycle.
+pub struct TrialRegistry {
+	config: TrialConfig,
+	store: Arc<dyn KvStore>,
+	admin_store: Arc<dyn KvStoreAdmin>,
+	user_token_hasher: Option<Arc<UserTokenHasher>>,
+	accounts: RwLock<HashMap<String, TrialAccount>>,
+}
+
+impl TrialRegistry {
+	pub fn new(
+		config: TrialConfig, store: Arc<dyn KvStore>, admin_store: Arc<dyn KvStoreAdmin>,
+		user_token_hasher: Option<Arc<UserTokenHasher>>,
+	) -> Self {
+		Self {
+			config,
+			store,
+			admin_store,
+			user_token_hasher,
+			accounts: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Resolves the trial identity of a credential-less request: issues a fresh identity when no
+	/// token is presented, accepts a presented token while it is alive and rejects unknown or
+	/// expired ones (the client discards its token and starts over). Returns the effective
+	/// trial token and whether it was newly issued.
+	pub fn resolve(&self, presented: Option<&str>) -> Result<(String, bool), VssError> {
+		self.resolve_at(presented, now_millis())
+	}
+
+	fn resolve_at(
+		&self, presented: Option<&str>, now_millis: u64,
+	) -> Result<(String, bool), VssError> {
+		let mut accounts = self.accounts.write().unwrap();
+		if let Some(token) = presented {
+			return match accounts.get(token) {
+				Some(account) if account.expires_at_millis > now_millis => {
+					Ok((token.to_string(), false))
+				},
+				_ => Err(VssError::AuthError(
+					"Unknown or expired trial identity, discard the token to start over."
+						.to_string(),
+				)),
+			};
+		}
+		let mut random_bytes = [0u8; 16];
+		OsRng.fill_bytes(&mut random_bytes);
+		let token = format!("trial-{}", hex::encode(random_bytes));
+		let account = TrialAccount {
+			expires_at_millis: now_millis + self.config.ttl_secs * 1000,
+			items_written: 0,
+			value_bytes_written: 0,
+		};
+		accounts.insert(token.clone(), account);
+		Ok((token, true))
+	}
+
+	/// Charges a write of the given shape against the trial identity's quota, returning whether
+	/// it fits. A rejected write leaves the counters untouched.
+	pub fn check_write_quota(&self, token: &str, items: u64, value_bytes: u64) -> bool {
+		let max_items = self.config.max_items.unwrap_or(DEFAULT_TRIAL_MAX_ITEMS);
+		let max_value_bytes =
+			self.config.max_value_bytes.unwrap_or(DEFAULT_TRIAL_MAX_VALUE_BYTES);
+		let mut accounts = self.accounts.write().unwrap();
+		let account = match accounts.get_mut(token) {
+			Some(account) => account,
+			None => return false,
+		};
+		if account.items_written + items > max_items
+			|| account.value_bytes_written + value_bytes > max_value_bytes
+		{
+			return false;
+		}
+		account.items_written += items;
+		account.value_bytes_written += value_bytes;
+		true
+	}
+
+	/// Upgrades a trial identity to the given authenticated user: all of the trial user's data
+	/// is moved into the authenticated user's namespace (under the same store ids) and the trial
+	/// account is retired. Returns the number of migrated items, or `None` for an unknown or
+	/// already-claimed token (so clients re-sending the header after an upgrade are unaffected).
+	pub async fn claim(
+		&self, trial_token: &str, user_token: &str,
+	) -> Result<Option<u64>, VssError> {
+		if self.accounts.write().unwrap().remove(trial_token).is_none() {
+			return Ok(None);
+		}
+		let migrated = self.move_user_data(&self.storage_token(trial_token), user_token).await?;
+		Ok(Some(migrated))
+	}
+
+	/// Retires all expired trial identities and purges their data, returning the number of
+	/// purged identities.
+	pub async fn purge_expired(&self) -> Result<u64, VssError> {
+		let now_millis = now_millis();
+		let expired: Vec<String> = {
+			let mut accounts = self.accounts.write().unwrap();
+			let expired = accounts
+				.iter()
+				.filter(|(_, account)| account.expires_at_millis <= now_millis)
+				.map(|(token, _)| token.clone())
+				.collect::<Vec<_>>();
+			for token in &expired {
+				accounts.remove(token);
+			}
+			expired
+		};
+		let purged = expired.len() as u64;
+		for token in expired {
+			self.delete_user_data(&self.storage_token(&token)).await?;
+		}
+		Ok(purged)
+	}
+
+	/// The token the trial user's data is stored under: with user token hashing configured, the
+	/// service hashes trial tokens like any other authenticated token.
+	fn storage_token(&self, trial_token: &str) -> String {
+		match &self.user_token_hasher {
+			Some(hasher) => hasher.hash(trial_token),
+			None => trial_token.to_string(),
+		}
+	}
+
+	/// Moves every key of every store of `from_token` into `to_token`'s namespace, returning the
+	/// number of moved items. Writes go through the regular [`KvStore`], so they are logged and
+	/// replicated like any other mutation.
+	async fn move_user_data(&self, from_token: &str, to_token: &str) -> Result<u64, VssError> {
+		let mut moved = 0;
+		for store_id in self.admin_store.list_store_ids(from_token.to_string()).await? {
+			for key in self.store_keys(from_token, &store_id).await? {
+				let get_request =
+					GetObjectRequest { store_id: store_id.clone(), key: key.clone() };
+				let response = self
+					.store
+					.get(RequestContext::new(from_token.to_string()), get_request)
+					.await?;
+				let value = match response.value {
+					Some(key_value) => key_value.value,
+					None => continue,
+				};
+				let put_request = PutObjectRequest {
+					store_id: store_id.clone(),
+					global_version: None,
+					transaction_items: vec![KeyValue { key: key.clone(), version: -1, value }],
+					delete_items: vec![],
+					dry_run: false,
+				};
+				self.store.put(RequestContext::new(to_token.to_string()), put_request).await?;
+				self.delete_key(from_token, &store_id, &key).await?;
+				moved += 1;
+			}
+		}
+		Ok(moved)
+	}
+
+	/// Deletes every key of every store of the given user.
+	async fn delete_user_data(&self, user_token: &str) -> Result<(), VssError> {
+		for store_id in self.admin_store.list_store_ids(user_token.to_string()).await? {
+			for key in self.store_keys(user_token, &store_id).await? {
+				self.delete_key(user_token, &store_id, &key).await?;
+			}
+		}
+		Ok(())
+	}
+
+	async fn store_keys(&self, user_token: &str, store_id: &str) -> Result<Vec<String>, VssError> {
+		let mut keys = Vec::new();
+		let mut page_token: Option<String> = None;
+		loop {
+			let request = ListKeyVersionsRequest {
+				store_id: store_id.to_string(),
+				key_prefix: None,
+				page_size: None,
+				page_token: page_token.clone(),
+			};
+			let response = self
+				.store
+				.list_key_versions(RequestContext::new(user_token.to_string()), request)
+				.await?;
+			keys.extend(response.key_versions.into_iter().map(|key_version| key_version.key));
+			match response.next_page_token {
+				Some(token) if !token.is_empty() => page_token = Some(token),
+				_ => break,
+			}
+		}
+		Ok(keys)
+	}
+
+	async fn delete_key(
+		&self, user_token: &str, store_id: &str, key: &str,
+	) -> Result<(), VssError> {
+		let delete_request = DeleteObjectRequest {
+			store_id: store_id.to_string(),
+			key_value: Some(KeyValue {
+				key: key.to_string(),
+				version: -1,
+				value: Default::default(),
+			}),
+		};
+		self.store.delete(RequestContext::new(user_token.to_string()), delete_request).await?;
+		Ok(())
+	}
+}
+
+fn now_millis() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use impls::memory_store::MemoryBackendImpl;
+
+	fn registry(config: TrialConfig) -> TrialRegistry {
+		let backend = Arc::new(MemoryBackendImpl::new());
+		TrialRegistry::new(config, backend.clone(), backend, None)
+	}
+
+	fn config(ttl_secs: u64) -> TrialConfig {
+		TrialConfig {
+			ttl_secs,
+			max_items: Some(3),
+			max_value_bytes: Some(100),
+			purge_interval_secs: None,
+		}
+	}
+
+	#[test]
+	fn identities_are_issued_and_expire() {
+		let registry = registry(config(10));
+		let (token, issued) = registry.resolve_at(None, 1_000).unwrap();
+		assert!(issued);
+		assert!(token.starts_with("trial-"));
+
+		// Presenting the issued token resolves to the same identity while it is alive.
+		let (resolved, issued) = registry.resolve_at(Some(&token), 5_000).unwrap();
+		assert_eq!(resolved, token);
+		assert!(!issued);
+
+		// Past the TTL (and for made-up tokens) the identity is rejected.
+		assert!(registry.resolve_at(Some(&token), 11_001).is_err());
+		assert!(registry.resolve_at(Some("trial-unknown"), 5_000).is_err());
+	}
+
+	#[test]
+	fn write_quotas_are_cumulative() {
+		let registry = registry(config(10));
+		let (token, _) = registry.resolve_at(None, 1_000).unwrap();
+
+		assert!(registry.check_write_quota(&token, 2, 50));
+		// A write that would exceed either cap is rejected without charging the counters.
+		assert!(!registry.check_write_quota(&token, 2, 10));
+		assert!(!registry.check_write_quota(&token, 1, 60));
+		assert!(registry.check_write_quota(&token, 1, 50));
+	}
+
+	#[tokio::test]
+	async fn claiming_moves_data_and_purging_deletes_it() {
+		let backend = Arc::new(MemoryBackendImpl::new());
+		let registry =
+			TrialRegistry::new(config(0), backend.clone(), backend.clone(), None);
+		let (token, _) = registry.resolve_at(None, 1_000).unwrap();
+
+		let put_request = PutObjectRequest {
+			store_id: "store".to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: "k1".to_string(),
+				version: 0,
+				value: b"v1".to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		let store: Arc<dyn KvStore> = backend.clone();
+		store.put(RequestContext::new(token.clone()), put_request).await.unwrap();
+
+		// Claiming moves the data under the authenticated user and retires the identity.
+		assert_eq!(registry.claim(&token, "real-user").await.unwrap(), Some(1));
+		assert_eq!(registry.claim(&token, "real-user").await.unwrap(), None);
+		let get_request =
+			GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+		let response = store
+			.get(RequestContext::new("real-user".to_string()), get_request.clone())
+			.await
+			.unwrap();
+		assert_eq!(response.value.unwrap().value, b"v1".to_vec());
+		let result = store.get(RequestContext::new(token.clone()), get_request.clone()).await;
+		assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+
+		// An expired identity's data is swept.
+		let (expired_token, _) = registry.resolve_at(None, 0).unwrap();
+		let put_request = PutObjectRequest {
+			store_id: "store".to_string(),
+			global_version: None,
+			transaction_items: vec![KeyValue {
+				key: "k1".to_string(),
+				version: 0,
+				value: b"v2".to_vec().into(),
+			}],
+			delete_items: vec![],
+			dry_run: false,
+		};
+		store.put(RequestContext::new(expired_token.clone()), put_request).await.unwrap();
+		assert_eq!(registry.purge_expired().await.unwrap(), 1);
+		let result = store.get(RequestContext::new(expired_token), get_request).await;
+		assert!(matches!(result, Err(VssError::NoSuchKeyError(..))));
+	}
+}
@@ -0,0 +1,1787 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures_util::stream;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::service::Service;
+use hyper::{HeaderMap, Request, Response, StatusCode};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use tracing::{field, info, warn, Instrument};
+
+use api::auth::{
+	ip_within_cidrs, scopes_contain, store_id_allowed, AuthFailureAuditLog, AuthFailureEvent,
+	AuthResponse, Authorizer, RequestHeaders, ALLOWED_CIDRS_ATTRIBUTE,
+	ALLOWED_STORE_IDS_ATTRIBUTE, SCOPES_ATTRIBUTE, WRITE_SCOPE,
+};
+use api::error::{sub_codes, VssError};
+use api::kv_store::{KvStore, RequestContext};
+use api::types::{
+	AcquireLeaseRequest, AcquireLeaseResponse, DeleteObjectRequest, DeleteObjectResponse,
+	ErrorCode, ErrorResponse, GetObjectRequest, GetObjectResponse, GetStoreStatsRequest,
+	GetStoreStatsResponse, GrantStoreAccessRequest, GrantStoreAccessResponse, ListDevicesRequest,
+	ListDevicesResponse, ListKeyVersionsRequest,
+	ListKeyVersionsResponse, PutObjectRequest, PutObjectResponse, RegisterDeviceRequest,
+	RegisterDeviceResponse, ReleaseLeaseRequest, ReleaseLeaseResponse, RenameObjectRequest,
+	RenameObjectResponse, RevokeStoreAccessRequest, RevokeStoreAccessResponse, SwapObjectRequest,
+	SwapObjectResponse,
+};
+
+use crate::admin_service::{AdminService, AdminState, ADMIN_PATH_PREFIX};
+use crate::alerts::AlertMonitor;
+use crate::capture::{
+	anonymize_identifier, anonymize_store_id, now_millis, CaptureEntry, CaptureLog,
+};
+use crate::grants::{GrantRegistry, GRANT_OWNER_HEADER};
+use crate::metrics::RequestMetrics;
+use crate::rate_limit::RateLimiter;
+use crate::secrets::hmac_sha256;
+use crate::tenants::TenantRegistry;
+use crate::trial::{TrialRegistry, TRIAL_TOKEN_HEADER};
+
+const BASE_PATH_PREFIX: &str = "/vss";
+
+/// The unauthenticated readiness probe endpoint, reporting 503 while the instance is draining
+/// or its storage backend is unreachable, so load balancers rotate it out of service.
+/// [`HEALTH_READINESS_PATH`] is an alias matching the conventional Kubernetes probe layout.
+pub const READINESS_PATH: &str = "/ready";
+
+/// The unauthenticated liveness probe endpoint, reporting 200 as long as the process serves
+/// requests at all. Unlike readiness it deliberately ignores backend health: restarting the
+/// server does not cure a database outage.
+pub const LIVENESS_PATH: &str = "/health/live";
+
+/// The Kubernetes-style alias of [`READINESS_PATH`].
+pub const HEALTH_READINESS_PATH: &str = "/health/ready";
+
+/// The default cap on request body sizes, see
+/// [`VssService::with_max_request_body_bytes`].
+///
+/// Generous enough for the 1000-item put envelope with multi-megabyte channel monitors, while
+/// bounding what a single request can make the server buffer.
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// List pages with at least this many key-versions are streamed instead of buffered in full.
+const STREAMED_LIST_MIN_ITEMS: usize = 64;
+
+/// Cost-accounting response headers reporting what each request moved and how long the backend
+/// took, helping client developers spot chatty or oversized access patterns during development.
+pub const COST_REQUEST_BYTES_HEADER: &str = "x-vss-request-bytes";
+pub const COST_RESPONSE_BYTES_HEADER: &str = "x-vss-response-bytes";
+pub const COST_ROWS_HEADER: &str = "x-vss-rows";
+pub const COST_BACKEND_MICROS_HEADER: &str = "x-vss-backend-micros";
+
+/// The response body type served by [`VssService`]: either a buffered protobuf message or a
+/// streamed encoding of a large list page.
+pub type ResponseBody = BoxBody<Bytes, Infallible>;
+
+/// Limits enforced on request-supplied identifiers, see
+/// [`VssService::with_validation_limits`].
+///
+/// Requests violating the limits are rejected with HTTP 400 at the service layer, instead of
+/// failing deep in the storage backend (e.g. as a varchar length error surfacing as HTTP 500).
+#[derive(Clone, Copy, Debug)]
+pub struct ValidationLimits {
+	/// The maximum accepted `key` length in characters.
+	pub max_key_length: usize,
+	/// The maximum accepted `store_id` length in characters.
+	pub max_store_id_length: usize,
+}
+
+impl Default for ValidationLimits {
+	// The defaults match the column widths of the PostgreSQL schema.
+	fn default() -> Self {
+		ValidationLimits { max_key_length: 600, max_store_id_length: 120 }
+	}
+}
+
+fn validate_store_id(store_id: &str, limits: &ValidationLimits) -> Result<(), VssError> {
+	validate_identifier("store_id", store_id, limits.max_store_id_length)
+}
+
+fn validate_key(key: &str, limits: &ValidationLimits) -> Result<(), VssError> {
+	validate_identifier("key", key, limits.max_key_length)
+}
+
+// The lease name must form a valid key together with the reserved prefix, see `crate::lease`.
+fn validate_lease_name(lease_name: &str, limits: &ValidationLimits) -> Result<(), VssError> {
+	if lease_name.is_empty() {
+		return Err(VssError::InvalidRequestError("lease_name must not be empty.".to_string()));
+	}
+	validate_key(&crate::lease::lease_key(lease_name), limits)
+}
+
+/// Parses the entries of a W3C `baggage` header into key/value pairs, ignoring the optional
+/// per-entry properties.
+fn parse_baggage(header: &str) -> impl Iterator<Item = (&str, &str)> {
+	header.split(',').filter_map(|entry| {
+		let entry = entry.split(';').next().unwrap_or_default();
+		let (key, value) = entry.split_once('=')?;
+		Some((key.trim(), value.trim()))
+	})
+}
+
+/// Renders request attributes (authorizer-provided and propagated baggage) for span annotation.
+fn format_attributes(attributes: &std::collections::HashMap<String, String>) -> String {
+	let mut pairs: Vec<String> =
+		attributes.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+	pairs.sort();
+	pairs.join(",")
+}
+
+/// Formats a key version as the strong entity tag carried in the `ETag` response header.
+fn format_etag(version: i64) -> String {
+	format!("\"{}\"", version)
+}
+
+/// Parses a key version out of a strong entity tag, as previously emitted by [`format_etag`].
+fn parse_etag_version(value: &str) -> Result<i64, VssError> {
+	let inner = value.strip_prefix('"').and_then(|value| value.strip_suffix('"'));
+	match inner.and_then(|version| version.parse::<i64>().ok()) {
+		Some(version) if version >= 0 => Ok(version),
+		_ => Err(VssError::InvalidRequestError(format!("Invalid entity tag: {}", value))),
+	}
+}
+
+fn validate_identifier(name: &str, value: &str, max_length: usize) -> Result<(), VssError> {
+	if value.is_empty() {
+		return Err(VssError::InvalidRequestError(format!("{} must not be empty.", name)));
+	}
+	if value.chars().count() > max_length {
+		return Err(VssError::InvalidRequestError(format!(
+			"{} exceeds the maximum length of {} characters.",
+			name, max_length
+		)));
+	}
+	if value.chars().any(char::is_control) {
+		return Err(VssError::InvalidRequestError(format!(
+			"{} must not contain control characters.",
+			name
+		)));
+	}
+	Ok(())
+}
+
+/// Replaces authenticated user tokens with a keyed hash (HMAC-SHA256 with a config-supplied
+/// pepper) before they reach the storage layer, logs or rate-limiter bookkeeping.
+///
+/// Signature-auth deployments would otherwise persist users' public keys verbatim, which
+/// operators holding the database shouldn't need to see. Everything downstream of the
+/// authorizer (including the user tokens accepted by the admin API) operates on hashed tokens.
+pub struct UserTokenHasher {
+	pepper: Vec<u8>,
+}
+
+impl UserTokenHasher {
+	pub fn new(pepper: String) -> Self {
+		Self { pepper: pepper.into_bytes() }
+	}
+
+	pub fn hash(&self, user_token: &str) -> String {
+		hex::encode(hmac_sha256(&self.pepper, user_token.as_bytes()))
+	}
+}
+
+/// A [`hyper`] service routing requests of the VSS HTTP API to the configured [`KvStore`] and
+/// [`Authorizer`], and requests of the admin API to the [`AdminService`] (if configured).
+#[derive(Clone)]
+pub struct VssService {
+	store: Arc<dyn KvStore>,
+	authorizer: Arc<dyn Authorizer>,
+	tenants: Arc<TenantRegistry>,
+	admin_state: Arc<AdminState>,
+	admin_service: Option<Arc<AdminService>>,
+	grants: Arc<GrantRegistry>,
+	user_token_hasher: Option<Arc<UserTokenHasher>>,
+	audit_log: Option<Arc<dyn AuthFailureAuditLog>>,
+	capture_log: Option<Arc<CaptureLog>>,
+	metrics: Option<Arc<RequestMetrics>>,
+	alerts: Option<Arc<AlertMonitor>>,
+	trials: Option<Arc<TrialRegistry>>,
+	rate_limiter: Option<Arc<RateLimiter>>,
+	baggage_keys: Arc<Vec<String>>,
+	trusted_proxies: Arc<Vec<String>>,
+	max_request_body_bytes: usize,
+	validation_limits: ValidationLimits,
+	peer_addr: Option<SocketAddr>,
+	peer_certificate: Option<Arc<Vec<u8>>>,
+}
+
+impl VssService {
+	pub fn new(
+		store: Arc<dyn KvStore>, authorizer: Arc<dyn Authorizer>, tenants: Arc<TenantRegistry>,
+		admin_state: Arc<AdminState>, admin_service: Option<Arc<AdminService>>,
+		user_token_hasher: Option<Arc<UserTokenHasher>>,
+		audit_log: Option<Arc<dyn AuthFailureAuditLog>>,
+	) -> Self {
+		let grants = Arc::new(GrantRegistry::new(Arc::clone(&store)));
+		Self {
+			store,
+			authorizer,
+			tenants,
+			admin_state,
+			admin_service,
+			grants,
+			user_token_hasher,
+			audit_log,
+			capture_log: None,
+			metrics: None,
+			alerts: None,
+			trials: None,
+			rate_limiter: None,
+			baggage_keys: Arc::new(Vec::new()),
+			trusted_proxies: Arc::new(Vec::new()),
+			max_request_body_bytes: DEFAULT_MAX_REQUEST_BODY_BYTES,
+			validation_limits: ValidationLimits::default(),
+			peer_addr: None,
+			peer_certificate: None,
+		}
+	}
+
+	/// Returns a copy of this service enforcing the given [`ValidationLimits`] on
+	/// request-supplied identifiers instead of the defaults.
+	pub fn with_validation_limits(mut self, validation_limits: ValidationLimits) -> Self {
+		self.validation_limits = validation_limits;
+		self
+	}
+
+	/// Returns a copy of this service capping request bodies at the given size instead of
+	/// [`DEFAULT_MAX_REQUEST_BODY_BYTES`]. Larger requests are rejected with HTTP 413 before the
+	/// body is buffered in full.
+	pub fn with_max_request_body_bytes(mut self, max_request_body_bytes: usize) -> Self {
+		self.max_request_body_bytes = max_request_body_bytes;
+		self
+	}
+
+	/// Returns a copy of this service recording sanitized request/response records to the given
+	/// [`CaptureLog`].
+	pub fn with_capture_log(mut self, capture_log: Arc<CaptureLog>) -> Self {
+		self.capture_log = Some(capture_log);
+		self
+	}
+
+	/// Returns a copy of this service recording per-request instruments to the given
+	/// [`RequestMetrics`].
+	pub fn with_metrics(mut self, metrics: Arc<RequestMetrics>) -> Self {
+		self.metrics = Some(metrics);
+		self
+	}
+
+	/// Returns a copy of this service evaluating response statuses against the given
+	/// [`AlertMonitor`]'s thresholds.
+	pub fn with_alerts(mut self, alerts: Arc<AlertMonitor>) -> Self {
+		self.alerts = Some(alerts);
+		self
+	}
+
+	/// Returns a copy of this service serving credential-less requests under random, expiring
+	/// trial identities from the given [`TrialRegistry`].
+	pub fn with_trials(mut self, trials: Arc<TrialRegistry>) -> Self {
+		self.trials = Some(trials);
+		self
+	}
+
+	/// Returns a copy of this service drawing every request from the given [`RateLimiter`]'s
+	/// per-user token buckets (and failed authentication attempts from per-IP buckets) before it
+	/// reaches the storage backend. Exhausted buckets yield HTTP 429.
+	pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+		self.rate_limiter = Some(rate_limiter);
+		self
+	}
+
+	/// Returns a copy of this service propagating the listed W3C `baggage` entries (e.g. a
+	/// client app version or device id) into request attributes and span annotations. Entries
+	/// not listed are ignored.
+	pub fn with_baggage_keys(mut self, baggage_keys: Vec<String>) -> Self {
+		self.baggage_keys = Arc::new(baggage_keys);
+		self
+	}
+
+	/// Returns a copy of this service honoring `X-Forwarded-For` on connections from the given
+	/// proxy addresses (or CIDR ranges). Without this, the effective client IP is always the
+	/// peer address of the connection, so clients cannot spoof IP-bound credentials or per-IP
+	/// rate limits via forwarded headers.
+	pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<String>) -> Self {
+		self.trusted_proxies = Arc::new(trusted_proxies);
+		self
+	}
+
+	/// Returns a copy of this service bound to the peer address of a single accepted
+	/// connection, serving as the effective client IP unless a trusted proxy forwards another
+	/// address.
+	pub fn with_peer_addr(mut self, peer_addr: SocketAddr) -> Self {
+		self.peer_addr = Some(peer_addr);
+		self
+	}
+
+	/// Returns a copy of this service bound to the DER-encoded client certificate presented on a
+	/// single accepted TLS connection, exposed to authorizers via
+	/// [`RequestHeaders::peer_certificate`].
+	pub fn with_peer_certificate(mut self, peer_certificate: Arc<Vec<u8>>) -> Self {
+		self.peer_certificate = Some(peer_certificate);
+		self
+	}
+
+	/// Reloads the persisted store grants from the backing store; called once at startup, before
+	/// the service answers requests.
+	pub async fn load_grants(&self) -> Result<(), VssError> {
+		self.grants.load().await
+	}
+}
+
+/// Provides access to the `store_id` a request operates on (allowing the service to resolve the
+/// responsible tenant before authentication and dispatch) and to the request's traffic shape
+/// recorded by the capture log.
+trait StoreRequest {
+	fn store_id(&self) -> &str;
+	fn operation(&self) -> &'static str;
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(self.store_id(), limits)
+	}
+	fn item_count(&self) -> usize {
+		1
+	}
+	fn value_bytes(&self) -> usize {
+		0
+	}
+	fn page_size(&self) -> Option<i32> {
+		None
+	}
+	/// Whether the operation removes stored rows, making it subject to a store's deletion
+	/// protection.
+	fn deletes_rows(&self) -> bool {
+		false
+	}
+	/// Whether the operation mutates stored rows at all, making it subject to a store being
+	/// archived (frozen read-only).
+	fn mutates_rows(&self) -> bool {
+		false
+	}
+	/// Applies the conditional HTTP headers (`If-Match`/`If-None-Match`) to the request, mapping
+	/// entity tags back to key versions where the operation supports them.
+	fn apply_conditional_headers(
+		&mut self, _headers: &dyn RequestHeaders,
+	) -> Result<(), VssError> {
+		Ok(())
+	}
+	/// The entity tag a successful response carries, where it is determined by the request
+	/// itself: a single-item put supersedes the item's version by one.
+	fn response_etag(&self) -> Option<String> {
+		None
+	}
+}
+
+/// Maps a response's key version to a strong `ETag` header value, where one applies, so generic
+/// HTTP caches and client libraries can issue conditional requests without decoding protobuf.
+trait StoreResponse {
+	fn etag(&self) -> Option<String> {
+		None
+	}
+}
+
+impl StoreRequest for GetObjectRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"get"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		validate_key(&self.key, limits)
+	}
+}
+
+impl StoreRequest for PutObjectRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"put"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		for kv in self.transaction_items.iter().chain(self.delete_items.iter()) {
+			validate_key(&kv.key, limits)?;
+		}
+		Ok(())
+	}
+
+	fn item_count(&self) -> usize {
+		self.transaction_items.len() + self.delete_items.len()
+	}
+
+	fn value_bytes(&self) -> usize {
+		self.transaction_items.iter().map(|kv| kv.value.len()).sum()
+	}
+
+	fn deletes_rows(&self) -> bool {
+		!self.delete_items.is_empty()
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+
+	fn apply_conditional_headers(&mut self, headers: &dyn RequestHeaders) -> Result<(), VssError> {
+		let if_match = headers.get_header("if-match").map(str::trim);
+		let if_none_match = headers.get_header("if-none-match").map(str::trim);
+		if if_match.is_none() && if_none_match.is_none() {
+			return Ok(());
+		}
+		if if_match.is_some() && if_none_match.is_some() {
+			return Err(VssError::InvalidRequestError(
+				"If-Match and If-None-Match must not be combined.".to_string(),
+			));
+		}
+		if self.transaction_items.len() != 1 || !self.delete_items.is_empty() {
+			return Err(VssError::InvalidRequestError(
+				"Conditional headers apply to puts of a single item.".to_string(),
+			));
+		}
+		let item = &mut self.transaction_items[0];
+		if let Some(if_match) = if_match {
+			// `If-Match: *` makes the write unconditional, a concrete entity tag pins the stored
+			// version the write must supersede.
+			item.version = if if_match == "*" { -1 } else { parse_etag_version(if_match)? };
+		}
+		if let Some(if_none_match) = if_none_match {
+			if if_none_match != "*" {
+				return Err(VssError::InvalidRequestError(
+					"If-None-Match on puts only supports *.".to_string(),
+				));
+			}
+			// Create-only: version 0 requires that the key does not exist yet.
+			item.version = 0;
+		}
+		Ok(())
+	}
+
+	fn response_etag(&self) -> Option<String> {
+		match self.transaction_items.as_slice() {
+			[item] if self.delete_items.is_empty() && item.version >= 0 => {
+				Some(format_etag(item.version + 1))
+			},
+			_ => None,
+		}
+	}
+}
+
+impl StoreRequest for DeleteObjectRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"delete"
+	}
+
+	fn deletes_rows(&self) -> bool {
+		true
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		if let Some(key_value) = &self.key_value {
+			validate_key(&key_value.key, limits)?;
+		}
+		Ok(())
+	}
+}
+
+impl StoreRequest for ListKeyVersionsRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"list"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		// An absent or empty prefix is valid, but the limits apply to any provided one.
+		match self.key_prefix.as_deref() {
+			Some(key_prefix) if !key_prefix.is_empty() => validate_key(key_prefix, limits),
+			_ => Ok(()),
+		}
+	}
+
+	fn page_size(&self) -> Option<i32> {
+		self.page_size
+	}
+}
+
+impl StoreRequest for GetStoreStatsRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"get_store_stats"
+	}
+}
+
+impl StoreRequest for SwapObjectRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"swap"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		if let Some(item) = &self.item {
+			validate_key(&item.key, limits)?;
+		}
+		Ok(())
+	}
+
+	fn value_bytes(&self) -> usize {
+		self.item.as_ref().map(|item| item.value.len()).unwrap_or(0)
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+}
+
+impl StoreRequest for RenameObjectRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"rename"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		validate_key(&self.from_key, limits)?;
+		validate_key(&self.to_key, limits)
+	}
+
+	fn deletes_rows(&self) -> bool {
+		// The value survives under the new key, but the source row is still removed.
+		true
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+}
+
+impl StoreResponse for GetObjectResponse {
+	fn etag(&self) -> Option<String> {
+		self.value.as_ref().map(|key_value| format_etag(key_value.version))
+	}
+}
+
+impl StoreResponse for PutObjectResponse {}
+
+impl StoreResponse for DeleteObjectResponse {}
+
+impl StoreResponse for ListKeyVersionsResponse {}
+
+impl StoreRequest for GrantStoreAccessRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"grant_store_access"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		if self.grantee_token.is_empty() {
+			return Err(VssError::InvalidRequestError(
+				"grantee_token must not be empty.".to_string(),
+			));
+		}
+		Ok(())
+	}
+}
+
+impl StoreRequest for RevokeStoreAccessRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"revoke_store_access"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		if self.grantee_token.is_empty() {
+			return Err(VssError::InvalidRequestError(
+				"grantee_token must not be empty.".to_string(),
+			));
+		}
+		Ok(())
+	}
+}
+
+impl StoreRequest for AcquireLeaseRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"acquire_lease"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		validate_lease_name(&self.lease_name, limits)?;
+		if self.holder_id.is_empty() {
+			return Err(VssError::InvalidRequestError("holder_id must not be empty.".to_string()));
+		}
+		if self.ttl_secs == 0 {
+			return Err(VssError::InvalidRequestError(
+				"ttl_secs must be greater than 0.".to_string(),
+			));
+		}
+		Ok(())
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+}
+
+impl StoreRequest for ReleaseLeaseRequest {
+	fn store_id(&self) -> &str {
+		&self.store_id
+	}
+
+	fn operation(&self) -> &'static str {
+		"release_lease"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		validate_store_id(&self.store_id, limits)?;
+		validate_lease_name(&self.lease_name, limits)?;
+		if self.holder_id.is_empty() {
+			return Err(VssError::InvalidRequestError("holder_id must not be empty.".to_string()));
+		}
+		Ok(())
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+}
+
+impl StoreRequest for RegisterDeviceRequest {
+	fn store_id(&self) -> &str {
+		crate::devices::DEVICE_STORE_ID
+	}
+
+	fn operation(&self) -> &'static str {
+		"register_device"
+	}
+
+	fn validate(&self, limits: &ValidationLimits) -> Result<(), VssError> {
+		if self.device_id.is_empty() {
+			return Err(VssError::InvalidRequestError("device_id must not be empty.".to_string()));
+		}
+		// The device id becomes the record's key in the reserved device store.
+		validate_key(&self.device_id, limits)
+	}
+
+	fn value_bytes(&self) -> usize {
+		self.push_token.len()
+	}
+
+	fn mutates_rows(&self) -> bool {
+		true
+	}
+}
+
+impl StoreRequest for ListDevicesRequest {
+	fn store_id(&self) -> &str {
+		crate::devices::DEVICE_STORE_ID
+	}
+
+	fn operation(&self) -> &'static str {
+		"list_devices"
+	}
+
+	fn validate(&self, _limits: &ValidationLimits) -> Result<(), VssError> {
+		Ok(())
+	}
+}
+
+impl StoreResponse for GetStoreStatsResponse {}
+
+impl StoreResponse for RegisterDeviceResponse {}
+
+impl StoreResponse for ListDevicesResponse {}
+
+impl StoreResponse for AcquireLeaseResponse {}
+
+impl StoreResponse for ReleaseLeaseResponse {}
+
+impl StoreResponse for GrantStoreAccessResponse {}
+
+impl StoreResponse for RevokeStoreAccessResponse {}
+
+impl StoreResponse for RenameObjectResponse {}
+
+impl StoreResponse for SwapObjectResponse {
+	fn etag(&self) -> Option<String> {
+		// The write always supersedes the previous version by one (1 for a first write).
+		Some(format_etag(self.previous.as_ref().map(|kv| kv.version).unwrap_or(0) + 1))
+	}
+}
+
+impl Service<Request<Incoming>> for VssService {
+	type Response = Response<ResponseBody>;
+	type Error = hyper::http::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn call(&self, req: Request<Incoming>) -> Self::Future {
+		let service = self.clone();
+		let path = req.uri().path().to_owned();
+
+		Box::pin(async move {
+			if path.starts_with(ADMIN_PATH_PREFIX) {
+				return match &service.admin_service {
+					Some(admin_service) => admin_service
+						.handle(req)
+						.await
+						.map(|response| response.map(BodyExt::boxed)),
+					None => Response::builder()
+						.status(StatusCode::NOT_FOUND)
+						.body(Full::default().boxed()),
+				};
+			}
+			// Served unauthenticated: load balancers rotate a draining or backend-less instance
+			// out on the first failing probe, while in-flight requests keep being answered.
+			if path == LIVENESS_PATH {
+				return Response::builder()
+					.header(hyper::header::CONTENT_TYPE, "text/plain")
+					.body(Full::new(Bytes::from("ok")).boxed());
+			}
+			if path == READINESS_PATH || path == HEALTH_READINESS_PATH {
+				let draining = service.admin_state.draining.load(Ordering::Acquire);
+				let (status, body) = if draining {
+					(StatusCode::SERVICE_UNAVAILABLE, "draining")
+				} else if service.store.health_check().await.is_err() {
+					(StatusCode::SERVICE_UNAVAILABLE, "backend unavailable")
+				} else {
+					(StatusCode::OK, "ok")
+				};
+				return Response::builder()
+					.status(status)
+					.header(hyper::header::CONTENT_TYPE, "text/plain")
+					.body(Full::new(Bytes::from(body)).boxed());
+			}
+			// Served unauthenticated: clients of the signature-validating authorizer embed a
+			// timestamp in their tokens and use this to correct for device clock drift, which
+			// would otherwise surface as mysterious auth failures on skewed clocks.
+			if path == format!("{}/time", BASE_PATH_PREFIX) {
+				let now_secs = std::time::SystemTime::now()
+					.duration_since(std::time::UNIX_EPOCH)
+					.map(|elapsed| elapsed.as_secs())
+					.unwrap_or(0);
+				return Response::builder()
+					.header(hyper::header::CONTENT_TYPE, "text/plain")
+					.body(Full::new(Bytes::from(now_secs.to_string())).boxed());
+			}
+			// While in maintenance mode, reject all write operations so operators can safely
+			// perform backend maintenance. Reads remain available.
+			let is_write_path = path == format!("{}/putObjects", BASE_PATH_PREFIX)
+				|| path == format!("{}/deleteObject", BASE_PATH_PREFIX)
+				|| path == format!("{}/swapObject", BASE_PATH_PREFIX)
+				|| path == format!("{}/renameObject", BASE_PATH_PREFIX)
+				|| path == format!("{}/acquireLease", BASE_PATH_PREFIX)
+				|| path == format!("{}/releaseLease", BASE_PATH_PREFIX)
+				|| path == format!("{}/registerDevice", BASE_PATH_PREFIX);
+			if is_write_path && service.admin_state.maintenance_mode.load(Ordering::Acquire) {
+				let error_response = ErrorResponse {
+					error_code: ErrorCode::InternalServerException.into(),
+					message: "Server is in maintenance mode, please retry later.".to_string(),
+					sub_code: sub_codes::UNAVAILABLE_MAINTENANCE.to_string(),
+				};
+				return Response::builder()
+					.status(StatusCode::SERVICE_UNAVAILABLE)
+					.body(Full::new(Bytes::from(error_response.encode_to_vec())).boxed());
+			}
+			let alerts = service.alerts.clone();
+			let admin_state = Arc::clone(&service.admin_state);
+			let response = match path.as_str() {
+				path if path == format!("{}/getObject", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.get(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/putObjects", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.put(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/deleteObject", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.delete(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/swapObject", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.swap(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/renameObject", BASE_PATH_PREFIX)
+					&& service.admin_state.features.rename_object.load(Ordering::Acquire) =>
+				{
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.rename(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/acquireLease", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							crate::lease::acquire(store, context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/releaseLease", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							crate::lease::release(store, context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/registerDevice", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							crate::devices::register(store, context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/listDevices", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							crate::devices::list(store, context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/grantStoreAccess", BASE_PATH_PREFIX)
+					&& service.admin_state.features.store_grants.load(Ordering::Acquire) =>
+				{
+					let grants = Arc::clone(&service.grants);
+					let hasher = service.user_token_hasher.clone();
+					handle_request(
+						service,
+						req,
+						move |_store, context, request: GrantStoreAccessRequest| async move {
+							// Stored tokens must compare against the (possibly hashed)
+							// authenticated tokens grantees later present.
+							let grantee_token = match &hasher {
+								Some(hasher) => hasher.hash(&request.grantee_token),
+								None => request.grantee_token,
+							};
+							grants
+								.grant(context.user_token, grantee_token, request.store_id)
+								.await?;
+							Ok(GrantStoreAccessResponse {})
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/revokeStoreAccess", BASE_PATH_PREFIX)
+					&& service.admin_state.features.store_grants.load(Ordering::Acquire) =>
+				{
+					let grants = Arc::clone(&service.grants);
+					let hasher = service.user_token_hasher.clone();
+					handle_request(
+						service,
+						req,
+						move |_store, context, request: RevokeStoreAccessRequest| async move {
+							let grantee_token = match &hasher {
+								Some(hasher) => hasher.hash(&request.grantee_token),
+								None => request.grantee_token,
+							};
+							grants
+								.revoke(&context.user_token, &grantee_token, &request.store_id)
+								.await?;
+							Ok(RevokeStoreAccessResponse {})
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/getStoreStats", BASE_PATH_PREFIX) => {
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.get_store_stats(context, request).await
+						},
+						buffered_response,
+					)
+					.await
+				},
+				path if path == format!("{}/listKeyVersions", BASE_PATH_PREFIX) => {
+					// Streaming is the default; the flag keeps an escape hatch to the buffered
+					// encoding should a client choke on multi-frame bodies.
+					let streamed = service
+						.admin_state
+						.features
+						.streamed_list_responses
+						.load(Ordering::Acquire);
+					let encode =
+						if streamed { streamed_list_response } else { buffered_response };
+					handle_request(
+						service,
+						req,
+						|store, context, request| async move {
+							store.list_key_versions(context, request).await
+						},
+						encode,
+					)
+					.await
+				},
+				_ => Response::builder()
+					.status(StatusCode::NOT_FOUND)
+					.body(Full::default().boxed()),
+			};
+			// Only RPC responses feed the alert rates; the unauthenticated time endpoint and
+			// the admin API are not client traffic.
+			if let (Some(alerts), Ok(response)) = (&alerts, &response) {
+				alerts.observe(response.status());
+			}
+			let mut response = response;
+			// While draining, ask clients to close their keep-alive connections once the
+			// current request is answered, so the listener empties out for a clean rotation.
+			if admin_state.draining.load(Ordering::Acquire) {
+				if let Ok(response) = &mut response {
+					response.headers_mut().insert(
+						hyper::header::CONNECTION,
+						hyper::header::HeaderValue::from_static("close"),
+					);
+				}
+			}
+			response
+		})
+	}
+}
+
+async fn handle_request<
+	T: Message + Default + StoreRequest + serde::de::DeserializeOwned,
+	R: Message + StoreResponse + serde::Serialize,
+	F: FnOnce(Arc<dyn KvStore>, RequestContext, T) -> Fut,
+	Fut: Future<Output = Result<R, VssError>>,
+>(
+	service: VssService, request: Request<Incoming>, handler: F, encode: fn(R) -> ResponseBody,
+) -> Result<Response<ResponseBody>, hyper::http::Error> {
+	let started_at = std::time::Instant::now();
+	let (parts, body) = request.into_parts();
+
+	// A request with `content-type: application/json` is decoded from the canonical proto3 JSON
+	// mapping (see [`api::json`]) instead of binary protobuf, so the API can be exercised with
+	// curl and from environments without protobuf support. The response mirrors the request
+	// encoding unless an `accept` header asks for the other one.
+	let json_request = parts
+		.headers
+		.get(hyper::header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.trim_start().starts_with("application/json"));
+	let json_response = match parts.headers.get(hyper::header::ACCEPT) {
+		Some(accept) => accept.to_str().is_ok_and(|accept| accept.contains("application/json")),
+		None => json_request,
+	};
+
+	// A body declared oversized upfront is rejected before reading a single frame of it.
+	let declared_length = parts
+		.headers
+		.get(hyper::header::CONTENT_LENGTH)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.parse::<usize>().ok());
+	if declared_length.is_some_and(|length| length > service.max_request_body_bytes) {
+		return payload_too_large_response(service.max_request_body_bytes, json_response);
+	}
+
+	// Stream the body frame by frame and abort early once it exceeds the configured cap, so a
+	// single request can never make the server buffer unbounded amounts of data.
+	let mut body = body;
+	let mut body_bytes = Vec::new();
+	while let Some(frame) = body.frame().await {
+		let frame = match frame {
+			Ok(frame) => frame,
+			Err(e) => {
+				return error_response(
+					&VssError::InternalServerError(format!("Failed to read request body: {}", e)),
+					json_response,
+				)
+			},
+		};
+		if let Some(data) = frame.data_ref() {
+			if body_bytes.len() + data.len() > service.max_request_body_bytes {
+				return payload_too_large_response(service.max_request_body_bytes, json_response);
+			}
+			body_bytes.extend_from_slice(data);
+		}
+	}
+	let client_ip = effective_client_ip(
+		service.peer_addr.map(|peer_addr| peer_addr.ip()),
+		parts.headers.get("x-forwarded-for").and_then(|value| value.to_str().ok()),
+		&service.trusted_proxies,
+	);
+	let headers = HeaderView {
+		headers: &parts.headers,
+		path: parts.uri.path(),
+		body_sha256: Sha256::digest(&body_bytes).into(),
+		peer_certificate: service.peer_certificate.as_deref().map(Vec::as_slice),
+		client_ip,
+	};
+	let body_len = body_bytes.len();
+	let mut request = if json_request {
+		// An empty JSON body decodes to the default message, matching protobuf semantics for
+		// bodyless requests like `ListDevices`.
+		match serde_json::from_slice::<T>(if body_bytes.is_empty() { b"{}" } else { &body_bytes })
+		{
+			Ok(request) => request,
+			Err(e) => {
+				// The JSON path exists for hand-written clients, so unlike the protobuf path the
+				// deserialization error is worth relaying.
+				return error_response(
+					&VssError::InvalidRequestError(format!(
+						"Failed to deserialize request: {}",
+						e
+					)),
+					json_response,
+				)
+			},
+		}
+	} else {
+		match T::decode(body_bytes.as_slice()) {
+			Ok(request) => request,
+			Err(_) => {
+				return error_response(
+					&VssError::InvalidRequestError("Failed to deserialize request.".to_string()),
+					json_response,
+				)
+			},
+		}
+	};
+	if let Err(e) = request.validate(&service.validation_limits) {
+		return error_response(&e, json_response);
+	}
+	if let Err(e) = request.apply_conditional_headers(&headers) {
+		return error_response(&e, json_response);
+	}
+
+	// With trial mode configured, a request carrying no credentials is served under a random,
+	// server-issued identity instead of the unauthenticated fallback: the issuing response
+	// carries the token in [`TRIAL_TOKEN_HEADER`] and the client presents it from then on.
+	let mut trial_identity: Option<(String, bool)> = None;
+	if let Some(trials) = &service.trials {
+		if auth_scheme(&headers) == "none" {
+			match trials.resolve(headers.get_header(TRIAL_TOKEN_HEADER)) {
+				Ok(identity) => trial_identity = Some(identity),
+				Err(e) => {
+					if let Some(metrics) = &service.metrics {
+						metrics.record_auth("trial", "expired");
+					}
+					record_auth_failure(&service, &headers, "trial_expired").await;
+					return error_response(&e, json_response);
+				},
+			}
+		}
+	}
+
+	// Requests are authenticated with the tenant's authorizer (if one is configured for the
+	// request's store_id), falling back to the server-wide default.
+	let tenant = service.tenants.resolve(request.store_id());
+	let effective_authorizer =
+		tenant.and_then(|tenant| tenant.authorizer.as_ref()).unwrap_or(&service.authorizer);
+	let auth_response = match &trial_identity {
+		Some((trial_token, _)) => AuthResponse::new(trial_token.clone()),
+		None => match effective_authorizer.verify(&headers).await {
+			Ok(auth_response) => {
+				if let Some(metrics) = &service.metrics {
+					metrics.record_auth(auth_scheme(&headers), "ok");
+				}
+				auth_response
+			},
+			Err(e) => {
+				if let Some(metrics) = &service.metrics {
+					metrics.record_auth(auth_scheme(&headers), classify_auth_failure(&e));
+				}
+				record_auth_failure(&service, &headers, "invalid_credentials").await;
+				// Failed attempts draw from an IP-keyed bucket, so a brute-forcing client is
+				// cut off with 429 rather than probing credentials at full speed.
+				if let (Some(rate_limiter), Some(ip)) =
+					(&service.rate_limiter, headers.client_ip())
+				{
+					if !rate_limiter.check(&format!("ip/{}", ip), request.operation()) {
+						return too_many_requests_response(json_response);
+					}
+				}
+				return error_response(&e, json_response);
+			},
+		},
+	};
+
+	// Trial identities write against a deliberately tight cumulative quota.
+	if let (Some(trials), Some((trial_token, _))) = (&service.trials, &trial_identity) {
+		if request.mutates_rows()
+			&& !trials.check_write_quota(
+				trial_token,
+				request.item_count() as u64,
+				request.value_bytes() as u64,
+			) {
+			let error_response = ErrorResponse {
+				error_code: ErrorCode::InternalServerException.into(),
+				message: "Trial quota exceeded, upgrade to a real credential to continue."
+					.to_string(),
+				sub_code: sub_codes::LIMIT_TRIAL_QUOTA.to_string(),
+			};
+			return encoded_error_response(
+				StatusCode::TOO_MANY_REQUESTS,
+				error_response,
+				json_response,
+			);
+		}
+	}
+	// An IP-bound credential is only honored from the CIDR ranges it names; with no resolvable
+	// client IP the constraint fails closed.
+	if let Some(allowed_cidrs) = auth_response.attributes.get(ALLOWED_CIDRS_ATTRIBUTE) {
+		let within_allowed_range = headers
+			.client_ip()
+			.and_then(|ip| ip.parse::<IpAddr>().ok())
+			.map(|ip| ip_within_cidrs(ip, allowed_cidrs))
+			.unwrap_or(false);
+		if !within_allowed_range {
+			if let Some(metrics) = &service.metrics {
+				metrics.record_auth(auth_scheme(&headers), "ip_not_allowed");
+			}
+			record_auth_failure(&service, &headers, "ip_not_allowed").await;
+			return error_response(
+				&VssError::AuthError("Credential is not valid from this client address.".to_string()),
+				json_response,
+			);
+		}
+	}
+	// A scoped credential must hold the write scope for any mutating RPC, so issuers can hand
+	// out read-only tokens to watch-only wallet features. Unscoped credentials stay
+	// unrestricted.
+	if let Some(scopes) = auth_response.attributes.get(SCOPES_ATTRIBUTE) {
+		if request.mutates_rows() && !scopes_contain(scopes, WRITE_SCOPE) {
+			if let Some(metrics) = &service.metrics {
+				metrics.record_auth(auth_scheme(&headers), "missing_scope");
+			}
+			record_auth_failure(&service, &headers, "missing_scope").await;
+			return error_response(
+				&VssError::AuthError("Credential does not permit writes.".to_string()),
+				json_response,
+			);
+		}
+	}
+	// A store-restricted credential may only touch the store ids it explicitly names, whatever
+	// the operation, so a token issued for one wallet's store cannot reach its user's other
+	// stores.
+	if let Some(allowed_store_ids) = auth_response.attributes.get(ALLOWED_STORE_IDS_ATTRIBUTE) {
+		if !store_id_allowed(request.store_id(), allowed_store_ids) {
+			if let Some(metrics) = &service.metrics {
+				metrics.record_auth(auth_scheme(&headers), "store_not_allowed");
+			}
+			record_auth_failure(&service, &headers, "store_not_allowed").await;
+			return error_response(
+				&VssError::AuthError("Credential is not valid for this store_id.".to_string()),
+				json_response,
+			);
+		}
+	}
+	// With user token hashing configured, the raw token never leaves the authorizer: storage,
+	// suspension and rate limiting all operate on the hashed token.
+	let mut context = RequestContext::from(auth_response);
+	if let Some(hasher) = &service.user_token_hasher {
+		context.user_token = hasher.hash(&context.user_token);
+	}
+	// Presenting a trial token alongside a real credential upgrades the trial: its data is
+	// migrated into the authenticated user's namespace and the trial identity is retired. An
+	// unknown or already-claimed token is ignored, so clients may keep re-sending the header.
+	if let (Some(trials), None) = (&service.trials, &trial_identity) {
+		if let Some(trial_token) = headers.get_header(TRIAL_TOKEN_HEADER) {
+			match trials.claim(trial_token, &context.user_token).await {
+				Ok(Some(migrated)) => {
+					info!("Migrated {} trial items into an authenticated account.", migrated);
+				},
+				Ok(None) => {},
+				Err(e) => return error_response(&e, json_response),
+			}
+		}
+	}
+	// Client-supplied baggage entries flow into the request attributes (and from there into
+	// span annotations), restricted to the configured allowlist.
+	if !service.baggage_keys.is_empty() {
+		if let Some(baggage) = headers.get_header("baggage") {
+			for (key, value) in parse_baggage(baggage) {
+				if service.baggage_keys.iter().any(|allowed| allowed == key) {
+					context.attributes.insert(key.to_string(), value.to_string());
+				}
+			}
+		}
+	}
+	let user_token = &context.user_token;
+	if service.admin_state.is_user_suspended(user_token) {
+		if let Some(metrics) = &service.metrics {
+			metrics.record_auth(auth_scheme(&headers), "user_suspended");
+		}
+		record_auth_failure(&service, &headers, "user_suspended").await;
+		return error_response(
+			&VssError::AuthError("User is suspended.".to_string()),
+			json_response,
+		);
+	}
+	if let Some(tenant) = tenant {
+		if !service.tenants.check_rate_limit(tenant, user_token) {
+			return too_many_requests_response(json_response);
+		}
+	}
+	// Every caller additionally draws from a per-user token bucket before the request reaches
+	// the backend, so one busy client cannot monopolize the store.
+	if let Some(rate_limiter) = &service.rate_limiter {
+		if !rate_limiter.check(&format!("user/{}", user_token), request.operation()) {
+			return too_many_requests_response(json_response);
+		}
+	}
+
+	// A caller reading a store shared with it via [`GrantStoreAccess`] names the owner in the
+	// `x-vss-store-owner` header; once the grant checks out, the request proceeds under the
+	// owner's token. Grants are strictly read-only and never extend to a suspended owner's data.
+	//
+	// [`GrantStoreAccess`]: api::types::GrantStoreAccessRequest
+	if let Some(owner_token) = headers.get_header(GRANT_OWNER_HEADER) {
+		if request.mutates_rows() {
+			return error_response(
+				&VssError::AuthError("Store grants are read-only.".to_string()),
+				json_response,
+			);
+		}
+		let owner_token = match &service.user_token_hasher {
+			Some(hasher) => hasher.hash(owner_token.trim()),
+			None => owner_token.trim().to_string(),
+		};
+		if !service.grants.is_granted(&owner_token, user_token, request.store_id())
+			|| service.admin_state.is_user_suspended(&owner_token)
+		{
+			return error_response(
+				&VssError::AuthError("No grant for this store.".to_string()),
+				json_response,
+			);
+		}
+		context.user_token = owner_token;
+	}
+	let user_token = &context.user_token;
+
+	if request.mutates_rows()
+		&& service.admin_state.is_store_archived(user_token, request.store_id())
+	{
+		return error_response(
+			&VssError::InvalidRequestError(
+				"Store is archived and read-only, writes are refused until it is unarchived."
+					.to_string(),
+			),
+			json_response,
+		);
+	}
+	if request.deletes_rows()
+		&& service.admin_state.is_store_delete_protected(user_token, request.store_id())
+	{
+		return error_response(
+			&VssError::InvalidRequestError(
+				"Store is delete-protected, deletes are refused until protection is lifted."
+					.to_string(),
+			),
+			json_response,
+		);
+	}
+
+	let capture_shape = service.capture_log.as_ref().map(|_| {
+		(
+			request.operation(),
+			anonymize_store_id(request.store_id()),
+			request.item_count(),
+			request.value_bytes(),
+			request.page_size(),
+		)
+	});
+	let conditional_headers_present =
+		headers.contains_header("if-match") || headers.contains_header("if-none-match");
+	let if_none_match = headers.get_header("if-none-match").map(|value| value.trim().to_string());
+	let supports_not_modified = request.operation() == "get";
+	let request_etag = request.response_etag();
+	let operation = request.operation();
+	let item_count = request.item_count();
+	// The request span identifies traffic by short stable hashes of the store id and user
+	// token, so traces answer "which store and which user was slow" without recording raw
+	// identities. Backend implementations emit child spans per database statement.
+	let span = tracing::info_span!(
+		"vss_request",
+		operation,
+		store = %anonymize_store_id(request.store_id()),
+		user = %anonymize_identifier(user_token),
+		items = item_count as u64,
+		attributes = %format_attributes(&context.attributes),
+		outcome = field::Empty,
+		backend_micros = field::Empty,
+		response_bytes = field::Empty,
+	);
+	let backend_started_at = std::time::Instant::now();
+	let result =
+		handler(Arc::clone(&service.store), context, request).instrument(span.clone()).await;
+	let backend_duration = backend_started_at.elapsed();
+	let outcome = match &result {
+		Ok(_) => "ok",
+		Err(VssError::NoSuchKeyError(..)) => "no_such_key",
+		Err(VssError::InvalidRequestError(..)) => "invalid_request",
+		Err(VssError::ConflictError(..)) => "conflict",
+		Err(VssError::AuthError(..)) => "auth_failure",
+		Err(VssError::InternalServerError(..)) => "internal_error",
+	};
+	span.record("outcome", outcome);
+	span.record("backend_micros", backend_duration.as_micros() as u64);
+	let (status, body, response_bytes, etag) = match result {
+		Ok(response) => {
+			let etag = response.etag().or(request_etag);
+			// A get conditioned on the entity tag the client already holds (or on `*`, i.e.
+			// on the key existing at all) is answered with 304 and no body.
+			let not_modified = supports_not_modified
+				&& matches!((&etag, &if_none_match), (Some(etag), Some(tag))
+					if tag == etag || tag == "*");
+			if not_modified {
+				(StatusCode::NOT_MODIFIED, Full::default().boxed(), 0, etag)
+			} else if json_response {
+				// JSON responses are always buffered; the streamed list encoding is
+				// protobuf-specific.
+				let payload = serde_json::to_vec(&response)
+					.expect("serializing a response type cannot fail");
+				let response_bytes = payload.len();
+				(StatusCode::OK, Full::new(Bytes::from(payload)).boxed(), response_bytes, etag)
+			} else {
+				let response_bytes = response.encoded_len();
+				(StatusCode::OK, encode(response), response_bytes, etag)
+			}
+		},
+		Err(e) => {
+			// A failed version check on a request conditioned via HTTP headers is a failed
+			// precondition in HTTP terms; protobuf-level conflicts keep reporting 409.
+			let (status, payload) = error_payload(&e, json_response);
+			let status =
+				if conditional_headers_present && matches!(e, VssError::ConflictError(..)) {
+					StatusCode::PRECONDITION_FAILED
+				} else {
+					status
+				};
+			let response_bytes = payload.len();
+			(status, Full::new(payload).boxed(), response_bytes, None)
+		},
+	};
+	if let Some(metrics) = &service.metrics {
+		metrics.record(
+			operation,
+			status.as_u16(),
+			body_len,
+			response_bytes,
+			started_at.elapsed(),
+			backend_duration,
+		);
+	}
+	if let (Some(capture_log), Some((operation, store, item_count, value_bytes, page_size))) =
+		(&service.capture_log, capture_shape)
+	{
+		capture_log.record(CaptureEntry {
+			ts_millis: now_millis(),
+			operation: operation.to_string(),
+			store,
+			request_bytes: body_len,
+			item_count,
+			value_bytes,
+			page_size,
+			status: status.as_u16(),
+			response_bytes,
+		});
+	}
+	span.record("response_bytes", response_bytes as u64);
+	let mut response = Response::builder().status(status);
+	if json_response {
+		response = response.header(hyper::header::CONTENT_TYPE, "application/json");
+	}
+	if let Some(etag) = etag {
+		response = response.header(hyper::header::ETAG, etag);
+	}
+	// A freshly issued trial identity rides back on the response for the client to persist.
+	if let Some((trial_token, true)) = trial_identity {
+		response = response.header(TRIAL_TOKEN_HEADER, trial_token);
+	}
+	// Cost accounting for client developers: how much a request moved and how long the backend
+	// took, so chatty or oversized access patterns show up without server-side tooling.
+	response = response
+		.header(COST_REQUEST_BYTES_HEADER, body_len)
+		.header(COST_RESPONSE_BYTES_HEADER, response_bytes)
+		.header(COST_ROWS_HEADER, item_count)
+		.header(COST_BACKEND_MICROS_HEADER, backend_duration.as_micros() as u64);
+	response.body(body)
+}
+
+/// Encodes a response message into a single buffered body.
+fn buffered_response<R: Message>(response: R) -> ResponseBody {
+	Full::new(Bytes::from(response.encode_to_vec())).boxed()
+}
+
+/// Encodes a list response lazily, one key-version per body frame, so large pages are never
+/// buffered as a single fully-encoded protobuf.
+///
+/// Concatenated encodings of a message's fields are themselves a valid encoding of that message,
+/// so the scalar fields (`next_page_token`, `global_version`) are sent as a leading frame
+/// followed by one frame per repeated `key_versions` entry, encoded only when the connection is
+/// ready for it. Small pages keep the buffered single-frame path.
+fn streamed_list_response(response: ListKeyVersionsResponse) -> ResponseBody {
+	if response.key_versions.len() < STREAMED_LIST_MIN_ITEMS {
+		return buffered_response(response);
+	}
+	let ListKeyVersionsResponse { key_versions, next_page_token, global_version } = response;
+	let header =
+		ListKeyVersionsResponse { key_versions: Vec::new(), next_page_token, global_version };
+	let chunks = std::iter::once(Bytes::from(header.encode_to_vec())).chain(
+		key_versions.into_iter().map(|key_version| {
+			let mut buf = Vec::with_capacity(key_version.encoded_len() + 4);
+			prost::encoding::message::encode(1, &key_version, &mut buf);
+			Bytes::from(buf)
+		}),
+	);
+	let frames = stream::iter(chunks.map(|chunk| Ok::<_, Infallible>(Frame::data(chunk))));
+	StreamBody::new(frames).boxed()
+}
+
+/// A borrowing [`RequestHeaders`] view over hyper's header map, so authorizing a request does
+/// not copy every header into an owned map. The request path and body digest are exposed
+/// alongside, so authorizers can validate content-bound signatures.
+struct HeaderView<'a> {
+	headers: &'a HeaderMap,
+	path: &'a str,
+	body_sha256: [u8; 32],
+	peer_certificate: Option<&'a [u8]>,
+	client_ip: Option<String>,
+}
+
+impl RequestHeaders for HeaderView<'_> {
+	fn get_header(&self, name: &str) -> Option<&str> {
+		self.headers.get(name).and_then(|value| value.to_str().ok())
+	}
+
+	fn contains_header(&self, name: &str) -> bool {
+		self.headers.contains_key(name)
+	}
+
+	fn path(&self) -> Option<&str> {
+		Some(self.path)
+	}
+
+	fn body_sha256(&self) -> Option<[u8; 32]> {
+		Some(self.body_sha256)
+	}
+
+	fn peer_certificate(&self) -> Option<&[u8]> {
+		self.peer_certificate
+	}
+
+	fn client_ip(&self) -> Option<&str> {
+		self.client_ip.as_deref()
+	}
+}
+
+/// The credential scheme a request attempted, inferred from the headers it carries.
+fn auth_scheme(headers: &HeaderView<'_>) -> &'static str {
+	if headers.contains_header("authorization") {
+		"bearer"
+	} else if headers.contains_header("x-vss-signature") {
+		"signature"
+	} else if headers.peer_certificate.is_some() {
+		"mtls"
+	} else {
+		"none"
+	}
+}
+
+/// Maps an authentication failure to a coarse category, so the auth metrics distinguish broken
+/// clients (missing or malformed credentials) and expired tokens from outright bad signatures.
+fn classify_auth_failure(error: &VssError) -> &'static str {
+	let message = match error {
+		VssError::AuthError(message) => message.to_ascii_lowercase(),
+		_ => return "other",
+	};
+	if message.contains("missing") {
+		"missing_credentials"
+	} else if message.contains("expired") || message.contains("clock skew") {
+		"expired"
+	} else if message.contains("signature") {
+		"bad_signature"
+	} else if message.contains("token") {
+		"invalid_token"
+	} else {
+		"invalid_credentials"
+	}
+}
+
+/// Resolves the effective client IP of a request: the peer address of the connection, unless it
+/// belongs to one of the configured trusted proxies, in which case the nearest `X-Forwarded-For`
+/// hop not itself a trusted proxy. Forwarded entries are client-controlled, so they are never
+/// honored on direct connections, and a malformed entry falls back to the peer address rather
+/// than trusting anything to its left.
+fn effective_client_ip(
+	peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted_proxies: &[String],
+) -> Option<String> {
+	let peer = peer?;
+	let trusted = |ip: IpAddr| trusted_proxies.iter().any(|cidr| ip_within_cidrs(ip, cidr));
+	if trusted(peer) {
+		if let Some(forwarded_for) = forwarded_for {
+			for entry in forwarded_for.rsplit(',') {
+				match entry.trim().parse::<IpAddr>() {
+					Ok(hop) if trusted(hop) => continue,
+					Ok(hop) => return Some(hop.to_string()),
+					Err(_) => break,
+				}
+			}
+		}
+	}
+	Some(peer.to_string())
+}
+
+/// Emits a structured audit event for a failed authentication attempt, both as a log line on
+/// the `vss_audit` target and (if configured) to the [`AuthFailureAuditLog`].
+async fn record_auth_failure(service: &VssService, headers: &HeaderView<'_>, reason: &str) {
+	let scheme = auth_scheme(headers);
+	let source_ip = headers.client_ip().map(str::to_string);
+	warn!(
+		target: "vss_audit",
+		"Authentication failure: scheme={}, reason={}, source_ip={}",
+		scheme,
+		reason,
+		source_ip.as_deref().unwrap_or("unknown")
+	);
+	if let Some(audit_log) = &service.audit_log {
+		let event = AuthFailureEvent {
+			scheme: scheme.to_string(),
+			reason: reason.to_string(),
+			source_ip,
+		};
+		if let Err(e) = audit_log.record_auth_failure(event).await {
+			warn!("Failed to record auth failure audit event: {}", e);
+		}
+	}
+}
+
+/// Builds the 413 response returned when a request body exceeds the configured cap.
+fn payload_too_large_response(
+	max_request_body_bytes: usize, as_json: bool,
+) -> Result<Response<ResponseBody>, hyper::http::Error> {
+	let error_response = ErrorResponse {
+		error_code: ErrorCode::InvalidRequestException.into(),
+		message: format!("Request body exceeds the maximum of {} bytes.", max_request_body_bytes),
+		sub_code: sub_codes::LIMIT_REQUEST_SIZE.to_string(),
+	};
+	encoded_error_response(StatusCode::PAYLOAD_TOO_LARGE, error_response, as_json)
+}
+
+/// Builds the 429 response returned when a caller exhausts a rate limit.
+fn too_many_requests_response(as_json: bool) -> Result<Response<ResponseBody>, hyper::http::Error> {
+	let error_response = ErrorResponse {
+		error_code: ErrorCode::InternalServerException.into(),
+		message: "Rate limit exceeded, please retry later.".to_string(),
+		sub_code: sub_codes::LIMIT_RATE.to_string(),
+	};
+	encoded_error_response(StatusCode::TOO_MANY_REQUESTS, error_response, as_json)
+}
+
+/// Builds a response around an [`ErrorResponse`] encoded in the negotiated body format.
+fn encoded_error_response(
+	status_code: StatusCode, error_response: ErrorResponse, as_json: bool,
+) -> Result<Response<ResponseBody>, hyper::http::Error> {
+	let mut response = Response::builder().status(status_code);
+	if as_json {
+		response = response.header(hyper::header::CONTENT_TYPE, "application/json");
+	}
+	response.body(Full::new(encode_error(&error_response, as_json)).boxed())
+}
+
+fn encode_error(error_response: &ErrorResponse, as_json: bool) -> Bytes {
+	if as_json {
+		Bytes::from(
+			serde_json::to_vec(error_response).expect("serializing an ErrorResponse cannot fail"),
+		)
+	} else {
+		Bytes::from(error_response.encode_to_vec())
+	}
+}
+
+fn error_response(
+	error: &VssError, as_json: bool,
+) -> Result<Response<ResponseBody>, hyper::http::Error> {
+	let (status_code, body) = error_payload(error, as_json);
+	let mut response = Response::builder().status(status_code);
+	if as_json {
+		response = response.header(hyper::header::CONTENT_TYPE, "application/json");
+	}
+	response.body(Full::new(body).boxed())
+}
+
+fn error_payload(error: &VssError, as_json: bool) -> (StatusCode, Bytes) {
+	let (status_code, error_code) = match error {
+		VssError::NoSuchKeyError(..) => (StatusCode::NOT_FOUND, ErrorCode::NoSuchKeyException),
+		VssError::InvalidRequestError(..) => {
+			(StatusCode::BAD_REQUEST, ErrorCode::InvalidRequestException)
+		},
+		VssError::ConflictError(..) => (StatusCode::CONFLICT, ErrorCode::ConflictException),
+		VssError::AuthError(..) => (StatusCode::UNAUTHORIZED, ErrorCode::AuthException),
+		VssError::InternalServerError(..) => {
+			(StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalServerException)
+		},
+	};
+	let error_response =
+		ErrorResponse {
+			error_code: error_code.into(),
+			message: error.to_string(),
+			sub_code: error.sub_code().to_string(),
+		};
+	(status_code, encode_error(&error_response, as_json))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn auth_failures_classify_by_category() {
+		let auth = |message: &str| VssError::AuthError(message.to_string());
+		let classified = |message: &str| classify_auth_failure(&auth(message));
+		assert_eq!(classified("Missing Authorization header."), "missing_credentials");
+		assert_eq!(classified("Timestamp outside of allowed clock skew."), "expired");
+		assert_eq!(classified("Signature verification failed."), "bad_signature");
+		assert_eq!(classified("Invalid JWT token: oops"), "invalid_token");
+		assert_eq!(classified("User is suspended."), "invalid_credentials");
+		let internal = VssError::InternalServerError("db".to_string());
+		assert_eq!(classify_auth_failure(&internal), "other");
+	}
+
+	#[test]
+	fn baggage_entries_parse_without_properties() {
+		let entries: Vec<_> =
+			parse_baggage("app.version=1.2.3;prop=x, device.id = abc,malformed").collect();
+		assert_eq!(entries, vec![("app.version", "1.2.3"), ("device.id", "abc")]);
+	}
+
+	#[test]
+	fn forwarded_headers_are_only_honored_behind_trusted_proxies() {
+		let peer = Some("10.0.0.1".parse::<IpAddr>().unwrap());
+		let proxies = vec!["10.0.0.0/8".to_string()];
+
+		// Without configured trusted proxies, X-Forwarded-For is attacker-chosen and ignored.
+		assert_eq!(
+			effective_client_ip(peer, Some("198.51.100.7"), &[]),
+			Some("10.0.0.1".to_string())
+		);
+		// Behind a trusted proxy, the nearest non-proxy hop wins; entries the client prepended
+		// to its left are ignored.
+		assert_eq!(
+			effective_client_ip(peer, Some("6.6.6.6, 198.51.100.7"), &proxies),
+			Some("198.51.100.7".to_string())
+		);
+		// Intermediate trusted hops (proxy chains) are skipped.
+		assert_eq!(
+			effective_client_ip(peer, Some("198.51.100.7, 10.0.0.2"), &proxies),
+			Some("198.51.100.7".to_string())
+		);
+		// A peer outside the trusted ranges speaks for itself, whatever it forwards.
+		let direct = Some("203.0.113.9".parse::<IpAddr>().unwrap());
+		assert_eq!(
+			effective_client_ip(direct, Some("198.51.100.7"), &proxies),
+			Some("203.0.113.9".to_string())
+		);
+		// A malformed entry falls back to the peer rather than trusting anything to its left.
+		assert_eq!(
+			effective_client_ip(peer, Some("198.51.100.7, garbage"), &proxies),
+			Some("10.0.0.1".to_string())
+		);
+		// No peer address, no client IP: IP-bound checks fail closed.
+		assert_eq!(effective_client_ip(None, Some("198.51.100.7"), &proxies), None);
+	}
+
+	#[test]
+	fn user_token_hashing_is_keyed_and_deterministic() {
+		let hasher = UserTokenHasher::new("pepper".to_string());
+		let hashed = hasher.hash("02abcdef");
+		assert_eq!(hashed, hasher.hash("02abcdef"));
+		assert_ne!(hashed, "02abcdef");
+		assert_ne!(hashed, UserTokenHasher::new("other-pepper".to_string()).hash("02abcdef"));
+	}
+}
@@ -0,0 +1,1099 @@
+//! End-to-end tests booting the real HTTP service (random port, in-memory backend) and
+//! exercising it over HTTP with protobuf bodies, covering auth failures, malformed bodies,
+//! conflicts and pagination for each authorizer.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request, StatusCode};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use prost::Message;
+use tokio::net::TcpListener;
+
+use api::auth::{Authorizer, NoopAuthorizer};
+use api::kv_store::KvStore;
+use api::types::{
+	ErrorCode, ErrorResponse, GetObjectRequest, GetObjectResponse, GetStoreStatsRequest,
+	GetStoreStatsResponse, KeyValue, ListKeyVersionsRequest, ListKeyVersionsResponse,
+	PutObjectRequest, SwapObjectRequest, SwapObjectResponse,
+};
+use impls::auth::jwt_authorizer::JwtAuthorizer;
+use impls::auth::signature_validating_authorizer::{
+	SignatureValidatingAuthorizer, PUBKEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use impls::memory_store::MemoryBackendImpl;
+use vss_server::admin_service::AdminState;
+use vss_server::replication::ReplicatedKvStore;
+use vss_server::tenants::TenantRegistry;
+use vss_server::trial::{TrialRegistry, TRIAL_TOKEN_HEADER};
+use vss_server::vss_service::VssService;
+
+const JWT_TEST_PRIVATE_KEY_PEM: &[u8] = include_bytes!("fixtures/jwt-test-private-key.pem");
+const JWT_TEST_PUBLIC_KEY_PEM: &[u8] = include_bytes!("fixtures/jwt-test-public-key.pem");
+
+/// Constructs a [`VssService`] against a fresh in-memory backend.
+fn build_service(authorizer: Arc<dyn Authorizer>) -> VssService {
+	let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let admin_state = Arc::new(AdminState::default());
+	VssService::new(store, authorizer, tenants, admin_state, None, None, None)
+}
+
+/// Boots the real [`VssService`] against a fresh in-memory backend on a random port, returning
+/// the bound address.
+async fn start_server(authorizer: Arc<dyn Authorizer>) -> SocketAddr {
+	start_service(build_service(authorizer)).await
+}
+
+async fn start_service(service: VssService) -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		loop {
+			let (stream, peer_addr) = match listener.accept().await {
+				Ok(accepted) => accepted,
+				Err(_) => return,
+			};
+			let service = service.clone().with_peer_addr(peer_addr);
+			tokio::spawn(async move {
+				// Mirrors the production accept loop: HTTP/1.1 and h2c negotiated per connection.
+				let _ = auto::Builder::new(TokioExecutor::new())
+					.serve_connection(TokioIo::new(stream), service)
+					.await;
+			});
+		}
+	});
+	addr
+}
+
+async fn request_raw(
+	addr: SocketAddr, path: &str, body: Vec<u8>, headers: &HashMap<String, String>,
+) -> (StatusCode, Bytes) {
+	let (status, _, body_bytes) = request_with_headers(addr, path, body, headers).await;
+	(status, body_bytes)
+}
+
+async fn request_with_headers(
+	addr: SocketAddr, path: &str, body: Vec<u8>, headers: &HashMap<String, String>,
+) -> (StatusCode, hyper::HeaderMap, Bytes) {
+	let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+	let mut builder = Request::builder()
+		.method(Method::POST)
+		.uri(format!("http://{}/vss/{}", addr, path));
+	for (name, value) in headers {
+		builder = builder.header(name, value);
+	}
+	let response = client.request(builder.body(Full::new(Bytes::from(body))).unwrap()).await.unwrap();
+	let status = response.status();
+	let response_headers = response.headers().clone();
+	let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+	(status, response_headers, body_bytes)
+}
+
+async fn request<T: Message, R: Message + Default>(
+	addr: SocketAddr, path: &str, request: T, headers: &HashMap<String, String>,
+) -> Result<R, (StatusCode, ErrorResponse)> {
+	let (status, body) = request_raw(addr, path, request.encode_to_vec(), headers).await;
+	if status == StatusCode::OK {
+		Ok(R::decode(body).unwrap())
+	} else {
+		Err((status, ErrorResponse::decode(body).unwrap()))
+	}
+}
+
+fn put_request(store_id: &str, key: &str, version: i64, value: &[u8]) -> PutObjectRequest {
+	PutObjectRequest {
+		store_id: store_id.to_string(),
+		global_version: None,
+		transaction_items: vec![KeyValue {
+			key: key.to_string(),
+			version,
+			value: value.to_vec().into(),
+		}],
+		delete_items: vec![],
+		dry_run: false,
+	}
+}
+
+fn signed_headers() -> HashMap<String, String> {
+	let secp = secp256k1::Secp256k1::new();
+	let secret_key = secp256k1::SecretKey::from_slice(&[0x42; 32]).unwrap();
+	let pubkey = secret_key.public_key(&secp);
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+	let signature = secp.sign_ecdsa(
+		&SignatureValidatingAuthorizer::challenge_message(timestamp),
+		&secret_key,
+	);
+
+	let mut headers = HashMap::new();
+	headers.insert(PUBKEY_HEADER.to_string(), hex::encode(pubkey.serialize()));
+	headers.insert(TIMESTAMP_HEADER.to_string(), timestamp.to_string());
+	headers.insert(SIGNATURE_HEADER.to_string(), hex::encode(signature.serialize_compact()));
+	headers
+}
+
+fn jwt_headers(private_key_pem: &[u8]) -> HashMap<String, String> {
+	#[derive(serde::Serialize)]
+	struct Claims {
+		sub: String,
+		exp: u64,
+	}
+	let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 300;
+	let token = jsonwebtoken::encode(
+		&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+		&Claims { sub: "e2e-user".to_string(), exp },
+		&jsonwebtoken::EncodingKey::from_rsa_pem(private_key_pem).unwrap(),
+	)
+	.unwrap();
+
+	let mut headers = HashMap::new();
+	headers.insert("authorization".to_string(), format!("Bearer {}", token));
+	headers
+}
+
+#[tokio::test]
+async fn put_get_roundtrip_and_conflict_over_http() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &headers)
+			.await
+			.unwrap();
+
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&headers,
+	)
+	.await
+	.unwrap();
+	let key_value = response.value.unwrap();
+	assert_eq!(key_value.version, 1);
+	assert_eq!(key_value.value, b"v1"[..]);
+
+	// Re-using the already consumed version must surface as HTTP 409 with a ConflictException.
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v2"), &headers).await;
+	let (status, error_response) = result.unwrap_err();
+	assert_eq!(status, StatusCode::CONFLICT);
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::ConflictException));
+}
+
+// The same listener must also serve prior-knowledge HTTP/2 clients (e.g. h2c reverse proxies).
+#[tokio::test]
+async fn http2_prior_knowledge_roundtrip() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+
+	let client =
+		Client::builder(TokioExecutor::new()).http2_only(true).build_http::<Full<Bytes>>();
+	let request = Request::builder()
+		.method(Method::POST)
+		.uri(format!("http://{}/vss/putObjects", addr))
+		.body(Full::new(Bytes::from(put_request("store", "k1", 0, b"v1").encode_to_vec())))
+		.unwrap();
+	let response = client.request(request).await.unwrap();
+	assert_eq!(response.version(), hyper::Version::HTTP_2);
+	assert_eq!(response.status(), StatusCode::OK);
+
+	let request = Request::builder()
+		.method(Method::POST)
+		.uri(format!("http://{}/vss/getObject", addr))
+		.body(Full::new(Bytes::from(
+			GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() }
+				.encode_to_vec(),
+		)))
+		.unwrap();
+	let response = client.request(request).await.unwrap();
+	assert_eq!(response.status(), StatusCode::OK);
+	let body = response.into_body().collect().await.unwrap().to_bytes();
+	let response = GetObjectResponse::decode(body).unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+}
+
+#[tokio::test]
+async fn malformed_body_is_rejected() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+
+	let (status, body) =
+		request_raw(addr, "putObjects", vec![0xff; 64], &HashMap::new()).await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+	let error_response = ErrorResponse::decode(body).unwrap();
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::InvalidRequestException));
+}
+
+// The API also speaks canonical proto3 JSON for clients without protobuf support: a
+// `content-type: application/json` request is decoded from JSON and answered in kind, errors
+// included.
+#[tokio::test]
+async fn json_requests_round_trip_via_content_negotiation() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let mut headers = HashMap::new();
+	headers.insert("content-type".to_string(), "application/json".to_string());
+
+	let put = r#"{"storeId": "store", "transactionItems":
+		[{"key": "k1", "version": 0, "value": "aGVsbG8="}]}"#;
+	let (status, response_headers, _) =
+		request_with_headers(addr, "putObjects", put.as_bytes().to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(response_headers.get("content-type").unwrap(), "application/json");
+
+	let get = r#"{"storeId": "store", "key": "k1"}"#;
+	let (status, body) = request_raw(addr, "getObject", get.as_bytes().to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::OK);
+	let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+	assert_eq!(response["value"]["version"], "1");
+	assert_eq!(response["value"]["value"], "aGVsbG8=");
+
+	// Errors come back as JSON too, with the enum serialized by name.
+	let conflicting = r#"{"storeId": "store", "transactionItems":
+		[{"key": "k1", "version": 0, "value": "aGVsbG8="}]}"#;
+	let (status, body) =
+		request_raw(addr, "putObjects", conflicting.as_bytes().to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::CONFLICT);
+	let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+	assert_eq!(error["errorCode"], "CONFLICT_EXCEPTION");
+
+	// Malformed JSON is rejected with a 400 relaying the deserialization error.
+	let (status, body) =
+		request_raw(addr, "putObjects", b"{\"storeId\":".to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+	let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+	assert_eq!(error["errorCode"], "INVALID_REQUEST_EXCEPTION");
+
+	// A protobuf client can opt into JSON responses via the `accept` header.
+	let mut accept_headers = HashMap::new();
+	accept_headers.insert("accept".to_string(), "application/json".to_string());
+	let get = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+	let (status, body) =
+		request_raw(addr, "getObject", get.encode_to_vec(), &accept_headers).await;
+	assert_eq!(status, StatusCode::OK);
+	let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+	assert_eq!(response["value"]["key"], "k1");
+}
+
+#[tokio::test]
+async fn oversized_body_is_rejected_with_413() {
+	let service =
+		build_service(Arc::new(NoopAuthorizer::new())).with_max_request_body_bytes(1024);
+	let addr = start_service(service).await;
+
+	let oversized = put_request("store", "k1", 0, &[0u8; 4096]);
+	let (status, body) =
+		request_raw(addr, "putObjects", oversized.encode_to_vec(), &HashMap::new()).await;
+	assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+	let error_response = ErrorResponse::decode(body).unwrap();
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::InvalidRequestException));
+
+	// Requests within the limit still go through.
+	let (status, _) = request_raw(
+		addr,
+		"putObjects",
+		put_request("store", "k1", 0, b"v1").encode_to_vec(),
+		&HashMap::new(),
+	)
+	.await;
+	assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn invalid_identifiers_are_rejected() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	// Empty keys, keys over the configured maximum length (default: 600 characters) and keys
+	// holding control characters must be rejected upfront with an InvalidRequestException,
+	// instead of failing in the storage backend.
+	for key in ["", &"k".repeat(601), "key\nwith\nnewlines"] {
+		let result: Result<api::types::PutObjectResponse, _> =
+			request(addr, "putObjects", put_request("store", key, 0, b"v"), &headers).await;
+		let (status, error_response) = result.unwrap_err();
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+		assert_eq!(error_response.error_code, i32::from(ErrorCode::InvalidRequestException));
+	}
+
+	// The same limits apply to store ids (default: 120 characters).
+	let result: Result<api::types::PutObjectResponse, _> = request(
+		addr,
+		"putObjects",
+		put_request(&"s".repeat(121), "k1", 0, b"v"),
+		&headers,
+	)
+	.await;
+	let (status, error_response) = result.unwrap_err();
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::InvalidRequestException));
+
+	// Identifiers within the limits keep working.
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", &"k".repeat(600), 0, b"v"), &headers)
+			.await
+			.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_path_returns_not_found() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+
+	let (status, _) = request_raw(addr, "unknownOperation", vec![], &HashMap::new()).await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn list_key_versions_paginates_over_http() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	for key in ["k1", "k2", "k3", "k4", "k5"] {
+		let _: api::types::PutObjectResponse =
+			request(addr, "putObjects", put_request("store", key, 0, b"v"), &headers)
+				.await
+				.unwrap();
+	}
+
+	let mut listed_keys = Vec::new();
+	let mut page_token: Option<String> = None;
+	let mut pages = 0;
+	loop {
+		let list_request = ListKeyVersionsRequest {
+			store_id: "store".to_string(),
+			key_prefix: None,
+			page_size: Some(2),
+			page_token: page_token.clone(),
+		};
+		let response: ListKeyVersionsResponse =
+			request(addr, "listKeyVersions", list_request, &headers).await.unwrap();
+		pages += 1;
+		listed_keys.extend(response.key_versions.into_iter().map(|kv| kv.key));
+		match response.next_page_token {
+			Some(token) if !token.is_empty() => page_token = Some(token),
+			_ => break,
+		}
+	}
+	assert!(pages >= 3);
+	listed_keys.sort();
+	assert_eq!(listed_keys, vec!["k1", "k2", "k3", "k4", "k5"]);
+}
+
+// Pages with many key-versions are streamed frame by frame on the wire; the concatenated frames
+// must still decode as a single ListKeyVersionsResponse.
+#[tokio::test]
+async fn large_list_pages_decode_after_streaming() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	let transaction_items = (0..200)
+		.map(|i| KeyValue { key: format!("key-{:03}", i), version: 0, value: b"v".to_vec().into() })
+		.collect();
+	let put = PutObjectRequest {
+		store_id: "store".to_string(),
+		global_version: None,
+		transaction_items,
+		delete_items: vec![],
+		dry_run: false,
+	};
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put, &headers).await.unwrap();
+
+	let list_request = ListKeyVersionsRequest {
+		store_id: "store".to_string(),
+		key_prefix: None,
+		page_size: Some(150),
+		page_token: None,
+	};
+	let response: ListKeyVersionsResponse =
+		request(addr, "listKeyVersions", list_request, &headers).await.unwrap();
+	assert_eq!(response.key_versions.len(), 150);
+	assert_eq!(response.global_version, Some(0));
+	assert_eq!(response.key_versions[0].key, "key-000");
+	assert_eq!(response.key_versions[149].key, "key-149");
+	assert!(response.next_page_token.is_some());
+}
+
+// Key versions surface as standard ETag/If-Match/If-None-Match semantics, so generic HTTP
+// clients can issue conditional requests without decoding protobuf.
+#[tokio::test]
+async fn etags_map_key_versions_to_conditional_requests() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let no_headers = HashMap::new();
+
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &no_headers)
+			.await
+			.unwrap();
+	let get = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+	let (status, response_headers, body) =
+		request_with_headers(addr, "getObject", get.encode_to_vec(), &no_headers).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(response_headers.get("etag").unwrap(), "\"1\"");
+	assert!(GetObjectResponse::decode(body).unwrap().value.is_some());
+
+	// A get conditioned on the entity tag the client already holds short-circuits to 304.
+	let mut headers = HashMap::new();
+	headers.insert("If-None-Match".to_string(), "\"1\"".to_string());
+	let (status, response_headers, body) =
+		request_with_headers(addr, "getObject", get.encode_to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::NOT_MODIFIED);
+	assert!(body.is_empty());
+	assert_eq!(response_headers.get("etag").unwrap(), "\"1\"");
+
+	// If-Match supersedes the item's version; the response carries the new entity tag, and
+	// replaying the stale tag fails the precondition.
+	let mut headers = HashMap::new();
+	headers.insert("If-Match".to_string(), "\"1\"".to_string());
+	let stale_put = put_request("store", "k1", 0, b"v2");
+	let (status, response_headers, _) =
+		request_with_headers(addr, "putObjects", stale_put.encode_to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(response_headers.get("etag").unwrap(), "\"2\"");
+	let (status, _, _) =
+		request_with_headers(addr, "putObjects", stale_put.encode_to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+	// If-None-Match: * makes a put create-only, regardless of the version in the body.
+	let mut headers = HashMap::new();
+	headers.insert("If-None-Match".to_string(), "*".to_string());
+	let create_put = put_request("store", "k2", 7, b"v1");
+	let (status, response_headers, _) =
+		request_with_headers(addr, "putObjects", create_put.encode_to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(response_headers.get("etag").unwrap(), "\"1\"");
+	let (status, _, _) =
+		request_with_headers(addr, "putObjects", create_put.encode_to_vec(), &headers).await;
+	assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+async fn responses_carry_cost_accounting_headers() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let no_headers = HashMap::new();
+
+	let put = put_request("store", "k1", 0, b"v1");
+	let (status, response_headers, _) =
+		request_with_headers(addr, "putObjects", put.encode_to_vec(), &no_headers).await;
+	assert_eq!(status, StatusCode::OK);
+	let header = |name: &str| {
+		response_headers.get(name).unwrap().to_str().unwrap().parse::<u64>().unwrap()
+	};
+	assert_eq!(header("x-vss-request-bytes"), put.encode_to_vec().len() as u64);
+	assert_eq!(header("x-vss-rows"), 1);
+	assert!(header("x-vss-backend-micros") > 0);
+
+	let get = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+	let (status, response_headers, body) =
+		request_with_headers(addr, "getObject", get.encode_to_vec(), &no_headers).await;
+	assert_eq!(status, StatusCode::OK);
+	let header = |name: &str| {
+		response_headers.get(name).unwrap().to_str().unwrap().parse::<u64>().unwrap()
+	};
+	assert_eq!(header("x-vss-response-bytes"), body.len() as u64);
+
+	// Errors report their cost too, so a misbehaving access pattern is visible either way.
+	let missing = GetObjectRequest { store_id: "store".to_string(), key: "gone".to_string() };
+	let (status, response_headers, _) =
+		request_with_headers(addr, "getObject", missing.encode_to_vec(), &no_headers).await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+	assert!(response_headers.contains_key("x-vss-backend-micros"));
+}
+
+#[tokio::test]
+async fn store_stats_are_served_over_http() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	for (key, value) in [("small", vec![1u8; 2]), ("large", vec![2u8; 64])] {
+		let response: Result<api::types::PutObjectResponse, _> =
+			request(addr, "putObjects", put_request("store", key, 0, &value), &headers).await;
+		response.unwrap();
+	}
+
+	let stats_request = GetStoreStatsRequest { store_id: "store".to_string() };
+	let stats: GetStoreStatsResponse =
+		request(addr, "getStoreStats", stats_request, &headers).await.unwrap();
+	assert_eq!(stats.key_count, 2);
+	assert_eq!(stats.total_value_bytes, 66);
+	assert_eq!(stats.largest_keys[0].key, "large");
+	assert!(stats.newest_updated_at_millis >= stats.oldest_updated_at_millis);
+	assert!(stats.oldest_updated_at_millis > 0);
+}
+
+#[tokio::test]
+async fn swap_object_round_trip() {
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	let headers = HashMap::new();
+
+	let swap_request = |version: i64, value: &[u8]| SwapObjectRequest {
+		store_id: "store".to_string(),
+		item: Some(KeyValue { key: "leader".to_string(), version, value: value.to_vec().into() }),
+	};
+
+	let response: SwapObjectResponse =
+		request(addr, "swapObject", swap_request(0, b"device-a"), &headers).await.unwrap();
+	assert!(response.previous.is_none());
+
+	let response: SwapObjectResponse =
+		request(addr, "swapObject", swap_request(-1, b"device-b"), &headers).await.unwrap();
+	let previous = response.previous.unwrap();
+	assert_eq!(previous.version, 1);
+	assert_eq!(previous.value, b"device-a"[..]);
+
+	// A stale version must conflict with HTTP 409 instead of overwriting.
+	let result: Result<SwapObjectResponse, _> =
+		request(addr, "swapObject", swap_request(1, b"device-c"), &headers).await;
+	let (status, error_response) = result.unwrap_err();
+	assert_eq!(status, StatusCode::CONFLICT);
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::ConflictException));
+}
+
+// For deployments behind an already-authenticating reverse proxy, the user token may be taken
+// from a trusted header instead of the fixed fallback user.
+#[tokio::test]
+async fn trusted_header_maps_to_user_token() {
+	let addr =
+		start_server(Arc::new(NoopAuthorizer::with_trusted_header("x-auth-user".to_string())))
+			.await;
+
+	// Requests missing the trusted header must be rejected with HTTP 401.
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &HashMap::new()).await;
+	let (status, error_response) = result.unwrap_err();
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::AuthException));
+
+	let mut alice_headers = HashMap::new();
+	alice_headers.insert("x-auth-user".to_string(), "alice".to_string());
+	let mut bob_headers = HashMap::new();
+	bob_headers.insert("x-auth-user".to_string(), "bob".to_string());
+
+	// Data written under one header value must not be visible under another.
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &alice_headers)
+			.await
+			.unwrap();
+	let get_request = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+	let response: GetObjectResponse =
+		request(addr, "getObject", get_request.clone(), &alice_headers).await.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+	let result: Result<GetObjectResponse, _> =
+		request(addr, "getObject", get_request, &bob_headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn store_grants_allow_read_only_cross_user_access() {
+	// Store grants sit behind a feature flag and are disabled by default.
+	let authorizer = Arc::new(NoopAuthorizer::with_trusted_header("x-auth-user".to_string()));
+	let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let admin_state = Arc::new(AdminState::default());
+	admin_state.features.store_grants.store(true, Ordering::Release);
+	let service = VssService::new(store, authorizer, tenants, admin_state, None, None, None);
+	let addr = start_service(service).await;
+
+	let mut alice_headers = HashMap::new();
+	alice_headers.insert("x-auth-user".to_string(), "alice".to_string());
+	let mut bob_headers = HashMap::new();
+	bob_headers.insert("x-auth-user".to_string(), "bob".to_string());
+	// Bob addresses Alice's store by naming her as the owner.
+	let mut bob_granted_headers = bob_headers.clone();
+	bob_granted_headers.insert("x-vss-store-owner".to_string(), "alice".to_string());
+
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &alice_headers)
+			.await
+			.unwrap();
+	let get_request = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+
+	// Without a grant, naming the owner must be rejected with HTTP 401.
+	let result: Result<GetObjectResponse, _> =
+		request(addr, "getObject", get_request.clone(), &bob_granted_headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	let grant_request = api::types::GrantStoreAccessRequest {
+		store_id: "store".to_string(),
+		grantee_token: "bob".to_string(),
+	};
+	let _: api::types::GrantStoreAccessResponse =
+		request(addr, "grantStoreAccess", grant_request, &alice_headers).await.unwrap();
+
+	// With the grant in place, Bob can read Alice's store...
+	let response: GetObjectResponse =
+		request(addr, "getObject", get_request.clone(), &bob_granted_headers).await.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+
+	// ...but writes never honor grants.
+	let result: Result<api::types::PutObjectResponse, _> = request(
+		addr,
+		"putObjects",
+		put_request("store", "k1", 1, b"v2"),
+		&bob_granted_headers,
+	)
+	.await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	// Revoking closes the access again.
+	let revoke_request = api::types::RevokeStoreAccessRequest {
+		store_id: "store".to_string(),
+		grantee_token: "bob".to_string(),
+	};
+	let _: api::types::RevokeStoreAccessResponse =
+		request(addr, "revokeStoreAccess", revoke_request, &alice_headers).await.unwrap();
+	let result: Result<GetObjectResponse, _> =
+		request(addr, "getObject", get_request, &bob_granted_headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	// With the feature flag at its default, the grant RPCs are not served at all.
+	let flagged_off =
+		start_server(Arc::new(NoopAuthorizer::with_trusted_header("x-auth-user".to_string())))
+			.await;
+	let grant_request = api::types::GrantStoreAccessRequest {
+		store_id: "store".to_string(),
+		grantee_token: "bob".to_string(),
+	};
+	let (status, _) = request_raw(
+		flagged_off,
+		"grantStoreAccess",
+		grant_request.encode_to_vec(),
+		&alice_headers,
+	)
+	.await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn draining_flips_readiness_and_closes_connections() {
+	let store: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let admin_state = Arc::new(AdminState::default());
+	let service = VssService::new(
+		store,
+		Arc::new(NoopAuthorizer::new()),
+		tenants,
+		Arc::clone(&admin_state),
+		None,
+		None,
+		None,
+	);
+	let addr = start_service(service).await;
+
+	let probe = |addr: SocketAddr| async move {
+		let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+		let request = Request::builder()
+			.method(Method::GET)
+			.uri(format!("http://{}/ready", addr))
+			.body(Full::new(Bytes::new()))
+			.unwrap();
+		client.request(request).await.unwrap().status()
+	};
+	assert_eq!(probe(addr).await, StatusCode::OK);
+
+	admin_state.draining.store(true, Ordering::Release);
+	assert_eq!(probe(addr).await, StatusCode::SERVICE_UNAVAILABLE);
+
+	// Requests are still answered while draining, but ask the client to close the connection.
+	let put = put_request("store", "k1", 0, b"v1");
+	let (status, response_headers, _) =
+		request_with_headers(addr, "putObjects", put.encode_to_vec(), &HashMap::new()).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(response_headers.get("connection").unwrap(), "close");
+
+	// Undraining returns the instance to service.
+	admin_state.draining.store(false, Ordering::Release);
+	assert_eq!(probe(addr).await, StatusCode::OK);
+}
+
+// Liveness only reports whether the process is up; readiness additionally requires a healthy
+// storage backend, so load balancers stop routing traffic during a database outage without
+// restart loops.
+#[tokio::test]
+async fn health_endpoints_distinguish_liveness_and_readiness() {
+	struct UnhealthyStore;
+	#[async_trait::async_trait]
+	impl KvStore for UnhealthyStore {
+		async fn get(
+			&self, _: api::kv_store::RequestContext, _: GetObjectRequest,
+		) -> Result<GetObjectResponse, api::error::VssError> {
+			unimplemented!()
+		}
+		async fn put(
+			&self, _: api::kv_store::RequestContext, _: PutObjectRequest,
+		) -> Result<api::types::PutObjectResponse, api::error::VssError> {
+			unimplemented!()
+		}
+		async fn delete(
+			&self, _: api::kv_store::RequestContext, _: api::types::DeleteObjectRequest,
+		) -> Result<api::types::DeleteObjectResponse, api::error::VssError> {
+			unimplemented!()
+		}
+		async fn list_key_versions(
+			&self, _: api::kv_store::RequestContext, _: ListKeyVersionsRequest,
+		) -> Result<ListKeyVersionsResponse, api::error::VssError> {
+			unimplemented!()
+		}
+		async fn get_store_stats(
+			&self, _: api::kv_store::RequestContext, _: api::types::GetStoreStatsRequest,
+		) -> Result<api::types::GetStoreStatsResponse, api::error::VssError> {
+			unimplemented!()
+		}
+		async fn health_check(&self) -> Result<(), api::error::VssError> {
+			Err(api::error::VssError::InternalServerError("database unreachable".to_string()))
+		}
+	}
+
+	let probe = |addr: SocketAddr, path: &'static str| async move {
+		let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+		let request = Request::builder()
+			.method(Method::GET)
+			.uri(format!("http://{}{}", addr, path))
+			.body(Full::new(Bytes::new()))
+			.unwrap();
+		client.request(request).await.unwrap().status()
+	};
+
+	// A healthy backend reports ready on both the classic and the Kubernetes-style path.
+	let addr = start_server(Arc::new(NoopAuthorizer::new())).await;
+	assert_eq!(probe(addr, "/health/live").await, StatusCode::OK);
+	assert_eq!(probe(addr, "/health/ready").await, StatusCode::OK);
+	assert_eq!(probe(addr, "/ready").await, StatusCode::OK);
+
+	// An unreachable backend flips readiness but leaves liveness untouched.
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let service = VssService::new(
+		Arc::new(UnhealthyStore),
+		Arc::new(NoopAuthorizer::new()),
+		tenants,
+		Arc::new(AdminState::default()),
+		None,
+		None,
+		None,
+	);
+	let addr = start_service(service).await;
+	assert_eq!(probe(addr, "/health/live").await, StatusCode::OK);
+	assert_eq!(probe(addr, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(probe(addr, "/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn signature_authorizer_end_to_end() {
+	let addr = start_server(Arc::new(SignatureValidatingAuthorizer::new())).await;
+
+	// Unauthenticated requests must be rejected with HTTP 401 and an AuthException.
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &HashMap::new()).await;
+	let (status, error_response) = result.unwrap_err();
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+	assert_eq!(error_response.error_code, i32::from(ErrorCode::AuthException));
+
+	// A tampered signature must be rejected as well.
+	let mut tampered_headers = signed_headers();
+	tampered_headers.insert(SIGNATURE_HEADER.to_string(), hex::encode([0u8; 64]));
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &tampered_headers)
+			.await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	// Properly signed requests must go through.
+	let headers = signed_headers();
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &headers)
+			.await
+			.unwrap();
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&headers,
+	)
+	.await
+	.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+}
+
+#[tokio::test]
+async fn replicated_writes_propagate_and_late_conflicts_are_rejected() {
+	// Peer B maps the user token forwarded by A's replication back via a trusted header.
+	let authorizer_b = NoopAuthorizer::with_trusted_header("x-auth-user".to_string());
+	let addr_b = start_server(Arc::new(authorizer_b)).await;
+	let mut headers = HashMap::new();
+	headers.insert("x-auth-user".to_string(), "alice".to_string());
+
+	// Seed k1 on B, so A's later conditional write of k1 is a late conflicting write.
+	let _: api::types::PutObjectResponse =
+		request(addr_b, "putObjects", put_request("store", "k1", 0, b"b-wins"), &headers)
+			.await
+			.unwrap();
+
+	// Boot A with its own backend, replicating to B.
+	let store_a: Arc<dyn KvStore> = Arc::new(MemoryBackendImpl::new());
+	let replicated: Arc<dyn KvStore> = Arc::new(ReplicatedKvStore::new(
+		store_a,
+		format!("http://{}", addr_b),
+		Some("x-auth-user".to_string()),
+		64,
+	));
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let admin_state = Arc::new(AdminState::default());
+	let authorizer_a = NoopAuthorizer::with_trusted_header("x-auth-user".to_string());
+	let service = VssService::new(
+		replicated,
+		Arc::new(authorizer_a),
+		tenants,
+		admin_state,
+		None,
+		None,
+		None,
+	);
+	let addr_a = start_service(service).await;
+
+	// k1 is already taken on B: the write must still succeed locally on A, while its replication
+	// is rejected by B's version check. k2 is uncontended and must propagate.
+	let _: api::types::PutObjectResponse =
+		request(addr_a, "putObjects", put_request("store", "k1", 0, b"a-late"), &headers)
+			.await
+			.unwrap();
+	let _: api::types::PutObjectResponse =
+		request(addr_a, "putObjects", put_request("store", "k2", 0, b"v2"), &headers)
+			.await
+			.unwrap();
+
+	// Writes are forwarded in commit order, so once k2 is visible on B, k1's replication outcome
+	// is settled as well.
+	let get_k2 = GetObjectRequest { store_id: "store".to_string(), key: "k2".to_string() };
+	let mut replicated_k2 = None;
+	for _ in 0..100 {
+		let result: Result<GetObjectResponse, _> =
+			request(addr_b, "getObject", get_k2.clone(), &headers).await;
+		match result {
+			Ok(response) => {
+				replicated_k2 = Some(response);
+				break;
+			},
+			Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+		}
+	}
+	let replicated_k2 = replicated_k2.expect("k2 was not replicated to the peer");
+	assert_eq!(replicated_k2.value.unwrap().value, b"v2"[..]);
+
+	let get_k1 = GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() };
+	let response: GetObjectResponse =
+		request(addr_b, "getObject", get_k1, &headers).await.unwrap();
+	assert_eq!(response.value.unwrap().value, b"b-wins"[..]);
+}
+
+// The time endpoint is served without authentication, so clients can correct for clock drift
+// before constructing their timestamped auth tokens.
+#[tokio::test]
+async fn server_time_is_served_unauthenticated() {
+	let authorizer = JwtAuthorizer::new(JWT_TEST_PUBLIC_KEY_PEM).unwrap();
+	let addr = start_server(Arc::new(authorizer)).await;
+
+	let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+	let (status, body) = request_raw(addr, "time", vec![], &HashMap::new()).await;
+	assert_eq!(status, StatusCode::OK);
+	let server_time: u64 = std::str::from_utf8(&body).unwrap().parse().unwrap();
+	let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+	assert!(server_time >= before && server_time <= after);
+}
+
+#[tokio::test]
+async fn jwt_authorizer_end_to_end() {
+	let authorizer = JwtAuthorizer::new(JWT_TEST_PUBLIC_KEY_PEM).unwrap();
+	let addr = start_server(Arc::new(authorizer)).await;
+
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &HashMap::new()).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	let mut invalid_headers = HashMap::new();
+	invalid_headers.insert("authorization".to_string(), "Bearer not-a-jwt".to_string());
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &invalid_headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	let headers = jwt_headers(JWT_TEST_PRIVATE_KEY_PEM);
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &headers)
+			.await
+			.unwrap();
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&headers,
+	)
+	.await
+	.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+}
+
+// A token carrying an `allowed_cidrs` claim is only honored from the listed ranges, checked
+// against the peer address — X-Forwarded-For only counts behind a configured trusted proxy.
+#[tokio::test]
+async fn ip_bound_tokens_are_rejected_outside_their_ranges() {
+	fn bound_jwt_headers(allowed_cidrs: &[&str]) -> HashMap<String, String> {
+		#[derive(serde::Serialize)]
+		struct Claims {
+			sub: String,
+			exp: u64,
+			allowed_cidrs: Vec<String>,
+		}
+		let exp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 300;
+		let claims = Claims {
+			sub: "e2e-user".to_string(),
+			exp,
+			allowed_cidrs: allowed_cidrs.iter().map(|cidr| cidr.to_string()).collect(),
+		};
+		let token = jsonwebtoken::encode(
+			&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+			&claims,
+			&jsonwebtoken::EncodingKey::from_rsa_pem(JWT_TEST_PRIVATE_KEY_PEM).unwrap(),
+		)
+		.unwrap();
+		let mut headers = HashMap::new();
+		headers.insert("authorization".to_string(), format!("Bearer {}", token));
+		headers
+	}
+
+	let authorizer = JwtAuthorizer::new(JWT_TEST_PUBLIC_KEY_PEM).unwrap();
+	let addr = start_server(Arc::new(authorizer)).await;
+	// The connection's peer address (127.0.0.1) falls within the bound range.
+	let headers = bound_jwt_headers(&["127.0.0.0/8"]);
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k1", 0, b"v1"), &headers).await.unwrap();
+
+	// Bound to an unrelated range, the same connection is rejected — and forging
+	// X-Forwarded-For does not rescue it, since no trusted proxy is configured.
+	let mut headers = bound_jwt_headers(&["203.0.113.0/24"]);
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k2", 0, b"v2"), &headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+	headers.insert("x-forwarded-for".to_string(), "203.0.113.9".to_string());
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k2", 0, b"v2"), &headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	// With the connection's peer declared a trusted proxy, the forwarded client address is what
+	// counts instead.
+	let authorizer = JwtAuthorizer::new(JWT_TEST_PUBLIC_KEY_PEM).unwrap();
+	let proxied_addr = start_service(
+		build_service(Arc::new(authorizer)).with_trusted_proxies(vec!["127.0.0.0/8".to_string()]),
+	)
+	.await;
+	let mut headers = bound_jwt_headers(&["203.0.113.0/24"]);
+	headers.insert("x-forwarded-for".to_string(), "203.0.113.9".to_string());
+	let _: api::types::PutObjectResponse =
+		request(proxied_addr, "putObjects", put_request("store", "k2", 0, b"v2"), &headers)
+			.await
+			.unwrap();
+	headers.insert("x-forwarded-for".to_string(), "198.51.100.1".to_string());
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(proxied_addr, "putObjects", put_request("store", "k2", 1, b"v3"), &headers).await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+	// An unbound token keeps working from anywhere.
+	let headers = jwt_headers(JWT_TEST_PRIVATE_KEY_PEM);
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&headers,
+	)
+	.await
+	.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+}
+
+// Trial mode: credential-less requests are issued a random identity persisted via the
+// x-vss-trial-token header, with a tight write quota, and presenting the token alongside a real
+// credential migrates the trial data into the authenticated account.
+#[tokio::test]
+async fn trial_identities_are_issued_quota_limited_and_claimable() {
+	let backend = Arc::new(MemoryBackendImpl::new());
+	let trial_config = vss_server::config::TrialConfig {
+		ttl_secs: 3600,
+		max_items: Some(2),
+		max_value_bytes: Some(1024),
+		purge_interval_secs: None,
+	};
+	let trials = Arc::new(TrialRegistry::new(
+		trial_config,
+		backend.clone(),
+		backend.clone(),
+		None,
+	));
+	let authorizer = Arc::new(JwtAuthorizer::new(JWT_TEST_PUBLIC_KEY_PEM).unwrap());
+	let store: Arc<dyn KvStore> = backend;
+	let tenants = Arc::new(TenantRegistry::new(vec![]));
+	let admin_state = Arc::new(AdminState::default());
+	let service = VssService::new(store, authorizer, tenants, admin_state, None, None, None)
+		.with_trials(trials);
+	let addr = start_service(service).await;
+
+	// The first credential-less request is served and issues a trial token to persist.
+	let (status, response_headers, _) = request_with_headers(
+		addr,
+		"putObjects",
+		put_request("store", "k1", 0, b"v1").encode_to_vec(),
+		&HashMap::new(),
+	)
+	.await;
+	assert_eq!(status, StatusCode::OK);
+	let trial_token =
+		response_headers.get(TRIAL_TOKEN_HEADER).unwrap().to_str().unwrap().to_string();
+	assert!(trial_token.starts_with("trial-"));
+
+	// Subsequent requests present the token and resolve to the same identity.
+	let mut trial_headers = HashMap::new();
+	trial_headers.insert(TRIAL_TOKEN_HEADER.to_string(), trial_token.clone());
+	let _: api::types::PutObjectResponse =
+		request(addr, "putObjects", put_request("store", "k2", 0, b"v2"), &trial_headers)
+			.await
+			.unwrap();
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&trial_headers,
+	)
+	.await
+	.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+
+	// The third written item exceeds the two-item trial quota.
+	let result: Result<api::types::PutObjectResponse, _> =
+		request(addr, "putObjects", put_request("store", "k3", 0, b"v3"), &trial_headers).await;
+	let (status, error) = result.unwrap_err();
+	assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(error.sub_code, "LIMIT_TRIAL_QUOTA");
+
+	// Presenting the trial token alongside a real credential migrates the data.
+	let mut upgrade_headers = jwt_headers(JWT_TEST_PRIVATE_KEY_PEM);
+	upgrade_headers.insert(TRIAL_TOKEN_HEADER.to_string(), trial_token.clone());
+	let response: GetObjectResponse = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&upgrade_headers,
+	)
+	.await
+	.unwrap();
+	assert_eq!(response.value.unwrap().value, b"v1"[..]);
+
+	// The retired trial identity is rejected afterwards; the client starts over.
+	let result: Result<GetObjectResponse, _> = request(
+		addr,
+		"getObject",
+		GetObjectRequest { store_id: "store".to_string(), key: "k1".to_string() },
+		&trial_headers,
+	)
+	.await;
+	assert_eq!(result.unwrap_err().0, StatusCode::UNAUTHORIZED);
+}